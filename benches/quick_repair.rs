@@ -0,0 +1,100 @@
+//! Criterion micro-benchmarks for single and batched writes under both quick_repair settings.
+//!
+//! These complement the macro benchmark in `main` with small, statistically rigorous
+//! samples on a freshly created database.
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use spike_redb_quick_repair::redb::Database;
+use spike_redb_quick_repair::{TABLE, VALUE_SIZE, generate_random_value};
+use std::path::PathBuf;
+
+const BATCH_SIZE: usize = 100;
+
+fn bench_db_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("criterion_{name}.redb"))
+}
+
+fn create_db(name: &str) -> (Database, PathBuf) {
+    let path = bench_db_path(name);
+    let _ = std::fs::remove_file(&path);
+    let db = Database::builder()
+        .set_cache_size(64 * 1024 * 1024)
+        .create(&path)
+        .unwrap();
+    (db, path)
+}
+
+fn single_write(c: &mut Criterion) {
+    let mut group = c.benchmark_group("single_write");
+    group.throughput(Throughput::Elements(1));
+
+    for quick_repair in [false, true] {
+        let (db, path) = create_db(&format!("single_write_{quick_repair}"));
+        let value = generate_random_value(VALUE_SIZE);
+        let mut key = 0u64;
+
+        group.bench_with_input(
+            BenchmarkId::new("quick_repair", quick_repair),
+            &quick_repair,
+            |b, &quick_repair| {
+                b.iter(|| {
+                    let mut write_txn = db.begin_write().unwrap();
+                    write_txn.set_quick_repair(quick_repair);
+                    {
+                        let mut table = write_txn.open_table(TABLE).unwrap();
+                        table.insert(key, value.as_slice()).unwrap();
+                    }
+                    write_txn.commit().unwrap();
+                    key += 1;
+                })
+            },
+        );
+
+        drop(db);
+        let _ = std::fs::remove_file(path);
+    }
+
+    group.finish();
+}
+
+fn batch_write(c: &mut Criterion) {
+    let mut group = c.benchmark_group("batch_write");
+    group.throughput(Throughput::Elements(BATCH_SIZE as u64));
+
+    for quick_repair in [false, true] {
+        let (db, path) = create_db(&format!("batch_write_{quick_repair}"));
+        let value = generate_random_value(VALUE_SIZE);
+        let mut key = 0u64;
+
+        group.bench_with_input(
+            BenchmarkId::new("quick_repair", quick_repair),
+            &quick_repair,
+            |b, &quick_repair| {
+                b.iter(|| {
+                    let mut write_txn = db.begin_write().unwrap();
+                    write_txn.set_quick_repair(quick_repair);
+                    {
+                        let mut table = write_txn.open_table(TABLE).unwrap();
+                        for _ in 0..BATCH_SIZE {
+                            table.insert(key, value.as_slice()).unwrap();
+                            key += 1;
+                        }
+                    }
+                    write_txn.commit().unwrap();
+                })
+            },
+        );
+
+        drop(db);
+        let _ = std::fs::remove_file(path);
+    }
+
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(50);
+    targets = single_write, batch_write
+}
+criterion_main!(benches);
@@ -0,0 +1,111 @@
+//! Alternating `set_quick_repair(true)` / `(false)` on successive transactions against one
+//! database, as an application enabling it only for "important" commits would.
+
+use crate::payload::ValueGen;
+use crate::report::Section;
+use crate::{BenchDb, BenchmarkStats, StatsAccumulator, TABLE, clock, compat, hooks, record_op};
+use redb::{Database, Error};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use tracing::{info, info_span};
+
+pub struct AlternateReport {
+    pub quick_repair_true: BenchmarkStats,
+    pub quick_repair_false: BenchmarkStats,
+    /// Keys read back and checked after reopening the database.
+    pub verified: u64,
+}
+
+pub(crate) fn checksum(value: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Perform `num_writes` single-insert transactions on `bench_db`, toggling quick_repair on
+/// every commit, then reopen the database and verify every written value.
+pub fn benchmark_alternating(
+    bench_db: &mut BenchDb,
+    num_writes: usize,
+    values: ValueGen,
+) -> Result<AlternateReport, Error> {
+    let _span = info_span!("alternate", db_path = %bench_db.path).entered();
+    info!(num_writes, "Alternating quick_repair on successive commits");
+
+    let db = bench_db.create(
+        Database::builder().set_cache_size(1024 * 1024 * 1024), // 1GB cache
+    )?;
+
+    let start_key = bench_db.next_key;
+    let mut latencies: [StatsAccumulator; 2] = Default::default();
+    let mut checksums = Vec::with_capacity(num_writes);
+
+    for (i, key) in (start_key..).take(num_writes).enumerate() {
+        let quick_repair = i % 2 == 1;
+        let value = values.generate(key);
+        checksums.push(checksum(&value));
+
+        let start = clock::now();
+        let mut write_txn = hooks::begin_write(&db)?;
+        write_txn.set_quick_repair(quick_repair);
+        {
+            let mut table = write_txn.open_table(TABLE)?;
+            table.insert(key, value.as_slice())?;
+        }
+        write_txn.commit()?;
+        let duration = start.elapsed();
+        latencies[quick_repair as usize].record(duration);
+        record_op(
+            "alternate",
+            &bench_db.path,
+            quick_repair,
+            i as u64,
+            1,
+            value.len(),
+            duration,
+        );
+    }
+    bench_db.next_key = start_key + num_writes as u64;
+    drop(db);
+
+    // Reopen so the check covers what was persisted, not just the in-memory state.
+    let db = bench_db.create(&mut Database::builder())?;
+    let read_txn = compat::begin_read(&db)?;
+    let table = read_txn.open_table(TABLE)?;
+    let mut verified = 0;
+    for entry in table.range(start_key..bench_db.next_key)? {
+        let (key, value) = entry?;
+        let index = (key.value() - start_key) as usize;
+        if checksum(value.value()) != checksums[index] {
+            return Err(Error::Corrupted(format!(
+                "key {} has unexpected contents after alternating commits",
+                key.value()
+            )));
+        }
+        verified += 1;
+    }
+    if verified != num_writes as u64 {
+        return Err(Error::Corrupted(format!(
+            "expected {num_writes} keys after alternating commits, found {verified}"
+        )));
+    }
+    info!(verified, "Verified alternating commits");
+
+    Ok(AlternateReport {
+        quick_repair_true: latencies[1].stats(),
+        quick_repair_false: latencies[0].stats(),
+        verified,
+    })
+}
+
+impl AlternateReport {
+    pub fn sections(&self) -> Vec<Section> {
+        vec![
+            self.quick_repair_false
+                .section("Alternating Writes - quick_repair(false) commits"),
+            self.quick_repair_true
+                .section("Alternating Writes - quick_repair(true) commits")
+                .field("Keys verified after reopening", self.verified),
+        ]
+    }
+}
@@ -0,0 +1,320 @@
+//! `--assert` thresholds on the final write-benchmark statistics, so that automated
+//! harnesses can gate on a run's exit code instead of parsing the report.
+//!
+//! An assertion is `METRIC OP VALUE`, e.g. `writes_per_second >= 500`. The metric holds for
+//! both quick_repair settings unless suffixed with `.false` or `.true`
+//! (`avg_write_us.true <= 2000`); `speedup`, quick_repair(false) writes per second over
+//! quick_repair(true), takes no suffix.
+
+use crate::BenchmarkStats;
+use crate::report::Section;
+use std::fmt;
+use std::str::FromStr;
+
+/// Process exit code when an assertion fails.
+pub const EXIT_CODE: u8 = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Metric {
+    WritesPerSecond,
+    AvgWriteUs,
+    TrimmedMeanUs,
+    MinWriteUs,
+    MaxWriteUs,
+    Outliers,
+    Speedup,
+}
+
+impl Metric {
+    const ALL: [Metric; 7] = [
+        Metric::WritesPerSecond,
+        Metric::AvgWriteUs,
+        Metric::TrimmedMeanUs,
+        Metric::MinWriteUs,
+        Metric::MaxWriteUs,
+        Metric::Outliers,
+        Metric::Speedup,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Metric::WritesPerSecond => "writes_per_second",
+            Metric::AvgWriteUs => "avg_write_us",
+            Metric::TrimmedMeanUs => "trimmed_mean_us",
+            Metric::MinWriteUs => "min_write_us",
+            Metric::MaxWriteUs => "max_write_us",
+            Metric::Outliers => "outliers",
+            Metric::Speedup => "speedup",
+        }
+    }
+
+    fn of(self, stats: &BenchmarkStats) -> f64 {
+        let us = |d: std::time::Duration| d.as_secs_f64() * 1e6;
+        match self {
+            Metric::WritesPerSecond => stats.writes_per_second,
+            Metric::AvgWriteUs => us(stats.avg_write_time),
+            Metric::TrimmedMeanUs => us(stats.trimmed_mean),
+            Metric::MinWriteUs => us(stats.min_write_time),
+            Metric::MaxWriteUs => us(stats.max_write_time),
+            Metric::Outliers => stats.outliers as f64,
+            Metric::Speedup => unreachable!("speedup compares both settings"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Op {
+    Ge,
+    Le,
+    Gt,
+    Lt,
+    Eq,
+}
+
+impl Op {
+    /// Longest first, so that `>=` is not read as `>`.
+    const ALL: [(&'static str, Op); 5] = [
+        (">=", Op::Ge),
+        ("<=", Op::Le),
+        ("==", Op::Eq),
+        (">", Op::Gt),
+        ("<", Op::Lt),
+    ];
+
+    fn holds(self, value: f64, threshold: f64) -> bool {
+        match self {
+            Op::Ge => value >= threshold,
+            Op::Le => value <= threshold,
+            Op::Gt => value > threshold,
+            Op::Lt => value < threshold,
+            Op::Eq => value == threshold,
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        Op::ALL.iter().find(|&&(_, op)| op == self).unwrap().0
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Assertion {
+    pub metric: Metric,
+    /// The quick_repair setting checked, or both.
+    pub quick_repair: Option<bool>,
+    pub op: Op,
+    pub threshold: f64,
+}
+
+impl FromStr for Assertion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (at, symbol, op) = Op::ALL
+            .iter()
+            .filter_map(|&(symbol, op)| s.find(symbol).map(|at| (at, symbol, op)))
+            .min_by_key(|&(at, _, _)| at)
+            .ok_or_else(|| {
+                format!("invalid assertion `{s}`, expected e.g. `writes_per_second >= 500`")
+            })?;
+        let (name, threshold) = (s[..at].trim(), s[at + symbol.len()..].trim());
+        let threshold: f64 = threshold
+            .parse()
+            .map_err(|_| format!("invalid threshold `{threshold}` in assertion `{s}`"))?;
+        let (name, quick_repair) = match name.rsplit_once('.') {
+            Some((name, "false")) => (name, Some(false)),
+            Some((name, "true")) => (name, Some(true)),
+            _ => (name, None),
+        };
+        let metric = Metric::ALL
+            .into_iter()
+            .find(|metric| metric.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<_> = Metric::ALL.iter().map(|m| m.name()).collect();
+                format!(
+                    "unknown metric `{name}` in assertion `{s}`, expected one of {}",
+                    names.join(", ")
+                )
+            })?;
+        if metric == Metric::Speedup && quick_repair.is_some() {
+            return Err(format!(
+                "`speedup` compares both quick_repair settings and takes no suffix, in `{s}`"
+            ));
+        }
+        Ok(Self {
+            metric,
+            quick_repair,
+            op,
+            threshold,
+        })
+    }
+}
+
+impl fmt::Display for Assertion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.metric.name())?;
+        if let Some(quick_repair) = self.quick_repair {
+            write!(f, ".{quick_repair}")?;
+        }
+        write!(f, " {} {}", self.op.symbol(), self.threshold)
+    }
+}
+
+/// One assertion checked against one run and quick_repair setting.
+pub struct Outcome {
+    pub assertion: Assertion,
+    /// The run checked, e.g. its database directory.
+    pub run: String,
+    /// The quick_repair setting checked, or `None` for `speedup`.
+    pub quick_repair: Option<bool>,
+    pub value: f64,
+    pub passed: bool,
+}
+
+impl Assertion {
+    /// Check against the quick_repair(false) and quick_repair(true) stats of `run`.
+    pub fn check(&self, run: &str, [without, with]: &[BenchmarkStats; 2]) -> Vec<Outcome> {
+        let outcome = |quick_repair, value| Outcome {
+            assertion: *self,
+            run: run.to_owned(),
+            quick_repair,
+            value,
+            passed: self.op.holds(value, self.threshold),
+        };
+        if self.metric == Metric::Speedup {
+            let speedup = without.writes_per_second / with.writes_per_second.max(f64::EPSILON);
+            return vec![outcome(None, speedup)];
+        }
+        [(false, without), (true, with)]
+            .into_iter()
+            .filter(|&(quick_repair, _)| self.quick_repair.is_none_or(|qr| qr == quick_repair))
+            .map(|(quick_repair, stats)| outcome(Some(quick_repair), self.metric.of(stats)))
+            .collect()
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.assertion)?;
+        if let Some(quick_repair) = self.quick_repair {
+            write!(f, " for quick_repair({quick_repair})")?;
+        }
+        if !self.run.is_empty() {
+            write!(f, " in {}", self.run)?;
+        }
+        write!(f, ": measured {:.2}", self.value)
+    }
+}
+
+pub fn section(title: impl Into<String>, outcomes: &[Outcome]) -> Section {
+    let failed = outcomes.iter().filter(|o| !o.passed).count();
+    let mut section = Section::new(title)
+        .field("Checked", outcomes.len())
+        .field("Failed", failed)
+        .table(["assertion", "run", "quick_repair", "measured", "result"]);
+    for outcome in outcomes {
+        section = section.row(vec![
+            outcome.assertion.to_string().into(),
+            outcome.run.clone().into(),
+            outcome
+                .quick_repair
+                .map_or("both".to_owned(), |qr| qr.to_string())
+                .into(),
+            outcome.value.into(),
+            if outcome.passed { "pass" } else { "FAIL" }.into(),
+        ]);
+    }
+    section
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StatsAccumulator;
+    use std::time::Duration;
+
+    fn assertion(s: &str) -> Assertion {
+        s.parse().unwrap()
+    }
+
+    fn stats(latency_ms: u64) -> BenchmarkStats {
+        [Duration::from_millis(latency_ms); 4]
+            .into_iter()
+            .collect::<StatsAccumulator>()
+            .stats()
+    }
+
+    #[test]
+    fn assertion_parses_metric_setting_op_and_threshold() {
+        assert_eq!(
+            assertion("writes_per_second >= 500"),
+            Assertion {
+                metric: Metric::WritesPerSecond,
+                quick_repair: None,
+                op: Op::Ge,
+                threshold: 500.0,
+            }
+        );
+        assert_eq!(
+            assertion("avg_write_us.true<=2000.5"),
+            Assertion {
+                metric: Metric::AvgWriteUs,
+                quick_repair: Some(true),
+                op: Op::Le,
+                threshold: 2000.5,
+            }
+        );
+        let outliers = assertion("outliers.false == 0");
+        assert_eq!(outliers.quick_repair, Some(false));
+        assert_eq!(outliers.op, Op::Eq);
+    }
+
+    #[test]
+    fn assertion_reads_two_character_ops_whole() {
+        assert_eq!(assertion("speedup > 1").op, Op::Gt);
+        assert_eq!(assertion("speedup >= 1").op, Op::Ge);
+        assert_eq!(assertion("max_write_us < 10").op, Op::Lt);
+        assert_eq!(assertion("max_write_us <= 10").op, Op::Le);
+    }
+
+    #[test]
+    fn assertion_rejects_malformed_input() {
+        for s in [
+            "",
+            "writes_per_second 500",
+            "writes_per_second >= fast",
+            "throughput >= 500",
+            "writes_per_second.maybe >= 500",
+            "speedup.true >= 1",
+        ] {
+            assert!(s.parse::<Assertion>().is_err(), "`{s}` parsed");
+        }
+    }
+
+    #[test]
+    fn assertion_round_trips_through_display() {
+        for s in ["writes_per_second >= 500", "trimmed_mean_us.false < 1500"] {
+            assert_eq!(assertion(s).to_string(), s);
+            assert_eq!(assertion(&assertion(s).to_string()), assertion(s));
+        }
+    }
+
+    #[test]
+    fn assertion_checks_the_requested_settings() {
+        let runs = [stats(1), stats(2)];
+
+        let both = assertion("writes_per_second >= 600").check("run", &runs);
+        let passed: Vec<_> = both.iter().map(|o| (o.quick_repair, o.passed)).collect();
+        assert_eq!(passed, [(Some(false), true), (Some(true), false)]);
+
+        let with = assertion("avg_write_us.true <= 2000").check("run", &runs);
+        assert_eq!(with.len(), 1);
+        assert_eq!(with[0].quick_repair, Some(true));
+        assert!(with[0].passed);
+
+        let speedup = assertion("speedup > 1.5").check("run", &runs);
+        assert_eq!(speedup.len(), 1);
+        assert_eq!(speedup[0].quick_repair, None);
+        assert!((speedup[0].value - 2.0).abs() < 1e-9);
+        assert!(speedup[0].passed);
+    }
+}
@@ -0,0 +1,138 @@
+//! Sidecar log of acknowledged commits, for auditing a database after a crash.
+//!
+//! A writer appends one record per written key, `(txn, key, checksum)` or a deletion, once
+//! `commit()` has returned for its transaction, so that the log holds exactly the state the
+//! writer was told is committed. Each commit's records go out in one unbuffered write: they
+//! reach the page cache before the next commit starts and survive the process dying.
+//!
+//! After the crash, [`check`] compares the reopened table against the latest acknowledged
+//! version of every logged key: each must hold its last value or be gone if last deleted,
+//! and no key past the logged range may exist. That is the property quick_repair (and the
+//! full repair) must preserve.
+
+use crate::TABLE;
+use crate::alternate::checksum;
+use crate::compat;
+use redb::{Database, Error};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Bytes per record: txn id, key, a present flag and the value checksum.
+const RECORD: usize = 25;
+
+/// Sidecar log of the database at `db_path`.
+pub fn sidecar_path(db_path: &Path) -> PathBuf {
+    let mut path = db_path.as_os_str().to_owned();
+    path.push(".audit");
+    PathBuf::from(path)
+}
+
+/// Writer's side of the log.
+pub struct AuditLog {
+    file: File,
+    txn: u64,
+}
+
+impl AuditLog {
+    /// Start an empty log at `path`, replacing any previous one.
+    pub fn create(path: &Path) -> io::Result<Self> {
+        Ok(Self {
+            file: File::create(path)?,
+            txn: 0,
+        })
+    }
+
+    /// Record an acknowledged commit of `writes`, `(key, Some(value))` for an insert and
+    /// `(key, None)` for a deletion.
+    pub fn commit<'a>(
+        &mut self,
+        writes: impl IntoIterator<Item = (u64, Option<&'a [u8]>)>,
+    ) -> io::Result<()> {
+        self.txn += 1;
+        let mut records = Vec::new();
+        for (key, value) in writes {
+            records.extend_from_slice(&self.txn.to_le_bytes());
+            records.extend_from_slice(&key.to_le_bytes());
+            records.push(value.is_some().into());
+            records.extend_from_slice(&value.map_or(0, checksum).to_le_bytes());
+        }
+        self.file.write_all(&records)
+    }
+}
+
+/// Latest acknowledged version of every logged key: its value checksum, or `None` when it
+/// was last deleted.
+pub struct Expected {
+    pub keys: BTreeMap<u64, Option<u64>>,
+    /// Last acknowledged transaction, 0 if none.
+    pub last_txn: u64,
+}
+
+impl Expected {
+    /// Read the log at `path`. A partial record at the end, from a crash in the middle of
+    /// the append, belongs to a commit that was never acknowledged and is ignored.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let bytes = fs::read(path)?;
+        let mut expected = Self {
+            keys: BTreeMap::new(),
+            last_txn: 0,
+        };
+        for record in bytes.chunks_exact(RECORD) {
+            let u64_at = |at: usize| u64::from_le_bytes(record[at..at + 8].try_into().unwrap());
+            let (txn, key) = (u64_at(0), u64_at(8));
+            let value = (record[16] != 0).then(|| u64_at(17));
+            expected.keys.insert(key, value);
+            expected.last_txn = expected.last_txn.max(txn);
+        }
+        Ok(expected)
+    }
+}
+
+/// What the audit found.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Audit {
+    /// Last acknowledged transaction in the log.
+    pub last_txn: u64,
+    /// Logged keys checked.
+    pub keys: u64,
+    /// Keys whose last acknowledged insert is missing.
+    pub lost: u64,
+    /// Keys holding another value than their last acknowledged one.
+    pub mismatched: u64,
+    /// Keys present that were last deleted, or past the logged range and never logged.
+    pub unexpected: u64,
+}
+
+impl Audit {
+    pub fn is_consistent(&self) -> bool {
+        self.lost == 0 && self.mismatched == 0 && self.unexpected == 0
+    }
+}
+
+/// Compare the benchmark table of `db` with `expected`, over every key from `from` on:
+/// the keys before it are not covered by the log.
+pub fn check(db: &Database, expected: &Expected, from: u64) -> Result<Audit, Error> {
+    let read_txn = compat::begin_read(db)?;
+    let table = read_txn.open_table(TABLE)?;
+    let mut audit = Audit {
+        last_txn: expected.last_txn,
+        keys: expected.keys.len() as u64,
+        ..Audit::default()
+    };
+    for entry in table.range(from..)? {
+        let (key, value) = entry?;
+        match expected.keys.get(&key.value()) {
+            Some(Some(sum)) if *sum == checksum(value.value()) => {}
+            Some(Some(_)) => audit.mismatched += 1,
+            Some(None) | None => audit.unexpected += 1,
+        }
+    }
+    for (&key, value) in expected.keys.range(from..) {
+        if value.is_some() && table.get(key)?.is_none() {
+            audit.lost += 1;
+        }
+    }
+    Ok(audit)
+}
@@ -0,0 +1,210 @@
+//! Custom redb [`StorageBackend`]s, to measure how much of the quick_repair(true) penalty an
+//! OS-level change to how commits reach the disk can recover: [`SyncBackend`] with a
+//! selectable sync strategy, [`FaultBackend`] simulating crashes and transient errors on
+//! top of it, [`TimedBackend`] timing the calls to another backend and, on Linux with the
+//! `io-uring` feature, an io_uring backend.
+//!
+//! Unlike redb's own file backend these take no file lock: the benchmark is the only
+//! process opening its files.
+
+mod fault;
+mod timed;
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+mod uring;
+
+pub use fault::{FaultBackend, FaultHandle, TearMode};
+pub use timed::{IoTimes, TimedBackend, TimingHandle};
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+pub use uring::UringBackend;
+
+use redb::StorageBackend;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Backend the benchmark databases are opened through, selected with `--backend`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BackendKind {
+    /// redb's own file backend.
+    #[default]
+    File,
+    /// Writes queued on an io_uring without waiting, drained by each sync.
+    IoUring,
+}
+
+impl BackendKind {
+    /// Whether this binary was built with support for the backend.
+    pub fn available(self) -> bool {
+        match self {
+            BackendKind::File => true,
+            BackendKind::IoUring => cfg!(all(target_os = "linux", feature = "io-uring")),
+        }
+    }
+}
+
+impl FromStr for BackendKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "file" => Ok(BackendKind::File),
+            "io-uring" => Ok(BackendKind::IoUring),
+            _ => Err(format!(
+                "unknown backend `{s}`, expected `file` or `io-uring`"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BackendKind::File => "file",
+            BackendKind::IoUring => "io-uring",
+        })
+    }
+}
+
+/// How [`SyncBackend`] makes a commit durable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SyncStrategy {
+    /// `fsync`: flush data and all metadata.
+    Fsync,
+    /// `fdatasync`: flush data and only the metadata needed to read it back; what redb's
+    /// own backend does on Linux.
+    Fdatasync,
+    /// Open with `O_DSYNC` so every write is synchronous and the sync itself is free,
+    /// except after a resize.
+    ODsync,
+}
+
+impl SyncStrategy {
+    pub const ALL: [SyncStrategy; 3] = [
+        SyncStrategy::Fsync,
+        SyncStrategy::Fdatasync,
+        SyncStrategy::ODsync,
+    ];
+}
+
+impl FromStr for SyncStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fsync" => Ok(SyncStrategy::Fsync),
+            "fdatasync" => Ok(SyncStrategy::Fdatasync),
+            "odsync" => Ok(SyncStrategy::ODsync),
+            _ => Err(format!(
+                "unknown sync strategy `{s}`, expected `fsync`, `fdatasync` or `odsync`"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for SyncStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SyncStrategy::Fsync => "fsync",
+            SyncStrategy::Fdatasync => "fdatasync",
+            SyncStrategy::ODsync => "odsync",
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct SyncBackend {
+    file: Mutex<File>,
+    strategy: SyncStrategy,
+    /// Set by `set_len`; with `O_DSYNC` the new length still needs an explicit sync.
+    resized: AtomicBool,
+}
+
+impl SyncBackend {
+    /// Open (or create) the database file at `path` for `strategy`.
+    pub fn open(path: &Path, strategy: SyncStrategy) -> io::Result<Self> {
+        let mut options = OpenOptions::new();
+        options.read(true).write(true).create(true).truncate(false);
+        if strategy == SyncStrategy::ODsync {
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::OpenOptionsExt;
+                options.custom_flags(libc::O_DSYNC);
+            }
+            #[cfg(not(unix))]
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "O_DSYNC is only available on Unix",
+            ));
+        }
+        Ok(Self {
+            file: Mutex::new(options.open(path)?),
+            strategy,
+            resized: AtomicBool::new(false),
+        })
+    }
+
+    fn file(&self) -> std::sync::MutexGuard<'_, File> {
+        self.file.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn read_into(&self, offset: u64, out: &mut [u8]) -> io::Result<()> {
+        let mut file = self.file();
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(out)
+    }
+
+    fn sync(&self) -> io::Result<()> {
+        let file = self.file();
+        match self.strategy {
+            SyncStrategy::Fsync => file.sync_all(),
+            SyncStrategy::Fdatasync => file.sync_data(),
+            SyncStrategy::ODsync if self.resized.swap(false, Ordering::AcqRel) => file.sync_data(),
+            SyncStrategy::ODsync => Ok(()),
+        }
+    }
+}
+
+impl StorageBackend for SyncBackend {
+    fn len(&self) -> Result<u64, io::Error> {
+        Ok(self.file().metadata()?.len())
+    }
+
+    #[cfg(not(any(feature = "redb3", feature = "redb4")))]
+    fn read(&self, offset: u64, len: usize) -> Result<Vec<u8>, io::Error> {
+        let mut out = vec![0; len];
+        self.read_into(offset, &mut out)?;
+        Ok(out)
+    }
+
+    #[cfg(any(feature = "redb3", feature = "redb4"))]
+    fn read(&self, offset: u64, out: &mut [u8]) -> Result<(), io::Error> {
+        self.read_into(offset, out)
+    }
+
+    fn set_len(&self, len: u64) -> Result<(), io::Error> {
+        self.file().set_len(len)?;
+        self.resized.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    // Like redb's own backend outside macOS, eventual syncs are full syncs too.
+    #[cfg(not(any(feature = "redb3", feature = "redb4")))]
+    fn sync_data(&self, _eventual: bool) -> Result<(), io::Error> {
+        self.sync()
+    }
+
+    #[cfg(any(feature = "redb3", feature = "redb4"))]
+    fn sync_data(&self) -> Result<(), io::Error> {
+        self.sync()
+    }
+
+    fn write(&self, offset: u64, data: &[u8]) -> Result<(), io::Error> {
+        let mut file = self.file();
+        file.seek(SeekFrom::Start(offset))?;
+        file.write_all(data)
+    }
+}
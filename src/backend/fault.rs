@@ -0,0 +1,176 @@
+//! Fault injection: a [`SyncBackend`] that can be armed to "crash" on a chosen write. The
+//! crashing write is torn, i.e. only partly written or written with garbage, and every
+//! later operation fails, so the file is left exactly as a power cut mid-commit would.
+//!
+//! It can also fail writes and syncs at random with a transient error, leaving the file
+//! untouched and the backend usable, like an I/O error the device recovers from.
+
+use super::{SyncBackend, SyncStrategy};
+use rand::Rng;
+use redb::StorageBackend;
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// What happens to the write on which the crash occurs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TearMode {
+    /// Only the first half of the write reaches the file.
+    Truncate,
+    /// The write reaches the file with its second half replaced by random bytes.
+    Corrupt,
+}
+
+impl fmt::Display for TearMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TearMode::Truncate => "truncated",
+            TearMode::Corrupt => "corrupted",
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+struct FaultState {
+    /// Writes issued since the backend was opened.
+    writes: usize,
+    /// Crash on this write (by `writes` count), tearing it.
+    crash_at: Option<(usize, TearMode)>,
+    crashed: bool,
+    /// Probability of failing each write and sync with a transient error.
+    error_rate: f64,
+    /// Transient errors returned so far.
+    injected: usize,
+}
+
+/// Shared with the phase, which arms the crash while redb owns the backend.
+#[derive(Clone, Debug, Default)]
+pub struct FaultHandle(Arc<Mutex<FaultState>>);
+
+impl FaultHandle {
+    fn state(&self) -> MutexGuard<'_, FaultState> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn writes(&self) -> usize {
+        self.state().writes
+    }
+
+    /// Crash on the `nth` write from now (1 is the next one).
+    pub fn arm(&self, nth: usize, mode: TearMode) {
+        let mut state = self.state();
+        state.crash_at = Some((state.writes + nth.max(1), mode));
+    }
+
+    pub fn crashed(&self) -> bool {
+        self.state().crashed
+    }
+
+    /// Fail each later write and sync with a transient error with probability `rate`.
+    pub fn set_error_rate(&self, rate: f64) {
+        self.state().error_rate = rate;
+    }
+
+    pub fn injected(&self) -> usize {
+        self.state().injected
+    }
+}
+
+#[derive(Debug)]
+pub struct FaultBackend {
+    inner: SyncBackend,
+    handle: FaultHandle,
+}
+
+impl FaultBackend {
+    pub fn open(path: &Path) -> io::Result<(Self, FaultHandle)> {
+        let handle = FaultHandle::default();
+        let backend = Self {
+            inner: SyncBackend::open(path, SyncStrategy::Fdatasync)?,
+            handle: handle.clone(),
+        };
+        Ok((backend, handle))
+    }
+
+    fn check(&self) -> io::Result<()> {
+        if self.handle.crashed() {
+            Err(io::Error::other("simulated crash"))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Fail with a transient error at the configured rate.
+    fn transient(&self) -> io::Result<()> {
+        let mut state = self.handle.state();
+        if state.error_rate > 0.0 && rand::rng().random_bool(state.error_rate) {
+            state.injected += 1;
+            Err(io::Error::other("injected transient error"))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl StorageBackend for FaultBackend {
+    fn len(&self) -> Result<u64, io::Error> {
+        self.check()?;
+        self.inner.len()
+    }
+
+    #[cfg(not(any(feature = "redb3", feature = "redb4")))]
+    fn read(&self, offset: u64, len: usize) -> Result<Vec<u8>, io::Error> {
+        self.check()?;
+        self.inner.read(offset, len)
+    }
+
+    #[cfg(any(feature = "redb3", feature = "redb4"))]
+    fn read(&self, offset: u64, out: &mut [u8]) -> Result<(), io::Error> {
+        self.check()?;
+        self.inner.read(offset, out)
+    }
+
+    fn set_len(&self, len: u64) -> Result<(), io::Error> {
+        self.check()?;
+        self.inner.set_len(len)
+    }
+
+    #[cfg(not(any(feature = "redb3", feature = "redb4")))]
+    fn sync_data(&self, eventual: bool) -> Result<(), io::Error> {
+        self.check()?;
+        self.transient()?;
+        self.inner.sync_data(eventual)
+    }
+
+    #[cfg(any(feature = "redb3", feature = "redb4"))]
+    fn sync_data(&self) -> Result<(), io::Error> {
+        self.check()?;
+        self.transient()?;
+        self.inner.sync_data()
+    }
+
+    fn write(&self, offset: u64, data: &[u8]) -> Result<(), io::Error> {
+        self.check()?;
+        self.transient()?;
+        let mut state = self.handle.state();
+        state.writes += 1;
+        let Some((_, mode)) = state.crash_at.filter(|&(at, _)| at == state.writes) else {
+            drop(state);
+            return self.inner.write(offset, data);
+        };
+        state.crashed = true;
+        drop(state);
+
+        let half = data.len() / 2;
+        match mode {
+            TearMode::Truncate => self.inner.write(offset, &data[..half])?,
+            TearMode::Corrupt => {
+                let mut torn = data.to_vec();
+                rand::rng().fill(&mut torn[half..]);
+                self.inner.write(offset, &torn)?;
+            }
+        }
+        Err(io::Error::other("simulated crash"))
+    }
+}
@@ -0,0 +1,170 @@
+//! Timing: a [`StorageBackend`] wrapper accumulating the calls made through it and the time
+//! spent in each kind, so a commit's latency can be split into I/O and in-process work.
+
+use crate::clock;
+use redb::StorageBackend;
+use std::io;
+use std::ops::{Add, Sub};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+#[derive(Debug, Default)]
+struct Counters {
+    reads: AtomicU64,
+    read_ns: AtomicU64,
+    writes: AtomicU64,
+    write_bytes: AtomicU64,
+    write_ns: AtomicU64,
+    syncs: AtomicU64,
+    sync_ns: AtomicU64,
+    resizes: AtomicU64,
+    resize_ns: AtomicU64,
+}
+
+/// Cumulative counts and times of the calls made through a [`TimedBackend`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct IoTimes {
+    pub reads: u64,
+    pub read: Duration,
+    pub writes: u64,
+    pub write_bytes: u64,
+    pub write: Duration,
+    pub syncs: u64,
+    pub sync: Duration,
+    pub resizes: u64,
+    pub resize: Duration,
+}
+
+impl IoTimes {
+    /// Time spent in the backend overall.
+    pub fn total(&self) -> Duration {
+        self.read + self.write + self.sync + self.resize
+    }
+}
+
+impl Add for IoTimes {
+    type Output = IoTimes;
+
+    fn add(self, other: IoTimes) -> IoTimes {
+        IoTimes {
+            reads: self.reads + other.reads,
+            read: self.read + other.read,
+            writes: self.writes + other.writes,
+            write_bytes: self.write_bytes + other.write_bytes,
+            write: self.write + other.write,
+            syncs: self.syncs + other.syncs,
+            sync: self.sync + other.sync,
+            resizes: self.resizes + other.resizes,
+            resize: self.resize + other.resize,
+        }
+    }
+}
+
+impl Sub for IoTimes {
+    type Output = IoTimes;
+
+    fn sub(self, earlier: IoTimes) -> IoTimes {
+        IoTimes {
+            reads: self.reads - earlier.reads,
+            read: self.read - earlier.read,
+            writes: self.writes - earlier.writes,
+            write_bytes: self.write_bytes - earlier.write_bytes,
+            write: self.write - earlier.write,
+            syncs: self.syncs - earlier.syncs,
+            sync: self.sync - earlier.sync,
+            resizes: self.resizes - earlier.resizes,
+            resize: self.resize - earlier.resize,
+        }
+    }
+}
+
+/// Shared with the phase, which reads the counters while redb owns the backend.
+#[derive(Clone, Debug, Default)]
+pub struct TimingHandle(Arc<Counters>);
+
+impl TimingHandle {
+    pub fn times(&self) -> IoTimes {
+        let c = &self.0;
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        let time = |counter: &AtomicU64| Duration::from_nanos(load(counter));
+        IoTimes {
+            reads: load(&c.reads),
+            read: time(&c.read_ns),
+            writes: load(&c.writes),
+            write_bytes: load(&c.write_bytes),
+            write: time(&c.write_ns),
+            syncs: load(&c.syncs),
+            sync: time(&c.sync_ns),
+            resizes: load(&c.resizes),
+            resize: time(&c.resize_ns),
+        }
+    }
+}
+
+fn timed<T>(count: &AtomicU64, ns: &AtomicU64, f: impl FnOnce() -> T) -> T {
+    let start = clock::now();
+    let result = f();
+    ns.fetch_add(start.elapsed().as_nanos() as u64, Ordering::Relaxed);
+    count.fetch_add(1, Ordering::Relaxed);
+    result
+}
+
+#[derive(Debug)]
+pub struct TimedBackend<B> {
+    inner: B,
+    handle: TimingHandle,
+}
+
+impl<B: StorageBackend> TimedBackend<B> {
+    pub fn new(inner: B) -> (Self, TimingHandle) {
+        let handle = TimingHandle::default();
+        let backend = Self {
+            inner,
+            handle: handle.clone(),
+        };
+        (backend, handle)
+    }
+}
+
+impl<B: StorageBackend> StorageBackend for TimedBackend<B> {
+    fn len(&self) -> Result<u64, io::Error> {
+        self.inner.len()
+    }
+
+    #[cfg(not(any(feature = "redb3", feature = "redb4")))]
+    fn read(&self, offset: u64, len: usize) -> Result<Vec<u8>, io::Error> {
+        let c = &self.handle.0;
+        timed(&c.reads, &c.read_ns, || self.inner.read(offset, len))
+    }
+
+    #[cfg(any(feature = "redb3", feature = "redb4"))]
+    fn read(&self, offset: u64, out: &mut [u8]) -> Result<(), io::Error> {
+        let c = &self.handle.0;
+        timed(&c.reads, &c.read_ns, || self.inner.read(offset, out))
+    }
+
+    fn set_len(&self, len: u64) -> Result<(), io::Error> {
+        let c = &self.handle.0;
+        timed(&c.resizes, &c.resize_ns, || self.inner.set_len(len))
+    }
+
+    #[cfg(not(any(feature = "redb3", feature = "redb4")))]
+    fn sync_data(&self, eventual: bool) -> Result<(), io::Error> {
+        let c = &self.handle.0;
+        timed(&c.syncs, &c.sync_ns, || self.inner.sync_data(eventual))
+    }
+
+    #[cfg(any(feature = "redb3", feature = "redb4"))]
+    fn sync_data(&self) -> Result<(), io::Error> {
+        let c = &self.handle.0;
+        timed(&c.syncs, &c.sync_ns, || self.inner.sync_data())
+    }
+
+    fn write(&self, offset: u64, data: &[u8]) -> Result<(), io::Error> {
+        let c = &self.handle.0;
+        c.write_bytes
+            .fetch_add(data.len() as u64, Ordering::Relaxed);
+        timed(&c.writes, &c.write_ns, || self.inner.write(offset, data))
+    }
+}
@@ -0,0 +1,177 @@
+//! Experimental io_uring backend. Writes are queued on the ring and submitted in batches
+//! without waiting, and a sync drains them behind an `IO_DRAIN` fdatasync, so the page
+//! writes of a commit are in flight together instead of one `pwrite` at a time. Reads,
+//! resizes and length queries wait for queued writes, then use plain syscalls.
+
+use io_uring::{IoUring, opcode, squeue, types};
+use redb::StorageBackend;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::unix::fs::FileExt;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
+/// Submission queue size, and the cap on operations in flight.
+const ENTRIES: u32 = 256;
+
+pub struct UringBackend {
+    file: File,
+    ring: Mutex<Ring>,
+}
+
+struct Ring {
+    ring: IoUring,
+    /// Buffers of queued operations by `user_data`; each must outlive its completion.
+    buffers: HashMap<u64, Vec<u8>>,
+    next_id: u64,
+    /// First failure among completed operations, returned by the next drain.
+    error: Option<io::Error>,
+}
+
+impl Ring {
+    fn push(&mut self, buffer: Vec<u8>, entry: squeue::Entry) -> io::Result<()> {
+        if self.buffers.len() >= ENTRIES as usize {
+            self.wait(1)?;
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        let entry = entry.user_data(id);
+        // Moving the Vec into the map keeps its heap buffer, and the pointer in `entry`, valid.
+        self.buffers.insert(id, buffer);
+        // SAFETY: the buffer `entry` points into stays in `self.buffers` until its
+        // completion is reaped.
+        while unsafe { self.ring.submission().push(&entry) }.is_err() {
+            self.ring.submit()?;
+        }
+        Ok(())
+    }
+
+    fn wait(&mut self, completions: usize) -> io::Result<()> {
+        self.ring.submit_and_wait(completions)?;
+        for cqe in self.ring.completion() {
+            let expected = self.buffers.remove(&cqe.user_data()).map_or(0, |b| b.len());
+            let result = cqe.result();
+            let error = if result < 0 {
+                Some(io::Error::from_raw_os_error(-result))
+            } else if expected > 0 && result as usize != expected {
+                Some(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    format!("short io_uring write: {result} of {expected} bytes"),
+                ))
+            } else {
+                None
+            };
+            if self.error.is_none() {
+                self.error = error;
+            }
+        }
+        Ok(())
+    }
+
+    /// Wait for every queued operation, returning the first failure among them.
+    fn drain(&mut self) -> io::Result<()> {
+        while !self.buffers.is_empty() {
+            self.wait(self.buffers.len())?;
+        }
+        self.error.take().map_or(Ok(()), Err)
+    }
+}
+
+impl UringBackend {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        let ring = Ring {
+            ring: IoUring::new(ENTRIES)?,
+            buffers: HashMap::new(),
+            next_id: 0,
+            error: None,
+        };
+        Ok(Self {
+            file,
+            ring: Mutex::new(ring),
+        })
+    }
+
+    fn ring(&self) -> MutexGuard<'_, Ring> {
+        self.ring.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn fd(&self) -> types::Fd {
+        types::Fd(self.file.as_raw_fd())
+    }
+
+    fn read_into(&self, offset: u64, out: &mut [u8]) -> io::Result<()> {
+        self.ring().drain()?;
+        self.file.read_exact_at(out, offset)
+    }
+
+    fn sync(&self) -> io::Result<()> {
+        let entry = opcode::Fsync::new(self.fd())
+            .flags(types::FsyncFlags::DATASYNC)
+            .build()
+            .flags(squeue::Flags::IO_DRAIN);
+        let mut ring = self.ring();
+        ring.push(Vec::new(), entry)?;
+        ring.drain()
+    }
+}
+
+impl fmt::Debug for UringBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UringBackend")
+            .field("file", &self.file)
+            .finish_non_exhaustive()
+    }
+}
+
+impl StorageBackend for UringBackend {
+    fn len(&self) -> Result<u64, io::Error> {
+        self.ring().drain()?;
+        Ok(self.file.metadata()?.len())
+    }
+
+    #[cfg(not(any(feature = "redb3", feature = "redb4")))]
+    fn read(&self, offset: u64, len: usize) -> Result<Vec<u8>, io::Error> {
+        let mut out = vec![0; len];
+        self.read_into(offset, &mut out)?;
+        Ok(out)
+    }
+
+    #[cfg(any(feature = "redb3", feature = "redb4"))]
+    fn read(&self, offset: u64, out: &mut [u8]) -> Result<(), io::Error> {
+        self.read_into(offset, out)
+    }
+
+    fn set_len(&self, len: u64) -> Result<(), io::Error> {
+        self.ring().drain()?;
+        self.file.set_len(len)
+    }
+
+    #[cfg(not(any(feature = "redb3", feature = "redb4")))]
+    fn sync_data(&self, _eventual: bool) -> Result<(), io::Error> {
+        self.sync()
+    }
+
+    #[cfg(any(feature = "redb3", feature = "redb4"))]
+    fn sync_data(&self) -> Result<(), io::Error> {
+        self.sync()
+    }
+
+    fn write(&self, offset: u64, data: &[u8]) -> Result<(), io::Error> {
+        let len = u32::try_from(data.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "write larger than 4 GiB"))?;
+        let buffer = data.to_vec();
+        let entry = opcode::Write::new(self.fd(), buffer.as_ptr(), len)
+            .offset(offset)
+            .build();
+        self.ring().push(buffer, entry)
+    }
+}
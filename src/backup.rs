@@ -0,0 +1,252 @@
+//! Online backups of each benchmark database while a writer keeps committing, since how
+//! often we can afford to back up depends on the durability strategy:
+//!
+//! - `snapshot`: one read transaction scanned in full into a new database file, with the
+//!   writer running throughout;
+//! - `file-copy`: the file copied as is while a write transaction holds the writer off, so
+//!   the copy holds the last durable commit but was not closed cleanly.
+//!
+//! Each backup is then restored by opening it with the repair callback installed, as a
+//! recovery would, and its entries are counted.
+
+use crate::config::MIB;
+use crate::payload::ValueGen;
+use crate::repair::RepairCallback;
+use crate::report::Section;
+use crate::{
+    BATCH_SIZE, BenchDb, BenchmarkStats, StatsAccumulator, TABLE, clock, compat, get_file_size,
+    hooks, record_op,
+};
+use redb::{Database, Error, ReadableTable, ReadableTableMetadata, TableDefinition};
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+use tracing::{info, info_span};
+
+/// Table the concurrent writer commits to, leaving the benchmark table to the backups.
+pub const WRITER_TABLE: TableDefinition<u64, &[u8]> = TableDefinition::new("benchmark_backup");
+/// Writer commits timed without a backup running, as the baseline.
+const BASELINE_WRITES: usize = 100;
+
+pub struct BackupRun {
+    pub method: &'static str,
+    pub elapsed: Duration,
+    /// Entries of the benchmark table in the restored backup.
+    pub entries: u64,
+    pub file_size: u64,
+    /// Opening the backup, including any repair.
+    pub restore: Duration,
+    pub restore_repaired: bool,
+    /// Commits of the concurrent writer while the backup ran.
+    pub writes: Option<BenchmarkStats>,
+}
+
+pub struct BackupReport {
+    pub source_size: u64,
+    /// The writer on its own.
+    pub baseline: BenchmarkStats,
+    pub runs: Vec<BackupRun>,
+}
+
+/// Commit single inserts to [`WRITER_TABLE`] until `stop` is set or `limit` commits are
+/// made, returning their latencies.
+fn write_until(
+    db: &Database,
+    bench_db: &BenchDb,
+    values: ValueGen,
+    stop: &AtomicBool,
+    first_key: u64,
+    limit: usize,
+) -> Result<StatsAccumulator, Error> {
+    let mut latencies = StatsAccumulator::new();
+    while latencies.count() < limit && !stop.load(Ordering::Relaxed) {
+        let key = first_key + latencies.count() as u64;
+        let value = values.generate(key);
+        let start = clock::now();
+        let mut write_txn = hooks::begin_write(db)?;
+        write_txn.set_quick_repair(bench_db.quick_repair);
+        {
+            let mut table = write_txn.open_table(WRITER_TABLE)?;
+            table.insert(key, value.as_slice())?;
+        }
+        write_txn.commit()?;
+        let duration = start.elapsed();
+        record_op(
+            "backup-write",
+            &bench_db.path,
+            bench_db.quick_repair,
+            key,
+            1,
+            value.len(),
+            duration,
+        );
+        latencies.record(duration);
+    }
+    Ok(latencies)
+}
+
+/// Copy the benchmark table of `db` into a new database at `dest` from one read transaction.
+fn snapshot_copy(db: &Database, dest: &str) -> Result<(), Error> {
+    let backup = Database::builder()
+        .set_cache_size(1024 * 1024 * 1024) // 1GB cache
+        .create(dest)?;
+    let read_txn = compat::begin_read(db)?;
+    let table = read_txn.open_table(TABLE)?;
+    let mut entries = table.iter()?.peekable();
+    while entries.peek().is_some() {
+        let write_txn = hooks::begin_write(&backup)?;
+        {
+            let mut backup_table = write_txn.open_table(TABLE)?;
+            for entry in entries.by_ref().take(BATCH_SIZE) {
+                let (key, value) = entry?;
+                backup_table.insert(key.value(), value.value())?;
+            }
+        }
+        write_txn.commit()?;
+    }
+    Ok(())
+}
+
+/// Copy the file of `db` to `dest` while a write transaction keeps other writers out.
+fn file_copy(db: &Database, source: &str, dest: &str) -> Result<(), Error> {
+    let write_txn = hooks::begin_write(db)?;
+    fs::copy(source, dest)?;
+    write_txn.abort()?;
+    Ok(())
+}
+
+/// Open the backup at `path` with `callback` installed and count its entries.
+fn restore(path: &str, callback: RepairCallback) -> Result<(Duration, bool, u64), Error> {
+    let mut builder = Database::builder();
+    builder.set_cache_size(1024 * 1024 * 1024); // 1GB cache
+    let probe = callback.install(&mut builder);
+    let start = clock::now();
+    let db = builder.create(path)?;
+    let elapsed = start.elapsed();
+    let read_txn = compat::begin_read(&db)?;
+    let entries = read_txn.open_table(TABLE)?.len()?;
+    Ok((elapsed, probe.repaired(), entries))
+}
+
+/// Back up `bench_db` with each method while a writer commits to it, then restore each
+/// backup with `callback` installed.
+pub fn benchmark_backup(
+    bench_db: &BenchDb,
+    values: ValueGen,
+    callback: RepairCallback,
+) -> Result<BackupReport, Error> {
+    let quick_repair = bench_db.quick_repair;
+    let _span = info_span!("backup", db_path = %bench_db.path, quick_repair).entered();
+    let source_size = get_file_size(&bench_db.path)?;
+    info!(size_mib = source_size / MIB, "Benchmarking online backups");
+
+    let db = bench_db.create(
+        Database::builder().set_cache_size(1024 * 1024 * 1024), // 1GB cache
+    )?;
+    let never = AtomicBool::new(false);
+    let baseline = write_until(&db, bench_db, values, &never, 0, BASELINE_WRITES)?;
+    let mut next_key = baseline.count() as u64;
+
+    let dest = format!("{}.backup", bench_db.path);
+    let mut runs = Vec::new();
+    for method in ["snapshot", "file-copy"] {
+        let _ = fs::remove_file(&dest);
+        let stop = AtomicBool::new(false);
+        let (elapsed, writes) = thread::scope(|scope| {
+            let writer =
+                scope.spawn(|| write_until(&db, bench_db, values, &stop, next_key, usize::MAX));
+            let start = clock::now();
+            let backed_up = match method {
+                "snapshot" => snapshot_copy(&db, &dest),
+                _ => file_copy(&db, &bench_db.path, &dest),
+            };
+            let elapsed = start.elapsed();
+            stop.store(true, Ordering::Relaxed);
+            let writes = writer.join().expect("backup writer panicked");
+            backed_up?;
+            Ok::<_, Error>((elapsed, writes?))
+        })?;
+        next_key += writes.count() as u64;
+
+        let file_size = get_file_size(&dest)?;
+        let outcome = restore(&dest, callback);
+        let _ = fs::remove_file(&dest);
+        let (restore, restore_repaired, entries) = outcome?;
+        info!(
+            method,
+            ?elapsed,
+            entries,
+            writes = writes.count(),
+            ?restore,
+            restore_repaired,
+            "Backed up"
+        );
+        record_op(
+            "backup",
+            &bench_db.path,
+            quick_repair,
+            runs.len() as u64,
+            entries as usize,
+            file_size as usize,
+            elapsed,
+        );
+        runs.push(BackupRun {
+            method,
+            elapsed,
+            entries,
+            file_size,
+            restore,
+            restore_repaired,
+            writes: (!writes.is_empty()).then(|| writes.stats()),
+        });
+    }
+
+    Ok(BackupReport {
+        source_size,
+        baseline: baseline.stats(),
+        runs,
+    })
+}
+
+impl BackupReport {
+    pub fn section(&self, title: impl Into<String>) -> Section {
+        let mut section = Section::new(title)
+            .field("Source size (MiB)", self.source_size as f64 / MIB as f64)
+            .field(
+                "Mean write latency, no backup",
+                self.baseline.avg_write_time,
+            )
+            .table([
+                "method",
+                "elapsed",
+                "MiB per second",
+                "backup size (MiB)",
+                "restore open",
+                "repaired",
+                "entries",
+                "writes during backup",
+                "mean write latency",
+                "max write latency",
+            ]);
+        for run in &self.runs {
+            let writes = run.writes.as_ref();
+            section = section.row(vec![
+                run.method.into(),
+                run.elapsed.into(),
+                (run.file_size as f64 / MIB as f64 / run.elapsed.as_secs_f64().max(f64::EPSILON))
+                    .into(),
+                (run.file_size as f64 / MIB as f64).into(),
+                run.restore.into(),
+                run.restore_repaired.to_string().into(),
+                run.entries.into(),
+                writes.map_or(0, |w| w.count).into(),
+                writes.map_or(Duration::ZERO, |w| w.avg_write_time).into(),
+                writes.map_or(Duration::ZERO, |w| w.max_write_time).into(),
+            ]);
+        }
+        section.note(
+            "A file copy is not closed cleanly, so restoring it runs the repair its quick_repair setting allows",
+        )
+    }
+}
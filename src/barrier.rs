@@ -0,0 +1,124 @@
+//! Production-style commit pattern: many cheap `Durability::Eventual` commits followed by a
+//! periodic durable barrier commit carrying the quick_repair setting.
+
+use crate::payload::ValueGen;
+use crate::report::Section;
+use crate::{BenchDb, BenchmarkStats, StatsAccumulator, TABLE, clock, compat, hooks, record_op};
+use redb::{Database, Durability, Error, WriteTransaction};
+use std::time::Duration;
+use tracing::{info, info_span};
+
+pub struct BarrierReport {
+    /// `Durability::Eventual` commits between barriers.
+    pub eventual: BenchmarkStats,
+    /// Durable commits issued every `interval` eventual commits.
+    pub barrier: BenchmarkStats,
+    pub interval: usize,
+}
+
+fn insert_one(write_txn: &WriteTransaction, key: u64, value: &[u8]) -> Result<(), Error> {
+    let mut table = write_txn.open_table(TABLE)?;
+    table.insert(key, value)?;
+    Ok(())
+}
+
+/// Commit one insert of `value` under `key`, durably with the quick_repair setting if
+/// `is_barrier` and `Durability::Eventual` otherwise, recording it as operation `op`.
+fn commit_one(
+    db: &Database,
+    bench_db: &BenchDb,
+    op: u64,
+    key: u64,
+    value: &[u8],
+    is_barrier: bool,
+) -> Result<Duration, Error> {
+    let quick_repair = bench_db.quick_repair;
+    let start = clock::now();
+    let mut write_txn = hooks::begin_write(db)?;
+    if is_barrier {
+        compat::set_durability(&mut write_txn, Durability::Immediate)?;
+        write_txn.set_quick_repair(quick_repair);
+    } else {
+        compat::set_durability(&mut write_txn, compat::RELAXED)?;
+    }
+    insert_one(&write_txn, key, value)?;
+    write_txn.commit()?;
+    let duration = start.elapsed();
+    let phase = if is_barrier { "barrier" } else { "eventual" };
+    let qr = is_barrier && quick_repair;
+    record_op(phase, &bench_db.path, qr, op, 1, value.len(), duration);
+    Ok(duration)
+}
+
+/// Perform `num_writes` single-insert transactions on `bench_db`, every `interval`-th of
+/// which is a durable barrier and the rest `Durability::Eventual`.
+pub fn benchmark_barrier(
+    bench_db: &mut BenchDb,
+    num_writes: usize,
+    interval: usize,
+    values: ValueGen,
+) -> Result<BarrierReport, Error> {
+    let quick_repair = bench_db.quick_repair;
+    let _span = info_span!("barrier", db_path = %bench_db.path, quick_repair).entered();
+    info!(
+        num_writes,
+        interval, "Benchmarking eventual commits with durable barriers"
+    );
+
+    let db = bench_db.create(
+        Database::builder().set_cache_size(1024 * 1024 * 1024), // 1GB cache
+    )?;
+
+    let mut eventual = StatsAccumulator::new();
+    let mut barrier = StatsAccumulator::new();
+    let start_key = bench_db.next_key;
+
+    for (i, key) in (start_key..).take(num_writes).enumerate() {
+        let value = values.generate(key);
+        let is_barrier = (i + 1).is_multiple_of(interval);
+        let duration = commit_one(&db, bench_db, i as u64, key, &value, is_barrier)?;
+        if is_barrier {
+            barrier.record(duration);
+        } else {
+            eventual.record(duration);
+        }
+    }
+    bench_db.next_key = start_key + num_writes as u64;
+
+    // Always end on a barrier so nothing is left only eventually durable.
+    if !num_writes.is_multiple_of(interval) {
+        let key = bench_db.next_key;
+        let value = values.generate(key);
+        barrier.record(commit_one(
+            &db,
+            bench_db,
+            num_writes as u64,
+            key,
+            &value,
+            true,
+        )?);
+        bench_db.next_key += 1;
+    }
+
+    Ok(BarrierReport {
+        eventual: eventual.stats(),
+        barrier: barrier.stats(),
+        interval,
+    })
+}
+
+impl BarrierReport {
+    pub fn sections(&self, label: &str) -> Vec<Section> {
+        let commits = (self.eventual.count + self.barrier.count) as u32;
+        let amortized = (self.eventual.total_duration + self.barrier.total_duration) / commits;
+        vec![
+            self.eventual.section(format!("Eventual Commits - {label}")),
+            self.barrier
+                .section(format!(
+                    "Durable Barriers (every {} commits) - {label}",
+                    self.interval
+                ))
+                .field("Amortized cost per commit", amortized),
+        ]
+    }
+}
@@ -0,0 +1,170 @@
+//! Bit rot: random bits flipped in a closed copy of each benchmark database, then what a
+//! normal open, normal reads and `check_integrity()` detect, to know the failure modes of
+//! each quick_repair history before relying on it.
+
+use crate::report::Section;
+use crate::{BenchDb, TABLE, compat};
+use rand::Rng;
+use redb::{Database, Error, ReadableTable};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use tracing::{info, info_span};
+
+/// Bits flipped per trial, each trial on a fresh copy.
+pub const FLIPS: [usize; 3] = [1, 16, 256];
+
+/// What reading every key of the damaged copy returned, compared with the original.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ScanResult {
+    pub read_errors: usize,
+    pub missing: usize,
+    /// Values read back without error but differing from the original: undetected damage.
+    pub changed: usize,
+}
+
+pub struct BitRotOutcome {
+    pub flips: usize,
+    pub open_error: Option<String>,
+    pub scan: Option<ScanResult>,
+    /// `Ok(true)` passed, `Ok(false)` failed but repaired, `Err` unrepairable.
+    pub integrity: Option<Result<bool, String>>,
+}
+
+pub struct BitRotReport {
+    pub outcomes: Vec<BitRotOutcome>,
+}
+
+fn flip_bits(path: &str, flips: usize) -> io::Result<()> {
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    let len = file.metadata()?.len();
+    let mut rng = rand::rng();
+    for _ in 0..flips {
+        let offset = rng.random_range(0..len);
+        let mut byte = [0u8];
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(&mut byte)?;
+        byte[0] ^= 1 << rng.random_range(0..8);
+        file.seek(SeekFrom::Start(offset))?;
+        file.write_all(&byte)?;
+    }
+    file.sync_all()
+}
+
+fn scan(original: &Database, damaged: &Database) -> Result<ScanResult, Error> {
+    let original_txn = compat::begin_read(original)?;
+    let original = original_txn.open_table(TABLE)?;
+    let damaged_txn = compat::begin_read(damaged)?;
+    let damaged = damaged_txn.open_table(TABLE)?;
+
+    let mut result = ScanResult::default();
+    for entry in original.iter()? {
+        let (key, value) = entry?;
+        match damaged.get(key.value()) {
+            Err(_) => result.read_errors += 1,
+            Ok(None) => result.missing += 1,
+            Ok(Some(found)) if found.value() != value.value() => result.changed += 1,
+            Ok(Some(_)) => {}
+        }
+    }
+    Ok(result)
+}
+
+/// Flip [`FLIPS`] random bits in copies of the (closed) `bench_db` and report what opening,
+/// reading every key and `check_integrity()` make of each copy.
+pub fn benchmark_bit_rot(bench_db: &BenchDb) -> Result<BitRotReport, Error> {
+    let _span =
+        info_span!("bit_rot", db_path = %bench_db.path, quick_repair = bench_db.quick_repair)
+            .entered();
+    let original = bench_db.create(
+        Database::builder().set_cache_size(1024 * 1024 * 1024), // 1GB cache
+    )?;
+
+    let copy = format!("{}.rot", bench_db.path);
+    let mut outcomes = Vec::with_capacity(FLIPS.len());
+    for flips in FLIPS {
+        fs::copy(&bench_db.path, &copy)?;
+        let outcome = trial(&original, &copy, flips);
+        let _ = fs::remove_file(&copy);
+        let outcome = outcome?;
+        info!(
+            flips,
+            open_error = outcome.open_error.as_deref(),
+            scan = ?outcome.scan,
+            integrity = ?outcome.integrity,
+            "Bit rot trial"
+        );
+        outcomes.push(outcome);
+    }
+    Ok(BitRotReport { outcomes })
+}
+
+fn trial(original: &Database, path: &str, flips: usize) -> Result<BitRotOutcome, Error> {
+    flip_bits(path, flips)?;
+
+    let mut damaged = match Database::builder().create(path) {
+        Ok(db) => db,
+        Err(e) => {
+            return Ok(BitRotOutcome {
+                flips,
+                open_error: Some(e.to_string()),
+                scan: None,
+                integrity: None,
+            });
+        }
+    };
+    let scan = match scan(original, &damaged) {
+        Ok(result) => Some(result),
+        // The table itself could not be opened or iterated: count it as one read error.
+        Err(_) => Some(ScanResult {
+            read_errors: 1,
+            ..ScanResult::default()
+        }),
+    };
+    let integrity = Some(damaged.check_integrity().map_err(|e| e.to_string()));
+
+    Ok(BitRotOutcome {
+        flips,
+        open_error: None,
+        scan,
+        integrity,
+    })
+}
+
+impl BitRotReport {
+    pub fn section(&self, title: impl Into<String>) -> Section {
+        let mut section = Section::new(title)
+            .table([
+                "bits flipped",
+                "open",
+                "read errors",
+                "missing keys",
+                "silently changed",
+                "check_integrity",
+            ])
+            .note(
+                "Silently changed values were read back without any error: damage that nothing detected",
+            );
+        for outcome in &self.outcomes {
+            let open = match &outcome.open_error {
+                Some(e) => format!("failed: {e}"),
+                None => "ok".to_owned(),
+            };
+            let scan = outcome.scan.unwrap_or_default();
+            let integrity = match &outcome.integrity {
+                None => "-".to_owned(),
+                Some(Ok(true)) => "passed".to_owned(),
+                Some(Ok(false)) => "failed, repaired".to_owned(),
+                Some(Err(e)) => format!("failed: {e}"),
+            };
+            section = section.row(vec![
+                outcome.flips.into(),
+                open.into(),
+                scan.read_errors.into(),
+                scan.missing.into(),
+                scan.changed.into(),
+                integrity.into(),
+            ]);
+        }
+        section
+    }
+}
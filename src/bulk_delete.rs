@@ -0,0 +1,147 @@
+//! Pruning large ranges of the oldest keys in a single transaction, with `Table::retain_in`
+//! and with a loop of ranged `remove` calls.
+
+use crate::report::Section;
+use crate::{BenchDb, TABLE, clock, compat, hooks, record_op};
+use redb::{Database, Error, ReadableTable, ReadableTableMetadata};
+use std::fmt;
+use std::ops::Range;
+use std::time::Duration;
+use tracing::{info, info_span};
+
+/// Fractions of the keys present at the start of the phase deleted per transaction.
+pub const FRACTIONS: [f64; 3] = [0.01, 0.05, 0.10];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeleteMethod {
+    /// `Table::retain_in(range, |_, _| false)`.
+    RetainIn,
+    /// `Table::remove` for every key in the range.
+    RemoveLoop,
+}
+
+impl fmt::Display for DeleteMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DeleteMethod::RetainIn => "retain_in",
+            DeleteMethod::RemoveLoop => "remove loop",
+        })
+    }
+}
+
+pub struct BulkDeleteSample {
+    pub method: DeleteMethod,
+    pub keys: u64,
+    pub latency: Duration,
+    /// Drop in allocated pages between the transactions before and after the deletion,
+    /// once the freed pages have been released.
+    pub freed_pages: i64,
+}
+
+pub struct BulkDeleteReport {
+    pub samples: Vec<BulkDeleteSample>,
+}
+
+fn allocated_pages(db: &Database) -> Result<u64, Error> {
+    let write_txn = hooks::begin_write(db)?;
+    let pages = write_txn.stats()?.allocated_pages();
+    write_txn.abort()?;
+    Ok(pages)
+}
+
+fn delete_range(
+    db: &Database,
+    keys: Range<u64>,
+    method: DeleteMethod,
+    quick_repair: bool,
+) -> Result<(), Error> {
+    let mut write_txn = hooks::begin_write(db)?;
+    write_txn.set_quick_repair(quick_repair);
+    {
+        let mut table = write_txn.open_table(TABLE)?;
+        match method {
+            DeleteMethod::RetainIn => table.retain_in(keys, |_, _| false)?,
+            DeleteMethod::RemoveLoop => {
+                for key in keys {
+                    table.remove(key)?;
+                }
+            }
+        }
+    }
+    write_txn.commit()?;
+    Ok(())
+}
+
+/// For each method and each entry of [`FRACTIONS`], delete the oldest remaining keys of
+/// `bench_db` in one transaction. The deleted keys are gone for any later phase.
+pub fn benchmark_bulk_delete(bench_db: &BenchDb) -> Result<BulkDeleteReport, Error> {
+    let _span =
+        info_span!("bulk_delete", db_path = %bench_db.path, quick_repair = bench_db.quick_repair)
+            .entered();
+
+    let db = bench_db.create(
+        Database::builder().set_cache_size(1024 * 1024 * 1024), // 1GB cache
+    )?;
+
+    let (mut low, total) = {
+        let read_txn = compat::begin_read(&db)?;
+        let table = read_txn.open_table(TABLE)?;
+        let low = table.first()?.map_or(0, |(key, _)| key.value());
+        (low, table.len()?)
+    };
+    info!(keys = total, "Deleting key ranges in single transactions");
+
+    let mut samples = Vec::new();
+    for method in [DeleteMethod::RetainIn, DeleteMethod::RemoveLoop] {
+        for fraction in FRACTIONS {
+            let keys = ((total as f64 * fraction) as u64).max(1);
+            let before = allocated_pages(&db)?;
+
+            let start = clock::now();
+            delete_range(&db, low..low + keys, method, bench_db.quick_repair)?;
+            let latency = start.elapsed();
+            record_op(
+                "bulk-delete",
+                &bench_db.path,
+                bench_db.quick_repair,
+                samples.len() as u64,
+                keys as usize,
+                0,
+                latency,
+            );
+
+            // Pages freed by a commit are only released by the next one.
+            let mut write_txn = hooks::begin_write(&db)?;
+            write_txn.set_quick_repair(bench_db.quick_repair);
+            write_txn.commit()?;
+            let freed_pages = before as i64 - allocated_pages(&db)? as i64;
+            info!(%method, keys, ?latency, freed_pages, "Deleted key range");
+            samples.push(BulkDeleteSample {
+                method,
+                keys,
+                latency,
+                freed_pages,
+            });
+            low += keys;
+        }
+    }
+
+    Ok(BulkDeleteReport { samples })
+}
+
+impl BulkDeleteReport {
+    pub fn section(&self, title: impl Into<String>) -> Section {
+        let mut section =
+            Section::new(title).table(["method", "keys", "latency", "keys/s", "freed pages"]);
+        for sample in &self.samples {
+            section = section.row(vec![
+                sample.method.to_string().into(),
+                sample.keys.into(),
+                sample.latency.into(),
+                (sample.keys as f64 / sample.latency.as_secs_f64()).into(),
+                sample.freed_pages.into(),
+            ]);
+        }
+        section
+    }
+}
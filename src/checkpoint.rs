@@ -0,0 +1,361 @@
+//! Checkpoint file recording the progress of the fill and write-benchmark phases, so that a
+//! crashed or interrupted multi-hour run can continue with `--resume-from` instead of
+//! refilling from scratch.
+//!
+//! The file is JSON, rewritten atomically (write to `<path>.tmp`, then rename) every
+//! `--checkpoint-every` operations and at the end of each fill and benchmark run. Only
+//! committed work is recorded: on resume, anything committed after the last checkpoint is
+//! simply overwritten.
+
+use crate::StatsAccumulator;
+use crate::config::Config;
+use crate::slo::OpSample;
+use serde_json::{Value, json};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Keys and value bytes inserted by the fill phase so far.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FillProgress {
+    pub keys: u64,
+    pub bytes: u64,
+}
+
+/// Progress of one benchmark database.
+#[derive(Clone, Debug, Default)]
+pub struct DbProgress {
+    pub fill: FillProgress,
+    pub fill_done: bool,
+    /// Next unused key once the recorded writes are committed.
+    pub next_key: u64,
+    /// Samples of each benchmark run (repeat) started so far, the last maybe partial; only
+    /// kept when the checkpoint is saved.
+    pub runs: Vec<Vec<OpSample>>,
+    /// Latencies of all the recorded writes, saved or not, for a partial report.
+    pub latencies: StatsAccumulator,
+}
+
+#[derive(Clone, Debug)]
+pub struct Checkpoint {
+    /// Run configuration the checkpoint belongs to; resuming with another one is refused.
+    pub config: String,
+    pub seed: u64,
+    /// Progress for quick_repair(false) and quick_repair(true).
+    pub dbs: [DbProgress; 2],
+}
+
+/// The parameters that must not change between a checkpointed run and its resumption.
+fn describe(config: &Config) -> String {
+    format!(
+        "fill {}, {} warmup + {} {} writes × {} repeats, shuffle {}, {} {}",
+        config.fill_target,
+        config.warmup.writes,
+        config.benchmark_writes,
+        config.keys,
+        config.repeats,
+        config.shuffle_order,
+        config.values,
+        config.values.codec,
+    )
+}
+
+impl Checkpoint {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            config: describe(config),
+            seed: config.seed,
+            dbs: Default::default(),
+        }
+    }
+
+    /// Fail unless the checkpoint was written for the same parameters as `config`.
+    pub fn check(&self, config: &Config) -> Result<(), String> {
+        let current = describe(config);
+        if self.config != current {
+            return Err(format!(
+                "checkpoint was written for `{}`, not `{current}`",
+                self.config
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let invalid = |what: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: invalid checkpoint ({what})", path.display()),
+            )
+        };
+        let document: Value = serde_json::from_slice(&fs::read(path)?)?;
+        let config = document["config"]
+            .as_str()
+            .ok_or_else(|| invalid("config"))?
+            .to_owned();
+        let seed = document["seed"].as_u64().ok_or_else(|| invalid("seed"))?;
+        let dbs = document["dbs"].as_array().ok_or_else(|| invalid("dbs"))?;
+        if dbs.len() != 2 {
+            return Err(invalid("dbs"));
+        }
+
+        let mut checkpoint = Self {
+            config,
+            seed,
+            dbs: Default::default(),
+        };
+        for (progress, db) in checkpoint.dbs.iter_mut().zip(dbs) {
+            progress.fill = FillProgress {
+                keys: db["fill_keys"]
+                    .as_u64()
+                    .ok_or_else(|| invalid("fill_keys"))?,
+                bytes: db["fill_bytes"]
+                    .as_u64()
+                    .ok_or_else(|| invalid("fill_bytes"))?,
+            };
+            progress.fill_done = db["fill_done"]
+                .as_bool()
+                .ok_or_else(|| invalid("fill_done"))?;
+            progress.next_key = db["next_key"].as_u64().ok_or_else(|| invalid("next_key"))?;
+            for run in db["runs"].as_array().ok_or_else(|| invalid("runs"))? {
+                let run = run.as_array().ok_or_else(|| invalid("runs"))?;
+                let samples = run
+                    .iter()
+                    .enumerate()
+                    .map(|(index, sample)| {
+                        // Each sample is `[unix_ms, latency_ns]`.
+                        match (sample[0].as_u64(), sample[1].as_u64()) {
+                            (Some(unix_ms), Some(latency_ns)) => Ok(OpSample {
+                                index,
+                                unix_ms,
+                                latency: Duration::from_nanos(latency_ns),
+                            }),
+                            _ => Err(invalid("sample")),
+                        }
+                    })
+                    .collect::<io::Result<Vec<_>>>()?;
+                progress
+                    .latencies
+                    .extend(samples.iter().map(|sample| sample.latency));
+                progress.runs.push(samples);
+            }
+        }
+        Ok(checkpoint)
+    }
+
+    fn to_json(&self) -> Value {
+        let dbs: Vec<Value> = self
+            .dbs
+            .iter()
+            .map(|db| {
+                let runs: Vec<Vec<[u64; 2]>> = db
+                    .runs
+                    .iter()
+                    .map(|run| {
+                        run.iter()
+                            .map(|s| [s.unix_ms, s.latency.as_nanos() as u64])
+                            .collect()
+                    })
+                    .collect();
+                json!({
+                    "fill_keys": db.fill.keys,
+                    "fill_bytes": db.fill.bytes,
+                    "fill_done": db.fill_done,
+                    "next_key": db.next_key,
+                    "runs": runs,
+                })
+            })
+            .collect();
+        json!({
+            "config": self.config,
+            "seed": self.seed,
+            "dbs": dbs,
+        })
+    }
+
+    /// Replace the file at `path` with this checkpoint, atomically.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+        fs::write(&tmp, serde_json::to_vec(&self.to_json())?)?;
+        fs::File::open(&tmp)?.sync_all()?;
+        fs::rename(&tmp, path)
+    }
+}
+
+/// Saves a [`Checkpoint`] every `every` recorded operations. Saving errors are logged, not
+/// returned, so a full disk for the checkpoint never aborts the run itself.
+pub struct Checkpointer {
+    path: Option<PathBuf>,
+    every: usize,
+    pending: usize,
+    pub state: Checkpoint,
+}
+
+impl Checkpointer {
+    /// Checkpoint `state` to `path`; with no path, progress is tracked but never saved.
+    pub fn new(path: Option<PathBuf>, every: usize, state: Checkpoint) -> Self {
+        Self {
+            path,
+            every: every.max(1),
+            pending: 0,
+            state,
+        }
+    }
+
+    /// Whether progress is saved to a file at all.
+    pub fn saves(&self) -> bool {
+        self.path.is_some()
+    }
+
+    pub fn save(&mut self) {
+        self.pending = 0;
+        if let Some(path) = &self.path {
+            match self.state.save(path) {
+                Ok(()) => info!(path = %path.display(), "Saved checkpoint"),
+                Err(e) => warn!(path = %path.display(), error = %e, "Could not save checkpoint"),
+            }
+        }
+    }
+
+    fn tick(&mut self) {
+        self.pending += 1;
+        if self.pending >= self.every {
+            self.save();
+        }
+    }
+
+    /// Record a committed fill batch of database `db`.
+    pub fn fill_batch(&mut self, db: usize, progress: FillProgress) {
+        self.state.dbs[db].fill = progress;
+        self.tick();
+    }
+
+    pub fn fill_done(&mut self, db: usize, next_key: u64) {
+        let progress = &mut self.state.dbs[db];
+        progress.fill_done = true;
+        progress.next_key = next_key;
+        self.save();
+    }
+
+    /// Record a committed timed write of benchmark run `run` of database `db`; the sample
+    /// is only kept when progress is saved.
+    pub fn write(&mut self, db: usize, run: usize, sample: OpSample, next_key: u64) {
+        let saves = self.saves();
+        let progress = &mut self.state.dbs[db];
+        progress.latencies.record(sample.latency);
+        if saves {
+            if progress.runs.len() <= run {
+                progress.runs.resize_with(run + 1, Vec::new);
+            }
+            progress.runs[run].push(sample);
+        }
+        progress.next_key = next_key;
+        self.tick();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(test: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("checkpoint-{}-{test}.json", std::process::id()))
+    }
+
+    fn sample(unix_ms: u64, latency_ns: u64) -> OpSample {
+        OpSample {
+            index: 0,
+            unix_ms,
+            latency: Duration::from_nanos(latency_ns),
+        }
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let config = Config {
+            seed: 42,
+            ..Config::default()
+        };
+        let mut checkpoint = Checkpoint::new(&config);
+        checkpoint.dbs[1] = DbProgress {
+            fill: FillProgress {
+                keys: 1_000,
+                bytes: 64_000,
+            },
+            fill_done: true,
+            next_key: 1_003,
+            runs: vec![
+                vec![sample(10, 1_500), sample(11, 2_500)],
+                vec![sample(20, 900)],
+            ],
+            ..DbProgress::default()
+        };
+
+        let path = temp_path("round-trip");
+        fs::write(&path, b"stale").unwrap();
+        checkpoint.save(&path).unwrap();
+        let mut tmp = path.clone().into_os_string();
+        tmp.push(".tmp");
+        assert!(!Path::new(&tmp).exists(), "temporary file renamed away");
+
+        let loaded = Checkpoint::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.config, checkpoint.config);
+        assert_eq!(loaded.seed, 42);
+        assert!(!loaded.dbs[0].fill_done);
+        assert!(loaded.dbs[0].runs.is_empty());
+
+        let db = &loaded.dbs[1];
+        assert_eq!(db.fill, checkpoint.dbs[1].fill);
+        assert!(db.fill_done);
+        assert_eq!(db.next_key, 1_003);
+        let runs: Vec<Vec<(usize, u64, u64)>> = db
+            .runs
+            .iter()
+            .map(|run| {
+                run.iter()
+                    .map(|s| (s.index, s.unix_ms, s.latency.as_nanos() as u64))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            runs,
+            [vec![(0, 10, 1_500), (1, 11, 2_500)], vec![(0, 20, 900)]]
+        );
+        assert_eq!(db.latencies.count(), 3);
+        loaded.check(&config).unwrap();
+    }
+
+    #[test]
+    fn check_rejects_other_parameters() {
+        let config = Config::default();
+        let checkpoint = Checkpoint::new(&config);
+        let other = Config {
+            benchmark_writes: config.benchmark_writes + 1,
+            ..Config::default()
+        };
+        let error = checkpoint.check(&other).unwrap_err();
+        assert!(error.starts_with("checkpoint was written for"), "{error}");
+
+        // The seed is restored from the checkpoint, not compared.
+        let reseeded = Config {
+            seed: config.seed + 1,
+            ..Config::default()
+        };
+        checkpoint.check(&reseeded).unwrap();
+    }
+
+    #[test]
+    fn load_rejects_malformed_checkpoints() {
+        let path = temp_path("malformed");
+        fs::write(&path, br#"{"config": "fill 1 MiB", "dbs": []}"#).unwrap();
+        let error = Checkpoint::load(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains("(seed)"), "{error}");
+    }
+}
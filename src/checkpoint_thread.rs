@@ -0,0 +1,198 @@
+//! Periodic durable checkpoints from a background thread: the foreground makes cheap
+//! non-durable single-insert commits while a checkpoint thread issues an empty durable
+//! commit carrying the quick_repair setting every interval, making everything before it
+//! durable. This is the middle ground between durable commits and eventual commits with
+//! barriers in the commit stream (see [`crate::barrier`]).
+//!
+//! redb has one writer, so a checkpoint delays the foreground commits queued behind it; the
+//! same commits are also timed without the thread as the baseline.
+
+use crate::payload::ValueGen;
+use crate::report::Section;
+use crate::{BenchDb, BenchmarkStats, StatsAccumulator, TABLE, clock, compat, hooks, record_op};
+use redb::{Database, Durability, Error};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{info, info_span};
+
+/// The checkpointed run lasts at least this many intervals, however few writes it makes.
+const MIN_CHECKPOINTS: usize = 3;
+
+pub struct CheckpointReport {
+    pub interval: Duration,
+    /// Foreground commits without the checkpoint thread.
+    pub baseline: BenchmarkStats,
+    /// Foreground commits while the checkpoint thread runs.
+    pub foreground: BenchmarkStats,
+    /// Checkpoint commits, from asking for the writer to the commit returning.
+    pub checkpoints: BenchmarkStats,
+    /// Mean time a checkpoint waited for the foreground to release the writer.
+    pub checkpoint_wait: Duration,
+}
+
+/// Make one non-durable single-insert commit of `key`, returning its latency.
+fn commit_eventual(
+    db: &Database,
+    bench_db: &BenchDb,
+    key: u64,
+    values: ValueGen,
+) -> Result<Duration, Error> {
+    let value = values.generate(key);
+    let start = clock::now();
+    let mut write_txn = hooks::begin_write(db)?;
+    compat::set_durability(&mut write_txn, compat::RELAXED)?;
+    {
+        let mut table = write_txn.open_table(TABLE)?;
+        table.insert(key, value.as_slice())?;
+    }
+    write_txn.commit()?;
+    let duration = start.elapsed();
+    record_op(
+        "checkpoint-foreground",
+        &bench_db.path,
+        false,
+        key,
+        1,
+        value.len(),
+        duration,
+    );
+    Ok(duration)
+}
+
+/// Make an empty durable commit, returning how long it waited for the writer and its
+/// total latency.
+fn checkpoint(db: &Database, quick_repair: bool) -> Result<(Duration, Duration), Error> {
+    let start = clock::now();
+    let mut write_txn = hooks::begin_write(db)?;
+    let wait = start.elapsed();
+    compat::set_durability(&mut write_txn, Durability::Immediate)?;
+    write_txn.set_quick_repair(quick_repair);
+    write_txn.commit()?;
+    Ok((wait, start.elapsed()))
+}
+
+/// Checkpoint `db` every `interval` until `stop` disconnects, counting them in `done`.
+/// Returns the total time the checkpoints waited for the writer and their latencies.
+fn run_checkpoints(
+    db: &Database,
+    bench_db: &BenchDb,
+    interval: Duration,
+    stop: mpsc::Receiver<()>,
+    done: &AtomicUsize,
+) -> Result<(Duration, StatsAccumulator), Error> {
+    let (mut waits, mut latencies) = (Duration::ZERO, StatsAccumulator::new());
+    let start = Instant::now();
+    loop {
+        let next = start + interval * (latencies.count() as u32 + 1);
+        match stop.recv_timeout(next.saturating_duration_since(Instant::now())) {
+            Err(RecvTimeoutError::Timeout) => {}
+            Ok(()) | Err(RecvTimeoutError::Disconnected) => return Ok((waits, latencies)),
+        }
+        let (wait, latency) = checkpoint(db, bench_db.quick_repair)?;
+        record_op(
+            "checkpoint",
+            &bench_db.path,
+            bench_db.quick_repair,
+            latencies.count() as u64,
+            0,
+            0,
+            latency,
+        );
+        waits += wait;
+        latencies.record(latency);
+        done.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Make `num_writes` non-durable single-insert commits to `bench_db`, then as many again
+/// (for at least [`MIN_CHECKPOINTS`] intervals) with a thread checkpointing every
+/// `interval`.
+pub fn benchmark_checkpoint_thread(
+    bench_db: &mut BenchDb,
+    num_writes: usize,
+    interval: Duration,
+    values: ValueGen,
+) -> Result<CheckpointReport, Error> {
+    let quick_repair = bench_db.quick_repair;
+    let _span = info_span!("checkpoint_thread", db_path = %bench_db.path, quick_repair).entered();
+    info!(
+        num_writes,
+        ?interval,
+        "Benchmarking eventual commits with a checkpoint thread"
+    );
+
+    let num_writes = num_writes.max(1);
+    let db = bench_db.create(
+        Database::builder().set_cache_size(1024 * 1024 * 1024), // 1GB cache
+    )?;
+    let mut next_key = bench_db.next_key;
+    let mut baseline = StatsAccumulator::new();
+    for _ in 0..num_writes {
+        baseline.record(commit_eventual(&db, bench_db, next_key, values)?);
+        next_key += 1;
+    }
+
+    let done = AtomicUsize::new(0);
+    let (stop, stopped) = mpsc::channel();
+    let bench: &BenchDb = bench_db;
+    let (foreground, (waits, checkpoints)) = thread::scope(|scope| {
+        let checkpointer = scope.spawn(|| run_checkpoints(&db, bench, interval, stopped, &done));
+        let written = (|| {
+            let mut foreground = StatsAccumulator::new();
+            while foreground.count() < num_writes || done.load(Ordering::Relaxed) < MIN_CHECKPOINTS
+            {
+                foreground.record(commit_eventual(&db, bench, next_key, values)?);
+                next_key += 1;
+            }
+            Ok::<_, Error>(foreground)
+        })();
+        drop(stop);
+        let checkpoints = checkpointer.join().expect("checkpoint thread panicked");
+        Ok::<_, Error>((written?, checkpoints?))
+    })?;
+    bench_db.next_key = next_key;
+
+    // End on a checkpoint so nothing is left only eventually durable.
+    checkpoint(&db, quick_repair)?;
+
+    let checkpoint_wait = waits / checkpoints.count().max(1) as u32;
+    Ok(CheckpointReport {
+        interval,
+        baseline: baseline.stats(),
+        foreground: foreground.stats(),
+        checkpoints: checkpoints.stats(),
+        checkpoint_wait,
+    })
+}
+
+impl CheckpointReport {
+    pub fn section(&self, title: impl Into<String>) -> Section {
+        let slowdown = self.foreground.avg_write_time.as_secs_f64()
+            / self.baseline.avg_write_time.as_secs_f64().max(f64::EPSILON);
+        Section::new(title)
+            .field("Checkpoint interval", self.interval)
+            .field("Checkpoints", self.checkpoints.count)
+            .field("Mean checkpoint commit", self.checkpoints.avg_write_time)
+            .field("Max checkpoint commit", self.checkpoints.max_write_time)
+            .field("Mean checkpoint wait for writer", self.checkpoint_wait)
+            .field("Foreground slowdown", slowdown)
+            .table(["foreground", "commits", "mean", "trimmed mean", "max"])
+            .row(vec![
+                "without checkpoints".into(),
+                self.baseline.count.into(),
+                self.baseline.avg_write_time.into(),
+                self.baseline.trimmed_mean.into(),
+                self.baseline.max_write_time.into(),
+            ])
+            .row(vec![
+                "with checkpoints".into(),
+                self.foreground.count.into(),
+                self.foreground.avg_write_time.into(),
+                self.foreground.trimmed_mean.into(),
+                self.foreground.max_write_time.into(),
+            ])
+            .note("A foreground commit queued behind a checkpoint waits for its fsync, which shows up in the max")
+    }
+}
@@ -0,0 +1,194 @@
+//! Clock used to time operations, and a startup calibration of its read overhead and
+//! resolution, since small-value operations take only a few microseconds and would
+//! otherwise be timed to within noise of the clock itself.
+//!
+//! `--clock tsc` reads the x86 time stamp counter directly instead of going through
+//! `Instant::now()` (a vDSO `clock_gettime` call), converting ticks with a frequency
+//! measured against `Instant` at startup. It needs an invariant TSC, i.e. one ticking at a
+//! constant rate in sync across cores and sleep states.
+
+use crate::report::Section;
+use std::fmt;
+use std::hint::black_box;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// Back-to-back reads timed to calibrate the clock overhead.
+const CALIBRATION_READS: u32 = 100_000;
+/// Wall time over which the TSC frequency is measured.
+const TSC_CALIBRATION: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ClockSource {
+    #[default]
+    Instant,
+    Tsc,
+}
+
+impl FromStr for ClockSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "instant" => Ok(ClockSource::Instant),
+            "tsc" => Ok(ClockSource::Tsc),
+            _ => Err(format!("unknown clock `{s}`, expected `instant` or `tsc`")),
+        }
+    }
+}
+
+impl fmt::Display for ClockSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ClockSource::Instant => "instant",
+            ClockSource::Tsc => "tsc",
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ClockCalibration {
+    pub source: ClockSource,
+    /// Mean cost of one clock read.
+    pub overhead: Duration,
+    /// Smallest non-zero step observed between consecutive reads.
+    pub resolution: Duration,
+    /// Measured TSC frequency, for [`ClockSource::Tsc`].
+    pub tsc_hz: Option<f64>,
+}
+
+static CALIBRATION: OnceLock<ClockCalibration> = OnceLock::new();
+
+/// A point in time read from the selected clock.
+#[derive(Clone, Copy, Debug)]
+pub enum Stamp {
+    Instant(Instant),
+    Tsc(u64),
+}
+
+/// Read the clock selected by [`select`], or `Instant` if none was.
+#[inline]
+pub fn now() -> Stamp {
+    match CALIBRATION.get().and_then(|c| c.tsc_hz) {
+        Some(_) => Stamp::Tsc(tsc::read()),
+        None => Stamp::Instant(Instant::now()),
+    }
+}
+
+impl Stamp {
+    #[inline]
+    pub fn elapsed(self) -> Duration {
+        match self {
+            Stamp::Instant(start) => start.elapsed(),
+            Stamp::Tsc(start) => {
+                let hz = CALIBRATION.get().and_then(|c| c.tsc_hz).unwrap_or(1.0);
+                Duration::from_secs_f64(tsc::read().saturating_sub(start) as f64 / hz)
+            }
+        }
+    }
+}
+
+/// Calibrate `source` and use it for all later [`now`] calls.
+pub fn select(source: ClockSource) -> Result<ClockCalibration, String> {
+    let calibration = match source {
+        ClockSource::Instant => calibrate_instant(),
+        ClockSource::Tsc => calibrate_tsc()?,
+    };
+    info!(
+        %source,
+        overhead = ?calibration.overhead,
+        resolution = ?calibration.resolution,
+        tsc_hz = calibration.tsc_hz,
+        "Calibrated clock"
+    );
+    let _ = CALIBRATION.set(calibration);
+    Ok(calibration)
+}
+
+/// The calibration of the selected clock, if [`select`] was called.
+pub fn calibration() -> Option<&'static ClockCalibration> {
+    CALIBRATION.get()
+}
+
+fn calibrate_instant() -> ClockCalibration {
+    let start = Instant::now();
+    let mut resolution = Duration::MAX;
+    let mut last = start;
+    for _ in 0..CALIBRATION_READS {
+        let now = black_box(Instant::now());
+        let step = now - last;
+        if !step.is_zero() {
+            resolution = resolution.min(step);
+        }
+        last = now;
+    }
+    ClockCalibration {
+        source: ClockSource::Instant,
+        overhead: start.elapsed() / CALIBRATION_READS,
+        resolution,
+        tsc_hz: None,
+    }
+}
+
+fn calibrate_tsc() -> Result<ClockCalibration, String> {
+    if !crate::platform::invariant_tsc() {
+        return Err(
+            "--clock tsc needs an x86_64 CPU with an invariant TSC (constant_tsc and nonstop_tsc)"
+                .into(),
+        );
+    }
+    let (start, start_ticks) = (Instant::now(), tsc::read());
+    thread::sleep(TSC_CALIBRATION);
+    let (elapsed, ticks) = (start.elapsed(), tsc::read() - start_ticks);
+    let hz = ticks as f64 / elapsed.as_secs_f64();
+
+    let start = tsc::read();
+    let mut resolution = u64::MAX;
+    let mut last = start;
+    for _ in 0..CALIBRATION_READS {
+        let now = black_box(tsc::read());
+        if now > last {
+            resolution = resolution.min(now - last);
+        }
+        last = now;
+    }
+    let to_duration = |ticks: u64| Duration::from_secs_f64(ticks as f64 / hz);
+    Ok(ClockCalibration {
+        source: ClockSource::Tsc,
+        overhead: to_duration(last - start) / CALIBRATION_READS,
+        resolution: to_duration(resolution),
+        tsc_hz: Some(hz),
+    })
+}
+
+#[cfg(target_arch = "x86_64")]
+mod tsc {
+    #[inline]
+    pub fn read() -> u64 {
+        // SAFETY: RDTSC is available on every x86_64 CPU.
+        unsafe { std::arch::x86_64::_rdtsc() }
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+mod tsc {
+    pub fn read() -> u64 {
+        unreachable!("the TSC clock is never selected off x86_64")
+    }
+}
+
+impl ClockCalibration {
+    pub fn section(&self, title: impl Into<String>) -> Section {
+        let mut section = Section::new(title)
+            .field("Source", self.source.to_string())
+            .field("Read overhead", self.overhead)
+            .field("Resolution", self.resolution);
+        if let Some(hz) = self.tsc_hz {
+            section = section.field("TSC frequency (MHz)", hz / 1e6);
+        }
+        section
+    }
+}
@@ -0,0 +1,215 @@
+//! The restart path of our service as one scenario: open the database, read a burst of
+//! random existing keys, then make a burst of single-insert commits, timed end to end and
+//! per step, with the file evicted from the page cache (a cold restart) and left cached.
+//! The reads draw their keys with `--read-pattern`.
+//!
+//! Each benchmark database carries the history of its quick_repair setting, which the
+//! writes keep. Starting the process itself is not included; it costs the same whatever
+//! the setting.
+
+use crate::config::ReadPattern;
+use crate::payload::ValueGen;
+use crate::platform::evict;
+use crate::repair::RepairCallback;
+use crate::report::Section;
+use crate::{BenchDb, BenchmarkStats, StatsAccumulator, TABLE, clock, compat, hooks, record_op};
+use redb::{Database, Error};
+use std::hint::black_box;
+use std::path::Path;
+use std::time::Duration;
+use tracing::{info, info_span, warn};
+
+/// Restarts timed per cache state.
+pub const ROUNDS: usize = 3;
+/// Default keys read after the open.
+pub const DEFAULT_READS: usize = 1000;
+/// Default commits after the reads.
+pub const DEFAULT_WRITES: usize = 100;
+
+/// Restarts with one cache state.
+pub struct Restarts {
+    pub open: BenchmarkStats,
+    pub reads: BenchmarkStats,
+    pub writes: BenchmarkStats,
+    pub total: BenchmarkStats,
+}
+
+pub struct ColdStartReport {
+    pub reads: usize,
+    pub read_pattern: ReadPattern,
+    pub writes: usize,
+    pub cold: Restarts,
+    pub warm: Restarts,
+    /// Whether the file could be evicted from the page cache before the cold restarts.
+    pub evicted: bool,
+}
+
+/// Open `bench_db`, read `reads` keys drawn with `pattern` and make `writes` commits,
+/// returning the time each step took.
+fn restart(
+    bench_db: &mut BenchDb,
+    reads: usize,
+    pattern: ReadPattern,
+    writes: usize,
+    values: ValueGen,
+    callback: RepairCallback,
+) -> Result<[Duration; 3], Error> {
+    let mut builder = Database::builder();
+    builder.set_cache_size(1024 * 1024 * 1024); // 1GB cache
+    callback.install(&mut builder);
+    let start = clock::now();
+    let db = bench_db.create(&mut builder)?;
+    let open = start.elapsed();
+
+    let mut rng = rand::rng();
+    let start = clock::now();
+    {
+        let read_txn = compat::begin_read(&db)?;
+        let table = read_txn.open_table(TABLE)?;
+        for _ in 0..reads {
+            let key = pattern.key(&mut rng, bench_db.next_key);
+            black_box(table.get(key)?.map(|v| v.value().len()));
+        }
+    }
+    let read_time = start.elapsed();
+
+    let start = clock::now();
+    for _ in 0..writes {
+        let value = values.generate(bench_db.next_key);
+        let commit_start = clock::now();
+        let mut write_txn = hooks::begin_write(&db)?;
+        write_txn.set_quick_repair(bench_db.quick_repair);
+        {
+            let mut table = write_txn.open_table(TABLE)?;
+            table.insert(bench_db.next_key, value.as_slice())?;
+        }
+        write_txn.commit()?;
+        record_op(
+            "cold-start",
+            &bench_db.path,
+            bench_db.quick_repair,
+            bench_db.next_key,
+            1,
+            value.len(),
+            commit_start.elapsed(),
+        );
+        bench_db.next_key += 1;
+    }
+    Ok([open, read_time, start.elapsed()])
+}
+
+fn restarts(
+    bench_db: &mut BenchDb,
+    cold: bool,
+    evicted: &mut bool,
+    (reads, writes): (usize, usize),
+    pattern: ReadPattern,
+    values: ValueGen,
+    callback: RepairCallback,
+) -> Result<Restarts, Error> {
+    let mut steps: [StatsAccumulator; 4] = Default::default();
+    for _ in 0..ROUNDS {
+        if cold {
+            *evicted &= evict(Path::new(&bench_db.path))?;
+        }
+        let times = restart(bench_db, reads, pattern, writes, values, callback)?;
+        for (step, time) in steps.iter_mut().zip(times) {
+            step.record(time);
+        }
+        steps[3].record(times.iter().sum());
+    }
+    let [open, reads, writes, total] = steps.map(|times| times.stats());
+    Ok(Restarts {
+        open,
+        reads,
+        writes,
+        total,
+    })
+}
+
+/// Restart `bench_db` [`ROUNDS`] times with a cold and with a warm page cache, reading
+/// `reads` keys drawn with `read_pattern` and making `writes` commits after each open.
+pub fn benchmark_cold_start(
+    bench_db: &mut BenchDb,
+    reads: usize,
+    read_pattern: ReadPattern,
+    writes: usize,
+    values: ValueGen,
+    callback: RepairCallback,
+) -> Result<ColdStartReport, Error> {
+    let quick_repair = bench_db.quick_repair;
+    let _span = info_span!("cold_start", db_path = %bench_db.path, quick_repair).entered();
+    info!(rounds = ROUNDS, reads, %read_pattern, writes, "Benchmarking restarts");
+
+    let mut evicted = true;
+    let bursts = (reads, writes);
+    let cold = restarts(
+        bench_db,
+        true,
+        &mut evicted,
+        bursts,
+        read_pattern,
+        values,
+        callback,
+    )?;
+    if !evicted {
+        warn!("Could not evict the database from the page cache; cold restarts ran warm");
+    }
+    let warm = restarts(
+        bench_db,
+        false,
+        &mut evicted,
+        bursts,
+        read_pattern,
+        values,
+        callback,
+    )?;
+    info!(
+        cold = ?cold.total.avg_write_time,
+        warm = ?warm.total.avg_write_time,
+        "Mean restart"
+    );
+
+    Ok(ColdStartReport {
+        reads,
+        read_pattern,
+        writes,
+        cold,
+        warm,
+        evicted,
+    })
+}
+
+impl ColdStartReport {
+    pub fn section(&self, title: impl Into<String>) -> Section {
+        let mut section = Section::new(title)
+            .field("Restarts per cache state", ROUNDS)
+            .field("Reads after open", self.reads)
+            .field("Read keys", self.read_pattern.to_string())
+            .field("Commits after reads", self.writes)
+            .table([
+                "page cache",
+                "mean open",
+                "mean reads",
+                "mean writes",
+                "mean total",
+                "max total",
+            ]);
+        for (state, restarts) in [("cold", &self.cold), ("warm", &self.warm)] {
+            section = section.row(vec![
+                state.into(),
+                restarts.open.avg_write_time.into(),
+                restarts.reads.avg_write_time.into(),
+                restarts.writes.avg_write_time.into(),
+                restarts.total.avg_write_time.into(),
+                restarts.total.max_write_time.into(),
+            ]);
+        }
+        if self.evicted {
+            section
+        } else {
+            section
+                .note("The file could not be evicted from the page cache; cold restarts ran warm")
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! Attribution of single-write commit latency to its parts, so the quick_repair(true)
+//! penalty is explained rather than just measured.
+//!
+//! redb exposes no internal commit timings, so the split is taken at the storage boundary:
+//! the database is opened through a [`TimedBackend`] (syncing with `fdatasync`, like redb's
+//! own backend on Linux) and each commit's time in backend writes, syncs, reads and resizes
+//! is subtracted from its latency. The rest is in-process work: page allocation, tree
+//! updates, checksums and, with quick_repair, serializing the allocator state.
+
+use crate::backend::{IoTimes, SyncBackend, SyncStrategy, TimedBackend};
+use crate::payload::ValueGen;
+use crate::report::Section;
+use crate::{BenchDb, record_op, write_single};
+use redb::{Database, Error};
+use std::path::Path;
+use std::time::Duration;
+use tracing::{info, info_span};
+
+/// Means per commit over the phase.
+#[derive(Clone, Copy, Debug)]
+pub struct CommitBreakdown {
+    pub commits: usize,
+    pub latency: Duration,
+    pub io: IoTimes,
+}
+
+impl CommitBreakdown {
+    /// Commit time not spent in the backend.
+    pub fn in_process(&self) -> Duration {
+        self.latency.saturating_sub(self.io.total())
+    }
+
+    /// `(component, mean time per commit)`, summing to the mean latency.
+    fn components(&self) -> [(&'static str, Duration); 5] {
+        [
+            (
+                "in-process (allocation, tree, checksums)",
+                self.in_process(),
+            ),
+            ("write", self.io.write),
+            ("sync", self.io.sync),
+            ("read", self.io.read),
+            ("resize", self.io.resize),
+        ]
+    }
+
+    fn per_commit(&self, count: u64) -> f64 {
+        count as f64 / self.commits.max(1) as f64
+    }
+}
+
+/// Time `num_writes` single-insert transactions on `bench_db` through a [`TimedBackend`],
+/// attributing their latency.
+pub fn benchmark_commit_breakdown(
+    bench_db: &mut BenchDb,
+    num_writes: usize,
+    values: ValueGen,
+) -> Result<CommitBreakdown, Error> {
+    let quick_repair = bench_db.quick_repair;
+    let _span = info_span!("commit_breakdown", db_path = %bench_db.path, quick_repair).entered();
+    info!(num_writes, "Attributing commit latency");
+
+    let (backend, handle) = TimedBackend::new(SyncBackend::open(
+        Path::new(&bench_db.path),
+        SyncStrategy::Fdatasync,
+    )?);
+    let db = Database::builder()
+        .set_cache_size(1024 * 1024 * 1024) // 1GB cache
+        .create_with_backend(backend)?;
+
+    let start = handle.times();
+    let mut latency = Duration::ZERO;
+    for i in 0..num_writes {
+        let value = values.generate(bench_db.next_key);
+        let duration = write_single(&db, bench_db.next_key, &value, quick_repair)?;
+        bench_db.next_key += 1;
+        latency += duration;
+        record_op(
+            "commit-breakdown",
+            &bench_db.path,
+            quick_repair,
+            i as u64,
+            1,
+            value.len(),
+            duration,
+        );
+    }
+    let total = handle.times() - start;
+
+    let n = num_writes.max(1) as u32;
+    let breakdown = CommitBreakdown {
+        commits: num_writes,
+        latency: latency / n,
+        io: IoTimes {
+            read: total.read / n,
+            write: total.write / n,
+            sync: total.sync / n,
+            resize: total.resize / n,
+            ..total
+        },
+    };
+    info!(
+        latency = ?breakdown.latency,
+        in_process = ?breakdown.in_process(),
+        write = ?breakdown.io.write,
+        sync = ?breakdown.io.sync,
+        "Commit breakdown done"
+    );
+    Ok(breakdown)
+}
+
+fn share(part: Duration, whole: Duration) -> f64 {
+    100.0 * part.as_secs_f64() / whole.as_secs_f64().max(f64::EPSILON)
+}
+
+impl CommitBreakdown {
+    pub fn section(&self, title: impl Into<String>) -> Section {
+        let mut section = Section::new(title)
+            .field("Commits", self.commits)
+            .field("Mean commit latency", self.latency)
+            .field("Writes per commit", self.per_commit(self.io.writes))
+            .field(
+                "Bytes written per commit",
+                self.per_commit(self.io.write_bytes),
+            )
+            .field("Syncs per commit", self.per_commit(self.io.syncs))
+            .field("Reads per commit", self.per_commit(self.io.reads))
+            .table(["component", "mean per commit", "share (%)"]);
+        for (name, time) in self.components() {
+            section = section.row(vec![
+                name.into(),
+                time.into(),
+                share(time, self.latency).into(),
+            ]);
+        }
+        section
+    }
+}
+
+/// Side-by-side breakdowns of quick_repair(false) and (true), splitting the latency delta.
+pub fn comparison_section(
+    title: impl Into<String>,
+    without: &CommitBreakdown,
+    with: &CommitBreakdown,
+) -> Section {
+    let delta = with.latency.as_secs_f64() - without.latency.as_secs_f64();
+    let mut section = Section::new(title)
+        .field("Latency delta (μs per commit)", delta * 1e6)
+        .field(
+            "Extra bytes written per commit",
+            with.per_commit(with.io.write_bytes) - without.per_commit(without.io.write_bytes),
+        )
+        .table([
+            "component",
+            "quick_repair(false)",
+            "quick_repair(true)",
+            "delta (μs)",
+            "share of delta (%)",
+        ]);
+    for ((name, a), (_, b)) in without.components().into_iter().zip(with.components()) {
+        let part = b.as_secs_f64() - a.as_secs_f64();
+        let share = if delta.abs() > f64::EPSILON {
+            100.0 * part / delta
+        } else {
+            0.0
+        };
+        section = section.row(vec![
+            name.into(),
+            a.into(),
+            b.into(),
+            (part * 1e6).into(),
+            share.into(),
+        ]);
+    }
+    section.note(
+        "I/O is timed at the storage backend; in-process is the remainder of each commit's latency",
+    )
+}
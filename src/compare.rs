@@ -0,0 +1,211 @@
+//! `report --compare`: the fields of several JSON reports (`--report json:PATH`) from prior
+//! runs aligned into one table per section, with each run's relative delta to the first,
+//! so runs on different machines, redb versions or settings can be compared directly.
+//!
+//! Sections and fields are matched by their keys. Durations are integer nanoseconds in the
+//! JSON reports and are compared as such; section tables are not compared.
+
+use crate::report::{Report, Section, Value};
+use serde_json::Value as Json;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// One prior run's report.
+pub struct RunReport {
+    /// File stem, naming the run's column.
+    pub label: String,
+    document: Json,
+}
+
+impl RunReport {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let document: Json = serde_json::from_str(&fs::read_to_string(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if !document["sections"].is_array() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} is not a JSON report", path.display()),
+            ));
+        }
+        Ok(Self {
+            label: path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+            document,
+        })
+    }
+
+    fn sections(&self) -> impl Iterator<Item = &Json> {
+        self.document["sections"].as_array().into_iter().flatten()
+    }
+
+    fn section(&self, key: &str) -> Option<&Json> {
+        self.sections().find(|section| section["key"] == key)
+    }
+
+    fn field(&self, key: &str, field: &str) -> Option<&Json> {
+        self.section(key)?["fields"].get(field)
+    }
+}
+
+/// `+12.3%`, relative to `base`.
+fn delta(value: f64, base: f64) -> Value {
+    if base == 0.0 {
+        return "-".into();
+    }
+    format!("{:+.1}%", 100.0 * (value - base) / base).into()
+}
+
+fn cell(value: Option<&Json>) -> Value {
+    match value {
+        Some(Json::Number(n)) => match n.as_i64() {
+            Some(i) => i.into(),
+            None => n.as_f64().unwrap_or_default().into(),
+        },
+        Some(Json::String(s)) => s.as_str().into(),
+        Some(other) => other.to_string().into(),
+        None => "-".into(),
+    }
+}
+
+/// One section per section key found in any run, in order of first appearance, with a row
+/// per field.
+pub fn compare(runs: &[RunReport]) -> Report {
+    let mut report = Report::new("RESULTS COMPARISON");
+    let Some(base) = runs.first() else {
+        return report;
+    };
+
+    let mut keys: Vec<(&str, &str)> = Vec::new();
+    for run in runs {
+        for section in run.sections() {
+            let key = section["key"].as_str().unwrap_or_default();
+            if !keys.iter().any(|(k, _)| *k == key) {
+                keys.push((key, section["title"].as_str().unwrap_or(key)));
+            }
+        }
+    }
+
+    let mut columns = vec!["metric".to_owned(), base.label.clone()];
+    for run in &runs[1..] {
+        columns.push(run.label.clone());
+        columns.push(format!("{} vs {}", run.label, base.label));
+    }
+
+    for (key, title) in keys {
+        let mut fields: Vec<&str> = Vec::new();
+        for run in runs {
+            let run_fields = run.section(key).and_then(|s| s["fields"].as_object());
+            for field in run_fields.into_iter().flat_map(|f| f.keys()) {
+                if !fields.contains(&field.as_str()) {
+                    fields.push(field);
+                }
+            }
+        }
+        if fields.is_empty() {
+            continue;
+        }
+
+        let mut section = Section::new(title).table(columns.iter().cloned());
+        for field in fields {
+            let base_value = base.field(key, field);
+            let mut row = vec![field.into(), cell(base_value)];
+            for run in &runs[1..] {
+                let run_value = run.field(key, field);
+                row.push(cell(run_value));
+                row.push(
+                    match (
+                        base_value.and_then(Json::as_f64),
+                        run_value.and_then(Json::as_f64),
+                    ) {
+                        (Some(base), Some(value)) => delta(value, base),
+                        _ => "".into(),
+                    },
+                );
+            }
+            section = section.row(row);
+        }
+        report.push(section);
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn run(label: &str, document: Json) -> RunReport {
+        RunReport {
+            label: label.to_owned(),
+            document,
+        }
+    }
+
+    fn rows(section: &Section) -> Vec<Vec<String>> {
+        let table = section.table.as_ref().expect("comparison table");
+        table
+            .rows
+            .iter()
+            .map(|row| row.iter().map(ToString::to_string).collect())
+            .collect()
+    }
+
+    #[test]
+    fn aligns_fields_across_runs_with_disjoint_sections() {
+        let before = run(
+            "before",
+            json!({"sections": [
+                {"key": "writes", "title": "Writes", "fields": {"count": 100, "mean_ns": 2000}},
+            ]}),
+        );
+        let after = run(
+            "after",
+            json!({"sections": [
+                {"key": "writes", "title": "Writes", "fields": {"mean_ns": 3000, "p99_ms": 1.5}},
+                {"key": "fill", "title": "Fill", "fields": {"rate": 10}},
+            ]}),
+        );
+        let report = compare(&[before, after]);
+
+        let titles: Vec<_> = report.sections.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles, ["Writes", "Fill"]);
+        let columns = &report.sections[0].table.as_ref().unwrap().columns;
+        assert_eq!(columns, &["metric", "before", "after", "after vs before"]);
+        assert_eq!(
+            rows(&report.sections[0]),
+            [
+                ["count", "100", "-", ""],
+                ["mean_ns", "2000", "3000", "+50.0%"],
+                ["p99_ms", "-", "1.50", ""],
+            ]
+        );
+        assert_eq!(rows(&report.sections[1]), [["rate", "-", "10", ""]]);
+    }
+
+    #[test]
+    fn delta_is_relative_to_a_nonzero_base() {
+        assert_eq!(delta(150.0, 100.0).to_string(), "+50.0%");
+        assert_eq!(delta(75.0, 100.0).to_string(), "-25.0%");
+        assert_eq!(delta(5.0, 0.0).to_string(), "-");
+        assert_eq!(delta(0.0, 0.0).to_string(), "-");
+    }
+
+    #[test]
+    fn cells_keep_integers_exact() {
+        assert!(matches!(
+            cell(Some(&json!(123_456_789_012i64))),
+            Value::Int(123_456_789_012)
+        ));
+        assert!(matches!(cell(Some(&json!(-3))), Value::Int(-3)));
+        assert!(matches!(cell(Some(&json!(2.5))), Value::Float(x) if x == 2.5));
+        // Beyond i64, and so only comparable as a float.
+        assert!(matches!(cell(Some(&json!(u64::MAX))), Value::Float(_)));
+        assert_eq!(cell(Some(&json!("lz4"))).to_string(), "lz4");
+        assert_eq!(cell(Some(&json!(true))).to_string(), "true");
+        assert_eq!(cell(None).to_string(), "-");
+    }
+}
@@ -0,0 +1,57 @@
+//! The few API differences between the redb releases selectable with the `redb*` features,
+//! so the phases can be written once against whichever one is [`crate::redb`].
+
+use redb::{
+    Builder, Database, DatabaseError, Durability, Error, ReadTransaction, TransactionError,
+    WriteTransaction,
+};
+use std::path::Path;
+
+/// Cheapest durability that still writes the commit out: `Eventual` before redb 3, which
+/// replaced it with `None` (persisted by the next durable commit).
+#[cfg(not(any(feature = "redb3", feature = "redb4")))]
+pub const RELAXED: Durability = Durability::Eventual;
+#[cfg(any(feature = "redb3", feature = "redb4"))]
+pub const RELAXED: Durability = Durability::None;
+
+/// `Database::begin_read`, which moved to the `ReadableDatabase` trait in redb 3.
+pub fn begin_read(db: &Database) -> Result<ReadTransaction, TransactionError> {
+    #[cfg(any(feature = "redb3", feature = "redb4"))]
+    use redb::ReadableDatabase;
+    db.begin_read()
+}
+
+/// `WriteTransaction::set_durability`, which became fallible in redb 3.
+pub fn set_durability(txn: &mut WriteTransaction, durability: Durability) -> Result<(), Error> {
+    #[cfg(not(any(feature = "redb3", feature = "redb4")))]
+    txn.set_durability(durability);
+    #[cfg(any(feature = "redb3", feature = "redb4"))]
+    txn.set_durability(durability)?;
+    Ok(())
+}
+
+/// Whether [`open_read_only`] really opens read-only: redb 2 has no read-only mode.
+pub const READ_ONLY_OPEN: bool = cfg!(any(feature = "redb3", feature = "redb4"));
+
+#[cfg(not(any(feature = "redb3", feature = "redb4")))]
+pub type ReadOnlyDatabase = Database;
+#[cfg(any(feature = "redb3", feature = "redb4"))]
+pub use redb::ReadOnlyDatabase;
+
+/// `Builder::open_read_only`, added in redb 3; a regular (writable) `Builder::open` before.
+pub fn open_read_only(
+    builder: &Builder,
+    path: impl AsRef<Path>,
+) -> Result<ReadOnlyDatabase, DatabaseError> {
+    #[cfg(not(any(feature = "redb3", feature = "redb4")))]
+    return builder.open(path);
+    #[cfg(any(feature = "redb3", feature = "redb4"))]
+    builder.open_read_only(path)
+}
+
+/// [`begin_read`] on a database opened with [`open_read_only`].
+pub fn begin_read_only(db: &ReadOnlyDatabase) -> Result<ReadTransaction, TransactionError> {
+    #[cfg(any(feature = "redb3", feature = "redb4"))]
+    use redb::ReadableDatabase;
+    db.begin_read()
+}
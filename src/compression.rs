@@ -0,0 +1,84 @@
+//! Optional value compression applied before insert and undone on read.
+//!
+//! Codecs are behind the `lz4` and `zstd` cargo features (`compression` enables both).
+
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+/// zstd level; favours speed since compression sits on the write path.
+#[cfg(feature = "zstd")]
+const ZSTD_LEVEL: i32 = 3;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Codec {
+    #[default]
+    None,
+    Lz4,
+    Zstd,
+}
+
+impl Codec {
+    /// Whether this binary was built with support for the codec.
+    pub fn available(self) -> bool {
+        match self {
+            Codec::None => true,
+            Codec::Lz4 => cfg!(feature = "lz4"),
+            Codec::Zstd => cfg!(feature = "zstd"),
+        }
+    }
+
+    pub fn compress(self, data: Vec<u8>) -> Vec<u8> {
+        match self {
+            Codec::None => data,
+            #[cfg(feature = "lz4")]
+            Codec::Lz4 => lz4_flex::compress_prepend_size(&data),
+            #[cfg(feature = "zstd")]
+            Codec::Zstd => {
+                zstd::bulk::compress(&data, ZSTD_LEVEL).expect("zstd compression failed")
+            }
+            #[allow(unreachable_patterns)]
+            _ => panic!("codec {self} not compiled in"),
+        }
+    }
+
+    pub fn decompress(self, data: &[u8], size: usize) -> io::Result<Vec<u8>> {
+        match self {
+            Codec::None => Ok(data.to_vec()),
+            #[cfg(feature = "lz4")]
+            Codec::Lz4 => lz4_flex::decompress_size_prepended(data).map_err(io::Error::other),
+            #[cfg(feature = "zstd")]
+            Codec::Zstd => zstd::bulk::decompress(data, size),
+            #[allow(unreachable_patterns)]
+            _ => {
+                let _ = size;
+                Err(io::Error::other(format!("codec {self} not compiled in")))
+            }
+        }
+    }
+}
+
+impl FromStr for Codec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Codec::None),
+            "lz4" => Ok(Codec::Lz4),
+            "zstd" => Ok(Codec::Zstd),
+            _ => Err(format!(
+                "unknown codec `{s}`, expected `none`, `lz4` or `zstd`"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Codec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Codec::None => "none",
+            Codec::Lz4 => "lz4",
+            Codec::Zstd => "zstd",
+        })
+    }
+}
@@ -0,0 +1,604 @@
+//! Run parameters, resolved from presets and command-line overrides.
+
+use crate::payload::{ValueGen, ValuePattern};
+use crate::repair::RepairCallback;
+use crate::warmup::Warmup;
+use crate::{BATCH_SIZE, BENCHMARK_WRITES, FILL_BATCH_BYTES, VALUE_SIZE};
+use rand::Rng;
+use std::fmt;
+use std::str::FromStr;
+
+pub const MIB: u64 = 1024 * 1024;
+pub const GIB: u64 = 1024 * MIB;
+
+/// When the fill phase stops inserting.
+#[derive(Clone, Copy, Debug)]
+pub enum FillTarget {
+    /// Stop once this many bytes of values have been inserted.
+    Bytes(u64),
+    /// Stop once this many keys have been inserted.
+    Entries(u64),
+}
+
+impl FillTarget {
+    /// How many more entries may be inserted after `entries` entries totalling `bytes`.
+    pub fn remaining_entries(self, bytes: u64, entries: u64, value_size: usize) -> u64 {
+        match self {
+            FillTarget::Bytes(target) => target.saturating_sub(bytes).div_ceil(value_size as u64),
+            FillTarget::Entries(target) => target.saturating_sub(entries),
+        }
+    }
+
+    /// Share of the target reached after `entries` entries totalling `bytes`.
+    pub fn fraction_done(self, bytes: u64, entries: u64) -> f64 {
+        match self {
+            FillTarget::Bytes(target) => bytes as f64 / target.max(1) as f64,
+            FillTarget::Entries(target) => entries as f64 / target.max(1) as f64,
+        }
+    }
+}
+
+impl fmt::Display for FillTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FillTarget::Bytes(bytes) => write!(f, "{} MiB", bytes / MIB),
+            FillTarget::Entries(entries) => write!(f, "{entries} entries"),
+        }
+    }
+}
+
+/// Keys written by the timed single writes.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum KeyPattern {
+    /// Fresh keys appended after the existing ones.
+    #[default]
+    Sequential,
+    /// Overwrites: `fraction` of the writes hit one of the first `hot_keys` keys, the rest a
+    /// key drawn uniformly from the whole existing key space, like counter updates
+    /// dirtying the same leaf pages every commit.
+    Hotspot { fraction: f64, hot_keys: u64 },
+    /// A `ratio` of the writes overwrite a key drawn uniformly from the existing key space,
+    /// freeing the pages of the old values; the rest insert fresh keys.
+    Overwrite { ratio: f64 },
+}
+
+impl KeyPattern {
+    /// Existing key to overwrite out of the `existing` keys `0..existing`, or `None` to
+    /// insert a fresh key.
+    pub fn overwrite(self, rng: &mut impl Rng, existing: u64) -> Option<u64> {
+        match self {
+            KeyPattern::Sequential => None,
+            _ if existing == 0 => None,
+            KeyPattern::Hotspot { fraction, hot_keys } => Some(if rng.random_bool(fraction) {
+                rng.random_range(0..hot_keys.clamp(1, existing))
+            } else {
+                rng.random_range(0..existing)
+            }),
+            KeyPattern::Overwrite { ratio } => rng
+                .random_bool(ratio)
+                .then(|| rng.random_range(0..existing)),
+        }
+    }
+}
+
+impl fmt::Display for KeyPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyPattern::Sequential => f.write_str("sequential"),
+            KeyPattern::Hotspot { fraction, hot_keys } => write!(
+                f,
+                "hotspot ({:.0}% on {hot_keys} hot keys, rest uniform)",
+                fraction * 100.0
+            ),
+            KeyPattern::Overwrite { ratio } => write!(
+                f,
+                "{:.0}% overwrites of existing keys, rest fresh",
+                ratio * 100.0
+            ),
+        }
+    }
+}
+
+/// Keys looked up by the phases reading existing keys (mix reads and cold-start reads),
+/// selectable with `--read-pattern`. Keys are inserted in ascending order, so the highest
+/// ones are the newest.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ReadPattern {
+    /// Drawn uniformly from the existing keys.
+    #[default]
+    Uniform,
+    /// Biased towards the newest keys: the newest fraction `p` of the keys gets a share
+    /// `p^(1/skew)` of the reads, so skew 1 is uniform and skew 4 sends 56% of the reads to
+    /// the newest 10% of the keys, like services mostly reading fresh data.
+    Latest { skew: f64 },
+}
+
+/// Skew of `latest` without an explicit one.
+pub const DEFAULT_RECENCY_SKEW: f64 = 4.0;
+
+impl ReadPattern {
+    /// Key to read out of the `existing` keys `0..existing`.
+    pub fn key(self, rng: &mut impl Rng, existing: u64) -> u64 {
+        let existing = existing.max(1);
+        match self {
+            ReadPattern::Uniform => rng.random_range(0..existing),
+            ReadPattern::Latest { skew } => {
+                let age = (rng.random::<f64>().powf(skew) * existing as f64) as u64;
+                existing - 1 - age.min(existing - 1)
+            }
+        }
+    }
+}
+
+impl FromStr for ReadPattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let skew = match s.split_once(':') {
+            None if s == "uniform" => return Ok(ReadPattern::Uniform),
+            None if s == "latest" => DEFAULT_RECENCY_SKEW,
+            Some(("latest", skew)) => skew
+                .parse()
+                .map_err(|_| format!("invalid recency skew `{skew}` in `{s}`"))?,
+            _ => {
+                return Err(format!(
+                    "unknown read pattern `{s}`, expected `uniform`, `latest` or `latest:SKEW`"
+                ));
+            }
+        };
+        if !skew.is_finite() || skew < 1.0 {
+            return Err(format!("recency skew must be at least 1, got `{s}`"));
+        }
+        Ok(ReadPattern::Latest { skew })
+    }
+}
+
+impl fmt::Display for ReadPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadPattern::Uniform => f.write_str("uniform"),
+            ReadPattern::Latest { skew } => write!(
+                f,
+                "latest (skew {skew}, {:.0}% on the newest 10% of keys)",
+                0.1f64.powf(1.0 / skew) * 100.0
+            ),
+        }
+    }
+}
+
+/// Number of inserts per transaction of the batch phase, selectable with `--batch-size`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BatchSize {
+    Fixed(usize),
+    /// Drawn uniformly from `min..=max`.
+    Uniform {
+        min: usize,
+        max: usize,
+    },
+    /// Drawn from a lognormal distribution with this mean and log-space standard deviation,
+    /// a few huge commits among many small ones, as production commit sizes tend to be.
+    LogNormal {
+        mean: f64,
+        sigma: f64,
+    },
+}
+
+/// Log-space standard deviation of `lognormal:MEAN` without an explicit one.
+const DEFAULT_SIGMA: f64 = 1.0;
+
+impl BatchSize {
+    /// Draw one transaction's insert count, at least 1.
+    pub fn sample(self, rng: &mut impl Rng) -> usize {
+        match self {
+            BatchSize::Fixed(n) => n.max(1),
+            BatchSize::Uniform { min, max } => rng.random_range(min..=max).max(1),
+            BatchSize::LogNormal { mean, sigma } => {
+                // Box-Muller, then shift the location so the distribution's mean is `mean`.
+                let (u1, u2): (f64, f64) = (1.0 - rng.random::<f64>(), rng.random());
+                let z = (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos();
+                let mu = mean.ln() - sigma * sigma / 2.0;
+                ((mu + sigma * z).exp().round() as usize).max(1)
+            }
+        }
+    }
+}
+
+impl FromStr for BatchSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "invalid batch size `{s}`, expected `N`, `uniform:MIN-MAX` or \
+                 `lognormal:MEAN[,SIGMA]`"
+            )
+        };
+        let batch_size = match s.split_once(':') {
+            None => BatchSize::Fixed(s.parse().map_err(|_| invalid())?),
+            Some(("uniform", range)) => {
+                let (min, max) = range.split_once('-').ok_or_else(invalid)?;
+                let (min, max) = (
+                    min.parse().map_err(|_| invalid())?,
+                    max.parse().map_err(|_| invalid())?,
+                );
+                if min > max {
+                    return Err(invalid());
+                }
+                BatchSize::Uniform { min, max }
+            }
+            Some(("lognormal", params)) => {
+                let (mean, sigma) = match params.split_once(',') {
+                    Some((mean, sigma)) => (mean, sigma.parse().map_err(|_| invalid())?),
+                    None => (params, DEFAULT_SIGMA),
+                };
+                let mean: f64 = mean.parse().map_err(|_| invalid())?;
+                if !(mean >= 1.0 && sigma >= 0.0) {
+                    return Err(invalid());
+                }
+                BatchSize::LogNormal { mean, sigma }
+            }
+            Some(_) => return Err(invalid()),
+        };
+        Ok(batch_size)
+    }
+}
+
+impl fmt::Display for BatchSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BatchSize::Fixed(n) => write!(f, "{n}"),
+            BatchSize::Uniform { min, max } => write!(f, "uniform {min}-{max}"),
+            BatchSize::LogNormal { mean, sigma } => write!(f, "lognormal mean {mean}, σ {sigma}"),
+        }
+    }
+}
+
+/// Whether the benchmark databases are removed when the process exits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CleanupPolicy {
+    Never,
+    OnSuccess,
+    Always,
+}
+
+impl CleanupPolicy {
+    pub fn should_remove(self, succeeded: bool) -> bool {
+        match self {
+            CleanupPolicy::Never => false,
+            CleanupPolicy::OnSuccess => succeeded,
+            CleanupPolicy::Always => true,
+        }
+    }
+}
+
+impl FromStr for CleanupPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "never" => Ok(CleanupPolicy::Never),
+            "on-success" => Ok(CleanupPolicy::OnSuccess),
+            "always" => Ok(CleanupPolicy::Always),
+            _ => Err(format!(
+                "unknown cleanup policy `{s}`, expected `never`, `on-success` or `always`"
+            )),
+        }
+    }
+}
+
+/// Shape of the data inserted, selectable with `--workload`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Workload {
+    /// Fixed 4 KiB values.
+    Default,
+    /// 1-16 MiB values spanning many pages per entry, like stored blocks.
+    LargeBlob,
+    /// 64-128 byte values and hundreds of millions of keys, dominated by tree depth
+    /// and branch-page churn.
+    Tiny,
+    /// Timed writes overwriting existing keys, most of them in a small hot range.
+    Hotspot,
+}
+
+impl Workload {
+    /// Apply the workload's value shape and defaults on top of `config`.
+    pub fn apply(self, config: &mut Config) {
+        match self {
+            Workload::Default => {}
+            Workload::LargeBlob => {
+                config.values.min_size = MIB as usize;
+                config.values.max_size = 16 * MIB as usize;
+                config.benchmark_writes = config.benchmark_writes.min(LARGE_BLOB_WRITES);
+            }
+            Workload::Tiny => {
+                config.values.min_size = 64;
+                config.values.max_size = 128;
+                config.fill_target = FillTarget::Entries(TINY_ENTRIES);
+                config.fill_batch_entries = TINY_FILL_BATCH;
+            }
+            Workload::Hotspot => {
+                config.keys = KeyPattern::Hotspot {
+                    fraction: HOT_FRACTION,
+                    hot_keys: HOT_KEYS,
+                };
+            }
+        }
+    }
+}
+
+/// Timed writes for the large-blob workload; each one commits ~8 MiB on average.
+const LARGE_BLOB_WRITES: usize = 100;
+/// Keys inserted by the tiny workload's fill phase.
+const TINY_ENTRIES: u64 = 300_000_000;
+/// Entries per fill transaction for the tiny workload, keeping the commit count manageable.
+const TINY_FILL_BATCH: usize = 100_000;
+/// Default share of the hotspot workload's writes on the hot keys.
+pub const HOT_FRACTION: f64 = 0.8;
+/// Default size of the hotspot workload's hot key range.
+pub const HOT_KEYS: u64 = 1000;
+
+impl FromStr for Workload {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "default" => Ok(Workload::Default),
+            "large-blob" => Ok(Workload::LargeBlob),
+            "tiny" => Ok(Workload::Tiny),
+            "hotspot" => Ok(Workload::Hotspot),
+            _ => Err(format!(
+                "unknown workload `{s}`, expected `default`, `large-blob`, `tiny` or `hotspot`"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Workload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Workload::Default => "default",
+            Workload::LargeBlob => "large-blob",
+            Workload::Tiny => "tiny",
+            Workload::Hotspot => "hotspot",
+        })
+    }
+}
+
+/// Parameters shared by all phases of a run.
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// How much data to insert into each database during the fill phase.
+    pub fill_target: FillTarget,
+    /// Maximum entries per fill transaction.
+    pub fill_batch_entries: usize,
+    /// Maximum value bytes per fill transaction.
+    pub fill_batch_bytes: u64,
+    /// Stat the database file for its size in the fill progress logs and metrics.
+    pub fill_stat: bool,
+    /// Fill the two databases from one thread each instead of one after the other.
+    pub fill_concurrent: bool,
+    /// Values inserted by every phase.
+    pub values: ValueGen,
+    /// Background threads generating fill values ahead of the writer; 0 generates inline.
+    pub generator_threads: usize,
+    /// Number of single-insert transactions timed per configuration.
+    pub benchmark_writes: usize,
+    /// Keys written by those transactions.
+    pub keys: KeyPattern,
+    /// Keys read by the mix and cold-start phases.
+    pub reads: ReadPattern,
+    /// Keep the latency of one in this many timed writes; see [`crate::set_sample_every`].
+    pub sample_every: u64,
+    /// Callback installed wherever a database may need repairing when opened.
+    pub repair_callback: RepairCallback,
+    /// Unmeasured writes performed before each timed configuration.
+    pub warmup: Warmup,
+    /// How many times each configuration is benchmarked.
+    pub repeats: usize,
+    /// Randomize the order of configurations within each repeat.
+    pub shuffle_order: bool,
+    /// Seed for all randomized choices made by the run.
+    pub seed: u64,
+    /// What to do with the database files at exit.
+    pub cleanup: CleanupPolicy,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            fill_target: FillTarget::Bytes(10 * GIB),
+            fill_batch_entries: BATCH_SIZE,
+            fill_batch_bytes: FILL_BATCH_BYTES,
+            fill_stat: true,
+            fill_concurrent: true,
+            values: ValueGen::fixed(ValuePattern::Random, VALUE_SIZE),
+            generator_threads: default_generator_threads(),
+            benchmark_writes: BENCHMARK_WRITES,
+            keys: KeyPattern::default(),
+            reads: ReadPattern::default(),
+            sample_every: 1,
+            repair_callback: RepairCallback::default(),
+            warmup: Warmup::default(),
+            repeats: 1,
+            shuffle_order: false,
+            seed: rand::random(),
+            cleanup: CleanupPolicy::Never,
+        }
+    }
+}
+
+/// One generator thread per spare core, up to [`MAX_GENERATOR_THREADS`]; on a single core
+/// there is nothing to overlap with and values are generated inline.
+fn default_generator_threads() -> usize {
+    std::thread::available_parallelism()
+        .map_or(0, |n| n.get() - 1)
+        .min(MAX_GENERATOR_THREADS)
+}
+
+/// Beyond this many threads value generation outpaces any disk.
+const MAX_GENERATOR_THREADS: usize = 4;
+
+/// Named parameter bundles selectable with `--preset`.
+#[derive(Clone, Copy, Debug)]
+pub enum Preset {
+    /// Tiny run exercising the whole pipeline in under a minute.
+    Smoke,
+    /// 100 GiB fill with one in 10 timed writes sampled, to stress a huge database without
+    /// keeping every operation.
+    Huge,
+}
+
+impl Preset {
+    /// Apply the preset on top of `config` (after any `--workload`).
+    pub fn apply(self, config: &mut Config) {
+        match self {
+            Preset::Smoke => {
+                config.fill_target = FillTarget::Bytes(100 * MIB);
+                config.benchmark_writes = config.benchmark_writes.min(200);
+            }
+            Preset::Huge => {
+                config.fill_target = FillTarget::Bytes(100 * GIB);
+                config.sample_every = config.sample_every.max(10);
+            }
+        }
+    }
+}
+
+impl FromStr for Preset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "smoke" => Ok(Preset::Smoke),
+            "huge" => Ok(Preset::Huge),
+            _ => Err(format!("unknown preset `{s}`, expected `smoke` or `huge`")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn batch_size_parses_every_form() {
+        assert_eq!("100".parse(), Ok(BatchSize::Fixed(100)));
+        assert_eq!(
+            "uniform:10-20".parse(),
+            Ok(BatchSize::Uniform { min: 10, max: 20 })
+        );
+        assert_eq!(
+            "lognormal:50".parse(),
+            Ok(BatchSize::LogNormal {
+                mean: 50.0,
+                sigma: DEFAULT_SIGMA
+            })
+        );
+        assert_eq!(
+            "lognormal:50,0.5".parse(),
+            Ok(BatchSize::LogNormal {
+                mean: 50.0,
+                sigma: 0.5
+            })
+        );
+    }
+
+    #[test]
+    fn batch_size_rejects_invalid_distributions() {
+        for s in [
+            "",
+            "-1",
+            "ten",
+            "fixed:10",
+            "uniform:10",
+            "uniform:20-10",
+            "lognormal:0.5",
+            "lognormal:50,-1",
+            "lognormal:NaN",
+        ] {
+            assert!(s.parse::<BatchSize>().is_err(), "`{s}` parsed");
+        }
+    }
+
+    #[test]
+    fn batch_size_samples_at_least_one_insert() {
+        let mut rng = StdRng::seed_from_u64(0);
+        assert_eq!(BatchSize::Fixed(0).sample(&mut rng), 1);
+        assert_eq!(BatchSize::Fixed(7).sample(&mut rng), 7);
+        for _ in 0..1000 {
+            assert!(BatchSize::Uniform { min: 0, max: 3 }.sample(&mut rng) >= 1);
+            let n = BatchSize::Uniform { min: 10, max: 20 }.sample(&mut rng);
+            assert!((10..=20).contains(&n), "{n}");
+        }
+    }
+
+    #[test]
+    fn lognormal_batch_sizes_have_the_requested_mean() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let batch_size = BatchSize::LogNormal {
+            mean: 50.0,
+            sigma: 1.0,
+        };
+        let draws = 20_000;
+        let total: usize = (0..draws).map(|_| batch_size.sample(&mut rng)).sum();
+        let mean = total as f64 / draws as f64;
+        assert!((mean - 50.0).abs() < 2.5, "{mean}");
+    }
+
+    #[test]
+    fn read_pattern_parses_every_form() {
+        assert_eq!("uniform".parse(), Ok(ReadPattern::Uniform));
+        assert_eq!(
+            "latest".parse(),
+            Ok(ReadPattern::Latest {
+                skew: DEFAULT_RECENCY_SKEW
+            })
+        );
+        assert_eq!("latest:2.5".parse(), Ok(ReadPattern::Latest { skew: 2.5 }));
+        assert_eq!("latest:1".parse(), Ok(ReadPattern::Latest { skew: 1.0 }));
+    }
+
+    #[test]
+    fn read_pattern_rejects_invalid_skews_and_names() {
+        for s in [
+            "",
+            "newest",
+            "uniform:2",
+            "latest:",
+            "latest:x",
+            "latest:0.5",
+            "latest:inf",
+            "latest:NaN",
+        ] {
+            assert!(s.parse::<ReadPattern>().is_err(), "`{s}` parsed");
+        }
+    }
+
+    /// Fraction of `draws` reads of `pattern` out of 1000 keys landing on the newest 100.
+    fn newest_tenth(pattern: ReadPattern, draws: usize) -> f64 {
+        let mut rng = StdRng::seed_from_u64(0);
+        let hits = (0..draws)
+            .map(|_| pattern.key(&mut rng, 1000))
+            .inspect(|&key| assert!(key < 1000, "{key}"))
+            .filter(|&key| key >= 900)
+            .count();
+        hits as f64 / draws as f64
+    }
+
+    #[test]
+    fn read_patterns_skew_reads_towards_the_newest_keys() {
+        let uniform = newest_tenth(ReadPattern::Uniform, 20_000);
+        assert!((uniform - 0.10).abs() < 0.01, "{uniform}");
+        let latest = newest_tenth(ReadPattern::Latest { skew: 4.0 }, 20_000);
+        assert!((latest - 0.1f64.powf(0.25)).abs() < 0.01, "{latest}");
+    }
+
+    #[test]
+    fn read_patterns_read_key_zero_of_an_empty_table() {
+        let mut rng = StdRng::seed_from_u64(0);
+        assert_eq!(ReadPattern::Uniform.key(&mut rng, 0), 0);
+        assert_eq!(ReadPattern::Latest { skew: 4.0 }.key(&mut rng, 0), 0);
+    }
+}
@@ -0,0 +1,237 @@
+//! Terminal rendering shared by the start-of-run banner and the console report: headings,
+//! aligned key/value lists and tables, with optional ANSI color.
+
+use std::env;
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+/// Total width of banners and section headings.
+const WIDTH: usize = 60;
+
+/// Whether console output is colored, selectable with `--color`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ColorChoice {
+    /// Color when writing to a terminal and `NO_COLOR` is unset.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn enabled(self, terminal: bool) -> bool {
+        match self {
+            ColorChoice::Auto => terminal && env::var_os("NO_COLOR").is_none(),
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(format!(
+                "unknown color choice `{s}`, expected `auto`, `always` or `never`"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ColorChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ColorChoice::Auto => "auto",
+            ColorChoice::Always => "always",
+            ColorChoice::Never => "never",
+        })
+    }
+}
+
+/// How much is written to the terminal, set with `-q` / `-v`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Verbosity {
+    /// Warnings only, and a summary without per-row tables.
+    Quiet,
+    #[default]
+    Normal,
+    /// Debug-level progress logs.
+    Verbose,
+}
+
+impl Verbosity {
+    /// Default `tracing` filter when `RUST_LOG` is unset.
+    pub fn log_filter(self) -> &'static str {
+        match self {
+            Verbosity::Quiet => "warn",
+            Verbosity::Normal => "info",
+            Verbosity::Verbose => "debug",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Style {
+    Title,
+    Heading,
+    Label,
+    Value,
+    Header,
+    Note,
+}
+
+impl Style {
+    fn ansi(self) -> &'static str {
+        match self {
+            Style::Title => "\x1b[1;35m",
+            Style::Heading => "\x1b[1;36m",
+            Style::Label => "\x1b[2m",
+            Style::Value => "\x1b[1m",
+            Style::Header => "\x1b[1;4m",
+            Style::Note => "\x1b[33m",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Console {
+    pub color: bool,
+    pub verbosity: Verbosity,
+}
+
+impl Console {
+    /// `terminal` is whether the destination is a terminal, consulted for [`ColorChoice::Auto`].
+    pub fn new(color: ColorChoice, verbosity: Verbosity, terminal: bool) -> Self {
+        Self {
+            color: color.enabled(terminal),
+            verbosity,
+        }
+    }
+
+    pub fn quiet(&self) -> bool {
+        self.verbosity == Verbosity::Quiet
+    }
+
+    /// Style `text`, which must already be padded since escape codes have no width.
+    fn paint(&self, style: Style, text: &str) -> String {
+        if self.color {
+            format!("{}{text}\x1b[0m", style.ansi())
+        } else {
+            text.to_owned()
+        }
+    }
+
+    /// A prominent title with optional subtitle lines, framed by heavy rules.
+    pub fn banner(&self, out: &mut impl Write, title: &str, subtitles: &[&str]) -> io::Result<()> {
+        let rule = self.paint(Style::Title, &"━".repeat(WIDTH));
+        writeln!(out, "\n{rule}")?;
+        writeln!(out, "{}", self.paint(Style::Title, title))?;
+        for subtitle in subtitles {
+            writeln!(out, "{subtitle}")?;
+        }
+        writeln!(out, "{rule}")
+    }
+
+    /// `── title ──────` padded to the standard width.
+    pub fn heading(&self, out: &mut impl Write, title: &str) -> io::Result<()> {
+        let fill = WIDTH.saturating_sub(title.chars().count() + 4).max(2);
+        let text = format!("── {title} {}", "─".repeat(fill));
+        writeln!(out, "\n{}", self.paint(Style::Heading, &text))
+    }
+
+    /// Labels left-aligned and values right-aligned, each in a common column.
+    pub fn fields(&self, out: &mut impl Write, fields: &[(String, String)]) -> io::Result<()> {
+        let label_width = fields.iter().map(|(l, _)| l.chars().count()).max();
+        let value_width = fields.iter().map(|(_, v)| v.chars().count()).max();
+        let (Some(label_width), Some(value_width)) = (label_width, value_width) else {
+            return Ok(());
+        };
+        for (label, value) in fields {
+            writeln!(
+                out,
+                "  {}  {}",
+                self.paint(Style::Label, &format!("{label:<label_width$}")),
+                self.paint(Style::Value, &format!("{value:>value_width$}"))
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Right-aligned columns under an underlined header row.
+    pub fn table(
+        &self,
+        out: &mut impl Write,
+        columns: &[String],
+        rows: &[Vec<String>],
+    ) -> io::Result<()> {
+        let widths: Vec<usize> = columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                rows.iter()
+                    .filter_map(|row| row.get(i))
+                    .chain([column])
+                    .map(|cell| cell.chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let line = |row: &[String], style: Option<Style>| {
+            row.iter()
+                .zip(&widths)
+                .map(|(cell, width)| {
+                    let cell = format!("{cell:>width$}");
+                    match style {
+                        Some(style) => self.paint(style, &cell),
+                        None => cell,
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("  ")
+        };
+        writeln!(out, "  {}", line(columns, Some(Style::Header)))?;
+        for row in rows {
+            writeln!(out, "  {}", line(row, None))?;
+        }
+        Ok(())
+    }
+
+    pub fn note(&self, out: &mut impl Write, note: &str) -> io::Result<()> {
+        writeln!(out, "  {}", self.paint(Style::Note, note))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color_choices_round_trip_through_their_names() {
+        for choice in [ColorChoice::Auto, ColorChoice::Always, ColorChoice::Never] {
+            assert_eq!(choice.to_string().parse::<ColorChoice>(), Ok(choice));
+        }
+        assert_eq!("always".parse::<ColorChoice>(), Ok(ColorChoice::Always));
+    }
+
+    #[test]
+    fn color_choice_rejects_unknown_names() {
+        assert!("yes".parse::<ColorChoice>().is_err());
+        assert!("Always".parse::<ColorChoice>().is_err());
+        assert!("".parse::<ColorChoice>().is_err());
+    }
+
+    #[test]
+    fn forced_color_choices_ignore_the_terminal() {
+        for terminal in [false, true] {
+            assert!(ColorChoice::Always.enabled(terminal));
+            assert!(!ColorChoice::Never.enabled(terminal));
+        }
+        assert!(!ColorChoice::Auto.enabled(false));
+    }
+}
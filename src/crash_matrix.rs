@@ -0,0 +1,337 @@
+//! Dirty-shutdown matrix: repair time after a real process crash, over every combination
+//! of quick_repair setting, commits since the last clean close, database size and repair
+//! callback.
+//!
+//! A base database of each size is filled and closed cleanly once. Every trial copies it,
+//! forks a child that opens the copy, makes the commits and then kills itself with
+//! `SIGKILL`, so nothing is shut down: the file is left exactly as a crashed writer leaves
+//! it (the page cache survives, as it does when only the process dies). The parent then
+//! times the reopen, which runs the repair, and audits the table against the child's
+//! [`audit`](crate::audit) log of acknowledged commits.
+
+use crate::audit::{self, Audit, AuditLog, Expected};
+use crate::config::{Config, FillTarget, MIB};
+use crate::error::{BenchError, Context, IoContext};
+use crate::payload::ValueGen;
+use crate::platform;
+use crate::repair::RepairCallback;
+use crate::report::{Report, Section, Tag};
+use crate::{BenchDb, TABLE, clock, fill_database, hooks};
+use redb::{Database, Error};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::Duration;
+use tracing::{info, info_span};
+
+/// Default database sizes, in MiB.
+pub const DEFAULT_SIZES_MB: [u64; 3] = [64, 256, 1024];
+/// Default numbers of commits between the clean close and the crash.
+pub const DEFAULT_COMMITS: [u64; 5] = [0, 1, 10, 100, 1000];
+/// Default crashes per cell.
+pub const DEFAULT_TRIALS: usize = 3;
+
+/// One crash and the reopen after it.
+pub struct Trial {
+    pub size_mb: u64,
+    pub callback: RepairCallback,
+    pub quick_repair: bool,
+    pub commits: u64,
+    pub trial: usize,
+    /// Whether redb invoked the repair callback on reopen.
+    pub repaired: bool,
+    pub callback_calls: u64,
+    /// Time to reopen, including the repair.
+    pub reopen: Duration,
+    /// Commits of the crashed process missing after reopening.
+    pub lost: u64,
+    /// Audit of the reopened table against the acknowledged commits.
+    pub audit: Audit,
+}
+
+pub struct CrashMatrix {
+    pub trials: Vec<Trial>,
+}
+
+/// The dimensions of the matrix.
+pub struct MatrixSpec {
+    pub sizes_mb: Vec<u64>,
+    pub commits: Vec<u64>,
+    /// Repair callbacks installed for the reopen.
+    pub callbacks: Vec<RepairCallback>,
+    pub trials: usize,
+}
+
+/// Run every cell of `spec` in `dir`, filling the base databases with `config`'s values.
+pub fn run(spec: &MatrixSpec, config: &Config, dir: &Path) -> Result<CrashMatrix, BenchError> {
+    let _span = info_span!("crash_matrix", dir = %dir.display()).entered();
+    let mut trials = Vec::new();
+    for &size_mb in &spec.sizes_mb {
+        let base = dir.join(format!("crash_matrix_{size_mb}mb.redb"));
+        let base_path = base.display().to_string();
+        let _ = fs::remove_file(&base);
+        // Values generated inline: no generator thread may be running when a child forks.
+        let fill_config = Config {
+            fill_target: FillTarget::Bytes(size_mb * MIB),
+            generator_threads: 0,
+            ..config.clone()
+        };
+        info!(size_mb, path = %base_path, "Filling base database");
+        let next_key = fill_database(
+            &BenchDb::new(base_path.clone(), false),
+            &fill_config,
+            Default::default(),
+            |_| {},
+        )?;
+
+        let copy = dir.join(format!("crash_matrix_{size_mb}mb.trial.redb"));
+        for &callback in &spec.callbacks {
+            for quick_repair in [false, true] {
+                for &commits in &spec.commits {
+                    for trial in 0..spec.trials {
+                        fs::copy(&base, &copy).io_context(|| {
+                            format!("copying {} to {}", base.display(), copy.display())
+                        })?;
+                        let outcome = crash_trial(
+                            &copy,
+                            next_key,
+                            commits,
+                            quick_repair,
+                            config.values,
+                            callback,
+                        );
+                        let _ = fs::remove_file(&copy);
+                        let _ = fs::remove_file(audit::sidecar_path(&copy));
+                        let (callback_calls, reopen, audit) = outcome?;
+                        let lost = audit.lost;
+                        info!(
+                            size_mb,
+                            %callback,
+                            quick_repair,
+                            commits,
+                            trial,
+                            callback_calls,
+                            ?reopen,
+                            lost,
+                            consistent = audit.is_consistent(),
+                            "Reopened after crash"
+                        );
+                        trials.push(Trial {
+                            size_mb,
+                            callback,
+                            quick_repair,
+                            commits,
+                            trial,
+                            repaired: callback_calls > 0,
+                            callback_calls,
+                            reopen,
+                            lost,
+                            audit,
+                        });
+                    }
+                }
+            }
+        }
+        if config.cleanup.should_remove(true) {
+            let _ = fs::remove_file(&base);
+        }
+    }
+    Ok(CrashMatrix { trials })
+}
+
+/// Crash a child after `commits` commits on `path`, then reopen it with `callback`:
+/// `(callback calls, reopen, audit)`.
+fn crash_trial(
+    path: &Path,
+    next_key: u64,
+    commits: u64,
+    quick_repair: bool,
+    values: ValueGen,
+    callback: RepairCallback,
+) -> Result<(u64, Duration, Audit), BenchError> {
+    let db_path = path.display().to_string();
+    crash_child(path, next_key, commits, quick_repair, values)
+        .io_context(|| format!("crashing a writer on {db_path}"))?;
+
+    let mut builder = Database::builder();
+    builder.set_cache_size(1024 * 1024 * 1024); // 1GB cache
+    let probe = callback.install(&mut builder);
+    let start = clock::now();
+    let db = builder.create(path).in_phase("crash-matrix", &db_path)?;
+    let reopen = start.elapsed();
+    let sidecar = audit::sidecar_path(path);
+    let expected = Expected::load(&sidecar)
+        .io_context(|| format!("reading the audit log {}", sidecar.display()))?;
+    let audit = audit::check(&db, &expected, next_key).in_phase("crash-matrix", &db_path)?;
+    Ok((probe.calls(), reopen, audit))
+}
+
+/// Fork a child that opens `path`, makes `commits` single-insert commits and is killed
+/// without closing the database; wait for it.
+fn crash_child(
+    path: &Path,
+    next_key: u64,
+    commits: u64,
+    quick_repair: bool,
+    values: ValueGen,
+) -> io::Result<()> {
+    // SAFETY: no other thread runs during the matrix, so the child cannot inherit a lock
+    // held by one.
+    unsafe {
+        platform::crash_after(|| {
+            child_commits(path, next_key, commits, quick_repair, values).is_ok()
+        })
+    }
+}
+
+fn child_commits(
+    path: &Path,
+    next_key: u64,
+    commits: u64,
+    quick_repair: bool,
+    values: ValueGen,
+) -> Result<(), Error> {
+    let db = Database::builder()
+        .set_cache_size(1024 * 1024 * 1024) // 1GB cache
+        .create(path)?;
+    let mut log = AuditLog::create(&audit::sidecar_path(path))?;
+    for key in next_key..next_key + commits {
+        let value = values.generate(key);
+        let mut write_txn = hooks::begin_write(&db)?;
+        write_txn.set_quick_repair(quick_repair);
+        {
+            let mut table = write_txn.open_table(TABLE)?;
+            table.insert(key, value.as_slice())?;
+        }
+        write_txn.commit()?;
+        log.commit([(key, Some(value.as_slice()))])?;
+    }
+    // The caller kills the process with `db` still open.
+    std::mem::forget(db);
+    Ok(())
+}
+
+impl CrashMatrix {
+    /// One row per trial.
+    pub fn write_csv(&self, mut out: impl Write, tags: &[Tag]) -> io::Result<()> {
+        let (tag_keys, tag_values) = Tag::csv_columns(tags);
+        writeln!(
+            out,
+            "size_mb,callback,quick_repair,commits,trial,repaired,callback_calls,reopen_us,lost,\
+             mismatched,unexpected{tag_keys}"
+        )?;
+        for t in &self.trials {
+            writeln!(
+                out,
+                "{},{},{},{},{},{},{},{},{},{},{}{tag_values}",
+                t.size_mb,
+                t.callback,
+                t.quick_repair,
+                t.commits,
+                t.trial,
+                t.repaired,
+                t.callback_calls,
+                t.reopen.as_micros(),
+                t.lost,
+                t.audit.mismatched,
+                t.audit.unexpected
+            )?;
+        }
+        out.flush()
+    }
+
+    /// One row per cell, over its trials.
+    pub fn report(&self) -> Report {
+        let mut section = Section::new("Dirty Shutdown Matrix").table([
+            "size (MiB)",
+            "callback",
+            "quick_repair",
+            "commits",
+            "trials",
+            "repaired",
+            "mean callback calls",
+            "mean reopen",
+            "min reopen",
+            "max reopen",
+            "lost commits",
+            "wrong values",
+            "unacknowledged keys",
+        ]);
+        let cell = |t: &Trial| (t.size_mb, t.callback, t.quick_repair, t.commits);
+        let mut cells = Vec::new();
+        for t in &self.trials {
+            if !cells.contains(&cell(t)) {
+                cells.push(cell(t));
+            }
+        }
+        for key in cells {
+            let (size_mb, callback, quick_repair, commits) = key;
+            let trials: Vec<&Trial> = self.trials.iter().filter(|t| cell(t) == key).collect();
+            let reopens = trials.iter().map(|t| t.reopen);
+            let calls = trials.iter().map(|t| t.callback_calls).sum::<u64>();
+            section = section.row(vec![
+                size_mb.into(),
+                callback.to_string().into(),
+                quick_repair.to_string().into(),
+                commits.into(),
+                trials.len().into(),
+                trials.iter().filter(|t| t.repaired).count().into(),
+                (calls as f64 / trials.len() as f64).into(),
+                (reopens.clone().sum::<Duration>() / trials.len() as u32).into(),
+                reopens.clone().min().unwrap_or_default().into(),
+                reopens.max().unwrap_or_default().into(),
+                trials.iter().map(|t| t.lost).sum::<u64>().into(),
+                trials
+                    .iter()
+                    .map(|t| t.audit.mismatched)
+                    .sum::<u64>()
+                    .into(),
+                trials
+                    .iter()
+                    .map(|t| t.audit.unexpected)
+                    .sum::<u64>()
+                    .into(),
+            ]);
+        }
+        let mut report = Report::new("DIRTY SHUTDOWN MATRIX");
+        report.push(section.note(
+            "Each trial kills a writer process after the given commits since a clean close, then reopens",
+        ).note(
+            "The reopened table is audited against the writer's log of acknowledged commits: each key must hold its last acknowledged value",
+        ));
+        report
+    }
+}
+
+/// Parse a comma-separated list of numbers.
+pub fn parse_list(value: &str) -> Result<Vec<u64>, String> {
+    value
+        .split(',')
+        .map(|n| {
+            n.trim()
+                .parse()
+                .map_err(|_| format!("invalid number `{n}` in `{value}`"))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_trimmed_list_of_numbers() {
+        assert_eq!(parse_list("1,10, 100 "), Ok(vec![1, 10, 100]));
+        assert_eq!(parse_list("0"), Ok(vec![0]));
+    }
+
+    #[test]
+    fn rejects_anything_but_numbers() {
+        let error = parse_list("1,x,3").unwrap_err();
+        assert_eq!(error, "invalid number `x` in `1,x,3`");
+        assert!(parse_list("").is_err());
+        assert!(parse_list("1,,3").is_err());
+        assert!(parse_list("-1").is_err());
+        assert!(parse_list("1.5").is_err());
+    }
+}
@@ -0,0 +1,151 @@
+//! Transactions overwriting a varying number of random existing keys, recording an
+//! estimate of how many leaf pages each one dirties so that commit latency can be
+//! correlated with it.
+
+use crate::payload::ValueGen;
+use crate::report::{Section, Value};
+use crate::stats::LinearFit;
+use crate::{BenchDb, TABLE, clock, hooks, record_op};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use redb::{Database, Error};
+use std::collections::HashSet;
+use std::time::Duration;
+use tracing::{info, info_span};
+
+/// Keys overwritten per transaction; every count is sampled in each round.
+pub const KEY_COUNTS: [usize; 9] = [1, 2, 4, 8, 16, 32, 64, 128, 256];
+/// Rounds over [`KEY_COUNTS`] at the default `--writes`, which scale them.
+pub const ROUNDS: usize = 100;
+
+pub struct DirtyPageSample {
+    pub keys: usize,
+    /// Distinct leaf pages the keys fall into, assuming keys are laid out in order.
+    pub leaf_pages: usize,
+    pub latency: Duration,
+}
+
+pub struct DirtyPageReport {
+    pub keys_per_leaf: f64,
+    pub samples: Vec<DirtyPageSample>,
+}
+
+/// Run `rounds` transactions per entry of [`KEY_COUNTS`] against the filled `bench_db`, on
+/// keys drawn from an RNG seeded with `seed`.
+pub fn benchmark_dirty_pages(
+    bench_db: &BenchDb,
+    values: ValueGen,
+    rounds: usize,
+    seed: u64,
+) -> Result<DirtyPageReport, Error> {
+    let _span =
+        info_span!("dirty_pages", db_path = %bench_db.path, quick_repair = bench_db.quick_repair)
+            .entered();
+
+    let db = bench_db.create(
+        Database::builder().set_cache_size(1024 * 1024 * 1024), // 1GB cache
+    )?;
+
+    // Keys were inserted sequentially, so each leaf holds a contiguous key range.
+    let key_space = bench_db.next_key.max(1);
+    let write_txn = hooks::begin_write(&db)?;
+    let leaf_pages = write_txn.stats()?.leaf_pages().max(1);
+    write_txn.abort()?;
+    let keys_per_leaf = key_space as f64 / leaf_pages as f64;
+    info!(
+        leaf_pages,
+        keys_per_leaf = format_args!("{keys_per_leaf:.2}"),
+        rounds,
+        "Estimating dirty pages per transaction"
+    );
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut samples = Vec::with_capacity(rounds * KEY_COUNTS.len());
+
+    for _ in 0..rounds {
+        for &keys in &KEY_COUNTS {
+            let batch: Vec<(u64, Vec<u8>)> = (0..keys)
+                .map(|_| {
+                    let key = rng.random_range(0..key_space);
+                    (key, values.generate(key))
+                })
+                .collect();
+            let leaves: HashSet<u64> = batch
+                .iter()
+                .map(|(key, _)| (*key as f64 / keys_per_leaf) as u64)
+                .collect();
+
+            let start = clock::now();
+            let mut write_txn = hooks::begin_write(&db)?;
+            write_txn.set_quick_repair(bench_db.quick_repair);
+            {
+                let mut table = write_txn.open_table(TABLE)?;
+                for (key, value) in &batch {
+                    table.insert(key, value.as_slice())?;
+                }
+            }
+            write_txn.commit()?;
+            let latency = start.elapsed();
+            record_op(
+                "dirty-pages",
+                &bench_db.path,
+                bench_db.quick_repair,
+                samples.len() as u64,
+                keys,
+                batch.iter().map(|(_, value)| value.len()).sum(),
+                latency,
+            );
+
+            samples.push(DirtyPageSample {
+                keys,
+                leaf_pages: leaves.len(),
+                latency,
+            });
+        }
+    }
+
+    Ok(DirtyPageReport {
+        keys_per_leaf,
+        samples,
+    })
+}
+
+impl DirtyPageReport {
+    /// Fit of commit latency (μs) against estimated dirty leaf pages.
+    pub fn fit(&self) -> Option<LinearFit> {
+        let points: Vec<(f64, f64)> = self
+            .samples
+            .iter()
+            .map(|s| (s.leaf_pages as f64, s.latency.as_secs_f64() * 1e6))
+            .collect();
+        LinearFit::new(&points)
+    }
+
+    pub fn section(&self, title: impl Into<String>) -> Section {
+        let mut section = Section::new(title)
+            .field("Keys per leaf page (est.)", self.keys_per_leaf)
+            .table(["keys/txn", "leaf pages", "avg latency"]);
+        for &keys in &KEY_COUNTS {
+            let samples: Vec<_> = self.samples.iter().filter(|s| s.keys == keys).collect();
+            if samples.is_empty() {
+                continue;
+            }
+            let leaf_pages =
+                samples.iter().map(|s| s.leaf_pages).sum::<usize>() as f64 / samples.len() as f64;
+            let latency =
+                samples.iter().map(|s| s.latency).sum::<Duration>() / samples.len() as u32;
+            section = section.row(vec![keys.into(), Value::Float(leaf_pages), latency.into()]);
+        }
+        if let Some(fit) = self.fit() {
+            section = section
+                .field("Fit intercept (μs)", fit.intercept)
+                .field("Fit slope (μs per leaf page)", fit.slope)
+                .field("Fit correlation (r)", fit.r)
+                .note(format!(
+                    "Commit latency ≈ {:.0} μs + {:.1} μs × leaf pages (r = {:.3})",
+                    fit.intercept, fit.slope, fit.r
+                ));
+        }
+        section
+    }
+}
@@ -0,0 +1,377 @@
+//! Embedded key-value stores behind one interface, so the fill and the timed single-write
+//! benchmark can be repeated on other engines with identical workloads and compared with
+//! redb's quick_repair commit cost.
+//!
+//! redb itself is always available as a baseline; other engines are behind cargo features
+//! (`sqlite`, `sled`, `lmdb`, `rocksdb`) and [`EngineKind::available`] says whether this binary has them.
+
+#[cfg(feature = "lmdb")]
+mod lmdb;
+#[cfg(feature = "rocksdb")]
+mod rocksdb;
+#[cfg(feature = "sled")]
+mod sled;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+use crate::config::{Config, MIB};
+use crate::payload::{ValueGen, ValueStream};
+use crate::report::{Section, Value};
+use crate::{BenchmarkStats, StatsAccumulator, TABLE, clock, hooks, record_op};
+use redb::Database;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::{info, info_span, warn};
+
+pub type EngineError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A store the benchmark can write to. Every call is one durable transaction.
+pub trait StorageEngine {
+    /// Insert (or overwrite) all `entries` in one transaction, durable when this returns.
+    fn write_batch(&mut self, entries: &[(u64, Vec<u8>)]) -> Result<(), EngineError>;
+
+    /// Bytes the store currently occupies on disk, including logs and journals.
+    fn disk_usage(&self) -> u64;
+}
+
+/// An engine and configuration selectable with `--engine`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EngineKind {
+    Redb {
+        quick_repair: bool,
+    },
+    Sqlite(SqliteJournal),
+    Sled,
+    /// `sync: false` opens the environment with `MDB_NOSYNC`.
+    Lmdb {
+        sync: bool,
+    },
+    /// `sync: false` writes the WAL without fsyncing it.
+    RocksDb {
+        sync: bool,
+    },
+}
+
+/// SQLite journal mode; both run with `synchronous=FULL` to match redb's durable commits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SqliteJournal {
+    Wal,
+    Delete,
+}
+
+impl EngineKind {
+    /// Whether this binary was built with support for the engine.
+    pub fn available(self) -> bool {
+        match self {
+            EngineKind::Redb { .. } => true,
+            EngineKind::Sqlite(_) => cfg!(feature = "sqlite"),
+            EngineKind::Sled => cfg!(feature = "sled"),
+            EngineKind::Lmdb { .. } => cfg!(feature = "lmdb"),
+            EngineKind::RocksDb { .. } => cfg!(feature = "rocksdb"),
+        }
+    }
+
+    /// Cargo feature enabling the engine.
+    pub fn feature(self) -> Option<&'static str> {
+        match self {
+            EngineKind::Redb { .. } => None,
+            EngineKind::Sqlite(_) => Some("sqlite"),
+            EngineKind::Sled => Some("sled"),
+            EngineKind::Lmdb { .. } => Some("lmdb"),
+            EngineKind::RocksDb { .. } => Some("rocksdb"),
+        }
+    }
+
+    /// Whether commits use redb's quick_repair, for metric labels.
+    pub fn quick_repair(self) -> bool {
+        matches!(self, EngineKind::Redb { quick_repair: true })
+    }
+
+    /// Human-readable description for reports.
+    pub fn label(self) -> String {
+        match self {
+            EngineKind::Redb { quick_repair } => {
+                format!("redb quick_repair({quick_repair}), engine phase")
+            }
+            EngineKind::Sqlite(SqliteJournal::Wal) => "sqlite WAL".to_owned(),
+            EngineKind::Sqlite(SqliteJournal::Delete) => "sqlite rollback journal".to_owned(),
+            EngineKind::Sled => "sled".to_owned(),
+            EngineKind::Lmdb { sync: true } => "lmdb".to_owned(),
+            EngineKind::Lmdb { sync: false } => "lmdb NOSYNC".to_owned(),
+            EngineKind::RocksDb { sync: true } => "rocksdb WAL fsync".to_owned(),
+            EngineKind::RocksDb { sync: false } => "rocksdb WAL no fsync".to_owned(),
+        }
+    }
+
+    /// Path of the store in `dir`, a file or a directory depending on the engine.
+    pub fn path(self, dir: &Path) -> PathBuf {
+        dir.join(format!("benchmark_engine_{self}"))
+    }
+
+    pub fn open(self, path: &Path) -> Result<Box<dyn StorageEngine>, EngineError> {
+        match self {
+            EngineKind::Redb { quick_repair } => {
+                Ok(Box::new(RedbEngine::open(path, quick_repair)?))
+            }
+            #[cfg(feature = "sqlite")]
+            EngineKind::Sqlite(journal) => Ok(Box::new(sqlite::SqliteEngine::open(path, journal)?)),
+            #[cfg(feature = "sled")]
+            EngineKind::Sled => Ok(Box::new(sled::SledEngine::open(path)?)),
+            #[cfg(feature = "lmdb")]
+            EngineKind::Lmdb { sync } => Ok(Box::new(lmdb::LmdbEngine::open(path, sync)?)),
+            #[cfg(feature = "rocksdb")]
+            EngineKind::RocksDb { sync } => Ok(Box::new(rocksdb::RocksDbEngine::open(path, sync)?)),
+            #[allow(unreachable_patterns)]
+            _ => Err(format!("engine {self} not compiled in").into()),
+        }
+    }
+}
+
+impl FromStr for EngineKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "redb" => Ok(EngineKind::Redb {
+                quick_repair: false,
+            }),
+            "redb-quick-repair" => Ok(EngineKind::Redb { quick_repair: true }),
+            "sqlite-wal" => Ok(EngineKind::Sqlite(SqliteJournal::Wal)),
+            "sqlite-delete" => Ok(EngineKind::Sqlite(SqliteJournal::Delete)),
+            "sled" => Ok(EngineKind::Sled),
+            "lmdb" => Ok(EngineKind::Lmdb { sync: true }),
+            "lmdb-nosync" => Ok(EngineKind::Lmdb { sync: false }),
+            "rocksdb" => Ok(EngineKind::RocksDb { sync: true }),
+            "rocksdb-nosync" => Ok(EngineKind::RocksDb { sync: false }),
+            _ => Err(format!(
+                "unknown engine `{s}`, expected `redb`, `redb-quick-repair`, `sqlite-wal`, \
+                 `sqlite-delete`, `sled`, `lmdb`, `lmdb-nosync`, `rocksdb` or \
+                 `rocksdb-nosync`"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for EngineKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            EngineKind::Redb {
+                quick_repair: false,
+            } => "redb",
+            EngineKind::Redb { quick_repair: true } => "redb-quick-repair",
+            EngineKind::Sqlite(SqliteJournal::Wal) => "sqlite-wal",
+            EngineKind::Sqlite(SqliteJournal::Delete) => "sqlite-delete",
+            EngineKind::Sled => "sled",
+            EngineKind::Lmdb { sync: true } => "lmdb",
+            EngineKind::Lmdb { sync: false } => "lmdb-nosync",
+            EngineKind::RocksDb { sync: true } => "rocksdb",
+            EngineKind::RocksDb { sync: false } => "rocksdb-nosync",
+        })
+    }
+}
+
+struct RedbEngine {
+    db: Database,
+    path: PathBuf,
+    quick_repair: bool,
+}
+
+impl RedbEngine {
+    fn open(path: &Path, quick_repair: bool) -> Result<Self, EngineError> {
+        let db = Database::builder()
+            .set_cache_size(1024 * 1024 * 1024) // 1GB cache
+            .create(path)?;
+        Ok(Self {
+            db,
+            path: path.to_owned(),
+            quick_repair,
+        })
+    }
+}
+
+impl StorageEngine for RedbEngine {
+    fn write_batch(&mut self, entries: &[(u64, Vec<u8>)]) -> Result<(), EngineError> {
+        let mut write_txn = hooks::begin_write(&self.db)?;
+        write_txn.set_quick_repair(self.quick_repair);
+        {
+            let mut table = write_txn.open_table(TABLE)?;
+            for (key, value) in entries {
+                table.insert(key, value.as_slice())?;
+            }
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    fn disk_usage(&self) -> u64 {
+        disk_usage(&self.path)
+    }
+}
+
+/// Total size of `path`, a file or a directory tree.
+pub fn disk_usage(path: &Path) -> u64 {
+    match fs::metadata(path) {
+        Ok(meta) if meta.is_dir() => fs::read_dir(path)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| disk_usage(&entry.path()))
+            .sum(),
+        Ok(meta) => meta.len(),
+        Err(_) => 0,
+    }
+}
+
+/// Remove the store at `path` and any sibling files sharing its name (journals, logs).
+pub fn remove_store(path: &Path) {
+    let result = if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    };
+    match result {
+        Ok(()) => info!(path = %path.display(), "Removed engine store"),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => warn!(path = %path.display(), error = %e, "Could not remove engine store"),
+    }
+    for suffix in ["-wal", "-shm", "-journal"] {
+        let mut sibling = path.as_os_str().to_owned();
+        sibling.push(suffix);
+        let _ = fs::remove_file(sibling);
+    }
+}
+
+pub struct EngineReport {
+    pub kind: EngineKind,
+    pub fill_entries: u64,
+    pub fill_duration: Duration,
+    pub writes: BenchmarkStats,
+    pub disk_usage: u64,
+}
+
+/// Fill a fresh store of `kind` in `dir` to the configured target and time the configured number of
+/// single-entry writes on it, with the same batching and values as the redb phases. The
+/// store is removed afterwards.
+pub fn benchmark_engine(
+    kind: EngineKind,
+    dir: &Path,
+    config: &Config,
+    values: ValueGen,
+) -> Result<EngineReport, EngineError> {
+    let path = kind.path(dir);
+    let db_path = path.to_string_lossy().into_owned();
+    let _span = info_span!("engine", engine = %kind, db_path).entered();
+    remove_store(&path);
+    let result = run_engine(kind, &path, &db_path, config, values);
+    remove_store(&path);
+    result
+}
+
+fn run_engine(
+    kind: EngineKind,
+    path: &Path,
+    db_path: &str,
+    config: &Config,
+    values: ValueGen,
+) -> Result<EngineReport, EngineError> {
+    let mut engine = kind.open(path)?;
+    let quick_repair = kind.quick_repair();
+
+    info!(target = %config.fill_target, "Filling engine store");
+    let mut stream = ValueStream::spawn(values, config.generator_threads, 0);
+    let start = clock::now();
+    let (mut key, mut bytes, mut batch_index) = (0u64, 0u64, 0u64);
+    loop {
+        let batch_size = config
+            .fill_target
+            .remaining_entries(bytes, key, values.mean_size())
+            .min(config.fill_batch_entries as u64)
+            .min((config.fill_batch_bytes / values.mean_size() as u64).max(1));
+        if batch_size == 0 {
+            break;
+        }
+        let batch: Vec<(u64, Vec<u8>)> = (key..key + batch_size)
+            .map(|k| (k, stream.next_value(k).to_vec()))
+            .collect();
+        let batch_bytes = batch.iter().map(|(_, v)| v.len()).sum();
+
+        let batch_start = clock::now();
+        engine.write_batch(&batch)?;
+        record_op(
+            "engine-fill",
+            db_path,
+            quick_repair,
+            batch_index,
+            batch.len(),
+            batch_bytes,
+            batch_start.elapsed(),
+        );
+        key += batch_size;
+        bytes += batch_size * values.mean_size() as u64;
+        batch_index += 1;
+    }
+    let fill_duration = start.elapsed();
+    info!(records = key, elapsed = ?fill_duration, "Engine store filled");
+
+    let mut latencies = StatsAccumulator::new();
+    for i in 0..config.benchmark_writes {
+        let entry = [(key, values.generate(key))];
+        let start = clock::now();
+        engine.write_batch(&entry)?;
+        let duration = start.elapsed();
+        latencies.record(duration);
+        record_op(
+            "engine-write",
+            db_path,
+            quick_repair,
+            i as u64,
+            1,
+            entry[0].1.len(),
+            duration,
+        );
+        key += 1;
+    }
+
+    Ok(EngineReport {
+        kind,
+        fill_entries: key - config.benchmark_writes as u64,
+        fill_duration,
+        writes: latencies.stats(),
+        disk_usage: engine.disk_usage(),
+    })
+}
+
+impl EngineReport {
+    pub fn section(&self, title: impl Into<String>) -> Section {
+        self.writes
+            .section(title)
+            .field("Fill entries", self.fill_entries)
+            .field("Fill duration", self.fill_duration)
+            .field("Disk usage (MiB)", self.disk_usage as f64 / MIB as f64)
+    }
+}
+
+/// One row of the engine comparison table.
+pub fn comparison_row(label: String, writes: &BenchmarkStats, disk_usage: u64) -> Vec<Value> {
+    vec![
+        label.into(),
+        writes.avg_write_time.into(),
+        writes.trimmed_mean.into(),
+        writes.max_write_time.into(),
+        writes.writes_per_second.into(),
+        (disk_usage as f64 / MIB as f64).into(),
+    ]
+}
+
+/// Columns of the engine comparison table, matching [`comparison_row`].
+pub const COMPARISON_COLUMNS: [&str; 6] = [
+    "engine",
+    "avg write",
+    "trimmed mean",
+    "max write",
+    "writes/s",
+    "disk (MiB)",
+];
@@ -0,0 +1,55 @@
+//! LMDB through heed. LMDB is copy-on-write like redb, so it is the closest baseline;
+//! `sync` maps onto the durability axis, with `MDB_NOSYNC` skipping the fsync at commit.
+
+use super::{EngineError, StorageEngine, disk_usage};
+use heed::byteorder::BigEndian;
+use heed::types::{Bytes, U64};
+use heed::{Database, Env, EnvFlags, EnvOpenOptions};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Upper bound on the memory map; LMDB reserves address space, not disk.
+const MAP_SIZE: usize = 1 << 40;
+
+pub struct LmdbEngine {
+    env: Env,
+    db: Database<U64<BigEndian>, Bytes>,
+    path: PathBuf,
+}
+
+impl LmdbEngine {
+    pub fn open(path: &Path, sync: bool) -> Result<Self, EngineError> {
+        fs::create_dir_all(path)?;
+        let mut options = EnvOpenOptions::new();
+        options.map_size(MAP_SIZE).max_dbs(1);
+        if !sync {
+            // SAFETY: NO_SYNC only weakens durability, which is what is being measured.
+            unsafe { options.flags(EnvFlags::NO_SYNC) };
+        }
+        // SAFETY: the environment is opened once, by this process only.
+        let env = unsafe { options.open(path)? };
+        let mut wtxn = env.write_txn()?;
+        let db = env.create_database(&mut wtxn, Some("benchmark_data"))?;
+        wtxn.commit()?;
+        Ok(Self {
+            env,
+            db,
+            path: path.to_owned(),
+        })
+    }
+}
+
+impl StorageEngine for LmdbEngine {
+    fn write_batch(&mut self, entries: &[(u64, Vec<u8>)]) -> Result<(), EngineError> {
+        let mut wtxn = self.env.write_txn()?;
+        for (key, value) in entries {
+            self.db.put(&mut wtxn, key, value)?;
+        }
+        wtxn.commit()?;
+        Ok(())
+    }
+
+    fn disk_usage(&self) -> u64 {
+        disk_usage(&self.path)
+    }
+}
@@ -0,0 +1,42 @@
+//! RocksDB, the LSM-tree counterpart to redb's B-tree. Each batch is one `WriteBatch`;
+//! `sync` chooses whether the WAL is fsynced before the write returns.
+
+use super::{EngineError, StorageEngine, disk_usage};
+use rocksdb::{DB, Options, WriteBatch, WriteOptions};
+use std::path::{Path, PathBuf};
+
+pub struct RocksDbEngine {
+    db: DB,
+    write_options: WriteOptions,
+    path: PathBuf,
+}
+
+impl RocksDbEngine {
+    pub fn open(path: &Path, sync: bool) -> Result<Self, EngineError> {
+        let mut options = Options::default();
+        options.create_if_missing(true);
+        let mut write_options = WriteOptions::default();
+        write_options.set_sync(sync);
+        Ok(Self {
+            db: DB::open(&options, path)?,
+            write_options,
+            path: path.to_owned(),
+        })
+    }
+}
+
+impl StorageEngine for RocksDbEngine {
+    fn write_batch(&mut self, entries: &[(u64, Vec<u8>)]) -> Result<(), EngineError> {
+        let mut batch = WriteBatch::default();
+        for (key, value) in entries {
+            // Big-endian so the default bytewise comparator matches key order.
+            batch.put(key.to_be_bytes(), value);
+        }
+        self.db.write_opt(batch, &self.write_options)?;
+        Ok(())
+    }
+
+    fn disk_usage(&self) -> u64 {
+        disk_usage(&self.path)
+    }
+}
@@ -0,0 +1,36 @@
+//! sled, committing each batch atomically with `apply_batch` and making it durable with
+//! `flush`, the closest match to a durable redb commit.
+
+use super::{EngineError, StorageEngine, disk_usage};
+use std::path::{Path, PathBuf};
+
+pub struct SledEngine {
+    db: sled::Db,
+    path: PathBuf,
+}
+
+impl SledEngine {
+    pub fn open(path: &Path) -> Result<Self, EngineError> {
+        Ok(Self {
+            db: sled::open(path)?,
+            path: path.to_owned(),
+        })
+    }
+}
+
+impl StorageEngine for SledEngine {
+    fn write_batch(&mut self, entries: &[(u64, Vec<u8>)]) -> Result<(), EngineError> {
+        let mut batch = sled::Batch::default();
+        for (key, value) in entries {
+            // Big-endian so sled's byte order matches key order.
+            batch.insert(&key.to_be_bytes(), value.as_slice());
+        }
+        self.db.apply_batch(batch)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    fn disk_usage(&self) -> u64 {
+        disk_usage(&self.path)
+    }
+}
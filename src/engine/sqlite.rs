@@ -0,0 +1,56 @@
+//! SQLite through rusqlite, with `synchronous=FULL` so every commit is durable like redb's.
+
+use super::{EngineError, SqliteJournal, StorageEngine, disk_usage};
+use rusqlite::{Connection, params};
+use std::path::{Path, PathBuf};
+
+pub struct SqliteEngine {
+    conn: Connection,
+    path: PathBuf,
+}
+
+impl SqliteEngine {
+    pub fn open(path: &Path, journal: SqliteJournal) -> Result<Self, EngineError> {
+        let conn = Connection::open(path)?;
+        let mode = match journal {
+            SqliteJournal::Wal => "WAL",
+            SqliteJournal::Delete => "DELETE",
+        };
+        conn.pragma_update(None, "journal_mode", mode)?;
+        conn.pragma_update(None, "synchronous", "FULL")?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS benchmark_data (key INTEGER PRIMARY KEY, value BLOB NOT NULL)",
+        )?;
+        Ok(Self {
+            conn,
+            path: path.to_owned(),
+        })
+    }
+}
+
+impl StorageEngine for SqliteEngine {
+    fn write_batch(&mut self, entries: &[(u64, Vec<u8>)]) -> Result<(), EngineError> {
+        let txn = self.conn.transaction()?;
+        {
+            let mut insert = txn.prepare_cached(
+                "INSERT OR REPLACE INTO benchmark_data (key, value) VALUES (?1, ?2)",
+            )?;
+            for (key, value) in entries {
+                insert.execute(params![*key as i64, value])?;
+            }
+        }
+        txn.commit()?;
+        Ok(())
+    }
+
+    fn disk_usage(&self) -> u64 {
+        ["", "-wal", "-journal"]
+            .iter()
+            .map(|suffix| {
+                let mut file = self.path.as_os_str().to_owned();
+                file.push(suffix);
+                disk_usage(Path::new(&file))
+            })
+            .sum()
+    }
+}
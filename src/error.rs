@@ -0,0 +1,130 @@
+//! Error type of a benchmark run, carrying the phase, database and operation a failure
+//! happened in, so that an error hours into a run is diagnosable from its message alone.
+
+use crate::engine::EngineError;
+use std::io;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum BenchError {
+    /// Invalid options or inputs, reported before the affected work starts.
+    #[error("{0}")]
+    Config(String),
+    /// A redb call failed while running a phase against a database.
+    #[error("{phase} phase failed on {db_path}{}", op_suffix(*.op))]
+    Redb {
+        phase: String,
+        db_path: String,
+        /// Index of the failed operation within the phase, when known.
+        op: Option<u64>,
+        /// Boxed: redb's error is large enough to bloat every `Result` carrying it.
+        #[source]
+        source: Box<redb::Error>,
+    },
+    /// A comparison engine failed.
+    #[error("engines phase failed on {engine}")]
+    Engine {
+        engine: String,
+        #[source]
+        source: EngineError,
+    },
+    /// I/O outside the databases: reports, logs, checkpoints, profiles.
+    #[error("{context}")]
+    Io {
+        context: String,
+        #[source]
+        source: io::Error,
+    },
+    /// The filesystem filled up; the partial results were reported before returning this.
+    #[error("ran out of disk space in {dir}; {suggestion}")]
+    DiskFull {
+        dir: String,
+        /// What to change for the next run to fit.
+        suggestion: String,
+        #[source]
+        source: Box<BenchError>,
+    },
+    /// `--assert` thresholds the run did not meet, one message each.
+    #[error("{} assertion(s) failed:\n  {}", .0.len(), .0.join("\n  "))]
+    Assertions(Vec<String>),
+}
+
+impl BenchError {
+    /// Whether the failure was the filesystem running out of space or quota.
+    pub fn is_disk_full(&self) -> bool {
+        match self {
+            BenchError::Redb { source, .. } => {
+                matches!(&**source, redb::Error::Io(e) if is_disk_full(e))
+            }
+            BenchError::Io { source, .. } => is_disk_full(source),
+            BenchError::DiskFull { .. } => true,
+            _ => false,
+        }
+    }
+}
+
+/// `ENOSPC` and `EDQUOT`, or their Windows counterparts.
+fn is_disk_full(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::StorageFull | io::ErrorKind::QuotaExceeded
+    )
+}
+
+fn op_suffix(op: Option<u64>) -> String {
+    op.map(|op| format!(" at operation {op}"))
+        .unwrap_or_default()
+}
+
+impl From<String> for BenchError {
+    fn from(message: String) -> Self {
+        BenchError::Config(message)
+    }
+}
+
+impl From<&str> for BenchError {
+    fn from(message: &str) -> Self {
+        BenchError::Config(message.to_owned())
+    }
+}
+
+/// Attach phase and database context to redb results.
+pub trait Context<T> {
+    fn in_phase(self, phase: &str, db_path: &str) -> Result<T, BenchError>;
+    /// Like [`Context::in_phase`], for operation `op` of the phase.
+    fn at_op(self, phase: &str, db_path: &str, op: u64) -> Result<T, BenchError>;
+}
+
+impl<T, E: Into<redb::Error>> Context<T> for Result<T, E> {
+    fn in_phase(self, phase: &str, db_path: &str) -> Result<T, BenchError> {
+        self.map_err(|e| BenchError::Redb {
+            phase: phase.to_owned(),
+            db_path: db_path.to_owned(),
+            op: None,
+            source: Box::new(e.into()),
+        })
+    }
+
+    fn at_op(self, phase: &str, db_path: &str, op: u64) -> Result<T, BenchError> {
+        self.map_err(|e| BenchError::Redb {
+            phase: phase.to_owned(),
+            db_path: db_path.to_owned(),
+            op: Some(op),
+            source: Box::new(e.into()),
+        })
+    }
+}
+
+/// Attach a description of what was being done to I/O results.
+pub trait IoContext<T> {
+    fn io_context(self, context: impl FnOnce() -> String) -> Result<T, BenchError>;
+}
+
+impl<T> IoContext<T> for io::Result<T> {
+    fn io_context(self, context: impl FnOnce() -> String) -> Result<T, BenchError> {
+        self.map_err(|source| BenchError::Io {
+            context: context(),
+            source,
+        })
+    }
+}
@@ -0,0 +1,392 @@
+//! Compact binary log of every timed operation, written during the run and analyzed
+//! afterwards with the `analyze` subcommand, so measurement and analysis are decoupled.
+//!
+//! The file starts with [`MAGIC`], followed by little-endian records:
+//!
+//! - `0x01 id:u16 len:u8 name[len]` defines the name of phase `id`, before its first event;
+//! - `0x02 id:u16 quick_repair:u8 start_ns:u64 duration_ns:u64 bytes:u64` is one operation,
+//!   with `start_ns` counted from when the log was opened.
+//!
+//! Like [`crate::metrics`], recording is a no-op until [`open`] has been called.
+
+use crate::StatsAccumulator;
+use crate::config::MIB;
+use crate::report::Report;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+pub const MAGIC: &[u8; 8] = b"QRLOG\0\0\x01";
+const NAME: u8 = 0x01;
+const OP: u8 = 0x02;
+
+static LOG: OnceLock<Mutex<Writer>> = OnceLock::new();
+
+struct Writer {
+    out: BufWriter<File>,
+    epoch: Instant,
+    phases: Vec<String>,
+}
+
+impl Writer {
+    fn phase_id(&mut self, phase: &str) -> io::Result<u16> {
+        if let Some(id) = self.phases.iter().position(|p| p == phase) {
+            return Ok(id as u16);
+        }
+        let id = self.phases.len() as u16;
+        let name = &phase.as_bytes()[..phase.len().min(u8::MAX as usize)];
+        self.out.write_all(&[NAME])?;
+        self.out.write_all(&id.to_le_bytes())?;
+        self.out.write_all(&[name.len() as u8])?;
+        self.out.write_all(name)?;
+        self.phases.push(phase.to_owned());
+        Ok(id)
+    }
+
+    fn write_op(
+        &mut self,
+        phase: &str,
+        quick_repair: bool,
+        bytes: usize,
+        latency: Duration,
+    ) -> io::Result<()> {
+        let end = self.epoch.elapsed();
+        let start_ns = end.saturating_sub(latency).as_nanos() as u64;
+        let id = self.phase_id(phase)?;
+
+        let mut record = [0u8; 28];
+        record[0] = OP;
+        record[1..3].copy_from_slice(&id.to_le_bytes());
+        record[3] = quick_repair as u8;
+        record[4..12].copy_from_slice(&start_ns.to_le_bytes());
+        record[12..20].copy_from_slice(&(latency.as_nanos() as u64).to_le_bytes());
+        record[20..28].copy_from_slice(&(bytes as u64).to_le_bytes());
+        self.out.write_all(&record)
+    }
+}
+
+/// Start logging to `path`, truncating any previous log.
+pub fn open(path: &Path) -> io::Result<()> {
+    let mut out = BufWriter::with_capacity(1 << 20, File::create(path)?);
+    out.write_all(MAGIC)?;
+    let writer = Writer {
+        out,
+        epoch: Instant::now(),
+        phases: Vec::new(),
+    };
+    LOG.set(Mutex::new(writer))
+        .map_err(|_| io::Error::other("event log already open"))
+}
+
+/// Record one completed operation. Write errors are ignored so logging never aborts a run;
+/// they surface from [`flush`].
+pub fn record_op(phase: &str, quick_repair: bool, bytes: usize, latency: Duration) {
+    if let Some(log) = LOG.get() {
+        let mut writer = log.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = writer.write_op(phase, quick_repair, bytes, latency);
+    }
+}
+
+/// Flush buffered records to disk; call before the process exits.
+pub fn flush() -> io::Result<()> {
+    match LOG.get() {
+        Some(log) => log.lock().unwrap_or_else(|e| e.into_inner()).out.flush(),
+        None => Ok(()),
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Event {
+    /// Index into [`EventLog::phases`].
+    pub phase: usize,
+    pub quick_repair: bool,
+    pub start_ns: u64,
+    pub duration: Duration,
+    pub bytes: u64,
+}
+
+/// The operations of one phase and quick_repair setting, folded in as they are read.
+pub struct EventGroup {
+    /// Index into [`EventLog::phases`].
+    pub phase: usize,
+    pub quick_repair: bool,
+    pub latencies: StatsAccumulator,
+    pub bytes: u64,
+    /// Start of the first operation and end of the last, from when the log was opened.
+    pub first_ns: u64,
+    pub last_ns: u64,
+}
+
+impl EventGroup {
+    fn new(event: &Event) -> Self {
+        Self {
+            phase: event.phase,
+            quick_repair: event.quick_repair,
+            latencies: StatsAccumulator::new(),
+            bytes: 0,
+            first_ns: event.start_ns,
+            last_ns: 0,
+        }
+    }
+
+    fn record(&mut self, event: &Event) {
+        self.latencies.record(event.duration);
+        self.bytes += event.bytes;
+        self.first_ns = self.first_ns.min(event.start_ns);
+        self.last_ns = self
+            .last_ns
+            .max(event.start_ns + event.duration.as_nanos() as u64);
+    }
+}
+
+pub struct EventLog {
+    pub phases: Vec<String>,
+    /// Per phase and quick_repair setting, in order of first appearance.
+    pub groups: Vec<EventGroup>,
+}
+
+impl EventLog {
+    /// Read a whole log, in memory that grows with its phases, not its operations. A
+    /// record truncated by a crash ends the log without an error.
+    pub fn read(path: &Path) -> io::Result<Self> {
+        let mut input = BufReader::new(File::open(path)?);
+        let mut magic = [0u8; 8];
+        input.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} is not an event log", path.display()),
+            ));
+        }
+
+        let mut phases = Vec::new();
+        let mut groups: Vec<EventGroup> = Vec::new();
+        let mut tag = [0u8; 1];
+        loop {
+            match input.read_exact(&mut tag) {
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                result => result?,
+            }
+            let record = match tag[0] {
+                NAME => read_name(&mut input).map(|(id, name)| {
+                    if phases.len() <= id {
+                        phases.resize(id + 1, String::new());
+                    }
+                    phases[id] = name;
+                }),
+                OP => read_op(&mut input).map(|event| {
+                    let index = groups
+                        .iter()
+                        .position(|g| {
+                            (g.phase, g.quick_repair) == (event.phase, event.quick_repair)
+                        })
+                        .unwrap_or_else(|| {
+                            groups.push(EventGroup::new(&event));
+                            groups.len() - 1
+                        });
+                    groups[index].record(&event);
+                }),
+                other => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("unknown event log record tag {other:#04x}"),
+                    ));
+                }
+            };
+            match record {
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                result => result?,
+            }
+        }
+
+        Ok(Self { phases, groups })
+    }
+
+    /// Per phase and quick_repair setting, in order of first appearance.
+    pub fn analyze(&self) -> Report {
+        let mut report = Report::new("EVENT LOG ANALYSIS");
+        for group in &self.groups {
+            let bytes = group.bytes;
+            let wall = Duration::from_nanos(group.last_ns.saturating_sub(group.first_ns));
+            report.push(
+                group
+                    .latencies
+                    .stats()
+                    .detailed_section(format!(
+                        "{} - quick_repair({})",
+                        self.phases.get(group.phase).map_or("?", String::as_str),
+                        group.quick_repair
+                    ))
+                    .field("Operations", group.latencies.count())
+                    .field("Bytes written (MiB)", bytes as f64 / MIB as f64)
+                    .field("Wall-clock span", wall)
+                    .field(
+                        "Throughput (MiB/s, wall clock)",
+                        bytes as f64 / MIB as f64 / wall.as_secs_f64().max(f64::EPSILON),
+                    ),
+            );
+        }
+        report
+    }
+}
+
+fn read_name(input: &mut impl Read) -> io::Result<(usize, String)> {
+    let mut header = [0u8; 3];
+    input.read_exact(&mut header)?;
+    let id = u16::from_le_bytes([header[0], header[1]]) as usize;
+    let mut name = vec![0u8; header[2] as usize];
+    input.read_exact(&mut name)?;
+    Ok((id, String::from_utf8_lossy(&name).into_owned()))
+}
+
+fn read_op(input: &mut impl Read) -> io::Result<Event> {
+    let mut record = [0u8; 27];
+    input.read_exact(&mut record)?;
+    let u64_at = |i: usize| u64::from_le_bytes(record[i..i + 8].try_into().unwrap());
+    Ok(Event {
+        phase: u16::from_le_bytes([record[0], record[1]]) as usize,
+        quick_repair: record[2] != 0,
+        start_ns: u64_at(3),
+        duration: Duration::from_nanos(u64_at(11)),
+        bytes: u64_at(19),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name_record(id: u16, name: &str) -> Vec<u8> {
+        let mut record = vec![NAME];
+        record.extend(id.to_le_bytes());
+        record.push(name.len() as u8);
+        record.extend(name.as_bytes());
+        record
+    }
+
+    fn op_record(
+        id: u16,
+        quick_repair: bool,
+        start_ns: u64,
+        duration_ns: u64,
+        bytes: u64,
+    ) -> Vec<u8> {
+        let mut record = vec![OP];
+        record.extend(id.to_le_bytes());
+        record.push(quick_repair as u8);
+        for field in [start_ns, duration_ns, bytes] {
+            record.extend(field.to_le_bytes());
+        }
+        record
+    }
+
+    /// Write `records` after the magic to a fresh file and read it back.
+    fn read_back(test: &str, records: &[Vec<u8>]) -> io::Result<EventLog> {
+        let path = std::env::temp_dir().join(format!("event-log-{}-{test}", std::process::id()));
+        let mut bytes = MAGIC.to_vec();
+        records.iter().for_each(|record| bytes.extend(record));
+        std::fs::write(&path, bytes)?;
+        let log = EventLog::read(&path);
+        std::fs::remove_file(&path)?;
+        log
+    }
+
+    fn field(section: &crate::report::Section, label: &str) -> String {
+        section
+            .fields
+            .iter()
+            .find(|(name, _)| name == label)
+            .map(|(_, value)| value.to_string())
+            .unwrap_or_else(|| panic!("no field `{label}` in {}", section.title))
+    }
+
+    #[test]
+    fn reads_back_written_records() {
+        let log = read_back(
+            "round-trip",
+            &[
+                name_record(0, "write"),
+                op_record(0, false, 1_000, 500, 100),
+                op_record(0, false, 2_000, 1_500, 200),
+                name_record(1, "batch"),
+                op_record(1, true, 4_000, 2_000, 1_000),
+                op_record(0, true, 7_000, 300, 50),
+            ],
+        )
+        .unwrap();
+        assert_eq!(log.phases, ["write", "batch"]);
+
+        let groups: Vec<_> = log
+            .groups
+            .iter()
+            .map(|g| {
+                (
+                    g.phase,
+                    g.quick_repair,
+                    g.latencies.count(),
+                    g.bytes,
+                    g.first_ns,
+                    g.last_ns,
+                )
+            })
+            .collect();
+        assert_eq!(
+            groups,
+            [
+                (0, false, 2, 300, 1_000, 3_500),
+                (1, true, 1, 1_000, 4_000, 6_000),
+                (0, true, 1, 50, 7_000, 7_300),
+            ]
+        );
+        assert_eq!(
+            log.groups[0].latencies.stats().max_write_time,
+            Duration::from_nanos(1_500)
+        );
+
+        let report = log.analyze();
+        let titles: Vec<_> = report.sections.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(
+            titles,
+            [
+                "write - quick_repair(false)",
+                "batch - quick_repair(true)",
+                "write - quick_repair(true)"
+            ]
+        );
+        assert_eq!(field(&report.sections[0], "Operations"), "2");
+        assert_eq!(field(&report.sections[1], "Operations"), "1");
+    }
+
+    #[test]
+    fn truncated_record_ends_the_log() {
+        let mut truncated = op_record(0, false, 2_000, 1_500, 200);
+        truncated.truncate(10);
+        let log = read_back(
+            "truncated",
+            &[
+                name_record(0, "write"),
+                op_record(0, false, 1_000, 500, 100),
+                truncated,
+            ],
+        )
+        .unwrap();
+        assert_eq!(log.groups.len(), 1);
+        assert_eq!(log.groups[0].latencies.count(), 1);
+    }
+
+    #[test]
+    fn rejects_other_files_and_unknown_tags() {
+        let path = std::env::temp_dir().join(format!("event-log-{}-magic", std::process::id()));
+        std::fs::write(&path, b"not a log").unwrap();
+        assert_eq!(
+            EventLog::read(&path).err().map(|e| e.kind()),
+            Some(io::ErrorKind::InvalidData)
+        );
+        std::fs::remove_file(&path).unwrap();
+
+        let error = read_back("tag", &[vec![0x7f]]).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
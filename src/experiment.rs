@@ -0,0 +1,144 @@
+//! Canonical studies selectable with `--experiment`: each bundles the phases, the run
+//! parameters and the outputs that answer one of the questions we keep asking about
+//! quick_repair, so that a single flag reproduces the study.
+//!
+//! Explicit options still win: `--phases` replaces the experiment's phases, options that
+//! enable a phase add it, `--report` replaces its reports and `--manifest` moves its
+//! manifest. `--workload` and `--preset` apply as usual, so `--preset smoke` shrinks an
+//! experiment to check that it runs.
+
+use crate::config::Config;
+use crate::phase::Phase;
+use crate::report::{ReportFormat, ReportTarget};
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Configurations benchmarked per repeat by the studies that compare latencies.
+const REPEATS: usize = 3;
+/// Timed writes per configuration for the tail-latency study, enough for a stable p99.9.
+const TAIL_WRITES: usize = 100_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Experiment {
+    /// What quick_repair costs per commit: individual and batched writes, the sync
+    /// strategies and where the commit time goes, repeated in shuffled order.
+    CommitLatency,
+    /// What quick_repair saves on reopen: repair after torn writes and unclean shutdowns,
+    /// and the restart path with a cold and a warm page cache.
+    RepairTime,
+    /// How quick_repair shifts the slowest commits: many timed writes, a mixed workload
+    /// and a concurrent checkpoint thread.
+    TailLatency,
+    /// What quick_repair costs on disk: free-page growth under churn, expiring data and
+    /// bulk deletes.
+    SpaceUsage,
+}
+
+impl Experiment {
+    pub const ALL: [Experiment; 4] = [
+        Experiment::CommitLatency,
+        Experiment::RepairTime,
+        Experiment::TailLatency,
+        Experiment::SpaceUsage,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Experiment::CommitLatency => "commit-latency",
+            Experiment::RepairTime => "repair-time",
+            Experiment::TailLatency => "tail-latency",
+            Experiment::SpaceUsage => "space-usage",
+        }
+    }
+
+    /// Phases run unless `--phases` is given.
+    pub fn phases(self) -> &'static [Phase] {
+        match self {
+            Experiment::CommitLatency => &[
+                Phase::Fill,
+                Phase::WriteBench,
+                Phase::Batch,
+                Phase::SyncStrategies,
+                Phase::CommitBreakdown,
+            ],
+            Experiment::RepairTime => &[
+                Phase::Fill,
+                Phase::TornWrites,
+                Phase::ShutdownOpen,
+                Phase::ColdStart,
+            ],
+            Experiment::TailLatency => &[
+                Phase::Fill,
+                Phase::WriteBench,
+                Phase::CheckpointThread,
+                Phase::Mix,
+            ],
+            Experiment::SpaceUsage => {
+                &[Phase::Fill, Phase::FreePages, Phase::Ttl, Phase::BulkDelete]
+            }
+        }
+    }
+
+    /// Apply the experiment's run parameters on top of `config` (after any `--workload`,
+    /// before any `--preset`).
+    pub fn apply(self, config: &mut Config) {
+        match self {
+            Experiment::CommitLatency => {
+                config.repeats = REPEATS;
+                config.shuffle_order = true;
+                config.warmup.until_stable = true;
+            }
+            Experiment::RepairTime | Experiment::SpaceUsage => {}
+            Experiment::TailLatency => {
+                config.benchmark_writes = TAIL_WRITES;
+                config.repeats = REPEATS;
+                config.shuffle_order = true;
+                config.warmup.until_stable = true;
+            }
+        }
+    }
+
+    /// Reports written unless `--report` is given: the console summary, plus Markdown and
+    /// JSON copies named after the experiment in the working directory.
+    pub fn reports(self) -> Vec<ReportTarget> {
+        [
+            (ReportFormat::Console, None),
+            (ReportFormat::Markdown, Some("md")),
+            (ReportFormat::Json, Some("json")),
+        ]
+        .into_iter()
+        .map(|(format, extension)| ReportTarget {
+            format,
+            path: extension.map(|extension| PathBuf::from(format!("{self}.{extension}"))),
+        })
+        .collect()
+    }
+
+    /// Manifest written unless `--manifest` is given.
+    pub fn manifest(self) -> PathBuf {
+        PathBuf::from(format!("{self}.manifest.json"))
+    }
+}
+
+impl fmt::Display for Experiment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Experiment {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Experiment::ALL
+            .into_iter()
+            .find(|experiment| experiment.name() == s)
+            .ok_or_else(|| {
+                format!(
+                    "unknown experiment `{s}`, expected commit-latency, repair-time, tail-latency \
+                     or space-usage"
+                )
+            })
+    }
+}
@@ -0,0 +1,148 @@
+//! Write-delete churn sampling allocated versus free pages, to see how quickly the pages
+//! freed by each commit become reusable under each quick_repair setting.
+
+use crate::config::MIB;
+use crate::payload::ValueGen;
+use crate::report::Section;
+use crate::{
+    BenchDb, BenchmarkStats, StatsAccumulator, TABLE, clock, get_file_size, hooks, record_op,
+};
+use redb::{Database, Error};
+use tracing::{info, info_span};
+
+/// Keys inserted, and oldest keys deleted, per churn transaction.
+pub const CHURN_KEYS: u64 = 100;
+/// Number of page samples taken over the run.
+const SAMPLES: usize = 20;
+
+#[derive(Clone, Copy, Debug)]
+pub struct PageSample {
+    /// Churn transactions committed before the sample.
+    pub txns: usize,
+    pub file_size: u64,
+    pub allocated_pages: u64,
+    /// Pages in the file that are not allocated, i.e. free for reuse.
+    pub free_pages: u64,
+}
+
+impl PageSample {
+    /// Read the page counts through an aborted write transaction.
+    pub fn take(db: &Database, db_path: &str, txns: usize) -> Result<Self, Error> {
+        let write_txn = hooks::begin_write(db)?;
+        let stats = write_txn.stats()?;
+        write_txn.abort()?;
+
+        let file_size = get_file_size(db_path).unwrap_or(0);
+        let file_pages = file_size / stats.page_size() as u64;
+        Ok(Self {
+            txns,
+            file_size,
+            allocated_pages: stats.allocated_pages(),
+            free_pages: file_pages.saturating_sub(stats.allocated_pages()),
+        })
+    }
+}
+
+pub struct FreePageReport {
+    pub churn: BenchmarkStats,
+    pub samples: Vec<PageSample>,
+}
+
+/// Perform `num_txns` transactions on `bench_db` that each append [`CHURN_KEYS`] keys and
+/// delete as many of the oldest ones, sampling page usage periodically.
+pub fn benchmark_free_pages(
+    bench_db: &mut BenchDb,
+    num_txns: usize,
+    values: ValueGen,
+) -> Result<FreePageReport, Error> {
+    let quick_repair = bench_db.quick_repair;
+    let _span = info_span!("free_pages", db_path = %bench_db.path, quick_repair).entered();
+    info!(
+        num_txns,
+        keys_per_txn = CHURN_KEYS,
+        "Tracking free pages under write-delete churn"
+    );
+
+    let db = bench_db.create(
+        Database::builder().set_cache_size(1024 * 1024 * 1024), // 1GB cache
+    )?;
+
+    let sample_every = num_txns.div_ceil(SAMPLES).max(1);
+    let mut samples = vec![PageSample::take(&db, &bench_db.path, 0)?];
+    let mut latencies = StatsAccumulator::new();
+
+    for i in 0..num_txns {
+        let batch: Vec<Vec<u8>> = (bench_db.next_key..)
+            .take(CHURN_KEYS as usize)
+            .map(|key| values.generate(key))
+            .collect();
+
+        let start = clock::now();
+        let mut write_txn = hooks::begin_write(&db)?;
+        write_txn.set_quick_repair(quick_repair);
+        {
+            let mut table = write_txn.open_table(TABLE)?;
+            for value in &batch {
+                table.insert(bench_db.next_key, value.as_slice())?;
+                bench_db.next_key += 1;
+            }
+            for _ in 0..CHURN_KEYS {
+                table.pop_first()?;
+            }
+        }
+        write_txn.commit()?;
+        let duration = start.elapsed();
+        latencies.record(duration);
+        record_op(
+            "free-pages",
+            &bench_db.path,
+            quick_repair,
+            i as u64,
+            2 * CHURN_KEYS as usize,
+            batch.iter().map(Vec::len).sum(),
+            duration,
+        );
+
+        if (i + 1).is_multiple_of(sample_every) || i + 1 == num_txns {
+            let sample = PageSample::take(&db, &bench_db.path, i + 1)?;
+            info!(
+                txns = sample.txns,
+                allocated_pages = sample.allocated_pages,
+                free_pages = sample.free_pages,
+                "Page sample"
+            );
+            samples.push(sample);
+        }
+    }
+
+    Ok(FreePageReport {
+        churn: latencies.stats(),
+        samples,
+    })
+}
+
+impl FreePageReport {
+    pub fn section(&self, title: impl Into<String>) -> Section {
+        let peak = self.samples.iter().map(|s| s.free_pages).max().unwrap_or(0);
+        let first = self.samples.first().map_or(0, |s| s.file_size);
+        let last = self.samples.last().map_or(0, |s| s.file_size);
+        let mut section = self
+            .churn
+            .section(title)
+            .field("Peak free pages", peak)
+            .field(
+                "File growth (MiB)",
+                (last as f64 - first as f64) / MIB as f64,
+            )
+            .table(["txns", "allocated pages", "free pages", "file size (MiB)"]);
+        for sample in &self.samples {
+            section = section.row(vec![
+                sample.txns.into(),
+                sample.allocated_pages.into(),
+                sample.free_pages.into(),
+                (sample.file_size as f64 / MIB as f64).into(),
+            ]);
+        }
+        section
+    }
+}
@@ -0,0 +1,108 @@
+//! Hook points for custom instrumentation from library users: implement [`Hooks`] and
+//! [`install`] it to fire eBPF triggers, external markers or extra measurements around the
+//! benchmark's transactions and phases without forking its loops.
+//!
+//! Hooks run inline on the benchmark threads, inside the timed region for
+//! [`Hooks::on_txn_start`], so they should be cheap; the concurrent fill calls them from two
+//! threads at once. Like [`crate::metrics`], firing is a no-op until [`install`] has been
+//! called.
+
+use redb::{Database, TransactionError, WriteTransaction};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// One committed transaction, as reported by [`crate::record_op`].
+#[derive(Clone, Copy, Debug)]
+pub struct Commit<'a> {
+    pub phase: &'a str,
+    pub db_path: &'a str,
+    pub quick_repair: bool,
+    /// Index of the operation within its phase.
+    pub op: u64,
+    /// Inserts or deletes in the transaction.
+    pub entries: usize,
+    /// Value bytes the transaction carried.
+    pub bytes: usize,
+    pub latency: Duration,
+}
+
+/// Callbacks fired by the benchmark; every method defaults to doing nothing.
+pub trait Hooks: Send + Sync {
+    /// A write transaction of `phase` is about to begin.
+    fn on_txn_start(&self, phase: &str) {
+        let _ = phase;
+    }
+
+    /// A transaction committed; fired for every one, sampled or not.
+    fn on_commit_done(&self, commit: &Commit<'_>) {
+        let _ = commit;
+    }
+
+    /// `phase` ended after `elapsed`, when the next one started or the run finished.
+    fn on_phase_end(&self, phase: &str, elapsed: Duration) {
+        let _ = (phase, elapsed);
+    }
+}
+
+struct State {
+    hooks: Box<dyn Hooks>,
+    /// The running phase and when it started.
+    phase: Mutex<Option<(String, Instant)>>,
+}
+
+static STATE: OnceLock<State> = OnceLock::new();
+
+/// Install `hooks` for the rest of the process. Returns `false`, dropping `hooks`, if hooks
+/// were already installed.
+pub fn install(hooks: impl Hooks + 'static) -> bool {
+    STATE
+        .set(State {
+            hooks: Box::new(hooks),
+            phase: Mutex::new(None),
+        })
+        .is_ok()
+}
+
+fn phase(state: &State) -> String {
+    let phase = state.phase.lock().unwrap_or_else(|e| e.into_inner());
+    phase
+        .as_ref()
+        .map_or_else(String::new, |(name, _)| name.clone())
+}
+
+/// `Database::begin_write`, firing [`Hooks::on_txn_start`] first.
+pub fn begin_write(db: &Database) -> Result<WriteTransaction, TransactionError> {
+    if let Some(state) = STATE.get() {
+        state.hooks.on_txn_start(&phase(state));
+    }
+    db.begin_write()
+}
+
+pub fn commit_done(commit: &Commit<'_>) {
+    if let Some(state) = STATE.get() {
+        state.hooks.on_commit_done(commit);
+    }
+}
+
+/// Replace the running phase with `next`, ending the former.
+fn switch_phase(next: impl FnOnce() -> Option<(String, Instant)>) {
+    if let Some(state) = STATE.get() {
+        let previous = std::mem::replace(
+            &mut *state.phase.lock().unwrap_or_else(|e| e.into_inner()),
+            next(),
+        );
+        if let Some((name, start)) = previous {
+            state.hooks.on_phase_end(&name, start.elapsed());
+        }
+    }
+}
+
+/// Start `phase`, ending the previous one.
+pub fn set_phase(phase: &str) {
+    switch_phase(|| Some((phase.to_owned(), Instant::now())));
+}
+
+/// End the running phase, if any, once the last one is done.
+pub fn end_phase() {
+    switch_phase(|| None);
+}
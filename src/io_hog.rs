@@ -0,0 +1,139 @@
+//! `--io-hog`: competing disk I/O from a background thread during the write benchmark, to
+//! see how each quick_repair mode degrades next to a noisy neighbour on the same device.
+//!
+//! The hog writes fixed-size chunks to a scratch file in the database directory, syncing
+//! each one so the I/O reaches the device instead of the page cache, and wraps around at
+//! [`HOG_FILE_BYTES`] to bound the disk space it takes.
+
+use crate::config::MIB;
+use crate::report::Section;
+use rand::RngCore;
+use std::fs::{self, File};
+use std::io::{self, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Bytes written and synced per hog write.
+const CHUNK_BYTES: usize = MIB as usize;
+/// Size at which the scratch file wraps around to its start.
+const HOG_FILE_BYTES: u64 = 256 * MIB;
+
+#[derive(Clone, Copy, Debug, Default)]
+struct HogStats {
+    bytes: u64,
+    syncs: u64,
+    elapsed: Duration,
+}
+
+/// A running hog, stopped by [`IoHog::stop`].
+pub struct IoHog {
+    path: PathBuf,
+    /// Target rate in bytes per second, `None` for as fast as the device allows.
+    rate: Option<f64>,
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<io::Result<HogStats>>,
+}
+
+pub struct IoHogReport {
+    pub target_rate: Option<f64>,
+    /// Bytes per second actually written and synced.
+    pub achieved_rate: f64,
+    pub syncs: u64,
+    pub elapsed: Duration,
+}
+
+impl IoHog {
+    /// Start writing to a scratch file in `dir` at `rate_mib` MiB/s, or unthrottled if 0.
+    pub fn start(dir: &Path, rate_mib: f64) -> io::Result<Self> {
+        let path = dir.join("io_hog.scratch");
+        let file = File::create(&path)?;
+        let rate = (rate_mib > 0.0).then_some(rate_mib * MIB as f64);
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let stop = stop.clone();
+            thread::Builder::new()
+                .name("io-hog".to_owned())
+                .spawn(move || hog(file, rate, &stop))?
+        };
+        info!(path = %path.display(), rate_mib, "Started background I/O hog");
+        Ok(Self {
+            path,
+            rate,
+            stop,
+            thread,
+        })
+    }
+
+    /// Stop the hog, remove its scratch file and report the I/O it generated.
+    pub fn stop(self) -> io::Result<IoHogReport> {
+        self.stop.store(true, Ordering::Relaxed);
+        let stats = self.thread.join().expect("io-hog thread panicked");
+        if let Err(e) = fs::remove_file(&self.path) {
+            warn!(path = %self.path.display(), error = %e, "Could not remove I/O hog file");
+        }
+        let stats = stats?;
+        let report = IoHogReport {
+            target_rate: self.rate,
+            achieved_rate: stats.bytes as f64 / stats.elapsed.as_secs_f64().max(f64::EPSILON),
+            syncs: stats.syncs,
+            elapsed: stats.elapsed,
+        };
+        info!(
+            achieved_mib = report.achieved_rate / MIB as f64,
+            syncs = report.syncs,
+            "Stopped background I/O hog"
+        );
+        Ok(report)
+    }
+}
+
+fn hog(mut file: File, rate: Option<f64>, stop: &AtomicBool) -> io::Result<HogStats> {
+    // Random contents, so compressing or deduplicating storage cannot absorb the writes.
+    let mut chunk = vec![0; CHUNK_BYTES];
+    rand::rng().fill_bytes(&mut chunk);
+
+    let start = Instant::now();
+    let mut stats = HogStats::default();
+    let mut offset = 0;
+    while !stop.load(Ordering::Relaxed) {
+        if let Some(rate) = rate {
+            // Pace against the start so short sleeps do not accumulate drift.
+            let due = Duration::from_secs_f64(stats.bytes as f64 / rate);
+            if let Some(wait) = due.checked_sub(start.elapsed()) {
+                thread::sleep(wait.min(Duration::from_millis(100)));
+                continue;
+            }
+        }
+        if offset + CHUNK_BYTES as u64 > HOG_FILE_BYTES {
+            file.seek(SeekFrom::Start(0))?;
+            offset = 0;
+        }
+        file.write_all(&chunk)?;
+        file.sync_data()?;
+        offset += CHUNK_BYTES as u64;
+        stats.bytes += CHUNK_BYTES as u64;
+        stats.syncs += 1;
+    }
+    stats.elapsed = start.elapsed();
+    Ok(stats)
+}
+
+impl IoHogReport {
+    pub fn section(&self, title: impl Into<String>) -> Section {
+        Section::new(title)
+            .field(
+                "Target rate (MiB/s)",
+                self.target_rate.map_or_else(
+                    || "unthrottled".to_owned(),
+                    |rate| format!("{:.1}", rate / MIB as f64),
+                ),
+            )
+            .field("Achieved rate (MiB/s)", self.achieved_rate / MIB as f64)
+            .field("Chunk syncs", self.syncs)
+            .field("Duration", self.elapsed)
+    }
+}
@@ -0,0 +1,153 @@
+//! Iteration throughput over the filled table, forwards and in reverse: a scan from either
+//! end, and bounded ranges of [`PAGE`] entries starting or ending at random keys, read with
+//! `rev()` the way paginated newest-first queries read them.
+
+use crate::report::Section;
+use crate::{BenchDb, TABLE, clock, compat};
+use rand::Rng;
+use redb::{AccessGuard, Database, Error, ReadOnlyTable, ReadableTable, StorageError};
+use std::hint::black_box;
+use std::time::Duration;
+use tracing::{info, info_span};
+
+/// Entries read by each scan, at most.
+pub const SCAN_ENTRIES: usize = 1_000_000;
+/// Entries per bounded range.
+pub const PAGE: u64 = 100;
+/// Bounded ranges read per direction.
+pub const PAGES: usize = 1000;
+
+pub struct IterationSample {
+    pub name: &'static str,
+    /// Iterators created: 1 for a scan, one per page for bounded ranges.
+    pub ranges: usize,
+    pub entries: u64,
+    pub elapsed: Duration,
+}
+
+impl IterationSample {
+    pub fn entries_per_second(&self) -> f64 {
+        self.entries as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+pub struct IterationReport {
+    pub samples: Vec<IterationSample>,
+}
+
+type Entry<'a> = Result<(AccessGuard<'a, u64>, AccessGuard<'a, &'static [u8]>), StorageError>;
+
+/// Read every entry of `entries`, returning how many there were.
+fn consume<'a>(entries: impl Iterator<Item = Entry<'a>>) -> Result<u64, Error> {
+    let mut count = 0;
+    for entry in entries {
+        let (key, value) = entry?;
+        black_box((key.value(), value.value().len()));
+        count += 1;
+    }
+    Ok(count)
+}
+
+fn scan(
+    table: &ReadOnlyTable<u64, &'static [u8]>,
+    reverse: bool,
+) -> Result<IterationSample, Error> {
+    let start = clock::now();
+    let entries = if reverse {
+        consume(table.iter()?.rev().take(SCAN_ENTRIES))?
+    } else {
+        consume(table.iter()?.take(SCAN_ENTRIES))?
+    };
+    Ok(IterationSample {
+        name: if reverse { "scan, rev()" } else { "scan" },
+        ranges: 1,
+        entries,
+        elapsed: start.elapsed(),
+    })
+}
+
+fn pages(
+    table: &ReadOnlyTable<u64, &'static [u8]>,
+    keys: u64,
+    reverse: bool,
+) -> Result<IterationSample, Error> {
+    let mut rng = rand::rng();
+    let mut entries = 0;
+    let mut elapsed = Duration::ZERO;
+    for _ in 0..PAGES {
+        let first = rng.random_range(0..keys.saturating_sub(PAGE).max(1));
+        let start = clock::now();
+        let range = table.range(first..first + PAGE)?;
+        entries += if reverse {
+            consume(range.rev())?
+        } else {
+            consume(range)?
+        };
+        elapsed += start.elapsed();
+    }
+    Ok(IterationSample {
+        name: if reverse {
+            "bounded range, rev()"
+        } else {
+            "bounded range"
+        },
+        ranges: PAGES,
+        entries,
+        elapsed,
+    })
+}
+
+/// Scan up to [`SCAN_ENTRIES`] entries of `bench_db` from each end, then read [`PAGES`]
+/// bounded ranges forwards and in reverse, in one read transaction, after one untimed scan.
+pub fn benchmark_iteration(bench_db: &BenchDb) -> Result<IterationReport, Error> {
+    let quick_repair = bench_db.quick_repair;
+    let _span = info_span!("iteration", db_path = %bench_db.path, quick_repair).entered();
+    info!(
+        scan_entries = SCAN_ENTRIES,
+        pages = PAGES,
+        page = PAGE,
+        "Benchmarking iteration"
+    );
+
+    let db = bench_db.create(
+        Database::builder().set_cache_size(1024 * 1024 * 1024), // 1GB cache
+    )?;
+    let read_txn = compat::begin_read(&db)?;
+    let table = read_txn.open_table(TABLE)?;
+    let keys = table.last()?.map_or(0, |(key, _)| key.value() + 1);
+    // Untimed pass, so neither direction pays for loading the pages into the cache.
+    consume(table.iter()?.take(SCAN_ENTRIES))?;
+
+    Ok(IterationReport {
+        samples: vec![
+            scan(&table, false)?,
+            scan(&table, true)?,
+            pages(&table, keys, false)?,
+            pages(&table, keys, true)?,
+        ],
+    })
+}
+
+impl IterationReport {
+    pub fn section(&self, title: impl Into<String>) -> Section {
+        let mut section = Section::new(title).table([
+            "iteration",
+            "entries",
+            "elapsed",
+            "entries per second",
+            "mean per range",
+        ]);
+        for sample in &self.samples {
+            section = section.row(vec![
+                sample.name.into(),
+                sample.entries.into(),
+                sample.elapsed.into(),
+                sample.entries_per_second().into(),
+                (sample.elapsed / sample.ranges.max(1) as u32).into(),
+            ]);
+        }
+        section.note(format!(
+            "Scans stop after {SCAN_ENTRIES} entries; bounded ranges are {PAGE} keys at random offsets"
+        ))
+    }
+}
@@ -0,0 +1,208 @@
+//! Key encodings compared on fresh databases: native `u64` keys against the same numbers
+//! stored big-endian as fixed-width `&[u8; 8]` byte arrays (and as variable-width `&[u8]`
+//! slices, which carry a length), timing inserts, a full scan and point reads, and comparing
+//! how much space the table takes. Big-endian bytes sort like the numbers, so every encoding
+//! holds the same ordered table.
+//!
+//! Values are small, so that the key costs are not hidden behind copying values.
+
+use crate::config::MIB;
+use crate::payload::ValuePattern;
+use crate::report::Section;
+use crate::{BenchDb, clock, get_file_size, hooks, record_op};
+use rand::Rng;
+use rand::seq::SliceRandom;
+use redb::{Database, Error, Key, ReadableTable, ReadableTableMetadata, TableDefinition};
+use std::fs;
+use std::hint::black_box;
+use std::time::Duration;
+use tracing::{info, info_span};
+
+/// Keys inserted per encoding.
+pub const KEYS: u64 = 100_000;
+/// Inserts per transaction.
+const BATCH: usize = 1000;
+/// Point reads per encoding.
+const GETS: usize = 10_000;
+/// Bytes per value.
+const VALUE_BYTES: usize = 32;
+
+const NATIVE_TABLE: TableDefinition<u64, &[u8]> = TableDefinition::new("keys_u64");
+const FIXED_TABLE: TableDefinition<&[u8; 8], &[u8]> = TableDefinition::new("keys_be_array");
+const SLICE_TABLE: TableDefinition<&[u8], &[u8]> = TableDefinition::new("keys_be_slice");
+
+fn native(bytes: &[u8; 8]) -> u64 {
+    u64::from_be_bytes(*bytes)
+}
+
+fn fixed(bytes: &[u8; 8]) -> &[u8; 8] {
+    bytes
+}
+
+fn slice(bytes: &[u8; 8]) -> &[u8] {
+    bytes
+}
+
+pub struct EncodingSample {
+    pub name: &'static str,
+    pub insert: Duration,
+    pub scan: Duration,
+    /// Mean of the point reads.
+    pub get: Duration,
+    /// Key and value bytes in the table.
+    pub stored_bytes: u64,
+    /// Branch pages, key lengths and other overhead.
+    pub metadata_bytes: u64,
+    /// Leaf and branch pages of the table.
+    pub pages: u64,
+    pub file_size: u64,
+}
+
+pub struct KeyEncodingReport {
+    pub samples: Vec<EncodingSample>,
+}
+
+/// Insert `order` into `table` of a fresh database at `path`, keys encoded from their
+/// big-endian bytes with `encode`, then scan and read them back.
+fn run<K: Key + 'static>(
+    name: &'static str,
+    path: &str,
+    bench_db: &BenchDb,
+    table: TableDefinition<K, &[u8]>,
+    encode: for<'a> fn(&'a [u8; 8]) -> K::SelfType<'a>,
+    order: &[u64],
+) -> Result<EncodingSample, Error> {
+    let _ = fs::remove_file(path);
+    let db = Database::builder()
+        .set_cache_size(1024 * 1024 * 1024) // 1GB cache
+        .create(path)?;
+    let value = ValuePattern::Random.generate(VALUE_BYTES);
+
+    let start = clock::now();
+    for (i, batch) in order.chunks(BATCH).enumerate() {
+        let batch_start = clock::now();
+        let mut write_txn = hooks::begin_write(&db)?;
+        write_txn.set_quick_repair(bench_db.quick_repair);
+        {
+            let mut table = write_txn.open_table(table)?;
+            for key in batch {
+                table.insert(encode(&key.to_be_bytes()), value.as_slice())?;
+            }
+        }
+        write_txn.commit()?;
+        record_op(
+            "key-encoding",
+            &bench_db.path,
+            bench_db.quick_repair,
+            i as u64,
+            batch.len(),
+            batch.len() * VALUE_BYTES,
+            batch_start.elapsed(),
+        );
+    }
+    let insert = start.elapsed();
+
+    let read_txn = crate::compat::begin_read(&db)?;
+    let table = read_txn.open_table(table)?;
+    let start = clock::now();
+    for entry in table.iter()? {
+        let (key, value) = entry?;
+        black_box((key.value(), value.value().len()));
+    }
+    let scan = start.elapsed();
+
+    let mut rng = rand::rng();
+    let start = clock::now();
+    for _ in 0..GETS {
+        let key = rng.random_range(0..KEYS).to_be_bytes();
+        black_box(table.get(encode(&key))?.map(|v| v.value().len()));
+    }
+    let get = start.elapsed() / GETS as u32;
+    let stats = table.stats()?;
+    drop(table);
+    drop(read_txn);
+    drop(db);
+
+    Ok(EncodingSample {
+        name,
+        insert,
+        scan,
+        get,
+        stored_bytes: stats.stored_bytes(),
+        metadata_bytes: stats.metadata_bytes(),
+        pages: stats.leaf_pages() + stats.branch_pages(),
+        file_size: get_file_size(path)?,
+    })
+}
+
+/// Compare the key encodings on fresh databases next to `bench_db`, with its quick_repair
+/// setting, inserting the same [`KEYS`] keys in the same shuffled order into each.
+pub fn benchmark_key_encoding(bench_db: &BenchDb) -> Result<KeyEncodingReport, Error> {
+    let quick_repair = bench_db.quick_repair;
+    let _span = info_span!("key_encoding", db_path = %bench_db.path, quick_repair).entered();
+    info!(keys = KEYS, "Benchmarking key encodings");
+
+    let mut order: Vec<u64> = (0..KEYS).collect();
+    order.shuffle(&mut rand::rng());
+    let path = format!("{}.keys", bench_db.path);
+    let samples = (|| {
+        Ok::<_, Error>(vec![
+            run::<u64>("u64", &path, bench_db, NATIVE_TABLE, native, &order)?,
+            run::<&[u8; 8]>(
+                "[u8; 8] big-endian",
+                &path,
+                bench_db,
+                FIXED_TABLE,
+                fixed,
+                &order,
+            )?,
+            run::<&[u8]>(
+                "&[u8] big-endian",
+                &path,
+                bench_db,
+                SLICE_TABLE,
+                slice,
+                &order,
+            )?,
+        ])
+    })();
+    let _ = fs::remove_file(&path);
+    Ok(KeyEncodingReport { samples: samples? })
+}
+
+impl KeyEncodingReport {
+    pub fn section(&self, title: impl Into<String>) -> Section {
+        let per_second = |d: Duration| KEYS as f64 / d.as_secs_f64().max(f64::EPSILON);
+        let baseline = self.samples.first().map_or(1, |s| s.pages.max(1));
+        let mut section = Section::new(title)
+            .field("Keys", KEYS)
+            .field("Value size (bytes)", VALUE_BYTES)
+            .table([
+                "encoding",
+                "inserts per second",
+                "scanned per second",
+                "mean get",
+                "stored bytes per key",
+                "metadata bytes per key",
+                "pages",
+                "pages vs u64",
+                "file size (MiB)",
+            ]);
+        for sample in &self.samples {
+            section = section.row(vec![
+                sample.name.into(),
+                per_second(sample.insert).into(),
+                per_second(sample.scan).into(),
+                sample.get.into(),
+                (sample.stored_bytes as f64 / KEYS as f64).into(),
+                (sample.metadata_bytes as f64 / KEYS as f64).into(),
+                sample.pages.into(),
+                (sample.pages as f64 / baseline as f64).into(),
+                (sample.file_size as f64 / MIB as f64).into(),
+            ]);
+        }
+        section.note(format!(
+            "Keys inserted in shuffled order, {BATCH} per transaction, into a fresh database per encoding"
+        ))
+    }
+}
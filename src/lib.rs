@@ -0,0 +1,654 @@
+//! Benchmark comparing `quick_repair(true)` vs `quick_repair(false)` impact on write performance using a `redb` database.
+
+// `redb::Error` is large, but it is only ever returned once per run.
+#![allow(clippy::result_large_err)]
+
+// The redb release under test, selected by cargo feature (the newest enabled one wins).
+#[cfg(not(any(feature = "redb2", feature = "redb3", feature = "redb4")))]
+compile_error!("enable one of the `redb2`, `redb3` or `redb4` features");
+#[cfg(all(feature = "redb2", not(any(feature = "redb3", feature = "redb4"))))]
+pub extern crate redb2 as redb;
+#[cfg(all(feature = "redb3", not(feature = "redb4")))]
+pub extern crate redb3 as redb;
+#[cfg(feature = "redb4")]
+pub extern crate redb4 as redb;
+
+/// Release series of the redb crate this binary was built against.
+pub const REDB_VERSION: &str = if cfg!(feature = "redb4") {
+    "4.x"
+} else if cfg!(feature = "redb3") {
+    "3.x"
+} else {
+    "2.x"
+};
+
+pub mod alternate;
+pub mod assertion;
+pub mod audit;
+pub mod backend;
+pub mod backup;
+pub mod barrier;
+pub mod bit_rot;
+pub mod bulk_delete;
+pub mod checkpoint;
+pub mod checkpoint_thread;
+pub mod clock;
+pub mod cold_start;
+pub mod commit_breakdown;
+pub mod compare;
+pub mod compat;
+pub mod compression;
+pub mod config;
+pub mod console;
+pub mod crash_matrix;
+pub mod dirty_pages;
+pub mod engine;
+pub mod error;
+pub mod event_log;
+pub mod experiment;
+pub mod free_pages;
+pub mod hooks;
+pub mod io_hog;
+pub mod iteration;
+pub mod key_encoding;
+pub mod manifest;
+pub mod many_tables;
+pub mod metrics;
+pub mod micro_ops;
+pub mod migration;
+pub mod mix;
+pub mod payload;
+pub mod phase;
+pub mod pipeline;
+pub mod plan;
+pub mod platform;
+pub mod plot;
+pub mod preflight;
+pub mod profiling;
+pub mod queue;
+pub mod read_only_open;
+pub mod repair;
+pub mod report;
+pub mod result_line;
+pub mod retry;
+pub mod savepoints;
+pub mod sched;
+pub mod schema;
+pub mod shutdown_open;
+pub mod size_scaling;
+pub mod slo;
+pub mod snapshot;
+pub mod stats;
+pub mod stream;
+pub mod sync_strategy;
+pub mod torn_write;
+pub mod ttl;
+pub mod tui;
+pub mod warmup;
+pub mod watchdog;
+
+use backend::{BackendKind, IoTimes, TimedBackend, TimingHandle};
+use checkpoint::FillProgress;
+use compression::Codec;
+use config::{Config, KeyPattern};
+use error::{BenchError, Context};
+use payload::{ValueGen, ValuePattern, ValueStream};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use redb::{Builder, Database, DatabaseError, Error, ReadableTable, TableDefinition};
+use size_scaling::SizeProbe;
+use slo::OpSample;
+use std::collections::BTreeSet;
+use std::fs;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tracing::{info, info_span, trace_span, warn};
+use warmup::Warmup;
+
+pub use stats::{BenchmarkStats, StatsAccumulator, Volume};
+
+pub const TABLE: TableDefinition<u64, &[u8]> = TableDefinition::new("benchmark_data");
+
+// Configuration
+pub const VALUE_SIZE: usize = 4096; // 4KB per value
+pub const BATCH_SIZE: usize = 10000; // Number of inserts per fill transaction
+pub const FILL_BATCH_BYTES: u64 = 64 * 1024 * 1024; // Default cap on value bytes per fill transaction
+pub const FILL_PROGRESS_BATCHES: u64 = 10; // Fill transactions between progress logs
+pub const BENCHMARK_WRITES: usize = 10000; // Number of writes for benchmarking
+
+/// Mark `phase` as the currently running phase for the metrics endpoint, the watchdog, the
+/// dashboard and the installed [`hooks`].
+pub fn set_phase(phase: &str) {
+    hooks::set_phase(phase);
+    metrics::set_phase(phase);
+    watchdog::set_phase(phase);
+    tui::set_phase(phase);
+}
+
+/// One in how many operations is sampled; see [`set_sample_every`].
+static SAMPLE_EVERY: AtomicU64 = AtomicU64::new(1);
+
+/// Sample only every `every`-th operation: the write benchmark keeps only their latencies
+/// for its statistics, and only they reach the JSON Lines stream and the event log, so that
+/// huge runs do not grow memory and output with every operation. The metrics, the watchdog
+/// and the dashboard still see every operation.
+pub fn set_sample_every(every: u64) {
+    SAMPLE_EVERY.store(every.max(1), Ordering::Relaxed);
+}
+
+/// Whether operation `op` of a phase is sampled.
+pub fn sampled(op: u64) -> bool {
+    op.is_multiple_of(SAMPLE_EVERY.load(Ordering::Relaxed))
+}
+
+/// Report one committed transaction of `entries` inserts or deletes, carrying `bytes` of
+/// values, to the metrics endpoint, the JSON Lines stream, the binary event log, the
+/// watchdog, the dashboard and the installed [`hooks`]; the stream and the event log only
+/// get [`sampled`] ones.
+pub fn record_op(
+    phase: &str,
+    db_path: &str,
+    quick_repair: bool,
+    op: u64,
+    entries: usize,
+    bytes: usize,
+    latency: Duration,
+) {
+    metrics::record_op(phase, quick_repair, latency);
+    if sampled(op) {
+        stream::record_op(phase, db_path, quick_repair, op, entries, bytes, latency);
+        event_log::record_op(phase, quick_repair, bytes, latency);
+    }
+    watchdog::heartbeat();
+    tui::record_op(phase, db_path, quick_repair, bytes, latency);
+    hooks::commit_done(&hooks::Commit {
+        phase,
+        db_path,
+        quick_repair,
+        op,
+        entries,
+        bytes,
+        latency,
+    });
+}
+
+pub fn generate_random_value(size: usize) -> Vec<u8> {
+    ValuePattern::Random.generate(size)
+}
+
+pub fn get_file_size(path: &str) -> Result<u64, std::io::Error> {
+    let metadata = fs::metadata(path)?;
+    Ok(metadata.len())
+}
+
+/// Insert values into `bench_db` until `config.fill_target` is reached, continuing from
+/// `resume` and reporting the progress after every committed batch to `on_batch`. Returns
+/// the next unused key.
+pub fn fill_database(
+    bench_db: &BenchDb,
+    config: &Config,
+    resume: FillProgress,
+    on_batch: impl FnMut(FillProgress),
+) -> Result<u64, BenchError> {
+    fill_database_with_probes(bench_db, config, resume, 0, on_batch).map(|(next_key, _)| next_key)
+}
+
+/// Like [`fill_database`], pausing for `probes` [`size_scaling::probe`]s evenly spaced
+/// through the fill target, the last once it is reached. Returns the next unused key and
+/// the probes taken; with a `resume`, those before it are not repeated.
+pub fn fill_database_with_probes(
+    bench_db: &BenchDb,
+    config: &Config,
+    resume: FillProgress,
+    probes: usize,
+    mut on_batch: impl FnMut(FillProgress),
+) -> Result<(u64, Vec<SizeProbe>), BenchError> {
+    let db_path = bench_db.path.as_str();
+    let target = config.fill_target;
+    let values = config.values;
+    let _span = info_span!("fill", db_path, %target, %values).entered();
+    info!("Filling database");
+
+    let mut builder = Database::builder();
+    builder.set_cache_size(1024 * 1024 * 1024); // 1GB cache
+    config.repair_callback.install(&mut builder);
+    let db = bench_db.create(&mut builder).in_phase("fill", db_path)?;
+
+    let mut key_counter = resume.keys;
+    let mut stream = ValueStream::spawn(values, config.generator_threads, key_counter);
+    let mut total_bytes = resume.bytes;
+    if resume != FillProgress::default() {
+        info!(
+            keys = key_counter,
+            bytes = total_bytes,
+            "Resuming fill from checkpoint"
+        );
+    }
+    let mut batch_counter = 0;
+    let mut taken = Vec::with_capacity(probes);
+    // Probe k of 1..=probes is due once the fill is k / probes done.
+    let mut next_probe =
+        (target.fraction_done(total_bytes, key_counter) * probes as f64).floor() as usize + 1;
+
+    let start_time = clock::now();
+
+    loop {
+        let remaining = target.remaining_entries(total_bytes, key_counter, values.mean_size());
+        if remaining == 0 {
+            break;
+        }
+        let batch_size = remaining
+            .min(config.fill_batch_entries as u64)
+            .min((config.fill_batch_bytes / values.mean_size() as u64).max(1));
+
+        let _batch = trace_span!("batch", batch = batch_counter).entered();
+        let batch_start = clock::now();
+        let op = batch_counter;
+        let write_txn = hooks::begin_write(&db).at_op("fill", db_path, op)?;
+        let mut batch_bytes = 0;
+
+        {
+            let mut table = write_txn.open_table(TABLE).at_op("fill", db_path, op)?;
+
+            for _ in 0..batch_size {
+                let value = stream.next_value(key_counter);
+                table
+                    .insert(key_counter, value)
+                    .at_op("fill", db_path, op)?;
+                key_counter += 1;
+                total_bytes += values.mean_size() as u64;
+                batch_bytes += value.len();
+            }
+        }
+
+        trace_span!("commit")
+            .in_scope(|| write_txn.commit())
+            .at_op("fill", db_path, op)?;
+        record_op(
+            "fill",
+            db_path,
+            false,
+            batch_counter,
+            batch_size as usize,
+            batch_bytes,
+            batch_start.elapsed(),
+        );
+        while next_probe <= probes
+            && target.fraction_done(total_bytes, key_counter) >= next_probe as f64 / probes as f64
+        {
+            let (probe, bytes) = size_scaling::probe(
+                &db,
+                db_path,
+                bench_db.quick_repair,
+                &mut key_counter,
+                values,
+            )
+            .in_phase("fill", db_path)?;
+            total_bytes += bytes;
+            taken.push(probe);
+            next_probe += 1;
+        }
+        on_batch(FillProgress {
+            keys: key_counter,
+            bytes: total_bytes,
+        });
+
+        batch_counter += 1;
+
+        if batch_counter.is_multiple_of(FILL_PROGRESS_BATCHES) {
+            let current_written = total_bytes as f64 / (1024.0 * 1024.0 * 1024.0);
+            let elapsed = start_time.elapsed();
+            if config.fill_stat {
+                let current_size = get_file_size(db_path).unwrap_or(0);
+                metrics::set_file_size(db_path, current_size);
+                let current_gb = current_size as f64 / (1024.0 * 1024.0 * 1024.0);
+                info!(
+                    written_gb = format_args!("{current_written:.2}"),
+                    db_size_gb = format_args!("{current_gb:.2}"),
+                    records = key_counter,
+                    ?elapsed,
+                    "Progress"
+                );
+            } else {
+                info!(
+                    written_gb = format_args!("{current_written:.2}"),
+                    records = key_counter,
+                    ?elapsed,
+                    "Progress"
+                );
+            }
+        }
+    }
+
+    let final_size = get_file_size(db_path).unwrap_or(0);
+    metrics::set_file_size(db_path, final_size);
+    let final_gb = final_size as f64 / (1024.0 * 1024.0 * 1024.0);
+    let elapsed = start_time.elapsed();
+
+    info!(
+        final_size_gb = format_args!("{final_gb:.2}"),
+        records = key_counter,
+        ?elapsed,
+        "Database filled successfully"
+    );
+
+    Ok((key_counter, taken))
+}
+
+/// Insert `value` at `key` in its own transaction, returning the time from `begin_write`
+/// to the end of `commit`.
+pub fn write_single(
+    db: &Database,
+    key: u64,
+    value: &[u8],
+    quick_repair: bool,
+) -> Result<Duration, Error> {
+    let start = clock::now();
+
+    let mut write_txn = hooks::begin_write(db)?;
+    write_txn.set_quick_repair(quick_repair);
+    {
+        let mut table = write_txn.open_table(TABLE)?;
+        table.insert(key, value)?;
+    }
+    trace_span!("commit").in_scope(|| write_txn.commit())?;
+
+    Ok(start.elapsed())
+}
+
+/// One benchmarked database file and the quick_repair setting used for its transactions.
+pub struct BenchDb {
+    pub path: String,
+    pub quick_repair: bool,
+    /// Storage backend every phase opens the file through.
+    pub backend: BackendKind,
+    /// Next unused key; phases inserting fresh keys start here and advance it.
+    pub next_key: u64,
+}
+
+impl BenchDb {
+    pub fn new(path: impl Into<String>, quick_repair: bool) -> Self {
+        Self {
+            path: path.into(),
+            quick_repair,
+            backend: BackendKind::File,
+            next_key: 0,
+        }
+    }
+
+    /// Open or create the database with `builder`, through [`BenchDb::backend`].
+    pub fn create(&self, builder: &mut Builder) -> Result<Database, DatabaseError> {
+        match self.backend {
+            BackendKind::File => builder.create(&self.path),
+            #[cfg(all(target_os = "linux", feature = "io-uring"))]
+            BackendKind::IoUring => builder.create_with_backend(backend::UringBackend::open(
+                std::path::Path::new(&self.path),
+            )?),
+            #[allow(unreachable_patterns)]
+            backend => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                format!("backend {backend} not compiled in"),
+            )
+            .into()),
+        }
+    }
+
+    /// [`BenchDb::create`], with every call to the storage backend counted and timed through
+    /// a [`TimedBackend`]; for redb's own backend, the same file backend `create` uses.
+    pub fn create_timed(
+        &self,
+        builder: &mut Builder,
+    ) -> Result<(Database, TimingHandle), DatabaseError> {
+        match self.backend {
+            BackendKind::File => {
+                let file = fs::OpenOptions::new()
+                    .read(true)
+                    .write(true)
+                    .create(true)
+                    .truncate(false)
+                    .open(&self.path)?;
+                let (backend, handle) = TimedBackend::new(redb::backends::FileBackend::new(file)?);
+                Ok((builder.create_with_backend(backend)?, handle))
+            }
+            #[cfg(all(target_os = "linux", feature = "io-uring"))]
+            BackendKind::IoUring => {
+                let (backend, handle) = TimedBackend::new(backend::UringBackend::open(
+                    std::path::Path::new(&self.path),
+                )?);
+                Ok((builder.create_with_backend(backend)?, handle))
+            }
+            #[allow(unreachable_patterns)]
+            backend => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                format!("backend {backend} not compiled in"),
+            )
+            .into()),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        format!("quick_repair({})", self.quick_repair)
+    }
+}
+
+/// Time `num_writes` single-insert transactions against `bench_db`, after the warmup
+/// writes, passing the latency and completion time of each sampled one to `on_write` as it
+/// commits, with the next unused key, and returning the storage backend calls the timed
+/// writes made and the value bytes they wrote. `keys` picks between fresh keys and
+/// overwrites of existing ones, drawn from an RNG seeded with `seed` so that both databases
+/// overwrite the same keys.
+pub fn benchmark_writes(
+    bench_db: &mut BenchDb,
+    num_writes: usize,
+    warmup: Warmup,
+    values: ValueGen,
+    keys: KeyPattern,
+    seed: u64,
+    mut on_write: impl FnMut(OpSample, u64),
+) -> Result<(IoTimes, u64), BenchError> {
+    let db_path = bench_db.path.as_str();
+    let quick_repair = bench_db.quick_repair;
+    let _span = info_span!("write_bench", db_path, quick_repair).entered();
+    info!(
+        num_writes,
+        warmup_writes = warmup.writes,
+        "Benchmarking writes"
+    );
+
+    let (db, timing) = bench_db
+        .create_timed(
+            Database::builder().set_cache_size(1024 * 1024 * 1024), // 1GB cache
+        )
+        .in_phase("write-bench", db_path)?;
+
+    let start_key = bench_db.next_key
+        + warmup::warm_up(&db, bench_db.next_key, warmup, values, quick_repair)
+            .in_phase("warmup", db_path)?;
+    let io_start = timing.times();
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut next_key = start_key;
+    let mut bytes = 0;
+    // Existing keys rewritten by the run, kept only to read them back when compressing.
+    let mut overwritten = BTreeSet::new();
+
+    for i in 0..num_writes {
+        let key = match keys.overwrite(&mut rng, start_key) {
+            Some(key) => {
+                if values.codec != Codec::None {
+                    overwritten.insert(key);
+                }
+                key
+            }
+            None => {
+                next_key += 1;
+                next_key - 1
+            }
+        };
+        let value = values.generate(key);
+        let duration = trace_span!("txn", i)
+            .in_scope(|| write_single(&db, key, &value, quick_repair))
+            .at_op("write-bench", db_path, i as u64)?;
+        bytes += value.len() as u64;
+        if sampled(i as u64) {
+            let sample = OpSample {
+                index: i,
+                unix_ms: slo::unix_ms(),
+                latency: duration,
+            };
+            on_write(sample, next_key);
+        }
+        record_op(
+            "write",
+            db_path,
+            quick_repair,
+            i as u64,
+            1,
+            value.len(),
+            duration,
+        );
+
+        if (i + 1) % 1000 == 0 {
+            metrics::set_file_size(db_path, get_file_size(db_path).unwrap_or(0));
+            info!(completed = i + 1, total = num_writes, "Completed writes");
+        }
+    }
+    let io = timing.times() - io_start;
+
+    if values.codec != Codec::None {
+        let written = overwritten.into_iter().chain(start_key..next_key);
+        verify_values(&db, written, values).in_phase("write-bench", db_path)?;
+    }
+    bench_db.next_key = next_key;
+
+    Ok((io, bytes))
+}
+
+/// Read back `keys` and check that every value decompresses to the expected size.
+pub fn verify_values(
+    db: &Database,
+    keys: impl IntoIterator<Item = u64>,
+    values: ValueGen,
+) -> Result<(), Error> {
+    let start = clock::now();
+    let read_txn = compat::begin_read(db)?;
+    let table = read_txn.open_table(TABLE)?;
+    let mut stored_bytes = 0u64;
+    let mut raw_bytes = 0u64;
+    let mut count = 0u64;
+
+    for key in keys {
+        let guard = table
+            .get(key)?
+            .ok_or_else(|| Error::Corrupted(format!("key {key} missing on read back")))?;
+        let stored = guard.value();
+        stored_bytes += stored.len() as u64;
+
+        let value = values
+            .codec
+            .decompress(stored, values.max_size)
+            .map_err(Error::Io)?;
+        if !(values.min_size..=values.max_size).contains(&value.len()) {
+            return Err(Error::Corrupted(format!(
+                "key {key} decompressed to {} bytes, expected {}-{}",
+                value.len(),
+                values.min_size,
+                values.max_size
+            )));
+        }
+        raw_bytes += value.len() as u64;
+        count += 1;
+    }
+
+    info!(
+        count,
+        codec = %values.codec,
+        ratio = format_args!(
+            "{:.2}",
+            raw_bytes as f64 / stored_bytes.max(1) as f64
+        ),
+        elapsed = ?start.elapsed(),
+        "Verified compressed values"
+    );
+    Ok(())
+}
+
+/// Time one transaction per entry of `batch_sizes` against `bench_db`, each of that many
+/// fresh inserts.
+pub fn benchmark_batch_writes(
+    bench_db: &mut BenchDb,
+    batch_sizes: &[usize],
+    values: ValueGen,
+) -> Result<BenchmarkStats, Error> {
+    let db_path = bench_db.path.as_str();
+    let quick_repair = bench_db.quick_repair;
+    let _span = info_span!("batch_bench", db_path, quick_repair).entered();
+    let num_batches = batch_sizes.len();
+    let entries: usize = batch_sizes.iter().sum();
+    info!(num_batches, entries, "Benchmarking batch writes");
+
+    let db = bench_db.create(
+        Database::builder().set_cache_size(1024 * 1024 * 1024), // 1GB cache
+    )?;
+
+    let mut stats = StatsAccumulator::new();
+    let mut total_bytes = 0;
+    let mut key_counter = bench_db.next_key;
+
+    for (i, &batch_size) in batch_sizes.iter().enumerate() {
+        let start = clock::now();
+
+        let _txn = trace_span!("txn", i).entered();
+        let mut write_txn = hooks::begin_write(&db)?;
+        write_txn.set_quick_repair(quick_repair);
+        let mut batch_bytes = 0;
+        {
+            let mut table = write_txn.open_table(TABLE)?;
+            for _ in 0..batch_size {
+                let value = values.generate(key_counter);
+                table.insert(key_counter, value.as_slice())?;
+                key_counter += 1;
+                batch_bytes += value.len();
+            }
+        }
+        trace_span!("commit").in_scope(|| write_txn.commit())?;
+
+        let duration = start.elapsed();
+        stats.record(duration);
+        total_bytes += batch_bytes as u64;
+        record_op(
+            "batch",
+            db_path,
+            quick_repair,
+            i as u64,
+            batch_size,
+            batch_bytes,
+            duration,
+        );
+
+        if (i + 1) % 100 == 0 {
+            metrics::set_file_size(db_path, get_file_size(db_path).unwrap_or(0));
+            info!(completed = i + 1, total = num_batches, "Completed batches");
+        }
+    }
+
+    bench_db.next_key = key_counter;
+    Ok(stats
+        .stats()
+        .with_volume(entries as u64, total_bytes, num_batches))
+}
+
+/// Next unused key of an existing benchmark database: one past its largest key.
+pub fn next_unused_key(bench_db: &BenchDb) -> Result<u64, Error> {
+    let db = bench_db.create(&mut Database::builder())?;
+    let read_txn = compat::begin_read(&db)?;
+    let table = read_txn.open_table(TABLE)?;
+    Ok(table.last()?.map_or(0, |(key, _)| key.value() + 1))
+}
+
+pub fn cleanup_db(db_path: &str) {
+    match fs::remove_file(db_path) {
+        Ok(()) => info!(db_path, "Removed database file"),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => warn!(db_path, error = %e, "Could not remove database file"),
+    }
+}
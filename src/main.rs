@@ -1,349 +1,2582 @@
-//! Benchmark comparing `quick_repair(true)` vs `quick_repair(false)` impact on write performance using a `redb` database.
-
-use rand::Rng;
-use redb::{Database, Error, TableDefinition};
-use std::fs;
-use std::time::{Duration, Instant};
-
-const TABLE: TableDefinition<u64, &[u8]> = TableDefinition::new("benchmark_data");
-
-// Configuration
-const VALUE_SIZE: usize = 4096; // 4KB per value
-const BATCH_SIZE: usize = 1000; // Number of inserts per transaction
-const BENCHMARK_WRITES: usize = 10000; // Number of writes for benchmarking
-
-struct BenchmarkStats {
-    total_duration: Duration,
-    avg_write_time: Duration,
-    min_write_time: Duration,
-    max_write_time: Duration,
-    writes_per_second: f64,
+//! Command-line entry point for the quick_repair write benchmark.
+
+// The phase closures return `redb::Error` like the library; see its crate root.
+#![allow(clippy::result_large_err)]
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use spike_redb_quick_repair::assertion::{self, Assertion};
+use spike_redb_quick_repair::backend::{self, BackendKind, IoTimes};
+use spike_redb_quick_repair::checkpoint::{Checkpoint, Checkpointer};
+use spike_redb_quick_repair::clock::{self, ClockSource};
+use spike_redb_quick_repair::compare::{self, RunReport};
+use spike_redb_quick_repair::compression::Codec;
+use spike_redb_quick_repair::config::{
+    BatchSize, CleanupPolicy, Config, FillTarget, GIB, KeyPattern, MIB, Preset, ReadPattern,
+    Workload,
+};
+use spike_redb_quick_repair::console::{ColorChoice, Console, Verbosity};
+use spike_redb_quick_repair::engine::{self, EngineKind};
+use spike_redb_quick_repair::error::{BenchError, Context, IoContext};
+use spike_redb_quick_repair::event_log::{self, EventLog};
+use spike_redb_quick_repair::experiment::Experiment;
+use spike_redb_quick_repair::io_hog::IoHog;
+use spike_redb_quick_repair::manifest::{DbFile, Manifest};
+use spike_redb_quick_repair::mix::{self, Mix};
+use spike_redb_quick_repair::payload::ValuePattern;
+use spike_redb_quick_repair::phase::{self, DbPhase, PerDb, Phase};
+use spike_redb_quick_repair::platform;
+use spike_redb_quick_repair::plot::{self, ChartFormat, PlotData};
+use spike_redb_quick_repair::repair::{self, RepairCallback};
+use spike_redb_quick_repair::report::{Report, ReportTarget, Section, Tag, Value};
+use spike_redb_quick_repair::result_line::{ResultLine, WriteFigures};
+use spike_redb_quick_repair::retry::{self, RetryPolicy};
+use spike_redb_quick_repair::sched::{self, CpuSet, IoPriority};
+use spike_redb_quick_repair::schema::Schema;
+use spike_redb_quick_repair::size_scaling::{SizeProbe, SizeScalingReport};
+use spike_redb_quick_repair::slo::{LatencyObjective, OpSample, SloReport};
+use spike_redb_quick_repair::stats::{MannWhitney, Reservoir};
+use spike_redb_quick_repair::watchdog::{self, StallTimeout};
+use spike_redb_quick_repair::{
+    BENCHMARK_WRITES, BenchDb, BenchmarkStats, StatsAccumulator, alternate, backup, barrier,
+    benchmark_batch_writes, benchmark_writes, bit_rot, bulk_delete, checkpoint_thread, cleanup_db,
+    cold_start, commit_breakdown, crash_matrix, dirty_pages, fill_database_with_probes, free_pages,
+    get_file_size, hooks, iteration, key_encoding, many_tables, metrics, micro_ops, migration,
+    next_unused_key, pipeline, plan, preflight, profiling::Profiler, queue, read_only_open,
+    savepoints, set_phase, set_sample_every, shutdown_open, snapshot, stream, sync_strategy,
+    torn_write, ttl, tui,
+};
+use std::io::IsTerminal;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::thread;
+use tracing::{Span, info, info_span, warn};
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+
+/// Format of the progress log written to stderr
+#[derive(Clone, Copy, Debug)]
+enum LogFormat {
+    Text,
+    Json,
 }
 
-impl BenchmarkStats {
-    fn new(durations: &[Duration]) -> Self {
-        let total_duration: Duration = durations.iter().sum();
-        let count = durations.len() as f64;
-        let avg_write_time = total_duration / durations.len() as u32;
-        let min_write_time = *durations.iter().min().unwrap();
-        let max_write_time = *durations.iter().max().unwrap();
-        let writes_per_second = count / total_duration.as_secs_f64();
+impl FromStr for LogFormat {
+    type Err = String;
 
-        Self {
-            total_duration,
-            avg_write_time,
-            min_write_time,
-            max_write_time,
-            writes_per_second,
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!(
+                "unknown log format `{s}`, expected `text` or `json`"
+            )),
         }
     }
+}
+
+/// Install the global `tracing` subscriber. The filter defaults to `info` (`warn` with `-q`,
+/// `debug` with `-v`) and can be overridden with `RUST_LOG` (e.g. `RUST_LOG=trace` to see
+/// per-transaction spans). With `tui`, log lines go to the dashboard's log pane while it runs.
+fn init_tracing(format: LogFormat, verbosity: Verbosity, tui: bool) {
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(verbosity.log_filter()));
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+    let builder = if tui {
+        builder
+            .with_writer(BoxMakeWriter::new(tui::log_writer))
+            .with_ansi(false)
+    } else {
+        builder.with_writer(BoxMakeWriter::new(std::io::stderr))
+    };
 
-    fn print(&self, label: &str) {
-        println!("\n{}", "=".repeat(60));
-        println!("{}", label);
-        println!("{}", "=".repeat(60));
-        println!("Total duration:      {:?}", self.total_duration);
-        println!("Average write time:  {:?}", self.avg_write_time);
-        println!("Min write time:      {:?}", self.min_write_time);
-        println!("Max write time:      {:?}", self.max_write_time);
-        println!("Writes per second:   {:.2}", self.writes_per_second);
-        println!("{}", "=".repeat(60));
+    match format {
+        LogFormat::Text => builder.init(),
+        LogFormat::Json => builder.json().with_current_span(true).init(),
     }
 }
 
-fn generate_random_value(size: usize) -> Vec<u8> {
-    let mut rng = rand::rng();
-    (0..size).map(|_| rng.random::<u8>()).collect()
+/// Spike to benchmark redb write performance with different quick_repair settings
+#[derive(argh::FromArgs)]
+struct Args {
+    /// target database size in GiB (default: 10)
+    #[argh(option)]
+    target_size_gb: Option<u64>,
+
+    /// target database size in MiB, instead of --target-size-gb
+    #[argh(option)]
+    target_size_mb: Option<u64>,
+
+    /// fill until this many keys are inserted, instead of a size target
+    #[argh(option)]
+    target_entries: Option<u64>,
+
+    /// number of timed single-write transactions per configuration (default: 10000)
+    #[argh(option)]
+    writes: Option<usize>,
+
+    /// data shape: default (4 KiB values), large-blob (1-16 MiB values, at most
+    /// 100 timed writes), tiny (64-128 byte values, 300M keys) or hotspot (timed writes
+    /// overwrite existing keys, 80% of them among the first 1000)
+    #[argh(option)]
+    workload: Option<Workload>,
+
+    /// share of the hotspot workload's writes on the hot keys (default: 0.8)
+    #[argh(option)]
+    hot_fraction: Option<f64>,
+
+    /// number of hot keys in the hotspot workload (default: 1000)
+    #[argh(option)]
+    hot_keys: Option<u64>,
+
+    /// share of the timed writes that overwrite a random existing key instead of inserting
+    /// a fresh one, between 0 and 1 (default: 0)
+    #[argh(option)]
+    overwrite_ratio: Option<f64>,
+
+    /// keep the latency of only one in this many timed writes, and send only those
+    /// operations to --stream and --event-log, for huge runs (default: 1)
+    #[argh(option)]
+    sample_every: Option<u64>,
+
+    /// inserted value contents: random, zeroes, text-like or semi-compressible
+    /// (default: random)
+    #[argh(option)]
+    value_pattern: Option<ValuePattern>,
+
+    /// insert bincode-encoded synthetic records instead of raw bytes, shaped by e.g.
+    /// fields=8,strings=4:64,blobs=2:512,depth=2 (omitted keys take these defaults);
+    /// records derive from --seed and replace the value sizes and pattern
+    #[argh(option)]
+    schema: Option<Schema>,
+
+    /// compress values before insert: none, lz4 or zstd (requires the matching
+    /// cargo feature; default: none)
+    #[argh(option)]
+    compress: Option<Codec>,
+
+    /// maximum entries per fill transaction (default: 10000)
+    #[argh(option)]
+    fill_batch_entries: Option<usize>,
+
+    /// maximum MiB of values per fill transaction (default: 64)
+    #[argh(option)]
+    fill_batch_mb: Option<u64>,
+
+    /// do not stat the database file for the fill progress logs and file size metric
+    #[argh(switch)]
+    fill_skip_stat: bool,
+
+    /// fill the two databases one after the other instead of concurrently, so that
+    /// neither fill competes with the other for the disk
+    #[argh(switch)]
+    sequential_fill: bool,
+
+    /// pause the fill this many times, evenly spaced, to time a burst of commits at the
+    /// size reached, and fit how their latency scales with the file size
+    #[argh(option)]
+    size_probes: Option<usize>,
+
+    /// background threads generating fill values ahead of the writer, 0 to generate them
+    /// inline (default: one per spare core, at most 4)
+    #[argh(option)]
+    generator_threads: Option<usize>,
+
+    /// unmeasured writes per configuration before timing begins (default: 0)
+    #[argh(option)]
+    warmup: Option<u64>,
+
+    /// after the --warmup writes, keep warming up until throughput stabilizes
+    #[argh(switch)]
+    warmup_until_stable: bool,
+
+    /// benchmark each configuration this many times (default: 1)
+    #[argh(option)]
+    repeats: Option<usize>,
+
+    /// randomize the order of the quick_repair configurations within each repeat
+    #[argh(switch)]
+    shuffle_order: bool,
+
+    /// seed for randomized choices such as --shuffle-order (default: random, logged)
+    #[argh(option)]
+    seed: Option<u64>,
+
+    /// remove the database files at exit: never, on-success or always (default: never)
+    #[argh(option)]
+    cleanup: Option<CleanupPolicy>,
+
+    /// parameter preset; explicit options override it. `smoke` runs the whole
+    /// pipeline with a 100 MiB fill and 200 writes, `huge` fills 100 GiB and samples
+    /// one in 10 timed writes
+    #[argh(option)]
+    preset: Option<Preset>,
+
+    /// canonical study to run: commit-latency, repair-time, tail-latency or space-usage,
+    /// each with its own phases, parameters, reports and manifest in the working
+    /// directory; explicit options override it
+    #[argh(option)]
+    experiment: Option<Experiment>,
+
+    /// progress log format: text or json (default: text)
+    #[argh(option, default = "LogFormat::Text")]
+    log_format: LogFormat,
+
+    /// serve Prometheus metrics on this address (e.g. 0.0.0.0:9898)
+    #[argh(option)]
+    metrics_addr: Option<SocketAddr>,
+
+    /// print the planned phases with time and disk usage estimates from a short
+    /// calibration burst in each directory, then exit without running them
+    #[argh(switch)]
+    dry_run: bool,
+
+    /// save fill and write-benchmark progress to this checkpoint file, so an interrupted
+    /// run can be continued with --resume-from
+    #[argh(option)]
+    checkpoint: Option<PathBuf>,
+
+    /// operations (fill batches or timed writes) between checkpoint saves (default: 1000)
+    #[argh(option, default = "1000")]
+    checkpoint_every: usize,
+
+    /// continue the run recorded in this checkpoint file, reusing its databases; progress
+    /// keeps being saved there unless --checkpoint is given
+    #[argh(option)]
+    resume_from: Option<PathBuf>,
+
+    /// run only these comma-separated phases, always in pipeline order: fill, write-bench,
+    /// batch, dirty-pages, many-tables, alternate, barrier, pinned-snapshot, savepoints,
+    /// free-pages, queue, ttl, sync-strategies, commit-breakdown, torn-writes, bit-rot,
+    /// read-only-open, micro-ops, iteration, pipeline, backup, migration, key-encoding,
+    /// shutdown-open, checkpoint-thread, cold-start, mix, retry, bulk-delete or engines
+    /// (default: fill, write-bench and the phases enabled by their own options)
+    #[argh(option, from_str_fn(phase::parse_list))]
+    phases: Option<Vec<Phase>>,
+
+    /// abort the run if no operation completes for this long (e.g. 10m), keeping the
+    /// streamed results and last checkpoint (default: no watchdog)
+    #[argh(option)]
+    stall_timeout: Option<StallTimeout>,
+
+    /// generate competing disk I/O at this many MiB/s (0: unthrottled) during the write
+    /// benchmark, from a background thread writing and syncing a scratch file in the
+    /// database directory
+    #[argh(option)]
+    io_hog: Option<f64>,
+
+    /// clock timing the operations of every phase: instant (default) or tsc, reading the
+    /// x86 time stamp counter directly for lower overhead on sub-microsecond operations
+    #[argh(option, default = "ClockSource::default()")]
+    clock: ClockSource,
+
+    /// repair callback installed wherever a database may need repair: noop, log (default),
+    /// println or sleep:DURATION, sleeping that long on every call
+    #[argh(option)]
+    repair_callback: Option<RepairCallback>,
+
+    /// run the benchmark threads only on these CPUs, e.g. 0-3 or 0,2,4-5 (Linux only)
+    #[argh(option)]
+    pin_cpus: Option<CpuSet>,
+
+    /// nice value for the benchmark threads, from -20 (highest priority, needs
+    /// CAP_SYS_NICE) to 19 (Linux only)
+    #[argh(option)]
+    nice: Option<i32>,
+
+    /// scheduling class and level of the benchmark's disk I/O: realtime:N, best-effort:N (N from 0,
+    /// highest, to 7) or idle (Linux only)
+    #[argh(option)]
+    ionice: Option<IoPriority>,
+
+    /// skip the free disk space check before filling
+    #[argh(switch)]
+    skip_disk_check: bool,
+
+    /// after the write benchmark, run transactions overwriting 1-256 random keys and
+    /// correlate commit latency with the estimated number of dirtied leaf pages
+    #[argh(switch)]
+    dirty_pages: bool,
+
+    /// inserts per transaction of the batch phase: N, uniform:MIN-MAX or
+    /// lognormal:MEAN[,SIGMA], drawn per transaction from --seed (default: 100)
+    #[argh(option)]
+    batch_size: Option<BatchSize>,
+
+    /// after the write benchmark, time transactions inserting one key into each of
+    /// 1, 10, 100 and 1000 distinct tables
+    #[argh(switch)]
+    many_tables: bool,
+
+    /// after the write benchmark, alternate quick_repair(true/false) on successive
+    /// commits to one database and verify the result after reopening
+    #[argh(switch)]
+    alternate: bool,
+
+    /// after the write benchmark, issue Durability::Eventual commits with a durable
+    /// quick_repair barrier commit every N commits, N at least 2, timing both kinds
+    /// separately
+    #[argh(option)]
+    barrier_interval: Option<usize>,
+
+    /// after the write benchmark, overwrite random keys while a read transaction stays
+    /// open, verifying its snapshot and measuring the extra file growth it causes
+    #[argh(switch)]
+    pinned_snapshot: bool,
+
+    /// after the write benchmark, perform N commits that each create a persistent
+    /// savepoint, measuring latency and file growth as they accumulate
+    #[argh(option)]
+    savepoints: Option<usize>,
+
+    /// after the write benchmark, perform N transactions that each append 100 keys and
+    /// delete the 100 oldest, sampling allocated and free pages
+    #[argh(option)]
+    churn: Option<usize>,
+
+    /// after the write benchmark, use a second table as a durable queue, pushing at the
+    /// tail and popping from the head in separate transactions
+    #[argh(switch)]
+    queue: bool,
+
+    /// after the write benchmark, append timestamp-keyed entries to a third table, deleting
+    /// those older than --ttl-window every 100 appends
+    #[argh(switch)]
+    ttl: bool,
+
+    /// retention window of the ttl phase in milliseconds (default: 1000)
+    #[argh(option)]
+    ttl_window: Option<u64>,
+
+    /// after the write benchmark, repeat the timed writes through a custom storage backend
+    /// syncing with fsync, fdatasync and O_DSYNC, next to redb's own file backend
+    #[argh(switch)]
+    sync_strategies: bool,
+
+    /// after the write benchmark, time the single writes through a storage backend wrapper
+    /// and split commit latency into backend writes, syncs, reads and in-process work
+    #[argh(switch)]
+    commit_breakdown: bool,
+
+    /// after the write benchmark, crash mid-commit with a truncated and with a corrupted last
+    /// write, then check that reopening recovers the last committed state
+    #[argh(switch)]
+    torn_writes: bool,
+
+    /// after the write benchmark, flip random bits in closed copies of the databases and
+    /// report what opening, reading and check_integrity() detect
+    #[argh(switch)]
+    bit_rot: bool,
+
+    /// after the write benchmark, time opening each database read-only and reading from it,
+    /// with the file evicted from the page cache and cached
+    #[argh(switch)]
+    read_only_open: bool,
+
+    /// after the write benchmark, time first(), last(), len() and get() of present and
+    /// absent keys one call at a time
+    #[argh(switch)]
+    micro_ops: bool,
+
+    /// after the write benchmark, scan the table from both ends and read bounded ranges
+    /// forwards and with rev(), reporting entries per second
+    #[argh(switch)]
+    iteration: bool,
+
+    /// commit 1000 batches of 100 inserts prepared by worker threads, each in its own
+    /// keyspace, and handed to a single committer, against the same batches prepared inline
+    #[argh(switch)]
+    pipeline: bool,
+
+    /// worker threads preparing batches in the pipeline phase (default: 4)
+    #[argh(option)]
+    pipeline_workers: Option<usize>,
+
+    /// back up each database online, from a read transaction into a new file and as a
+    /// file copy while writers are held off, with a writer committing throughout, then
+    /// time restoring each backup
+    #[argh(switch)]
+    backup: bool,
+
+    /// rewrite every row of a copy of each database into a table with a new value layout,
+    /// in chunked transactions, reporting the total migration time
+    #[argh(switch)]
+    migration: bool,
+
+    /// rows moved per transaction in the migration phase (default: 10000)
+    #[argh(option)]
+    migration_chunk: Option<usize>,
+
+    /// insert, scan and read the same keys as native u64 and as big-endian byte arrays on
+    /// fresh databases, comparing throughput and file size
+    #[argh(switch)]
+    key_encoding: bool,
+
+    /// time opening each database after a clean shutdown and a copy of it after an unclean
+    /// exit, side by side for both quick_repair settings
+    #[argh(switch)]
+    shutdown_open: bool,
+
+    /// make the timed writes as non-durable commits, without and then with a thread making
+    /// an empty durable quick_repair commit every this many milliseconds
+    #[argh(option)]
+    checkpoint_interval: Option<u64>,
+
+    /// restart each database as our service does, with a cold and a warm page cache: open
+    /// it, read --cold-start-reads keys and make --cold-start-writes commits, timed end to end
+    #[argh(switch)]
+    cold_start: bool,
+
+    /// keys read after each open in the cold-start phase, drawn with --read-pattern
+    /// (default: 1000)
+    #[argh(option)]
+    cold_start_reads: Option<usize>,
+
+    /// keys read by the mix and cold-start phases: uniform, or latest[:SKEW] to bias them
+    /// towards the newest keys, the newest fraction p getting p^(1/SKEW) of the reads
+    /// (default: uniform; SKEW defaults to 4)
+    #[argh(option)]
+    read_pattern: Option<ReadPattern>,
+
+    /// single-insert commits after the reads in the cold-start phase (default: 100)
+    #[argh(option)]
+    cold_start_writes: Option<usize>,
+
+    /// run a mix of operations drawn with these weights, each in its own transaction,
+    /// e.g. `insert:60,get:30,delete:5,scan100:5`; operations are insert, update, delete,
+    /// get and scanN (default: insert:60,get:30,delete:5,scan100:5)
+    #[argh(option)]
+    mix: Option<Mix>,
+
+    /// operations per database in the mix phase (default: 10000)
+    #[argh(option)]
+    mix_ops: Option<usize>,
+
+    /// after all other phases, delete 1%, 5% and 10% of the keys in single transactions
+    /// with retain_in and with a remove loop, reporting latency and freed pages
+    #[argh(switch)]
+    bulk_delete: bool,
+
+    /// fail each storage write and sync with a transient error with this probability while
+    /// making --retry-writes commits, reopening and retrying failed ones with backoff
+    /// (default: 0.01)
+    #[argh(option)]
+    fault_rate: Option<f64>,
+
+    /// commits per database in the retry phase (default: 1000)
+    #[argh(option)]
+    retry_writes: Option<usize>,
+
+    /// retries of a failed commit in the retry phase before abandoning it (default: 5)
+    #[argh(option)]
+    retry_max: Option<usize>,
+
+    /// wait before the first retry of a commit in milliseconds, doubling with every further
+    /// retry (default: 10)
+    #[argh(option)]
+    retry_backoff_ms: Option<u64>,
+
+    /// after all redb phases, repeat the fill and timed writes on a fresh store of this
+    /// engine for comparison (repeatable): redb, redb-quick-repair, sqlite-wal,
+    /// sqlite-delete, sled, lmdb, lmdb-nosync, rocksdb or rocksdb-nosync; non-redb engines
+    /// need their cargo feature
+    #[argh(option)]
+    engine: Vec<EngineKind>,
+
+    /// storage backend every phase opens the databases through: file (redb's own, the
+    /// default) or io-uring (experimental, Linux with `--features io-uring`)
+    #[argh(option, default = "BackendKind::File")]
+    backend: BackendKind,
+
+    /// repeat the whole benchmark once per comma-separated directory, e.g. mounts of
+    /// different filesystems, and compare the results per directory
+    #[argh(option, from_str_fn(parse_dirs))]
+    db_dirs: Option<Vec<PathBuf>>,
+
+    /// label recorded as the redb version in the report, e.g. a git revision when testing
+    /// an unreleased redb through `[patch.crates-io]` (default: the release series built in)
+    #[argh(option)]
+    redb_label: Option<String>,
+
+    /// stream one JSON object per completed operation (or fill batch) to this file,
+    /// or to stdout with `-`, which moves the banner and console report to stderr
+    #[argh(option)]
+    stream_jsonl: Option<String>,
+
+    /// only print warnings and a condensed summary
+    #[argh(switch, short = 'q')]
+    quiet: bool,
+
+    /// print debug-level progress logs
+    #[argh(switch, short = 'v')]
+    verbose: bool,
+
+    /// color console output: auto, always or never (default: auto, honoring NO_COLOR)
+    #[argh(option, default = "ColorChoice::Auto")]
+    color: ColorChoice,
+
+    /// count write-benchmark operations slower than this latency objective (e.g. 5ms) and
+    /// list the worst offenders
+    #[argh(option)]
+    slo: Option<LatencyObjective>,
+
+    /// exit with code 2 unless the final write-benchmark stats meet METRIC OP VALUE, e.g.
+    /// "writes_per_second >= 500" or "avg_write_us.true <= 2000"; repeatable
+    #[argh(option, long = "assert")]
+    assertions: Vec<Assertion>,
+
+    /// write the results summary as FORMAT (console, json, csv, markdown, html) to stdout,
+    /// or to PATH with FORMAT:PATH; repeatable (default: console)
+    #[argh(option)]
+    report: Vec<ReportTarget>,
+
+    /// label the run with KEY=VALUE, e.g. disk=nvme0, in every report, the RESULT lines,
+    /// the manifest and the crash-matrix CSV; repeatable
+    #[argh(option, long = "tag")]
+    tags: Vec<Tag>,
+
+    /// record every timed operation to this compact binary event log, for offline
+    /// analysis with the `analyze` subcommand
+    #[argh(option)]
+    event_log: Option<PathBuf>,
+
+    /// write a JSON manifest of the run to this file: configuration, command line, seed,
+    /// binary version and git commit, and the SHA-256 of every database file, hashed
+    /// before cleanup
+    #[argh(option)]
+    manifest: Option<PathBuf>,
+
+    /// sample the benchmark phase with pprof and write a flamegraph per configuration
+    /// (requires the `profiling` feature)
+    #[argh(switch)]
+    profile: bool,
+
+    /// draw a live dashboard of throughput, commit latency, file size and phase progress,
+    /// with the log in a pane of it (requires the `tui` feature and a terminal)
+    #[argh(switch)]
+    tui: bool,
+
+    #[argh(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(argh::FromArgs)]
+#[argh(subcommand)]
+enum Command {
+    Analyze(AnalyzeArgs),
+    CrashMatrix(CrashMatrixArgs),
+    Plot(PlotArgs),
+    Report(ReportArgs),
+}
+
+/// Compute statistics from an event log written with --event-log, without running anything
+#[derive(argh::FromArgs)]
+#[argh(subcommand, name = "analyze")]
+struct AnalyzeArgs {
+    /// event log to analyze
+    #[argh(positional)]
+    path: PathBuf,
 }
 
-fn get_file_size(path: &str) -> Result<u64, std::io::Error> {
-    let metadata = fs::metadata(path)?;
-    Ok(metadata.len())
+/// Kill a writer process after N commits on databases of several sizes under both
+/// quick_repair settings, timing the repair on reopen; uses the first --db-dirs directory
+#[derive(argh::FromArgs)]
+#[argh(subcommand, name = "crash-matrix")]
+struct CrashMatrixArgs {
+    /// database sizes in MiB, comma-separated (default: 64,256,1024)
+    #[argh(option, from_str_fn(crash_matrix::parse_list))]
+    sizes_mb: Option<Vec<u64>>,
+
+    /// commits between the clean close and the crash, comma-separated
+    /// (default: 0,1,10,100,1000)
+    #[argh(option, from_str_fn(crash_matrix::parse_list))]
+    commits: Option<Vec<u64>>,
+
+    /// repair callbacks to compare, comma-separated: noop, log, println or sleep:DURATION
+    /// (default: --repair-callback)
+    #[argh(option, from_str_fn(repair::parse_list))]
+    repair_callbacks: Option<Vec<RepairCallback>>,
+
+    /// crashes per combination (default: 3)
+    #[argh(option, default = "crash_matrix::DEFAULT_TRIALS")]
+    trials: usize,
+
+    /// write one CSV row per crash to this file
+    #[argh(option)]
+    csv: Option<PathBuf>,
 }
 
-fn fill_database(db_path: &str, target_size_gb: u64) -> Result<u64, Error> {
-    println!("\n{}", "=".repeat(60));
-    println!("Filling database: {}", db_path);
-    println!("{}", "=".repeat(60));
+/// Align the fields of JSON reports (--report json:PATH) from prior runs into one table per
+/// section, with relative deltas to the first run, rendered in the --report format
+#[derive(argh::FromArgs)]
+#[argh(subcommand, name = "report")]
+struct ReportArgs {
+    /// compare the given reports (required)
+    #[argh(switch)]
+    compare: bool,
+
+    /// JSON reports to compare, the first being the baseline
+    #[argh(positional)]
+    paths: Vec<PathBuf>,
+}
+
+/// Render throughput, latency CDF and latency-over-time charts from JSON reports
+/// (--report json:PATH) and JSON Lines streams (--stream-jsonl), without running anything
+#[derive(argh::FromArgs)]
+#[argh(subcommand, name = "plot")]
+struct PlotArgs {
+    /// report and stream files to chart; series from several files are labelled with the
+    /// file name
+    #[argh(positional)]
+    paths: Vec<PathBuf>,
+
+    /// directory the charts are written to (default: current directory)
+    #[argh(option, default = "PathBuf::from(\".\")")]
+    out_dir: PathBuf,
+
+    /// chart format: svg (default) or png
+    #[argh(option, default = "ChartFormat::default()")]
+    format: ChartFormat,
+
+    /// stream phases to chart, e.g. write (repeatable; default: all but fill)
+    #[argh(option)]
+    phase: Vec<String>,
+
+    /// font file (TrueType) for chart text (default: a common system font)
+    #[argh(option)]
+    font: Option<PathBuf>,
+}
+
+fn main() -> ExitCode {
+    let args: Args = argh::from_env();
+    match try_main(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e @ BenchError::Assertions(_)) => {
+            eprintln!("{e}");
+            ExitCode::from(assertion::EXIT_CODE)
+        }
+        Err(e) => {
+            tui::stop();
+            eprintln!("Error: {e}");
+            let mut source = std::error::Error::source(&e);
+            while let Some(cause) = source {
+                eprintln!("  caused by: {cause}");
+                source = cause.source();
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn try_main(mut args: Args) -> Result<(), BenchError> {
+    let mut config = Config::default();
+    if let Some(workload) = args.workload {
+        workload.apply(&mut config);
+    }
+    if let Some(experiment) = args.experiment {
+        experiment.apply(&mut config);
+        if args.report.is_empty() {
+            args.report = experiment.reports();
+        }
+        args.manifest.get_or_insert_with(|| experiment.manifest());
+    }
+    if let Some(preset) = args.preset {
+        preset.apply(&mut config);
+    }
+    let fill_targets = [
+        args.target_size_gb.map(|gb| FillTarget::Bytes(gb * GIB)),
+        args.target_size_mb.map(|mb| FillTarget::Bytes(mb * MIB)),
+        args.target_entries.map(FillTarget::Entries),
+    ];
+    match fill_targets.into_iter().flatten().collect::<Vec<_>>()[..] {
+        [] => {}
+        [target] => config.fill_target = target,
+        _ => {
+            return Err(
+                "only one of --target-size-gb, --target-size-mb and --target-entries may be given"
+                    .into(),
+            );
+        }
+    }
+    if args.hot_fraction.is_some() || args.hot_keys.is_some() {
+        let KeyPattern::Hotspot { fraction, hot_keys } = &mut config.keys else {
+            return Err("--hot-fraction and --hot-keys need --workload hotspot".into());
+        };
+        if let Some(f) = args.hot_fraction {
+            if !(0.0..=1.0).contains(&f) {
+                return Err("--hot-fraction must be between 0 and 1".into());
+            }
+            *fraction = f;
+        }
+        if let Some(n) = args.hot_keys {
+            *hot_keys = n.max(1);
+        }
+    }
+    if let Some(ratio) = args.overwrite_ratio {
+        if !(0.0..=1.0).contains(&ratio) {
+            return Err("--overwrite-ratio must be between 0 and 1".into());
+        }
+        if matches!(config.keys, KeyPattern::Hotspot { .. }) {
+            return Err("--overwrite-ratio cannot be combined with --workload hotspot".into());
+        }
+        config.keys = KeyPattern::Overwrite { ratio };
+    }
+    if let Some(reads) = args.read_pattern {
+        config.reads = reads;
+    }
+    if let Some(callback) = args.repair_callback {
+        config.repair_callback = callback;
+    }
+    if let Some(every) = args.sample_every {
+        if every == 0 {
+            return Err("--sample-every must be at least 1".into());
+        }
+        config.sample_every = every;
+    }
+    if config.sample_every > 1 && (args.checkpoint.is_some() || args.resume_from.is_some()) {
+        return Err(
+            "--checkpoint and --resume-from need every timed write, not --sample-every".into(),
+        );
+    }
+    set_sample_every(config.sample_every);
+    for (i, tag) in args.tags.iter().enumerate() {
+        if args.tags[..i].iter().any(|seen| seen.key == tag.key) {
+            return Err(format!("--tag {} is given twice", tag.key).into());
+        }
+    }
+    if let Some(pattern) = args.value_pattern {
+        config.values.pattern = pattern;
+    }
+    if let Some(codec) = args.compress {
+        config.values.codec = codec;
+    }
+    if let Some(entries) = args.fill_batch_entries {
+        config.fill_batch_entries = entries.max(1);
+    }
+    if let Some(mb) = args.fill_batch_mb {
+        config.fill_batch_bytes = mb.max(1) * MIB;
+    }
+    config.fill_stat &= !args.fill_skip_stat;
+    config.fill_concurrent &= !args.sequential_fill;
+    if let Some(threads) = args.generator_threads {
+        config.generator_threads = threads;
+    }
+    if let Some(writes) = args.writes {
+        if writes == 0 {
+            return Err("--writes must be at least 1".into());
+        }
+        config.benchmark_writes = writes;
+    }
+    if let Some(warmup) = args.warmup {
+        config.warmup.writes = warmup;
+    }
+    config.warmup.until_stable |= args.warmup_until_stable;
+    if let Some(repeats) = args.repeats {
+        if repeats == 0 {
+            return Err("--repeats must be at least 1".into());
+        }
+        config.repeats = repeats;
+    }
+    config.shuffle_order |= args.shuffle_order;
+    if let Some(seed) = args.seed {
+        config.seed = seed;
+    }
+    if let Some(cleanup) = args.cleanup {
+        config.cleanup = cleanup;
+    }
+    if let Some(schema) = args.schema {
+        if args.value_pattern.is_some() {
+            return Err("--schema replaces --value-pattern; give only one".into());
+        }
+        config.values = config.values.with_schema(Schema {
+            seed: config.seed,
+            ..schema
+        });
+    }
+
+    let verbosity = match (args.quiet, args.verbose) {
+        (false, false) => Verbosity::Normal,
+        (true, false) => Verbosity::Quiet,
+        (false, true) => Verbosity::Verbose,
+        (true, true) => return Err("--quiet and --verbose are mutually exclusive".into()),
+    };
+    init_tracing(args.log_format, verbosity, args.tui);
+
+    if args.profile && !spike_redb_quick_repair::profiling::AVAILABLE {
+        return Err("--profile requires building with `--features profiling`".into());
+    }
+    if args.tui && !tui::AVAILABLE {
+        return Err("--tui requires building with `--features tui`".into());
+    }
+    if args.tui && !std::io::stdout().is_terminal() {
+        return Err("--tui needs stdout to be a terminal".into());
+    }
+    if args.tui && args.stream_jsonl.as_deref() == Some("-") {
+        return Err("--tui draws on stdout; give --stream-jsonl a file".into());
+    }
+    if !config.values.codec.available() {
+        return Err(format!(
+            "--compress {} requires building with `--features {}`",
+            config.values.codec, config.values.codec
+        )
+        .into());
+    }
+
+    if !args.backend.available() {
+        return Err(format!(
+            "--backend {} requires Linux and building with `--features {}`",
+            args.backend, args.backend
+        )
+        .into());
+    }
+    if let Some(kind) = args.engine.iter().find(|kind| !kind.available()) {
+        return Err(format!(
+            "--engine {kind} requires building with `--features {}`",
+            kind.feature().unwrap_or_default()
+        )
+        .into());
+    }
+
+    if args.io_hog.is_some_and(|rate| rate.is_nan() || rate < 0.0) {
+        return Err("--io-hog takes a rate in MiB/s, or 0 for unthrottled".into());
+    }
+
+    let phases = selected_phases(&args)?;
+    if args.barrier_interval.is_some_and(|interval| interval < 2) {
+        return Err(
+            "--barrier-interval must be at least 2 so that some commits are eventual".into(),
+        );
+    }
+    if phases.contains(&Phase::Alternate) && config.benchmark_writes < 2 {
+        return Err(
+            "the alternate phase needs at least 2 --writes, one per quick_repair mode".into(),
+        );
+    }
+    if args.size_probes.is_some_and(|probes| probes < 2) {
+        return Err("--size-probes needs at least 2 probes to fit a trend".into());
+    }
+    if args.size_probes.is_some() && !phases.contains(&Phase::Fill) {
+        return Err("--size-probes probes the fill phase, which is not run".into());
+    }
+    if args.read_pattern.is_some()
+        && !phases.contains(&Phase::Mix)
+        && !phases.contains(&Phase::ColdStart)
+    {
+        return Err(
+            "--read-pattern draws the reads of the mix and cold-start phases, neither of which \
+             is run"
+                .into(),
+        );
+    }
+    if args
+        .fault_rate
+        .is_some_and(|rate| !(rate > 0.0 && rate < 1.0))
+    {
+        return Err("--fault-rate must be above 0 and below 1".into());
+    }
+    if !args.assertions.is_empty() && !phases.contains(&Phase::WriteBench) {
+        return Err("--assert checks the write-bench phase, which is not run".into());
+    }
+    let dirs = args.db_dirs.clone().unwrap_or_else(|| vec![PathBuf::new()]);
+    if (args.checkpoint.is_some() || args.resume_from.is_some()) && dirs.len() > 1 {
+        return Err(
+            "--checkpoint and --resume-from cover a single directory, not --db-dirs".into(),
+        );
+    }
+    // Subcommands are dispatched before the benchmark's own setup below: CPU pinning, the
+    // clock, the dry run, the metrics server and the stream and event-log files.
+    if let Some(Command::Plot(plot)) = &args.command {
+        if !plot::AVAILABLE {
+            return Err("the plot subcommand requires building with `--features plot`".into());
+        }
+        if plot.paths.is_empty() {
+            return Err("plot needs at least one report or stream file".into());
+        }
+        let data = PlotData::load(&plot.paths, &plot.phase)
+            .io_context(|| "reading the files to plot".to_owned())?;
+        plot::render(&data, &plot.out_dir, plot.format, plot.font.as_deref())
+            .io_context(|| format!("rendering charts into {}", plot.out_dir.display()))?;
+        return Ok(());
+    }
+
+    if let Some(Command::Report(report_args)) = &args.command {
+        if !report_args.compare || report_args.paths.len() < 2 {
+            return Err("report takes --compare and at least two JSON reports".into());
+        }
+        let runs = report_args
+            .paths
+            .iter()
+            .map(|path| RunReport::load(path).io_context(|| format!("reading {}", path.display())))
+            .collect::<Result<Vec<_>, _>>()?;
+        emit_report(compare::compare(&runs), &args, verbosity)
+            .io_context(|| "writing the report".to_owned())?;
+        return Ok(());
+    }
+
+    if let Some(Command::Analyze(analyze)) = &args.command {
+        let report = EventLog::read(&analyze.path)
+            .io_context(|| format!("reading {}", analyze.path.display()))?
+            .analyze();
+        emit_report(report, &args, verbosity).io_context(|| "writing the report".to_owned())?;
+        return Ok(());
+    }
+
+    if let Some(Command::CrashMatrix(matrix_args)) = &args.command {
+        let spec = crash_matrix::MatrixSpec {
+            sizes_mb: matrix_args
+                .sizes_mb
+                .clone()
+                .unwrap_or(crash_matrix::DEFAULT_SIZES_MB.to_vec()),
+            commits: matrix_args
+                .commits
+                .clone()
+                .unwrap_or(crash_matrix::DEFAULT_COMMITS.to_vec()),
+            callbacks: matrix_args
+                .repair_callbacks
+                .clone()
+                .unwrap_or(vec![config.repair_callback]),
+            trials: matrix_args.trials.max(1),
+        };
+        let matrix = crash_matrix::run(&spec, &config, &dirs[0])?;
+        if let Some(path) = &matrix_args.csv {
+            std::fs::File::create(path)
+                .and_then(|file| matrix.write_csv(std::io::BufWriter::new(file), &args.tags))
+                .io_context(|| format!("writing {}", path.display()))?;
+        }
+        emit_report(matrix.report(), &args, verbosity)
+            .io_context(|| "writing the report".to_owned())?;
+        return Ok(());
+    }
 
-    let db = Database::builder()
-        .set_cache_size(1024 * 1024 * 1024) // 1GB cache
-        .set_repair_callback(move |session| {
-            println!("Repair progress: {:.2}%", session.progress() * 100.0);
+    let mut resume = args
+        .resume_from
+        .as_deref()
+        .map(|path| {
+            Checkpoint::load(path).io_context(|| format!("reading checkpoint {}", path.display()))
         })
-        .create(db_path)?;
+        .transpose()?;
+    if let Some(checkpoint) = &resume {
+        checkpoint.check(&config)?;
+        config.seed = checkpoint.seed;
+        // Schema records derive from the seed, so they follow the one being resumed.
+        if let Some(schema) = &mut config.values.schema {
+            schema.seed = checkpoint.seed;
+        }
+    }
+
+    // Before any other thread is spawned, so that they all inherit these.
+    if let Some(cpus) = &args.pin_cpus {
+        sched::pin_cpus(cpus).io_context(|| format!("pinning to CPUs {cpus}"))?;
+    }
+    if let Some(nice) = args.nice {
+        sched::set_nice(nice).io_context(|| format!("setting nice value {nice}"))?;
+    }
+    if let Some(priority) = args.ionice {
+        sched::set_io_priority(priority)
+            .io_context(|| format!("setting I/O priority {priority}"))?;
+    }
 
-    let target_bytes = target_size_gb * 1024 * 1024 * 1024;
-    let mut key_counter = 0u64;
-    let mut total_bytes = 0u64;
-    let mut batch_counter = 0;
+    clock::select(args.clock)?;
 
-    let start_time = Instant::now();
+    if args.dry_run {
+        let report = dry_run(&args, &config, &phases, &dirs)?;
+        emit_report(report, &args, verbosity).io_context(|| "writing the report".to_owned())?;
+        return Ok(());
+    }
 
-    while total_bytes < target_bytes {
-        let write_txn = db.begin_write()?;
+    if let Some(addr) = args.metrics_addr {
+        metrics::serve(addr).io_context(|| format!("serving metrics on {addr}"))?;
+    }
+    if let Some(path) = &args.stream_jsonl {
+        stream::open(path).io_context(|| format!("opening {path}"))?;
+    }
+    if let Some(path) = &args.event_log {
+        event_log::open(path).io_context(|| format!("opening {}", path.display()))?;
+    }
 
+    if let Some(timeout) = args.stall_timeout {
+        watchdog::start(timeout);
+    }
+
+    let (mut out, is_terminal) = stream::human_output();
+    let console = Console::new(args.color, verbosity, is_terminal);
+    if !console.quiet() && !args.tui {
+        console
+            .banner(
+                &mut out,
+                "REDB WRITE PERFORMANCE BENCHMARK",
+                &[
+                    "Comparing set_quick_repair(true) vs set_quick_repair(false)",
+                    &format!("redb {}", redb_label(&args)),
+                ],
+            )
+            .io_context(|| "writing the banner".to_owned())?;
+    }
+
+    if args.tui {
+        let names: Vec<&str> = phases.iter().map(|phase| phase.name()).collect();
+        tui::start(&names).io_context(|| "starting the dashboard".to_owned())?;
+    }
+
+    info!(
+        seed = config.seed,
+        values = %config.values,
+        codec = %config.values.codec,
+        "Run configuration"
+    );
+
+    let mut runs = Vec::with_capacity(dirs.len());
+    let mut preserved = Vec::new();
+    let mut manifest_dbs = Vec::new();
+    let mut result = Ok(());
+    // What a run that filled the disk got done, reported before its error.
+    let mut partial = None;
+    for dir in &dirs {
+        let _dir = info_span!("db_dir", dir = %dir.display()).entered();
+        let mut bench_dbs = [
+            BenchDb::new(
+                dir.join("benchmark_quick_repair_false.redb")
+                    .display()
+                    .to_string(),
+                false,
+            ),
+            BenchDb::new(
+                dir.join("benchmark_quick_repair_true.redb")
+                    .display()
+                    .to_string(),
+                true,
+            ),
+        ];
+
+        // Databases left behind by a previous run would skew the fill phase, unless they are
+        // the ones being resumed or the fill is skipped
+        let reuse = resume.is_some() || !phases.contains(&Phase::Fill);
+        for bench_db in bench_dbs.iter().filter(|_| !reuse) {
+            if let Ok(size) = get_file_size(&bench_db.path) {
+                warn!(
+                    db_path = %bench_db.path,
+                    size_mib = size / MIB,
+                    "Removing database file left by a previous run"
+                );
+                cleanup_db(&bench_db.path);
+            }
+        }
+
+        for bench_db in &mut bench_dbs {
+            bench_db.backend = args.backend;
+        }
+
+        if let Some(bench_db) = bench_dbs
+            .iter()
+            .find(|db| reuse && get_file_size(&db.path).is_err())
         {
-            let mut table = write_txn.open_table(TABLE)?;
+            result = Err(format!(
+                "{} is missing; resuming or skipping the fill phase needs the databases of a \
+                 previous run (kept with --cleanup never)",
+                bench_db.path
+            )
+            .into());
+            break;
+        }
+
+        let mut checkpointer = Checkpointer::new(
+            args.checkpoint.clone().or_else(|| args.resume_from.clone()),
+            args.checkpoint_every,
+            resume.take().unwrap_or_else(|| Checkpoint::new(&config)),
+        );
+        let run_result = run(
+            &args,
+            &config,
+            &phases,
+            dir,
+            &mut bench_dbs,
+            &mut checkpointer,
+        )
+        .map_err(|e| {
+            if !e.is_disk_full() {
+                return e;
+            }
+            let (report, suggestion) =
+                disk_full_report(&e, &config, dir, &bench_dbs, &checkpointer.state);
+            partial = Some(report);
+            BenchError::DiskFull {
+                dir: dir.display().to_string(),
+                suggestion,
+                source: Box::new(e),
+            }
+        });
 
-            for _ in 0..BATCH_SIZE {
-                let value = generate_random_value(VALUE_SIZE);
-                table.insert(key_counter, value.as_slice())?;
-                key_counter += 1;
-                total_bytes += VALUE_SIZE as u64;
+        if args.manifest.is_some() {
+            for bench_db in &bench_dbs {
+                match DbFile::hash(&bench_db.path, bench_db.quick_repair) {
+                    Ok(file) => manifest_dbs.push(file),
+                    Err(e) => warn!(
+                        db_path = %bench_db.path,
+                        error = %e,
+                        "Could not hash database file for the manifest"
+                    ),
+                }
             }
         }
 
-        write_txn.commit()?;
+        // A failed checkpointed run keeps its databases so that it can be resumed
+        let resumable = run_result.is_err() && checkpointer.saves();
+        if config.cleanup.should_remove(run_result.is_ok()) && !resumable {
+            for bench_db in &bench_dbs {
+                cleanup_db(&bench_db.path);
+            }
+        } else {
+            preserved.extend(bench_dbs.iter().rev().map(|bench_db| bench_db.path.clone()));
+        }
 
-        batch_counter += 1;
+        match run_result {
+            Ok(outcome) => runs.push((dir, outcome)),
+            Err(e) => {
+                result = Err(e);
+                break;
+            }
+        }
+    }
+    tui::stop();
+    if !preserved.is_empty() {
+        let (mut out, _) = stream::human_output();
+        let _ = writeln!(out, "\nDatabase files preserved for inspection:");
+        for path in &preserved {
+            let _ = writeln!(out, "  - {path}");
+        }
+    }
+    if let Err(e) = event_log::flush() {
+        warn!(error = %e, "Could not write the event log");
+    }
+    if let Some(path) = &args.manifest {
+        let manifest = Manifest {
+            command_line: std::env::args().collect(),
+            redb: redb_label(&args),
+            phases: phases.clone(),
+            databases: manifest_dbs,
+            succeeded: result.is_ok(),
+            tags: args.tags.clone(),
+        };
+        let written = manifest
+            .write(&config, path)
+            .io_context(|| format!("writing the manifest {}", path.display()));
+        if result.is_ok() {
+            written?;
+        } else if let Err(e) = written {
+            warn!(error = %e, "Could not write the manifest");
+        }
+    }
+    if let Some(report) = partial
+        && let Err(e) = emit_report(report, &args, verbosity)
+    {
+        warn!(error = %e, "Could not write the partial report");
+    }
+    result?;
 
-        if batch_counter % 100 == 0 {
-            let current_size = get_file_size(db_path).unwrap_or(0);
-            let current_written = total_bytes as f64 / (1024.0 * 1024.0 * 1024.0);
-            let current_gb = current_size as f64 / (1024.0 * 1024.0 * 1024.0);
-            let elapsed = start_time.elapsed();
-            println!(
-                "Progress: {:.2} GB written, DB size {:.2} GB, {} records, elapsed: {:?}",
-                current_written, current_gb, key_counter, elapsed
-            );
+    let mut report = match runs.as_slice() {
+        [(_, outcome)] => outcome.report.clone(),
+        _ => compare_dirs(&runs),
+    };
+    let mut outcomes = Vec::new();
+    for (dir, outcome) in &runs {
+        let run = if runs.len() > 1 {
+            dir.display().to_string()
+        } else {
+            String::new()
+        };
+        for check in &args.assertions {
+            outcomes.extend(outcome.individual.iter().flat_map(|s| check.check(&run, s)));
         }
     }
+    if !outcomes.is_empty() {
+        report.push(assertion::section("Assertions", &outcomes));
+    }
+    emit_report(report, &args, verbosity).io_context(|| "writing the report".to_owned())?;
+    // On stderr with the logs, leaving stdout to the report.
+    for (_, outcome) in &runs {
+        for line in &outcome.results {
+            eprintln!("{line}");
+        }
+    }
+    let failed: Vec<String> = outcomes
+        .iter()
+        .filter(|outcome| !outcome.passed)
+        .map(|outcome| outcome.to_string())
+        .collect();
+    if !failed.is_empty() {
+        return Err(BenchError::Assertions(failed));
+    }
+    Ok(())
+}
 
-    let final_size = get_file_size(db_path).unwrap_or(0);
-    let final_gb = final_size as f64 / (1024.0 * 1024.0 * 1024.0);
-    let elapsed = start_time.elapsed();
+/// Describe a run in `dir` that failed with `error` because the disk filled up: where it
+/// stopped, how far each database got, the write latencies timed so far, and a fill target
+/// that should fit in the space the run had. Returns the report and the suggestion.
+fn disk_full_report(
+    error: &BenchError,
+    config: &Config,
+    dir: &Path,
+    bench_dbs: &[BenchDb; 2],
+    progress: &Checkpoint,
+) -> (Report, String) {
+    let free = platform::available_space(dir).ok();
+    // Occupied rather than apparent sizes: redb preallocates, and a full disk is what the
+    // files occupy.
+    let sizes = bench_dbs
+        .each_ref()
+        .map(|bench_db| platform::allocated_size(Path::new(&bench_db.path)).unwrap_or(0));
+    // File bytes per value byte, as measured by the fill that got furthest.
+    let file_overhead = sizes
+        .iter()
+        .zip(&progress.dbs)
+        .filter(|(_, db)| db.fill.bytes > 0)
+        .map(|(&size, db)| size as f64 / db.fill.bytes as f64)
+        .reduce(f64::max)
+        .unwrap_or(preflight::FILE_OVERHEAD)
+        .max(1.0);
+    let space = free.map(|free| free + sizes.iter().sum::<u64>());
+    let suggestion =
+        match space.and_then(|space| preflight::fitting_fill_bytes(config, space, file_overhead)) {
+            Some(bytes) if bytes >= MIB => format!(
+                "a fill target of --target-size-mb {} or less should fit, or free up space",
+                bytes / MIB
+            ),
+            _ => format!("free up space in {} before running again", dir.display()),
+        };
 
-    println!("\nDatabase filled successfully!");
-    println!("Final size: {:.2} GB", final_gb);
-    println!("Total records: {}", key_counter);
-    println!("Time taken: {:?}", elapsed);
+    let mut section = Section::new("Disk Full")
+        .field("Directory", dir.display().to_string())
+        .field("Failed", error.to_string())
+        .field("Fill target", config.fill_target.to_string())
+        .field(
+            "Free space (MiB)",
+            free.map_or_else(|| "unknown".to_owned(), |free| (free / MIB).to_string()),
+        )
+        .field("File bytes per value byte", file_overhead)
+        .field("Suggestion", suggestion.clone())
+        .table([
+            "quick_repair",
+            "on disk (MiB)",
+            "fill entries",
+            "fill values (MiB)",
+            "fill done",
+            "timed writes",
+        ]);
+    for ((bench_db, size), db) in bench_dbs.iter().zip(sizes).zip(&progress.dbs) {
+        section = section.row(vec![
+            bench_db.quick_repair.to_string().into(),
+            (size / MIB).into(),
+            db.fill.keys.into(),
+            (db.fill.bytes / MIB).into(),
+            if db.fill_done { "yes" } else { "no" }.into(),
+            db.latencies.count().into(),
+        ]);
+    }
+    let mut report = Report::new("PARTIAL RESULTS (DISK FULL)");
+    report.push(section.note("The run stopped at the first write the filesystem refused"));
+    for (bench_db, db) in bench_dbs.iter().zip(&progress.dbs) {
+        if !db.latencies.is_empty() {
+            report.push(db.latencies.stats().section(format!(
+                "Individual Writes (partial) - quick_repair({})",
+                bench_db.quick_repair
+            )));
+        }
+    }
+    (report, suggestion)
+}
+
+/// Calibrate in every directory and describe what the run would do there.
+fn dry_run(
+    args: &Args,
+    config: &Config,
+    phases: &[Phase],
+    dirs: &[PathBuf],
+) -> Result<Report, BenchError> {
+    let mut template = BenchDb::new(String::new(), false);
+    template.backend = args.backend;
 
-    Ok(key_counter)
+    let mut report = Report::new("DRY RUN PLAN");
+    for dir in dirs {
+        let suffix = if dirs.len() > 1 {
+            format!(" - {}", dir.display())
+        } else {
+            String::new()
+        };
+        let calibration = plan::calibrate(dir, config, &template)?;
+        let planned = planned_phases(args, config, phases, &calibration);
+        let dir = if dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            dir
+        };
+        report.push(calibration.section(format!("Calibration{suffix}")));
+        report.push(
+            plan::Plan::new(dir, config, &calibration, planned).section(format!("Plan{suffix}")),
+        );
+    }
+    Ok(report)
 }
 
-fn benchmark_writes(
-    db_path: &str,
-    start_key: u64,
-    num_writes: usize,
-    quick_repair: bool,
-) -> Result<BenchmarkStats, Error> {
-    println!("\n{}", "=".repeat(60));
-    println!(
-        "Benchmarking writes on: {} (quick_repair={})",
-        db_path, quick_repair
-    );
-    println!("Number of writes: {}", num_writes);
-    println!("{}", "=".repeat(60));
+/// What `run` would execute for `phases`. Phases made of single-write-sized commits are
+/// estimated from the calibrated write latency; the others are not estimated.
+fn planned_phases(
+    args: &Args,
+    config: &Config,
+    phases: &[Phase],
+    calibration: &plan::Calibration,
+) -> Vec<plan::PlannedPhase> {
+    let phase =
+        |name: &str, detail: String, estimate: Option<std::time::Duration>| plan::PlannedPhase {
+            name: name.to_owned(),
+            detail,
+            estimate,
+        };
+    let writes = config.benchmark_writes as u64;
+    let mut planned = Vec::new();
+    if phases.contains(&Phase::Fill) {
+        planned.push(phase(
+            "fill",
+            format!("{} into each database", config.fill_target),
+            Some(calibration.fill_time(config)),
+        ));
+    }
+    if phases.contains(&Phase::WriteBench) {
+        planned.push(phase(
+            "write-bench",
+            format!(
+                "{} × ({} warmup + {writes} timed writes) per database",
+                config.repeats, config.warmup.writes
+            ),
+            Some(calibration.commits_time((config.warmup.writes + writes) * config.repeats as u64)),
+        ));
+    }
+    if phases.contains(&Phase::Batch) {
+        planned.push(phase(
+            "batch",
+            format!(
+                "{} transactions of {} inserts per database",
+                scaled(BATCH_TXNS, config),
+                args.batch_size.unwrap_or(BATCH_ENTRIES)
+            ),
+            None,
+        ));
+    }
+    if phases.contains(&Phase::DirtyPages) {
+        let commits = (scaled(dirty_pages::ROUNDS, config) * dirty_pages::KEY_COUNTS.len()) as u64;
+        planned.push(phase(
+            "dirty-pages",
+            format!("{commits} overwrite transactions per database"),
+            Some(calibration.commits_time(commits)),
+        ));
+    }
+    if phases.contains(&Phase::ManyTables) {
+        planned.push(phase(
+            "many-tables",
+            format!(
+                "{} transactions per database, up to 1000 tables each",
+                scaled(many_tables::ROUNDS, config) * many_tables::TABLE_COUNTS.len()
+            ),
+            None,
+        ));
+    }
+    if phases.contains(&Phase::Alternate) {
+        planned.push(phase(
+            "alternate",
+            format!("{writes} alternating commits"),
+            Some(calibration.commits_time(writes / 2)),
+        ));
+    }
+    if phases.contains(&Phase::Barrier) {
+        let interval = args.barrier_interval.unwrap_or(DEFAULT_BARRIER_INTERVAL);
+        planned.push(phase(
+            "barrier",
+            format!("{writes} commits per database, barrier every {interval}"),
+            Some(calibration.commits_time(writes)),
+        ));
+    }
+    if phases.contains(&Phase::PinnedSnapshot) {
+        planned.push(phase(
+            "pinned-snapshot",
+            format!("{writes} overwrites per database"),
+            Some(calibration.commits_time(writes)),
+        ));
+    }
+    if phases.contains(&Phase::Savepoints) {
+        let count = args
+            .savepoints
+            .unwrap_or_else(|| scaled(DEFAULT_SAVEPOINTS, config));
+        planned.push(phase(
+            "savepoints",
+            format!("{count} savepoint commits per database"),
+            Some(calibration.commits_time(count as u64)),
+        ));
+    }
+    if phases.contains(&Phase::FreePages) {
+        let num_txns = args.churn.unwrap_or(DEFAULT_CHURN_TXNS);
+        planned.push(phase(
+            "free-pages",
+            format!("{num_txns} churn transactions per database"),
+            Some(calibration.commits_time(num_txns as u64)),
+        ));
+    }
+    if phases.contains(&Phase::Queue) {
+        planned.push(phase(
+            "queue",
+            format!("{writes} pushes and pops per database"),
+            Some(calibration.commits_time(2 * writes)),
+        ));
+    }
+    if phases.contains(&Phase::Ttl) {
+        let window = args.ttl_window.unwrap_or(DEFAULT_TTL_WINDOW_MS);
+        planned.push(phase(
+            "ttl",
+            format!("{writes} appends per database, pruned to a {window}ms window"),
+            Some(calibration.commits_time(writes + writes / ttl::PRUNE_EVERY as u64)),
+        ));
+    }
+    if phases.contains(&Phase::SyncStrategies) {
+        let strategies = backend::SyncStrategy::ALL.len() as u64 + 1;
+        planned.push(phase(
+            "sync-strategies",
+            format!("{writes} writes × {strategies} backends per database"),
+            Some(calibration.commits_time(strategies * writes)),
+        ));
+    }
+    if phases.contains(&Phase::CommitBreakdown) {
+        planned.push(phase(
+            "commit-breakdown",
+            format!("{writes} writes per database"),
+            Some(calibration.commits_time(writes)),
+        ));
+    }
+    if phases.contains(&Phase::TornWrites) {
+        planned.push(phase(
+            "torn-writes",
+            "2 crash trials on a copy of each database".to_owned(),
+            None,
+        ));
+    }
+    if phases.contains(&Phase::BitRot) {
+        planned.push(phase(
+            "bit-rot",
+            format!(
+                "{} bit-flip trials on a copy of each database",
+                bit_rot::FLIPS.len()
+            ),
+            None,
+        ));
+    }
+    if phases.contains(&Phase::ReadOnlyOpen) {
+        planned.push(phase(
+            "read-only-open",
+            format!(
+                "{} cold and {0} warm opens per database",
+                read_only_open::ROUNDS
+            ),
+            None,
+        ));
+    }
+    if phases.contains(&Phase::MicroOps) {
+        planned.push(phase(
+            "micro-ops",
+            format!(
+                "{} calls of 5 read primitives per database",
+                micro_ops::CALLS
+            ),
+            None,
+        ));
+    }
+    if phases.contains(&Phase::Iteration) {
+        planned.push(phase(
+            "iteration",
+            format!(
+                "2 scans of up to {} entries and {} ranges of {} per direction per database",
+                iteration::SCAN_ENTRIES,
+                iteration::PAGES,
+                iteration::PAGE
+            ),
+            None,
+        ));
+    }
+    if phases.contains(&Phase::Pipeline) {
+        let workers = args.pipeline_workers.unwrap_or(pipeline::DEFAULT_WORKERS);
+        planned.push(phase(
+            "pipeline",
+            format!(
+                "2 × {} commits of {} inserts per database, inline and from {workers} workers",
+                pipeline::BATCHES,
+                pipeline::BATCH_ENTRIES
+            ),
+            Some(calibration.commits_time(2 * pipeline::BATCHES as u64)),
+        ));
+    }
+    if phases.contains(&Phase::Backup) {
+        planned.push(phase(
+            "backup",
+            "a snapshot and a file-copy backup per database, each restored".to_owned(),
+            None,
+        ));
+    }
+    if phases.contains(&Phase::Migration) {
+        let chunk = args
+            .migration_chunk
+            .unwrap_or(migration::DEFAULT_CHUNK_ROWS);
+        planned.push(phase(
+            "migration",
+            format!("every row of a copy of each database, {chunk} per transaction"),
+            None,
+        ));
+    }
+    if phases.contains(&Phase::KeyEncoding) {
+        planned.push(phase(
+            "key-encoding",
+            format!(
+                "{} keys per encoding on a fresh database per database",
+                key_encoding::KEYS
+            ),
+            None,
+        ));
+    }
+    if phases.contains(&Phase::ShutdownOpen) {
+        planned.push(phase(
+            "shutdown-open",
+            format!(
+                "{} opens per database after clean and unclean shutdowns",
+                shutdown_open::TRIALS
+            ),
+            None,
+        ));
+    }
+    if phases.contains(&Phase::CheckpointThread) {
+        let interval = args
+            .checkpoint_interval
+            .unwrap_or(DEFAULT_CHECKPOINT_INTERVAL_MS);
+        planned.push(phase(
+            "checkpoint-thread",
+            format!("2 × {writes} commits per database, checkpoint every {interval} ms"),
+            None,
+        ));
+    }
+    if phases.contains(&Phase::ColdStart) {
+        let reads = args.cold_start_reads.unwrap_or(cold_start::DEFAULT_READS);
+        let writes = args.cold_start_writes.unwrap_or(cold_start::DEFAULT_WRITES);
+        planned.push(phase(
+            "cold-start",
+            format!(
+                "2 × {} restarts per database, {reads} reads and {writes} commits each",
+                cold_start::ROUNDS
+            ),
+            None,
+        ));
+    }
+    if phases.contains(&Phase::Mix) {
+        let mix = args.mix.clone().unwrap_or_default();
+        let ops = args.mix_ops.unwrap_or(mix::DEFAULT_OPS);
+        planned.push(phase(
+            "mix",
+            format!("{ops} operations per database, {mix}"),
+            None,
+        ));
+    }
+    if phases.contains(&Phase::Retry) {
+        let rate = args.fault_rate.unwrap_or(retry::DEFAULT_FAULT_RATE);
+        let writes = args.retry_writes.unwrap_or(retry::DEFAULT_WRITES);
+        planned.push(phase(
+            "retry",
+            format!("{writes} commits per database, transient errors at rate {rate}"),
+            None,
+        ));
+    }
+    if phases.contains(&Phase::BulkDelete) {
+        planned.push(phase(
+            "bulk-delete",
+            "1%, 5% and 10% deletes per database".to_owned(),
+            None,
+        ));
+    }
+    for kind in args
+        .engine
+        .iter()
+        .filter(|_| phases.contains(&Phase::Engines))
+    {
+        planned.push(phase(
+            "engine",
+            format!("{kind}: fill and {writes} writes"),
+            None,
+        ));
+    }
+    planned
+}
 
-    let db = Database::builder()
-        .set_cache_size(1024 * 1024 * 1024) // 1GB cache
-        .create(db_path)?;
+/// Default parameters of phases selected with `--phases` without their own option.
+const DEFAULT_BARRIER_INTERVAL: usize = 100;
+const DEFAULT_SAVEPOINTS: usize = 1000;
+const DEFAULT_CHURN_TXNS: usize = 1000;
+const DEFAULT_TTL_WINDOW_MS: u64 = 1000;
+const DEFAULT_CHECKPOINT_INTERVAL_MS: u64 = 1000;
+/// Transactions, and inserts per transaction, of the batch phase.
+const BATCH_TXNS: usize = 1000;
+const BATCH_ENTRIES: BatchSize = BatchSize::Fixed(100);
 
-    let mut durations = Vec::with_capacity(num_writes);
-    let mut key_counter = start_key;
+/// `count`, a phase's transactions or rounds at the default `--writes`, scaled to the
+/// configured writes so that `--writes` and `--preset smoke` shorten every phase; at least 1.
+fn scaled(count: usize, config: &Config) -> usize {
+    (count.saturating_mul(config.benchmark_writes) / BENCHMARK_WRITES).max(1)
+}
+
+/// The phases to run, in pipeline order: `--phases` if given, otherwise the fill, the write
+/// benchmark and every phase enabled by its own option.
+fn selected_phases(args: &Args) -> Result<Vec<Phase>, String> {
+    let enabled_by = [
+        (Phase::WriteBench, args.io_hog.is_some(), "--io-hog"),
+        (Phase::Batch, args.batch_size.is_some(), "--batch-size"),
+        (Phase::DirtyPages, args.dirty_pages, "--dirty-pages"),
+        (Phase::ManyTables, args.many_tables, "--many-tables"),
+        (Phase::Alternate, args.alternate, "--alternate"),
+        (
+            Phase::Barrier,
+            args.barrier_interval.is_some(),
+            "--barrier-interval",
+        ),
+        (
+            Phase::PinnedSnapshot,
+            args.pinned_snapshot,
+            "--pinned-snapshot",
+        ),
+        (Phase::Savepoints, args.savepoints.is_some(), "--savepoints"),
+        (Phase::FreePages, args.churn.is_some(), "--churn"),
+        (Phase::Queue, args.queue, "--queue"),
+        (Phase::Ttl, args.ttl || args.ttl_window.is_some(), "--ttl"),
+        (
+            Phase::SyncStrategies,
+            args.sync_strategies,
+            "--sync-strategies",
+        ),
+        (
+            Phase::CommitBreakdown,
+            args.commit_breakdown,
+            "--commit-breakdown",
+        ),
+        (Phase::TornWrites, args.torn_writes, "--torn-writes"),
+        (Phase::BitRot, args.bit_rot, "--bit-rot"),
+        (Phase::ReadOnlyOpen, args.read_only_open, "--read-only-open"),
+        (Phase::MicroOps, args.micro_ops, "--micro-ops"),
+        (Phase::Iteration, args.iteration, "--iteration"),
+        (
+            Phase::Pipeline,
+            args.pipeline || args.pipeline_workers.is_some(),
+            "--pipeline",
+        ),
+        (Phase::Backup, args.backup, "--backup"),
+        (
+            Phase::Migration,
+            args.migration || args.migration_chunk.is_some(),
+            "--migration",
+        ),
+        (Phase::KeyEncoding, args.key_encoding, "--key-encoding"),
+        (Phase::ShutdownOpen, args.shutdown_open, "--shutdown-open"),
+        (
+            Phase::CheckpointThread,
+            args.checkpoint_interval.is_some(),
+            "--checkpoint-interval",
+        ),
+        (
+            Phase::ColdStart,
+            args.cold_start || args.cold_start_reads.is_some() || args.cold_start_writes.is_some(),
+            "--cold-start",
+        ),
+        (
+            Phase::Mix,
+            args.mix.is_some() || args.mix_ops.is_some(),
+            "--mix",
+        ),
+        (
+            Phase::Retry,
+            args.fault_rate.is_some()
+                || args.retry_writes.is_some()
+                || args.retry_max.is_some()
+                || args.retry_backoff_ms.is_some(),
+            "--fault-rate",
+        ),
+        (Phase::BulkDelete, args.bulk_delete, "--bulk-delete"),
+        (Phase::Engines, !args.engine.is_empty(), "--engine"),
+    ];
+    let Some(listed) = &args.phases else {
+        let base = args
+            .experiment
+            .map_or(&[Phase::Fill, Phase::WriteBench][..], Experiment::phases);
+        return Ok(Phase::ALL
+            .into_iter()
+            .filter(|phase| {
+                base.contains(phase) || enabled_by.iter().any(|(p, on, _)| p == phase && *on)
+            })
+            .collect());
+    };
+    if let Some((phase, _, option)) = enabled_by
+        .iter()
+        .find(|(phase, on, _)| *on && !listed.contains(phase))
+    {
+        return Err(format!(
+            "{option} enables the {phase} phase, which --phases leaves out"
+        ));
+    }
+    if listed.contains(&Phase::Engines) && args.engine.is_empty() {
+        return Err("the engines phase needs at least one --engine".to_owned());
+    }
+    Ok(Phase::ALL
+        .into_iter()
+        .filter(|phase| listed.contains(phase))
+        .collect())
+}
 
-    for i in 0..num_writes {
-        let value = generate_random_value(VALUE_SIZE);
+/// Fill both databases from one thread each, sharing the checkpointer; the progress shown
+/// is the mean of the two.
+fn fill_concurrently(
+    bench_dbs: &mut [BenchDb; 2],
+    config: &Config,
+    probes: usize,
+    checkpointer: &mut Checkpointer,
+) -> Result<[Vec<SizeProbe>; 2], BenchError> {
+    let resume = [0, 1].map(|i| checkpointer.state.dbs[i].fill);
+    let shared = Mutex::new((&mut *checkpointer, [0.0; 2]));
+    let span = Span::current();
+    let filled = thread::scope(|scope| {
+        let fills: Vec<_> = bench_dbs
+            .iter()
+            .enumerate()
+            .map(|(i, bench_db)| {
+                let (shared, span) = (&shared, span.clone());
+                scope.spawn(move || {
+                    let _span = span.enter();
+                    fill_database_with_probes(bench_db, config, resume[i], probes, |p| {
+                        let mut shared = shared.lock().expect("fill progress lock poisoned");
+                        let (checkpointer, done) = &mut *shared;
+                        done[i] = config.fill_target.fraction_done(p.bytes, p.keys);
+                        tui::set_progress((done[0] + done[1]) / 2.0);
+                        checkpointer.fill_batch(i, p);
+                    })
+                })
+            })
+            .collect();
+        fills
+            .into_iter()
+            .map(|fill| fill.join().expect("fill thread panicked"))
+            .collect::<Result<Vec<_>, _>>()
+    })?;
+    let mut taken: [Vec<SizeProbe>; 2] = Default::default();
+    for (i, (bench_db, (next_key, probes))) in bench_dbs.iter_mut().zip(filled).enumerate() {
+        bench_db.next_key = next_key;
+        checkpointer.fill_done(i, next_key);
+        taken[i] = probes;
+    }
+    Ok(taken)
+}
 
-        let start = Instant::now();
+/// What one benchmark run in one directory produced.
+struct RunOutcome {
+    report: Report,
+    /// Individual write stats for quick_repair(false) and quick_repair(true), unless the
+    /// write-bench phase was skipped.
+    individual: Option<[BenchmarkStats; 2]>,
+    /// One `RESULT` line per configuration.
+    results: Vec<ResultLine>,
+}
 
-        let mut write_txn = db.begin_write()?;
-        write_txn.set_quick_repair(quick_repair);
-        {
-            let mut table = write_txn.open_table(TABLE)?;
-            table.insert(key_counter, value.as_slice())?;
+/// The timed writes of one configuration across repeats, in memory that does not grow with
+/// `--writes`.
+struct WriteSamples {
+    latencies: StatsAccumulator,
+    /// Raw latencies for the Mann-Whitney test.
+    reservoir: Reservoir,
+    slo: Option<SloReport>,
+}
+
+impl WriteSamples {
+    fn new(seed: u64, slo: Option<LatencyObjective>) -> Self {
+        Self {
+            latencies: StatsAccumulator::new(),
+            reservoir: Reservoir::new(seed),
+            slo: slo.map(SloReport::new),
+        }
+    }
+
+    fn record(&mut self, sample: OpSample) {
+        self.latencies.record_at(sample.index, sample.latency);
+        self.reservoir.record(sample.latency);
+        if let Some(slo) = &mut self.slo {
+            slo.record(sample);
         }
-        write_txn.commit()?;
+    }
+}
 
-        let duration = start.elapsed();
-        durations.push(duration);
+fn run(
+    args: &Args,
+    config: &Config,
+    phases: &[Phase],
+    dir: &Path,
+    bench_dbs: &mut [BenchDb; 2],
+    checkpointer: &mut Checkpointer,
+) -> Result<RunOutcome, BenchError> {
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    if !args.skip_disk_check && phases.contains(&Phase::Fill) {
+        preflight::check_disk_space(dir, config)?;
+    }
 
-        key_counter += 1;
+    let mut size_probes: [Vec<SizeProbe>; 2] = Default::default();
+    if !phases.contains(&Phase::Fill) {
+        for bench_db in bench_dbs.iter_mut() {
+            bench_db.next_key = next_unused_key(bench_db).in_phase("fill", &bench_db.path)?;
+            info!(db_path = %bench_db.path, next_key = bench_db.next_key, "Skipping fill, reusing database");
+        }
+    } else {
+        let _phase = info_span!("phase", phase = 1, name = "fill").entered();
+        set_phase("fill");
+        info!(
+            target = %config.fill_target,
+            concurrent = config.fill_concurrent,
+            "Filling databases"
+        );
 
-        if (i + 1) % 1000 == 0 {
-            println!("Completed {} / {} writes", i + 1, num_writes);
+        let mut pending = Vec::new();
+        for (i, bench_db) in bench_dbs.iter_mut().enumerate() {
+            let progress = &checkpointer.state.dbs[i];
+            if progress.fill_done {
+                info!(db_path = %bench_db.path, "Fill already completed, resuming after it");
+                bench_db.next_key = progress.next_key;
+            } else {
+                pending.push(i);
+            }
+        }
+        let probes = args.size_probes.unwrap_or(0);
+        if config.fill_concurrent && pending.len() == 2 {
+            size_probes = fill_concurrently(bench_dbs, config, probes, checkpointer)?;
+        } else {
+            for i in pending {
+                let bench_db = &mut bench_dbs[i];
+                let resume = checkpointer.state.dbs[i].fill;
+                let (next_key, taken) =
+                    fill_database_with_probes(bench_db, config, resume, probes, |p| {
+                        tui::set_progress(config.fill_target.fraction_done(p.bytes, p.keys));
+                        checkpointer.fill_batch(i, p)
+                    })?;
+                bench_db.next_key = next_key;
+                size_probes[i] = taken;
+                checkpointer.fill_done(i, next_key);
+            }
         }
     }
 
-    Ok(BenchmarkStats::new(&durations))
-}
+    let mut samples = [0, 1].map(|i| WriteSamples::new(config.seed.wrapping_add(i), args.slo));
+    let (syncs, io_hog_report) = if !phases.contains(&Phase::WriteBench) {
+        Default::default()
+    } else {
+        let _phase = info_span!("phase", phase = 2, name = "write-bench").entered();
+        set_phase("write-bench");
+        info!(
+            repeats = config.repeats,
+            shuffle_order = config.shuffle_order,
+            "Benchmarking individual write performance"
+        );
 
-fn benchmark_batch_writes(
-    db_path: &str,
-    start_key: u64,
-    num_batches: usize,
-    batch_size: usize,
-    quick_repair: bool,
-) -> Result<BenchmarkStats, Error> {
-    println!("\n{}", "=".repeat(60));
-    println!(
-        "Benchmarking batch writes on: {} (quick_repair={})",
-        db_path, quick_repair
-    );
-    println!(
-        "Number of batches: {}, batch size: {}",
-        num_batches, batch_size
-    );
-    println!("{}", "=".repeat(60));
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        // Backend calls of the writes timed by this process, which excludes any resumed
+        // from a checkpoint.
+        let mut syncs: [(IoTimes, usize, u64); 2] = Default::default();
+        let io_hog = args
+            .io_hog
+            .map(|rate| IoHog::start(dir, rate))
+            .transpose()
+            .io_context(|| format!("starting the I/O hog in {}", dir.display()))?;
+
+        for repeat in 0..config.repeats {
+            let mut order = [0, 1];
+            if config.shuffle_order {
+                order.shuffle(&mut rng);
+            }
 
-    let db = Database::builder()
-        .set_cache_size(1024 * 1024 * 1024) // 1GB cache
-        .create(db_path)?;
+            for i in order {
+                let bench_db = &mut bench_dbs[i];
+                let label = if config.repeats > 1 {
+                    format!("quick_repair_{}_{repeat}", bench_db.quick_repair)
+                } else {
+                    format!("quick_repair_{}", bench_db.quick_repair)
+                };
 
-    let mut durations = Vec::with_capacity(num_batches);
-    let mut key_counter = start_key;
+                // Number the operations across repeats, not the samples kept of them.
+                let offset = repeat * config.benchmark_writes;
+                // Writes of this run recorded by the checkpoint being resumed
+                let resumed = checkpointer.state.dbs[i]
+                    .runs
+                    .get(repeat)
+                    .map_or(0, |done| {
+                        for &sample in done {
+                            samples[i].record(OpSample {
+                                index: offset + sample.index,
+                                ..sample
+                            });
+                        }
+                        done.len()
+                    });
+                let remaining = config.benchmark_writes.saturating_sub(resumed);
+                if remaining > 0 {
+                    if resumed > 0 {
+                        info!(
+                            db_path = %bench_db.path,
+                            done = resumed,
+                            remaining,
+                            "Resuming benchmark run from checkpoint"
+                        );
+                    }
+                    let profiler = args
+                        .profile
+                        .then(|| Profiler::start(&label))
+                        .transpose()
+                        .io_context(|| format!("starting the profiler for {label}"))?;
+                    let run_samples = &mut samples[i];
+                    let (io, bytes) = benchmark_writes(
+                        bench_db,
+                        remaining,
+                        config.warmup,
+                        config.values,
+                        config.keys,
+                        config.seed,
+                        |sample, next_key| {
+                            let index = resumed + sample.index;
+                            checkpointer.write(i, repeat, OpSample { index, ..sample }, next_key);
+                            run_samples.record(OpSample {
+                                index: offset + index,
+                                ..sample
+                            });
+                        },
+                    )?;
+                    checkpointer.save();
+                    syncs[i] = (syncs[i].0 + io, syncs[i].1 + remaining, syncs[i].2 + bytes);
+                    profiler
+                        .map(Profiler::finish)
+                        .transpose()
+                        .io_context(|| format!("writing the profile of {label}"))?;
+                }
+            }
+        }
+
+        let io_hog_report = io_hog
+            .map(IoHog::stop)
+            .transpose()
+            .io_context(|| "running the I/O hog".to_owned())?;
+        (syncs, io_hog_report)
+    };
+    let individual = phases.contains(&Phase::WriteBench).then(|| {
+        std::array::from_fn::<_, 2, _>(|i| {
+            let (io, commits, bytes) = &syncs[i];
+            samples[i]
+                .latencies
+                .stats()
+                .with_syncs(io, *commits)
+                .with_volume(*commits as u64, *bytes, *commits)
+        })
+    });
 
-    for i in 0..num_batches {
-        let start = Instant::now();
+    let mut phase_sections = Vec::new();
+    let mut repair_times = [None; 2];
+    for mut db_phase in db_phases(args, config)
+        .into_iter()
+        .filter(|db_phase| phases.contains(&db_phase.phase()))
+    {
+        let name = db_phase.phase().name();
+        let _phase = info_span!("phase", name).entered();
+        set_phase(name);
 
-        let mut write_txn = db.begin_write()?;
-        write_txn.set_quick_repair(quick_repair);
-        {
-            let mut table = write_txn.open_table(TABLE)?;
-            for _ in 0..batch_size {
-                let value = generate_random_value(VALUE_SIZE);
-                table.insert(key_counter, value.as_slice())?;
-                key_counter += 1;
+        for bench_db in bench_dbs.iter_mut() {
+            phase_sections.extend(db_phase.run(bench_db).in_phase(name, &bench_db.path)?);
+        }
+        phase_sections.extend(db_phase.finish());
+        for (time, bench_db) in repair_times.iter_mut().zip(bench_dbs.iter()) {
+            *time = db_phase.repair_time(bench_db.quick_repair).or(*time);
+        }
+    }
+
+    let engine_reports = if !phases.contains(&Phase::Engines) {
+        Vec::new()
+    } else {
+        let _phase = info_span!("phase", name = "engines").entered();
+        set_phase("engines");
+
+        let mut reports = Vec::new();
+        for &kind in &args.engine {
+            reports.push(
+                engine::benchmark_engine(kind, dir, config, config.values).map_err(|source| {
+                    BenchError::Engine {
+                        engine: kind.to_string(),
+                        source,
+                    }
+                })?,
+            );
+        }
+        reports
+    };
+    hooks::end_phase();
+
+    let mut report = Report::new("BENCHMARK RESULTS SUMMARY");
+    let mut run_config = Section::new("Run Configuration")
+        .field("redb version", redb_label(args))
+        .field("Storage backend", args.backend.to_string())
+        .field("Directory", dir.display().to_string())
+        .field(
+            "Filesystem",
+            platform::filesystem_type(dir).unwrap_or("unknown"),
+        )
+        .field("Fill target", config.fill_target.to_string())
+        .field("Values", config.values.to_string())
+        .field("Benchmark writes", config.benchmark_writes)
+        .field("Keys", config.keys.to_string())
+        .field("Reads", config.reads.to_string())
+        .field("Repair callback", config.repair_callback.to_string())
+        .field("Repeats", config.repeats)
+        .field("Seed", config.seed.to_string());
+    if let Some(experiment) = args.experiment {
+        run_config = run_config.field("Experiment", experiment.to_string());
+    }
+    if config.sample_every > 1 {
+        run_config = run_config.field("Sample every", config.sample_every);
+    }
+    if let Some(cpus) = &args.pin_cpus {
+        run_config = run_config.field("CPU affinity", cpus.to_string());
+    }
+    if let Some(nice) = args.nice {
+        run_config = run_config.field("Nice", i64::from(nice));
+    }
+    if let Some(priority) = args.ionice {
+        run_config = run_config.field("I/O priority", priority.to_string());
+    }
+    report.push(run_config);
+    if let Some(clock) = clock::calibration() {
+        report.push(clock.section("Clock"));
+    }
+    if let Some(schema) = config.values.schema {
+        report.push(schema.section("Synthetic Records"));
+    }
+    if let Some(io_hog) = &io_hog_report {
+        report.push(io_hog.section("Background I/O During Write Benchmark"));
+    }
+    if args.size_probes.is_some() {
+        report.push(
+            SizeScalingReport {
+                probes: size_probes,
             }
+            .section("Latency vs Database Size"),
+        );
+    }
+
+    if let Some([stats_individual_false, stats_individual_true]) = &individual {
+        push_write_comparison(
+            &mut report,
+            stats_individual_false,
+            stats_individual_true,
+            &samples,
+            bench_dbs,
+        );
+    }
+
+    report.sections.extend(phase_sections);
+
+    if !engine_reports.is_empty() {
+        for engine in &engine_reports {
+            report.push(engine.section(format!("Engine - {}", engine.kind.label())));
         }
-        write_txn.commit()?;
 
-        let duration = start.elapsed();
-        durations.push(duration);
+        let mut section = Section::new("Storage Engine Comparison")
+            .table(engine::COMPARISON_COLUMNS)
+            .note("Engine rows repeat the fill and timed writes on a fresh store of that engine");
+        for (bench_db, stats) in bench_dbs.iter().zip(individual.iter().flatten()) {
+            section = section.row(engine::comparison_row(
+                format!("redb {}", bench_db.label()),
+                stats,
+                get_file_size(&bench_db.path).unwrap_or(0),
+            ));
+        }
+        for engine in &engine_reports {
+            section = section.row(engine::comparison_row(
+                engine.kind.label(),
+                &engine.writes,
+                engine.disk_usage,
+            ));
+        }
+        report.push(section);
+    }
 
-        if (i + 1) % 100 == 0 {
-            println!("Completed {} / {} batches", i + 1, num_batches);
+    if let (true, Some(individual)) = (config.values.codec != Codec::None, &individual) {
+        let mut section = Section::new(format!(
+            "Compression ({}, {} values)",
+            config.values.codec, config.values
+        ))
+        .table([
+            "configuration",
+            "file size (MiB)",
+            "effective MiB/s (uncompressed)",
+        ]);
+        for (bench_db, stats) in bench_dbs.iter().zip(individual) {
+            let file_size = get_file_size(&bench_db.path).unwrap_or(0);
+            let effective = stats.writes_per_second * config.values.mean_size() as f64 / MIB as f64;
+            section = section.row(vec![
+                bench_db.label().into(),
+                (file_size as f64 / MIB as f64).into(),
+                effective.into(),
+            ]);
         }
+        report.push(section);
     }
 
-    Ok(BenchmarkStats::new(&durations))
+    let results = bench_dbs
+        .iter()
+        .zip(&samples)
+        .enumerate()
+        .map(|(i, (bench_db, samples))| ResultLine {
+            dir: dir.display().to_string(),
+            quick_repair: bench_db.quick_repair,
+            writes: WriteFigures::new(&samples.latencies).map(|figures| WriteFigures {
+                volume: individual.as_ref().and_then(|stats| stats[i].volume),
+                ..figures
+            }),
+            file_size: get_file_size(&bench_db.path).ok(),
+            repair: repair_times[i],
+            tags: args.tags.clone(),
+        })
+        .collect();
+
+    Ok(RunOutcome {
+        report,
+        individual,
+        results,
+    })
 }
 
-fn cleanup_db(db_path: &str) {
-    if let Err(e) = fs::remove_file(db_path) {
-        eprintln!("Warning: Could not remove {}: {}", db_path, e);
-    }
+/// The phases run against each benchmark database, in pipeline order, with the parameters
+/// `args` gives them.
+fn db_phases(args: &Args, config: &Config) -> Vec<Box<dyn DbPhase>> {
+    let (values, reads, callback) = (config.values, config.reads, config.repair_callback);
+    let seed = config.seed;
+    let writes = config.benchmark_writes;
+
+    // The same sizes for every database, so both settings commit identical batches.
+    let batch_size = args.batch_size.unwrap_or(BATCH_ENTRIES);
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let sizes: Vec<usize> = (0..scaled(BATCH_TXNS, config))
+        .map(|_| batch_size.sample(&mut rng))
+        .collect();
+    let dirty_rounds = scaled(dirty_pages::ROUNDS, config);
+    let many_tables_rounds = scaled(many_tables::ROUNDS, config);
+    let barrier_interval = args.barrier_interval.unwrap_or(DEFAULT_BARRIER_INTERVAL);
+    let savepoints = args
+        .savepoints
+        .unwrap_or_else(|| scaled(DEFAULT_SAVEPOINTS, config));
+    let churn = args.churn.unwrap_or(DEFAULT_CHURN_TXNS);
+    let ttl_window =
+        std::time::Duration::from_millis(args.ttl_window.unwrap_or(DEFAULT_TTL_WINDOW_MS));
+    let workers = args.pipeline_workers.unwrap_or(pipeline::DEFAULT_WORKERS);
+    let chunk = args
+        .migration_chunk
+        .unwrap_or(migration::DEFAULT_CHUNK_ROWS);
+    let checkpoint_interval = std::time::Duration::from_millis(
+        args.checkpoint_interval
+            .unwrap_or(DEFAULT_CHECKPOINT_INTERVAL_MS)
+            .max(1),
+    );
+    let cold_reads = args.cold_start_reads.unwrap_or(cold_start::DEFAULT_READS);
+    let cold_writes = args.cold_start_writes.unwrap_or(cold_start::DEFAULT_WRITES);
+    let mix = args.mix.clone().unwrap_or_default();
+    let mix_ops = args.mix_ops.unwrap_or(mix::DEFAULT_OPS);
+    let fault_rate = args.fault_rate.unwrap_or(retry::DEFAULT_FAULT_RATE);
+    let retry_writes = args.retry_writes.unwrap_or(retry::DEFAULT_WRITES);
+    let policy = RetryPolicy {
+        max_retries: args.retry_max.unwrap_or(retry::DEFAULT_MAX_RETRIES),
+        backoff: std::time::Duration::from_millis(
+            args.retry_backoff_ms.unwrap_or(retry::DEFAULT_BACKOFF_MS),
+        ),
+    };
+
+    vec![
+        Box::new(
+            PerDb::new(
+                Phase::Batch,
+                {
+                    let sizes = sizes.clone();
+                    move |db| benchmark_batch_writes(db, &sizes, values)
+                },
+                move |stats, label| {
+                    vec![stats.section(format!("Batch Writes ({batch_size} per txn) - {label}"))]
+                },
+            )
+            .compare(move |reports| match reports {
+                [without, with] => vec![batch_comparison(without, with, &sizes)],
+                _ => Vec::new(),
+            }),
+        ),
+        Box::new(PerDb::new(
+            Phase::DirtyPages,
+            move |db| dirty_pages::benchmark_dirty_pages(db, values, dirty_rounds, seed),
+            |dirty, label| vec![dirty.section(format!("Dirty Pages per Transaction - {label}"))],
+        )),
+        Box::new(PerDb::new(
+            Phase::ManyTables,
+            move |db| many_tables::benchmark_many_tables(db, many_tables_rounds),
+            |many, label| vec![many.section(format!("Tables per Transaction - {label}"))],
+        )),
+        Box::new(
+            PerDb::new(
+                Phase::Alternate,
+                move |db| alternate::benchmark_alternating(db, writes, values),
+                |alternate, _| alternate.sections(),
+            )
+            .first_only(),
+        ),
+        Box::new(PerDb::new(
+            Phase::Barrier,
+            move |db| barrier::benchmark_barrier(db, writes, barrier_interval, values),
+            |barrier, label| barrier.sections(label),
+        )),
+        Box::new(PerDb::new(
+            Phase::PinnedSnapshot,
+            move |db| snapshot::benchmark_pinned_snapshot(db, writes, values, seed),
+            |pinned, label| {
+                vec![pinned.section(format!("Overwrites Under a Pinned Snapshot - {label}"))]
+            },
+        )),
+        Box::new(PerDb::new(
+            Phase::Savepoints,
+            move |db| savepoints::benchmark_savepoints(db, savepoints, values),
+            |savepoint, label| {
+                vec![savepoint.section(format!("Persistent Savepoint Accumulation - {label}"))]
+            },
+        )),
+        Box::new(PerDb::new(
+            Phase::FreePages,
+            move |db| free_pages::benchmark_free_pages(db, churn, values),
+            |churn, label| {
+                vec![churn.section(format!("Free Pages Under Write-Delete Churn - {label}"))]
+            },
+        )),
+        Box::new(PerDb::new(
+            Phase::Queue,
+            move |db| queue::benchmark_queue(db, writes, values),
+            |queue, label| queue.sections(label),
+        )),
+        Box::new(PerDb::new(
+            Phase::Ttl,
+            move |db| ttl::benchmark_ttl(db, writes, ttl_window, values),
+            |ttl, label| ttl.sections(label),
+        )),
+        Box::new(PerDb::new(
+            Phase::SyncStrategies,
+            move |db| sync_strategy::benchmark_sync_strategies(db, writes, values),
+            |sync, label| vec![sync.section(format!("Sync Strategies - {label}"))],
+        )),
+        Box::new(
+            PerDb::new(
+                Phase::CommitBreakdown,
+                move |db| commit_breakdown::benchmark_commit_breakdown(db, writes, values),
+                |breakdown, label| vec![breakdown.section(format!("Commit Breakdown - {label}"))],
+            )
+            .compare(|reports| match reports {
+                [without, with] => vec![commit_breakdown::comparison_section(
+                    "Commit Breakdown Comparison",
+                    without,
+                    with,
+                )],
+                _ => Vec::new(),
+            }),
+        ),
+        Box::new(PerDb::new(
+            Phase::TornWrites,
+            move |db| torn_write::benchmark_torn_writes(db, values, callback),
+            |torn, label| vec![torn.section(format!("Torn-Write Recovery - {label}"))],
+        )),
+        Box::new(PerDb::new(
+            Phase::BitRot,
+            |db| bit_rot::benchmark_bit_rot(db),
+            |rot, label| vec![rot.section(format!("Bit Rot Detection - {label}"))],
+        )),
+        Box::new(PerDb::new(
+            Phase::ReadOnlyOpen,
+            |db| read_only_open::benchmark_read_only_open(db),
+            |open, label| vec![open.section(format!("Read-Only Open - {label}"))],
+        )),
+        Box::new(PerDb::new(
+            Phase::MicroOps,
+            |db| micro_ops::benchmark_micro_ops(db),
+            |micro, label| vec![micro.section(format!("Read Primitives - {label}"))],
+        )),
+        Box::new(PerDb::new(
+            Phase::Iteration,
+            |db| iteration::benchmark_iteration(db),
+            |iteration, label| vec![iteration.section(format!("Iteration - {label}"))],
+        )),
+        Box::new(PerDb::new(
+            Phase::Pipeline,
+            move |db| pipeline::benchmark_pipeline(db, workers, values),
+            |pipeline, label| pipeline.sections(label),
+        )),
+        Box::new(PerDb::new(
+            Phase::Backup,
+            move |db| backup::benchmark_backup(db, values, callback),
+            |backup, label| vec![backup.section(format!("Online Backup - {label}"))],
+        )),
+        Box::new(PerDb::new(
+            Phase::Migration,
+            move |db| migration::benchmark_migration(db, chunk),
+            |migration, label| migration.sections(label),
+        )),
+        Box::new(PerDb::new(
+            Phase::KeyEncoding,
+            |db| key_encoding::benchmark_key_encoding(db),
+            |keys, label| vec![keys.section(format!("Key Encoding - {label}"))],
+        )),
+        Box::new(
+            PerDb::new(
+                Phase::ShutdownOpen,
+                move |db| shutdown_open::benchmark_shutdown_open(db, values, callback),
+                |_, _| Vec::new(),
+            )
+            .compare(|reports| {
+                vec![shutdown_open::comparison_section(
+                    "Clean vs Unclean Shutdown Open",
+                    reports,
+                )]
+            })
+            .repair(|report| (report.quick_repair, report.unclean.latency.avg_write_time)),
+        ),
+        Box::new(PerDb::new(
+            Phase::CheckpointThread,
+            move |db| {
+                checkpoint_thread::benchmark_checkpoint_thread(
+                    db,
+                    writes,
+                    checkpoint_interval,
+                    values,
+                )
+            },
+            |checkpoints, label| vec![checkpoints.section(format!("Checkpoint Thread - {label}"))],
+        )),
+        Box::new(PerDb::new(
+            Phase::ColdStart,
+            move |db| {
+                cold_start::benchmark_cold_start(
+                    db,
+                    cold_reads,
+                    reads,
+                    cold_writes,
+                    values,
+                    callback,
+                )
+            },
+            |restarts, label| vec![restarts.section(format!("Cold Start - {label}"))],
+        )),
+        Box::new(PerDb::new(
+            Phase::Mix,
+            move |db| mix::benchmark_mix(db, &mix, mix_ops, reads, values, seed),
+            |mix, label| vec![mix.section(format!("Operation Mix - {label}"))],
+        )),
+        Box::new(PerDb::new(
+            Phase::Retry,
+            move |db| {
+                retry::benchmark_retry(db, fault_rate, policy, retry_writes, values, callback)
+            },
+            |retries, label| vec![retries.section(format!("Transient Errors - {label}"))],
+        )),
+        Box::new(PerDb::new(
+            Phase::BulkDelete,
+            |db| bulk_delete::benchmark_bulk_delete(db),
+            |bulk, label| vec![bulk.section(format!("Bulk Deletion - {label}"))],
+        )),
+    ]
 }
 
-/// Spike to benchmark redb write performance with different quick_repair settings
-#[derive(argh::FromArgs)]
-struct Args {
-    /// target database size in GiB (default: 10)
-    #[argh(option, default = "10")]
-    target_size_gb: u64,
+/// Compare the batch writes of quick_repair(false) and quick_repair(true), which committed
+/// batches of `sizes` inserts.
+fn batch_comparison(without: &BenchmarkStats, with: &BenchmarkStats, sizes: &[usize]) -> Section {
+    let mean_entries = sizes.iter().sum::<usize>() as f64 / sizes.len().max(1) as f64;
+    let speedup_batch = without.writes_per_second / with.writes_per_second;
+    let latency_diff_batch =
+        with.avg_write_time.as_micros() as i64 - without.avg_write_time.as_micros() as i64;
+    let mut comparison = Section::new("Batch Write Performance Comparison")
+        .field("Speedup of quick_repair(false)", speedup_batch)
+        .field(
+            "Latency difference (μs per batch commit)",
+            latency_diff_batch,
+        )
+        .field("Mean inserts per txn", mean_entries)
+        .field(
+            "Inserts per txn (min-max)",
+            format!(
+                "{}-{}",
+                sizes.iter().min().unwrap_or(&0),
+                sizes.iter().max().unwrap_or(&0)
+            ),
+        )
+        .field(
+            "Latency difference (μs per insert)",
+            latency_diff_batch as f64 / mean_entries.max(1.0),
+        );
+    if let (Some(volume_false), Some(volume_true)) = (without.volume, with.volume) {
+        comparison = comparison.field(
+            "Latency difference (μs per KiB)",
+            volume_true.us_per_kib(with.avg_write_time)
+                - volume_false.us_per_kib(without.avg_write_time),
+        );
+    }
+    comparison.note(format!(
+        "quick_repair(false) is {speedup_batch:.2}x faster than quick_repair(true)"
+    ))
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args: Args = argh::from_env();
-    let target_size_gb = args.target_size_gb;
-
-    println!("\n{}", "█".repeat(60));
-    println!("REDB WRITE PERFORMANCE BENCHMARK");
-    println!("Comparing set_quick_repair(true) vs set_quick_repair(false)");
-    println!("{}", "█".repeat(60));
-
-    // Database paths
-    let db_quick_repair_false = "benchmark_quick_repair_false.redb";
-    let db_quick_repair_true = "benchmark_quick_repair_true.redb";
-
-    // Clean up any existing databases
-    println!("\nCleaning up existing database files...");
-    cleanup_db(db_quick_repair_false);
-    cleanup_db(db_quick_repair_true);
-
-    println!("\n{}", "█".repeat(60));
-    println!("PHASE 1: Filling databases with {target_size_gb} GiB of data");
-    println!("{}", "█".repeat(60));
-
-    let max_key_false = fill_database(db_quick_repair_false, target_size_gb)?;
-    let max_key_true = fill_database(db_quick_repair_true, target_size_gb)?;
-
-    println!("\n{}", "█".repeat(60));
-    println!("PHASE 2: Benchmarking individual write performance");
-    println!("{}", "█".repeat(60));
-
-    // Benchmark individual writes on quick_repair = false
-    let stats_individual_false = benchmark_writes(
-        db_quick_repair_false,
-        max_key_false,
-        BENCHMARK_WRITES,
-        false,
-    )?;
-
-    // Benchmark individual writes on quick_repair = true
-    let stats_individual_true =
-        benchmark_writes(db_quick_repair_true, max_key_true, BENCHMARK_WRITES, true)?;
-
-    // println!("\n{}", "█".repeat(60));
-    // println!("PHASE 3: Benchmarking batch write performance");
-    // println!("{}", "█".repeat(60));
-    //
-    // // Benchmark batch writes on quick_repair = true
-    // let stats_batch_true = benchmark_batch_writes(
-    //     db_quick_repair_true,
-    //     max_key_true + BENCHMARK_WRITES as u64,
-    //     1000,
-    //     100,
-    //     true,
-    // )?;
-    //
-    // // Benchmark batch writes on quick_repair = false
-    // let stats_batch_false = benchmark_batch_writes(
-    //     db_quick_repair_false,
-    //     max_key_false + BENCHMARK_WRITES as u64,
-    //     1000,
-    //     100,
-    //     false,
-    // )?;
-
-    // Print all results
-    println!("\n\n");
-    println!("{}", "█".repeat(60));
-    println!("BENCHMARK RESULTS SUMMARY");
-    println!("{}", "█".repeat(60));
-
-    stats_individual_false.print("Individual Writes - quick_repair(false)");
-    stats_individual_true.print("Individual Writes - quick_repair(true)");
-
-    println!("\n{}", "-".repeat(60));
-    println!("Individual Write Performance Comparison:");
+/// Push the individual write sections, their comparison and the SLO sections.
+fn push_write_comparison(
+    report: &mut Report,
+    stats_individual_false: &BenchmarkStats,
+    stats_individual_true: &BenchmarkStats,
+    samples: &[WriteSamples; 2],
+    bench_dbs: &[BenchDb; 2],
+) {
+    report.push(stats_individual_false.detailed_section("Individual Writes - quick_repair(false)"));
+    report.push(stats_individual_true.detailed_section("Individual Writes - quick_repair(true)"));
+
     let speedup_individual =
         stats_individual_false.writes_per_second / stats_individual_true.writes_per_second;
-    println!(
-        "quick_repair(false) is {:.2}x faster than quick_repair(true)",
-        speedup_individual
-    );
     let latency_diff = stats_individual_true.avg_write_time.as_micros() as i64
         - stats_individual_false.avg_write_time.as_micros() as i64;
-    println!("Latency difference: {} μs per write", latency_diff);
-    println!("{}", "-".repeat(60));
-
-    // stats_batch_true.print("Batch Writes (100 per txn) - quick_repair(true)");
-    // stats_batch_false.print("Batch Writes (100 per txn) - quick_repair(false)");
-    //
-    // println!("\n{}", "-".repeat(60));
-    // println!("Batch Write Performance Comparison:");
-    // let speedup_batch = stats_batch_false.writes_per_second / stats_batch_true.writes_per_second;
-    // println!(
-    //     "quick_repair(false) is {:.2}x faster than quick_repair(true)",
-    //     speedup_batch
-    // );
-    // let latency_diff_batch = stats_batch_true.avg_write_time.as_micros() as i64
-    //     - stats_batch_false.avg_write_time.as_micros() as i64;
-    // println!(
-    //     "Latency difference: {} μs per batch commit",
-    //     latency_diff_batch
-    // );
-    // println!("{}", "-".repeat(60));
-
-    println!("\n{}", "█".repeat(60));
-    println!("BENCHMARK COMPLETE");
-    println!("{}", "█".repeat(60));
-
-    println!("\nDatabase files preserved for inspection:");
-    println!("  - {}", db_quick_repair_true);
-    println!("  - {}", db_quick_repair_false);
+    let mut comparison = Section::new("Individual Write Performance Comparison")
+        .field("Speedup of quick_repair(false)", speedup_individual)
+        .field("Latency difference (μs per write)", latency_diff)
+        .note(format!(
+            "quick_repair(false) is {:.2}x faster than quick_repair(true)",
+            speedup_individual
+        ));
+    if let (Some(syncs_false), Some(syncs_true)) = (
+        stats_individual_false.syncs_per_commit,
+        stats_individual_true.syncs_per_commit,
+    ) {
+        comparison = comparison.field("Extra syncs per commit", syncs_true - syncs_false);
+    }
+    if let (Some(volume_false), Some(volume_true)) =
+        (stats_individual_false.volume, stats_individual_true.volume)
+    {
+        comparison = comparison.field(
+            "Latency difference (μs per KiB)",
+            volume_true.us_per_kib(stats_individual_true.avg_write_time)
+                - volume_false.us_per_kib(stats_individual_false.avg_write_time),
+        );
+    }
+    let [samples_false, samples_true] = samples;
+    if let Some(test) = MannWhitney::new(
+        samples_false.reservoir.samples(),
+        samples_true.reservoir.samples(),
+    ) {
+        comparison = comparison
+            .field("Mann-Whitney U", test.u)
+            .field("p-value", test.p_value)
+            .field("Effect size (rank-biserial)", test.effect_size)
+            .note(format!(
+                "The difference is {} (p = {:.2e}, effect size {:+.2}; ±1 means every write of \
+                 one setting was slower than every write of the other)",
+                if test.p_value < 0.05 {
+                    "statistically significant"
+                } else {
+                    "not statistically significant"
+                },
+                test.p_value,
+                test.effect_size
+            ));
+    }
+    report.push(comparison);
+
+    for (bench_db, samples) in bench_dbs.iter().zip(samples) {
+        if let Some(slo) = &samples.slo {
+            report.push(slo.section(format!(
+                "Latency SLO ({}) - {}",
+                slo.objective,
+                bench_db.label()
+            )));
+        }
+    }
+}
+
+/// Combine the runs of `--db-dirs` into one report: every section prefixed with its
+/// directory, after a table comparing the directories side by side.
+fn compare_dirs(runs: &[(&PathBuf, RunOutcome)]) -> Report {
+    let mut report = Report::new("BENCHMARK RESULTS SUMMARY");
+    let mut comparison = Section::new("Filesystem Comparison").table([
+        "directory",
+        "filesystem",
+        "avg quick_repair(false)",
+        "avg quick_repair(true)",
+        "speedup of false",
+        "difference (μs)",
+    ]);
+    for (dir, outcome) in runs {
+        let Some([stats_false, stats_true]) = &outcome.individual else {
+            continue;
+        };
+        comparison = comparison.row(vec![
+            dir.display().to_string().into(),
+            platform::filesystem_type(dir).unwrap_or("unknown").into(),
+            stats_false.avg_write_time.into(),
+            stats_true.avg_write_time.into(),
+            (stats_false.writes_per_second / stats_true.writes_per_second).into(),
+            Value::from(
+                stats_true.avg_write_time.as_micros() as i64
+                    - stats_false.avg_write_time.as_micros() as i64,
+            ),
+        ]);
+    }
+    report.push(comparison);
 
+    for (dir, outcome) in runs {
+        for section in &outcome.report.sections {
+            let mut section = section.clone();
+            section.title = format!("{} - {}", dir.display(), section.title);
+            report.push(section);
+        }
+    }
+    report
+}
+
+/// Write `report`, labelled with the run's tags, to every `--report` target, or to the
+/// console when none was given.
+fn emit_report(report: Report, args: &Args, verbosity: Verbosity) -> std::io::Result<()> {
+    let report = Report {
+        tags: args.tags.clone(),
+        ..report
+    };
+    if args.report.is_empty() {
+        return ReportTarget::default().emit(&report, args.color, verbosity);
+    }
+    for target in &args.report {
+        target.emit(&report, args.color, verbosity)?;
+    }
     Ok(())
 }
+
+fn parse_dirs(value: &str) -> Result<Vec<PathBuf>, String> {
+    let dirs: Vec<PathBuf> = value
+        .split(',')
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .collect();
+    if dirs.is_empty() {
+        return Err("expected at least one directory".to_owned());
+    }
+    Ok(dirs)
+}
+
+fn redb_label(args: &Args) -> String {
+    args.redb_label
+        .clone()
+        .unwrap_or_else(|| spike_redb_quick_repair::REDB_VERSION.to_owned())
+}
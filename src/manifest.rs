@@ -0,0 +1,130 @@
+//! Run manifest written with `--manifest`: the exact configuration and command line, the
+//! seed, the binary that ran (version, git commit, redb release, features) and the SHA-256
+//! of every database file the run produced, so that results can be tied back to their
+//! inputs long after the run.
+//!
+//! The database files are hashed at the end of each directory's run, before cleanup.
+
+use crate::config::Config;
+use crate::phase::Phase;
+use crate::report::Tag;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Bumped whenever a field is removed or changes meaning.
+pub const VERSION: u64 = 1;
+
+/// Git commit the binary was built from, with `-dirty` for uncommitted changes.
+pub const GIT_HASH: &str = env!("GIT_HASH");
+
+/// Cargo features this binary was built with.
+fn features() -> Vec<&'static str> {
+    [
+        ("redb2", cfg!(feature = "redb2")),
+        ("redb3", cfg!(feature = "redb3")),
+        ("redb4", cfg!(feature = "redb4")),
+        ("profiling", cfg!(feature = "profiling")),
+        ("plot", cfg!(feature = "plot")),
+        ("lz4", cfg!(feature = "lz4")),
+        ("zstd", cfg!(feature = "zstd")),
+        ("sqlite", cfg!(feature = "sqlite")),
+        ("sled", cfg!(feature = "sled")),
+        ("lmdb", cfg!(feature = "lmdb")),
+        ("rocksdb", cfg!(feature = "rocksdb")),
+        ("io-uring", cfg!(feature = "io-uring")),
+        ("tui", cfg!(feature = "tui")),
+    ]
+    .into_iter()
+    .filter_map(|(name, on)| on.then_some(name))
+    .collect()
+}
+
+/// Hex SHA-256 of the file at `path`.
+pub fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut fs::File::open(path)?, &mut hasher)?;
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+/// One database file produced by the run.
+pub struct DbFile {
+    pub path: String,
+    pub quick_repair: bool,
+    pub size: u64,
+    pub sha256: String,
+}
+
+impl DbFile {
+    pub fn hash(path: &str, quick_repair: bool) -> io::Result<Self> {
+        Ok(Self {
+            path: path.to_owned(),
+            quick_repair,
+            size: fs::metadata(path)?.len(),
+            sha256: sha256_file(Path::new(path))?,
+        })
+    }
+}
+
+pub struct Manifest {
+    pub command_line: Vec<String>,
+    pub redb: String,
+    pub phases: Vec<Phase>,
+    pub databases: Vec<DbFile>,
+    pub succeeded: bool,
+    pub tags: Vec<Tag>,
+}
+
+impl Manifest {
+    pub fn to_json(&self, config: &Config) -> Value {
+        json!({
+            "version": VERSION,
+            "created_unix_ms": crate::slo::unix_ms(),
+            "succeeded": self.succeeded,
+            "binary": {
+                "name": env!("CARGO_PKG_NAME"),
+                "version": env!("CARGO_PKG_VERSION"),
+                "git_hash": GIT_HASH,
+                "redb": self.redb,
+                "features": features(),
+            },
+            "command_line": self.command_line,
+            "tags": Tag::to_json(&self.tags),
+            "seed": config.seed,
+            "config": {
+                "fill_target": config.fill_target.to_string(),
+                "fill_batch_entries": config.fill_batch_entries,
+                "fill_batch_bytes": config.fill_batch_bytes,
+                "fill_concurrent": config.fill_concurrent,
+                "values": config.values.to_string(),
+                "codec": config.values.codec.to_string(),
+                "generator_threads": config.generator_threads,
+                "benchmark_writes": config.benchmark_writes,
+                "keys": config.keys.to_string(),
+                "reads": config.reads.to_string(),
+                "repair_callback": config.repair_callback.to_string(),
+                "warmup_writes": config.warmup.writes,
+                "warmup_until_stable": config.warmup.until_stable,
+                "repeats": config.repeats,
+                "shuffle_order": config.shuffle_order,
+            },
+            "phases": self.phases.iter().map(|phase| phase.name()).collect::<Vec<_>>(),
+            "databases": self.databases.iter().map(|db| json!({
+                "path": db.path,
+                "quick_repair": db.quick_repair,
+                "size": db.size,
+                "sha256": db.sha256,
+            })).collect::<Vec<_>>(),
+        })
+    }
+
+    pub fn write(&self, config: &Config, path: &Path) -> io::Result<()> {
+        fs::write(path, serde_json::to_vec_pretty(&self.to_json(config))?)
+    }
+}
@@ -0,0 +1,114 @@
+//! Transactions inserting one key into each of many distinct tables, since every table
+//! touched adds a root update to the commit metadata.
+
+use crate::generate_random_value;
+use crate::report::Section;
+use crate::{BenchDb, clock, hooks, record_op};
+use redb::{Database, Error, TableDefinition, WriteTransaction};
+use std::time::Duration;
+use tracing::{info, info_span};
+
+/// Distinct tables touched per transaction; every count is sampled in each round.
+pub const TABLE_COUNTS: [usize; 4] = [1, 10, 100, 1000];
+/// Rounds over [`TABLE_COUNTS`] at the default `--writes`, which scale them.
+pub const ROUNDS: usize = 20;
+/// Values are kept small so the commit cost is dominated by the table roots.
+const VALUE_SIZE: usize = 64;
+
+pub struct ManyTablesSample {
+    pub tables: usize,
+    pub latency: Duration,
+}
+
+pub struct ManyTablesReport {
+    pub samples: Vec<ManyTablesSample>,
+}
+
+fn table_name(index: usize) -> String {
+    format!("many_tables_{index}")
+}
+
+fn insert_into_tables(
+    write_txn: &WriteTransaction,
+    tables: usize,
+    key: u64,
+    value: &[u8],
+) -> Result<(), Error> {
+    for index in 0..tables {
+        let name = table_name(index);
+        let mut table = write_txn.open_table(TableDefinition::<u64, &[u8]>::new(&name))?;
+        table.insert(key, value)?;
+    }
+    Ok(())
+}
+
+/// Run `rounds` transactions per entry of [`TABLE_COUNTS`] against `bench_db`. The tables
+/// are created in an untimed transaction first, so only updates to existing roots are timed.
+pub fn benchmark_many_tables(bench_db: &BenchDb, rounds: usize) -> Result<ManyTablesReport, Error> {
+    let _span =
+        info_span!("many_tables", db_path = %bench_db.path, quick_repair = bench_db.quick_repair)
+            .entered();
+    info!(
+        rounds,
+        "Measuring commit latency against tables per transaction"
+    );
+
+    let db = bench_db.create(
+        Database::builder().set_cache_size(1024 * 1024 * 1024), // 1GB cache
+    )?;
+    let value = generate_random_value(VALUE_SIZE);
+    let max_tables = TABLE_COUNTS.iter().copied().max().unwrap_or(0);
+
+    let write_txn = hooks::begin_write(&db)?;
+    insert_into_tables(&write_txn, max_tables, 0, &value)?;
+    write_txn.commit()?;
+
+    let mut samples = Vec::with_capacity(rounds * TABLE_COUNTS.len());
+    for round in 0..rounds {
+        for &tables in &TABLE_COUNTS {
+            let start = clock::now();
+            let mut write_txn = hooks::begin_write(&db)?;
+            write_txn.set_quick_repair(bench_db.quick_repair);
+            insert_into_tables(&write_txn, tables, round as u64 + 1, &value)?;
+            write_txn.commit()?;
+            let latency = start.elapsed();
+            record_op(
+                "many-tables",
+                &bench_db.path,
+                bench_db.quick_repair,
+                samples.len() as u64,
+                tables,
+                tables * value.len(),
+                latency,
+            );
+
+            samples.push(ManyTablesSample { tables, latency });
+        }
+    }
+
+    Ok(ManyTablesReport { samples })
+}
+
+impl ManyTablesReport {
+    pub fn section(&self, title: impl Into<String>) -> Section {
+        let mut section = Section::new(title).table(["tables/txn", "avg latency", "per table"]);
+        for &tables in &TABLE_COUNTS {
+            let latencies: Vec<_> = self
+                .samples
+                .iter()
+                .filter(|s| s.tables == tables)
+                .map(|s| s.latency)
+                .collect();
+            if latencies.is_empty() {
+                continue;
+            }
+            let latency = latencies.iter().sum::<Duration>() / latencies.len() as u32;
+            section = section.row(vec![
+                tables.into(),
+                latency.into(),
+                (latency / tables as u32).into(),
+            ]);
+        }
+        section
+    }
+}
@@ -0,0 +1,156 @@
+//! Optional Prometheus endpoint exposing live counters during long runs.
+//!
+//! Recording is a no-op until [`serve`] has been called, so the benchmark loops can
+//! report unconditionally.
+
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+use tracing::{info, warn};
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+struct Metrics {
+    registry: Registry,
+    ops: IntCounterVec,
+    latency: HistogramVec,
+    file_size: IntGaugeVec,
+    phase: IntGaugeVec,
+    process_io: IntGaugeVec,
+}
+
+impl Metrics {
+    fn new() -> prometheus::Result<Self> {
+        let registry = Registry::new();
+
+        let ops = IntCounterVec::new(
+            Opts::new("bench_ops_total", "Committed transactions"),
+            &["phase", "quick_repair"],
+        )?;
+        let latency = HistogramVec::new(
+            HistogramOpts::new("bench_txn_latency_seconds", "Transaction latency")
+                .buckets(prometheus::exponential_buckets(0.000_05, 2.0, 18)?),
+            &["phase", "quick_repair"],
+        )?;
+        let file_size = IntGaugeVec::new(
+            Opts::new("bench_db_file_size_bytes", "Database file size"),
+            &["db_path"],
+        )?;
+        let phase = IntGaugeVec::new(
+            Opts::new("bench_phase", "Currently running phase (1 = active)"),
+            &["phase"],
+        )?;
+
+        registry.register(Box::new(ops.clone()))?;
+        registry.register(Box::new(latency.clone()))?;
+        registry.register(Box::new(file_size.clone()))?;
+        let process_io = IntGaugeVec::new(
+            Opts::new(
+                "bench_process_io_bytes",
+                "Bytes the process has read from or written to storage, as reported by the OS",
+            ),
+            &["direction"],
+        )?;
+
+        registry.register(Box::new(phase.clone()))?;
+        registry.register(Box::new(process_io.clone()))?;
+
+        Ok(Self {
+            registry,
+            ops,
+            latency,
+            file_size,
+            phase,
+            process_io,
+        })
+    }
+}
+
+/// Start serving metrics on `addr` from a background thread.
+pub fn serve(addr: SocketAddr) -> io::Result<()> {
+    let metrics = Metrics::new().map_err(io::Error::other)?;
+    let listener = TcpListener::bind(addr)?;
+    info!(%addr, "Serving Prometheus metrics");
+
+    if METRICS.set(metrics).is_err() {
+        return Err(io::Error::other("metrics endpoint already started"));
+    }
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let result = stream.and_then(handle_connection);
+            if let Err(e) = result {
+                warn!(error = %e, "Failed to serve metrics request");
+            }
+        }
+    });
+
+    Ok(())
+}
+
+fn handle_connection(mut stream: TcpStream) -> io::Result<()> {
+    // Drain the request headers; every path returns the metrics page.
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+
+    let mut body = Vec::new();
+    if let Some(metrics) = METRICS.get() {
+        // Sampled per scrape; left out on platforms without per-process I/O accounting.
+        if let Some(io) = crate::platform::io_counters() {
+            for (direction, bytes) in [("read", io.read_bytes), ("write", io.write_bytes)] {
+                metrics
+                    .process_io
+                    .with_label_values(&[direction])
+                    .set(bytes as i64);
+            }
+        }
+        TextEncoder::new()
+            .encode(&metrics.registry.gather(), &mut body)
+            .map_err(io::Error::other)?;
+    }
+
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    )?;
+    stream.write_all(&body)
+}
+
+/// Record one committed transaction.
+pub fn record_op(phase: &str, quick_repair: bool, latency: Duration) {
+    if let Some(metrics) = METRICS.get() {
+        let labels = [phase, if quick_repair { "true" } else { "false" }];
+        metrics.ops.with_label_values(&labels).inc();
+        metrics
+            .latency
+            .with_label_values(&labels)
+            .observe(latency.as_secs_f64());
+    }
+}
+
+/// Record the current size of a database file.
+pub fn set_file_size(db_path: &str, bytes: u64) {
+    if let Some(metrics) = METRICS.get() {
+        metrics
+            .file_size
+            .with_label_values(&[db_path])
+            .set(bytes as i64);
+    }
+}
+
+/// Mark `phase` as the currently running phase.
+pub fn set_phase(phase: &str) {
+    if let Some(metrics) = METRICS.get() {
+        metrics.phase.reset();
+        metrics.phase.with_label_values(&[phase]).set(1);
+    }
+}
@@ -0,0 +1,113 @@
+//! Latency of single read primitives on the filled table: `first()`, `last()`, `len()`,
+//! and `get()` of present and absent keys, each timed on its own in one read transaction.
+
+use crate::report::Section;
+use crate::{BenchDb, BenchmarkStats, StatsAccumulator, TABLE, clock, compat};
+use rand::Rng;
+use redb::{Database, Error, ReadableTable, ReadableTableMetadata};
+use std::hint::black_box;
+use tracing::{info, info_span};
+
+/// Timed calls per primitive.
+pub const CALLS: usize = 10_000;
+
+pub struct MicroOpsReport {
+    /// `(primitive, latencies)`, in the order they ran.
+    pub primitives: Vec<(&'static str, BenchmarkStats)>,
+}
+
+fn time(mut op: impl FnMut() -> Result<(), Error>) -> Result<BenchmarkStats, Error> {
+    let mut stats = StatsAccumulator::new();
+    for _ in 0..CALLS {
+        let start = clock::now();
+        op()?;
+        stats.record(start.elapsed());
+    }
+    Ok(stats.stats())
+}
+
+/// Time [`CALLS`] calls of each read primitive against the benchmark table of `bench_db`.
+pub fn benchmark_micro_ops(bench_db: &BenchDb) -> Result<MicroOpsReport, Error> {
+    let quick_repair = bench_db.quick_repair;
+    let _span = info_span!("micro_ops", db_path = %bench_db.path, quick_repair).entered();
+    info!(calls = CALLS, "Timing read primitives");
+
+    let db = bench_db.create(
+        Database::builder().set_cache_size(1024 * 1024 * 1024), // 1GB cache
+    )?;
+    let read_txn = compat::begin_read(&db)?;
+    let table = read_txn.open_table(TABLE)?;
+    let keys = table.last()?.map_or(0, |(key, _)| key.value() + 1);
+    if keys == 0 {
+        return Err(Error::Corrupted("benchmark table is empty".to_owned()));
+    }
+
+    let mut rng = rand::rng();
+    let mut primitives = Vec::new();
+    primitives.push((
+        "first()",
+        time(|| {
+            black_box(table.first()?);
+            Ok(())
+        })?,
+    ));
+    primitives.push((
+        "last()",
+        time(|| {
+            black_box(table.last()?);
+            Ok(())
+        })?,
+    ));
+    primitives.push((
+        "len()",
+        time(|| {
+            black_box(table.len()?);
+            Ok(())
+        })?,
+    ));
+    primitives.push((
+        "get() hit",
+        time(|| {
+            let key = rng.random_range(0..keys);
+            let value = table.get(key)?;
+            black_box(value.as_ref().map(|v| v.value().len()));
+            Ok(())
+        })?,
+    ));
+    primitives.push((
+        "get() miss",
+        time(|| {
+            let key = rng.random_range(keys..keys.saturating_mul(2).max(keys + 1));
+            black_box(table.get(key)?.is_none());
+            Ok(())
+        })?,
+    ));
+
+    Ok(MicroOpsReport { primitives })
+}
+
+impl MicroOpsReport {
+    pub fn section(&self, title: impl Into<String>) -> Section {
+        let mut section = Section::new(title)
+            .field("Calls per primitive", CALLS)
+            .table([
+                "primitive",
+                "mean",
+                "trimmed mean",
+                "min",
+                "max",
+                "ops per second",
+            ]);
+        for (name, stats) in &self.primitives {
+            section = section.row(vec![
+                (*name).into(),
+                stats.avg_write_time.into(),
+                stats.trimmed_mean.into(),
+                stats.min_write_time.into(),
+                stats.max_write_time.into(),
+                stats.writes_per_second.into(),
+            ]);
+        }
+        section.note("Each call is timed on its own in one read transaction")
+    }
+}
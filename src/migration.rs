@@ -0,0 +1,175 @@
+//! Schema migration: every row of the benchmark table rewritten into a table with a new
+//! value layout, a version tag in front of the old bytes, in chunked transactions that each
+//! move a chunk of rows and delete them from the old table, then the emptied old table
+//! dropped. This is the maintenance operation that rewrites a whole database.
+//!
+//! It runs on a copy of each benchmark database, since later phases still need the old table.
+
+use crate::config::MIB;
+use crate::report::Section;
+use crate::{
+    BenchDb, BenchmarkStats, StatsAccumulator, TABLE, clock, get_file_size, hooks, record_op,
+};
+use redb::{Database, Error, ReadableTable, TableDefinition};
+use std::fs;
+use std::time::Duration;
+use tracing::{debug, info, info_span};
+
+/// The migrated table: `(layout version, old value)` per key.
+pub const MIGRATED_TABLE: TableDefinition<u64, (u32, &[u8])> =
+    TableDefinition::new("benchmark_migrated");
+/// Layout version written by the migration.
+const LAYOUT_VERSION: u32 = 2;
+/// Default rows moved per transaction.
+pub const DEFAULT_CHUNK_ROWS: usize = 10_000;
+
+pub struct MigrationReport {
+    pub chunk_rows: usize,
+    pub rows: u64,
+    pub elapsed: Duration,
+    /// One commit per chunk.
+    pub chunks: BenchmarkStats,
+    /// Deleting the emptied old table.
+    pub drop_table: Duration,
+    pub size_before: u64,
+    /// Largest file size seen after a chunk.
+    pub size_peak: u64,
+    pub size_after: u64,
+}
+
+/// Move the next `chunk_rows` rows of the old table into [`MIGRATED_TABLE`] in one
+/// transaction, returning how many were moved and their value bytes.
+fn migrate_chunk(
+    db: &Database,
+    quick_repair: bool,
+    chunk_rows: usize,
+) -> Result<(u64, usize), Error> {
+    let mut write_txn = hooks::begin_write(db)?;
+    write_txn.set_quick_repair(quick_repair);
+    let (moved, bytes) = {
+        let mut old = write_txn.open_table(TABLE)?;
+        let mut new = write_txn.open_table(MIGRATED_TABLE)?;
+        let rows = old
+            .iter()?
+            .take(chunk_rows)
+            .map(|entry| entry.map(|(key, value)| (key.value(), value.value().to_vec())))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut bytes = 0;
+        for (key, value) in &rows {
+            new.insert(key, (LAYOUT_VERSION, value.as_slice()))?;
+            bytes += value.len();
+        }
+        if let (Some((first, _)), Some((last, _))) = (rows.first(), rows.last()) {
+            old.retain_in(*first..=*last, |_, _| false)?;
+        }
+        (rows.len() as u64, bytes)
+    };
+    if moved == 0 {
+        write_txn.abort()?;
+    } else {
+        write_txn.commit()?;
+    }
+    Ok((moved, bytes))
+}
+
+fn migrate(path: &str, bench_db: &BenchDb, chunk_rows: usize) -> Result<MigrationReport, Error> {
+    let quick_repair = bench_db.quick_repair;
+    let size_before = get_file_size(path)?;
+    let db = Database::builder()
+        .set_cache_size(1024 * 1024 * 1024) // 1GB cache
+        .create(path)?;
+
+    let mut chunks = StatsAccumulator::new();
+    let mut rows = 0;
+    let mut size_peak = size_before;
+    let run_start = clock::now();
+    loop {
+        let start = clock::now();
+        let (moved, bytes) = migrate_chunk(&db, quick_repair, chunk_rows)?;
+        if moved == 0 {
+            break;
+        }
+        let duration = start.elapsed();
+        record_op(
+            "migrate",
+            &bench_db.path,
+            quick_repair,
+            chunks.count() as u64,
+            moved as usize,
+            bytes,
+            duration,
+        );
+        chunks.record(duration);
+        rows += moved;
+        size_peak = size_peak.max(get_file_size(path)?);
+        debug!(rows, ?duration, "Migrated chunk");
+    }
+
+    let start = clock::now();
+    let mut write_txn = hooks::begin_write(&db)?;
+    write_txn.set_quick_repair(quick_repair);
+    write_txn.delete_table(TABLE)?;
+    write_txn.commit()?;
+    let drop_table = start.elapsed();
+    let elapsed = run_start.elapsed();
+    drop(db);
+
+    Ok(MigrationReport {
+        chunk_rows,
+        rows,
+        elapsed,
+        chunks: if chunks.is_empty() {
+            BenchmarkStats::new(&[Duration::ZERO])
+        } else {
+            chunks.stats()
+        },
+        drop_table,
+        size_before,
+        size_peak,
+        size_after: get_file_size(path)?,
+    })
+}
+
+/// Migrate a copy of `bench_db` to the new value layout, `chunk_rows` rows per transaction.
+pub fn benchmark_migration(
+    bench_db: &BenchDb,
+    chunk_rows: usize,
+) -> Result<MigrationReport, Error> {
+    let quick_repair = bench_db.quick_repair;
+    let _span = info_span!("migration", db_path = %bench_db.path, quick_repair).entered();
+    info!(chunk_rows, "Benchmarking migration to a new value layout");
+
+    let copy = format!("{}.migrate", bench_db.path);
+    fs::copy(&bench_db.path, &copy)?;
+    let report = migrate(&copy, bench_db, chunk_rows.max(1));
+    let _ = fs::remove_file(&copy);
+    let report = report?;
+    info!(rows = report.rows, elapsed = ?report.elapsed, "Migrated");
+    Ok(report)
+}
+
+impl MigrationReport {
+    pub fn rows_per_second(&self) -> f64 {
+        self.rows as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+
+    pub fn sections(&self, label: &str) -> Vec<Section> {
+        let mib = |bytes: u64| bytes as f64 / MIB as f64;
+        let summary = Section::new(format!("Migration - {label}"))
+            .field("Rows migrated", self.rows)
+            .field("Rows per transaction", self.chunk_rows)
+            .field("Transactions", self.chunks.count)
+            .field("Total migration time", self.elapsed)
+            .field("Rows per second", self.rows_per_second())
+            .field("Drop old table", self.drop_table)
+            .field("File size before (MiB)", mib(self.size_before))
+            .field("Peak file size (MiB)", mib(self.size_peak))
+            .field("File size after (MiB)", mib(self.size_after))
+            .note("Each transaction moves a chunk of rows to the new table and deletes them from the old one");
+        vec![
+            summary,
+            self.chunks
+                .section(format!("Migration Chunk Commits - {label}")),
+        ]
+    }
+}
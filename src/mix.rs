@@ -0,0 +1,396 @@
+//! A workload mix given on the command line with `--mix`, e.g.
+//! `insert:60,get:30,delete:5,scan100:5`: each of the phase's operations is drawn at random
+//! with the given weights and runs in its own transaction against the benchmark table, so
+//! that a new mix needs neither code nor a new option.
+//!
+//! Operations are `insert` (a fresh key), `update` (overwrite an existing key), `delete`
+//! (remove an existing key, which may already be gone), `get` (a point read) and `scanN`
+//! (read N entries from a random key; `scan` alone reads [`DEFAULT_SCAN`]). Writes commit
+//! with the database's quick_repair setting. Gets and scans start at a key drawn with
+//! `--read-pattern`, updates and deletes at a uniformly drawn one.
+
+use crate::config::ReadPattern;
+use crate::payload::ValueGen;
+use crate::report::Section;
+use crate::{BenchDb, BenchmarkStats, StatsAccumulator, TABLE, clock, compat, hooks, record_op};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use redb::{Database, Error};
+use std::fmt;
+use std::hint::black_box;
+use std::str::FromStr;
+use tracing::{info, info_span};
+
+/// Default operations run per database.
+pub const DEFAULT_OPS: usize = 10_000;
+/// Entries read by a bare `scan`.
+pub const DEFAULT_SCAN: usize = 100;
+/// Mix run when the phase is listed in `--phases` without `--mix`.
+pub const DEFAULT_MIX: &str = "insert:60,get:30,delete:5,scan100:5";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MixOp {
+    Insert,
+    Update,
+    Delete,
+    Get,
+    /// Range read of this many entries.
+    Scan(usize),
+}
+
+impl MixOp {
+    pub fn is_write(self) -> bool {
+        matches!(self, MixOp::Insert | MixOp::Update | MixOp::Delete)
+    }
+}
+
+impl fmt::Display for MixOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MixOp::Insert => f.write_str("insert"),
+            MixOp::Update => f.write_str("update"),
+            MixOp::Delete => f.write_str("delete"),
+            MixOp::Get => f.write_str("get"),
+            MixOp::Scan(entries) => write!(f, "scan{entries}"),
+        }
+    }
+}
+
+impl FromStr for MixOp {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "insert" => Ok(MixOp::Insert),
+            "update" => Ok(MixOp::Update),
+            "delete" => Ok(MixOp::Delete),
+            "get" => Ok(MixOp::Get),
+            "scan" => Ok(MixOp::Scan(DEFAULT_SCAN)),
+            _ => match s.strip_prefix("scan").map(str::parse) {
+                Some(Ok(entries)) if entries > 0 => Ok(MixOp::Scan(entries)),
+                _ => Err(format!(
+                    "unknown operation `{s}`, expected insert, update, delete, get or scanN"
+                )),
+            },
+        }
+    }
+}
+
+/// Operations with their relative weights, in the order given.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mix {
+    pub ops: Vec<(MixOp, u32)>,
+}
+
+impl Mix {
+    fn total_weight(&self) -> u32 {
+        self.ops.iter().map(|&(_, weight)| weight).sum()
+    }
+
+    /// Draw an operation with its weight.
+    pub fn pick(&self, rng: &mut impl Rng) -> usize {
+        let mut draw = rng.random_range(0..self.total_weight());
+        for (i, &(_, weight)) in self.ops.iter().enumerate() {
+            if draw < weight {
+                return i;
+            }
+            draw -= weight;
+        }
+        unreachable!("draw below the total weight")
+    }
+}
+
+impl FromStr for Mix {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut ops: Vec<(MixOp, u32)> = Vec::new();
+        let mut total_weight = 0u32;
+        for part in s.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+            let (op, weight) = part
+                .split_once(':')
+                .ok_or_else(|| format!("invalid mix entry `{part}`, expected e.g. `get:30`"))?;
+            let op: MixOp = op.trim().parse()?;
+            let weight = weight
+                .trim()
+                .parse()
+                .map_err(|_| format!("invalid weight `{weight}` in mix entry `{part}`"))?;
+            if ops.iter().any(|&(seen, _)| seen == op) {
+                return Err(format!("`{op}` appears twice in the mix"));
+            }
+            total_weight = total_weight.checked_add(weight).ok_or_else(|| {
+                format!("the weights of mix `{s}` add up to more than {}", u32::MAX)
+            })?;
+            ops.push((op, weight));
+        }
+        if total_weight == 0 {
+            return Err(format!("mix `{s}` has no operation with a positive weight"));
+        }
+        Ok(Self { ops })
+    }
+}
+
+impl Default for Mix {
+    fn default() -> Self {
+        DEFAULT_MIX.parse().expect("valid default mix")
+    }
+}
+
+impl fmt::Display for Mix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (op, weight)) in self.ops.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{op}:{weight}")?;
+        }
+        Ok(())
+    }
+}
+
+pub struct OpStats {
+    pub op: MixOp,
+    /// `None` when the operation was never drawn.
+    pub latency: Option<BenchmarkStats>,
+}
+
+pub struct MixReport {
+    pub mix: Mix,
+    pub reads: ReadPattern,
+    pub ops: usize,
+    pub elapsed: std::time::Duration,
+    pub per_op: Vec<OpStats>,
+    /// Deletes of keys that were already gone.
+    pub missed_deletes: usize,
+}
+
+/// Run `ops` operations drawn from `mix` against `bench_db`, each in its own transaction,
+/// reading keys drawn with `reads`. Operations and keys come from an RNG seeded with `seed`,
+/// so both databases run the same sequence.
+pub fn benchmark_mix(
+    bench_db: &mut BenchDb,
+    mix: &Mix,
+    ops: usize,
+    reads: ReadPattern,
+    values: ValueGen,
+    seed: u64,
+) -> Result<MixReport, Error> {
+    let quick_repair = bench_db.quick_repair;
+    let _span = info_span!("mix", db_path = %bench_db.path, quick_repair).entered();
+    info!(%mix, ops, %reads, "Running operation mix");
+
+    let db = bench_db.create(
+        Database::builder().set_cache_size(1024 * 1024 * 1024), // 1GB cache
+    )?;
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut accumulators = vec![StatsAccumulator::new(); mix.ops.len()];
+    let mut missed_deletes = 0;
+
+    let start = clock::now();
+    for i in 0..ops {
+        let slot = mix.pick(&mut rng);
+        let op = mix.ops[slot].0;
+        let key = match op {
+            MixOp::Get | MixOp::Scan(_) => reads.key(&mut rng, bench_db.next_key),
+            _ => rng.random_range(0..bench_db.next_key.max(1)),
+        };
+        let value = match op {
+            MixOp::Insert => values.generate(bench_db.next_key),
+            MixOp::Update => values.generate(key),
+            _ => Vec::new(),
+        };
+        let op_start = clock::now();
+        run_op(&db, bench_db, op, key, &value, &mut missed_deletes)?;
+        let latency = op_start.elapsed();
+        accumulators[slot].record(latency);
+        if op.is_write() {
+            record_op(
+                "mix",
+                &bench_db.path,
+                quick_repair,
+                i as u64,
+                1,
+                value.len(),
+                latency,
+            );
+        }
+    }
+    let elapsed = start.elapsed();
+    info!(?elapsed, missed_deletes, "Operation mix done");
+
+    Ok(MixReport {
+        mix: mix.clone(),
+        reads,
+        ops,
+        elapsed,
+        per_op: mix
+            .ops
+            .iter()
+            .zip(&accumulators)
+            .map(|(&(op, _), accumulator)| OpStats {
+                op,
+                latency: (!accumulator.is_empty()).then(|| accumulator.stats()),
+            })
+            .collect(),
+        missed_deletes,
+    })
+}
+
+/// Run `op` on `key` (ignored by inserts), writing `value` for inserts and updates.
+fn run_op(
+    db: &Database,
+    bench_db: &mut BenchDb,
+    op: MixOp,
+    key: u64,
+    value: &[u8],
+    missed_deletes: &mut usize,
+) -> Result<(), Error> {
+    match op {
+        MixOp::Get => {
+            let read_txn = compat::begin_read(db)?;
+            let table = read_txn.open_table(TABLE)?;
+            black_box(table.get(key)?.map(|v| v.value().len()));
+            Ok(())
+        }
+        MixOp::Scan(entries) => {
+            let read_txn = compat::begin_read(db)?;
+            let table = read_txn.open_table(TABLE)?;
+            for entry in table.range(key..)?.take(entries) {
+                black_box(entry?.1.value().len());
+            }
+            Ok(())
+        }
+        MixOp::Insert | MixOp::Update | MixOp::Delete => {
+            let mut write_txn = hooks::begin_write(db)?;
+            write_txn.set_quick_repair(bench_db.quick_repair);
+            {
+                let mut table = write_txn.open_table(TABLE)?;
+                match op {
+                    MixOp::Delete => {
+                        if table.remove(key)?.is_none() {
+                            *missed_deletes += 1;
+                        }
+                    }
+                    MixOp::Insert => {
+                        table.insert(bench_db.next_key, value)?;
+                    }
+                    _ => {
+                        table.insert(key, value)?;
+                    }
+                }
+            }
+            write_txn.commit()?;
+            if op == MixOp::Insert {
+                bench_db.next_key += 1;
+            }
+            Ok(())
+        }
+    }
+}
+
+impl MixReport {
+    pub fn section(&self, title: impl Into<String>) -> Section {
+        let total_weight = f64::from(self.mix.total_weight());
+        let mut section = Section::new(title)
+            .field("Mix", self.mix.to_string())
+            .field("Reads", self.reads.to_string())
+            .field("Operations", self.ops)
+            .field("Elapsed", self.elapsed)
+            .field(
+                "Operations per second",
+                self.ops as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON),
+            )
+            .field("Deletes of missing keys", self.missed_deletes)
+            .table([
+                "op",
+                "weight %",
+                "count",
+                "mean",
+                "trimmed mean",
+                "max",
+                "per second",
+            ]);
+        for (stats, &(_, weight)) in self.per_op.iter().zip(&self.mix.ops) {
+            let share = f64::from(weight) / total_weight * 100.0;
+            section = section.row(match &stats.latency {
+                Some(latency) => vec![
+                    stats.op.to_string().into(),
+                    share.into(),
+                    latency.count.into(),
+                    latency.avg_write_time.into(),
+                    latency.trimmed_mean.into(),
+                    latency.max_write_time.into(),
+                    latency.writes_per_second.into(),
+                ],
+                None => vec![
+                    stats.op.to_string().into(),
+                    share.into(),
+                    0usize.into(),
+                    "-".into(),
+                    "-".into(),
+                    "-".into(),
+                    "-".into(),
+                ],
+            });
+        }
+        section
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn default_mix_round_trips_through_display() {
+        let mix = Mix::default();
+        assert_eq!(mix.to_string(), DEFAULT_MIX);
+        assert_eq!(mix.to_string().parse::<Mix>(), Ok(mix));
+    }
+
+    #[test]
+    fn bare_scan_reads_the_default_entries() {
+        assert_eq!("scan".parse(), Ok(MixOp::Scan(DEFAULT_SCAN)));
+        assert_eq!("scan7".parse(), Ok(MixOp::Scan(7)));
+        assert_eq!(MixOp::Scan(DEFAULT_SCAN).to_string(), "scan100");
+        assert!("scan0".parse::<MixOp>().is_err());
+        assert!("scanx".parse::<MixOp>().is_err());
+        assert!("put".parse::<MixOp>().is_err());
+    }
+
+    #[test]
+    fn rejects_duplicate_operations() {
+        let error = "scan:1,scan100:2".parse::<Mix>().unwrap_err();
+        assert!(error.contains("appears twice"), "{error}");
+        assert!("scan10:1,scan100:2".parse::<Mix>().is_ok());
+    }
+
+    #[test]
+    fn rejects_malformed_entries() {
+        assert!("insert".parse::<Mix>().is_err());
+        assert!("insert:x".parse::<Mix>().is_err());
+        assert!("insert:-1".parse::<Mix>().is_err());
+    }
+
+    #[test]
+    fn rejects_a_zero_total_weight() {
+        assert!("insert:0,get:0".parse::<Mix>().is_err());
+        assert!("".parse::<Mix>().is_err());
+    }
+
+    #[test]
+    fn never_picks_a_zero_weight() {
+        let mix: Mix = " insert:0 , get:5 ".parse().unwrap();
+        let mut rng = StdRng::seed_from_u64(0);
+        assert!((0..100).all(|_| mix.pick(&mut rng) == 1));
+    }
+
+    #[test]
+    fn rejects_weights_overflowing_u32() {
+        assert!("insert:4294967295,get:1".parse::<Mix>().is_err());
+        let mix: Mix = "insert:4294967294,get:1".parse().unwrap();
+        assert_eq!(mix.total_weight(), u32::MAX);
+    }
+}
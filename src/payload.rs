@@ -0,0 +1,281 @@
+//! Value generators for the inserted payloads.
+//!
+//! Pure random data is incompressible and therefore a worst case for filesystems with
+//! transparent compression (ZFS, btrfs); the other patterns approximate real payloads, and
+//! a [`Schema`] replaces them with encoded structured records.
+
+use crate::compression::Codec;
+use crate::schema::Schema;
+use rand::Rng;
+use rand::seq::IndexedRandom;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::mpsc::{Receiver, SyncSender, sync_channel};
+use std::thread;
+
+/// Vocabulary of the text-like pattern and of the strings in [`Schema`] records.
+pub(crate) const WORDS: &[&str] = &[
+    "the",
+    "block",
+    "commit",
+    "height",
+    "value",
+    "of",
+    "and",
+    "a",
+    "to",
+    "in",
+    "transaction",
+    "validator",
+    "hash",
+    "signature",
+    "round",
+    "proposal",
+    "is",
+    "for",
+    "with",
+    "state",
+];
+
+/// Chunk size used by the semi-compressible pattern.
+const CHUNK: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValuePattern {
+    /// Uniformly random bytes (incompressible).
+    Random,
+    /// All zero bytes (maximally compressible).
+    Zeroes,
+    /// Space-separated words from a small vocabulary, roughly 3-4x compressible.
+    TextLike,
+    /// Alternating random and constant 64-byte chunks, roughly 2x compressible.
+    SemiCompressible,
+}
+
+impl ValuePattern {
+    pub fn generate(self, size: usize) -> Vec<u8> {
+        let mut value = Vec::with_capacity(size);
+        self.generate_into(&mut value, size);
+        value
+    }
+
+    /// Overwrite `value` with a fresh value of `size` bytes, reusing its allocation.
+    pub fn generate_into(self, value: &mut Vec<u8>, size: usize) {
+        let mut rng = rand::rng();
+        value.clear();
+        match self {
+            ValuePattern::Random => {
+                value.resize(size, 0);
+                rng.fill(value.as_mut_slice());
+            }
+            ValuePattern::Zeroes => value.resize(size, 0),
+            ValuePattern::TextLike => {
+                while value.len() < size {
+                    value.extend_from_slice(WORDS.choose(&mut rng).unwrap().as_bytes());
+                    value.push(b' ');
+                }
+                value.truncate(size);
+            }
+            ValuePattern::SemiCompressible => {
+                value.resize(size, 0);
+                for chunk in value.chunks_mut(CHUNK).step_by(2) {
+                    rng.fill(chunk);
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for ValuePattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "random" => Ok(ValuePattern::Random),
+            "zeroes" => Ok(ValuePattern::Zeroes),
+            "text-like" => Ok(ValuePattern::TextLike),
+            "semi-compressible" => Ok(ValuePattern::SemiCompressible),
+            _ => Err(format!(
+                "unknown value pattern `{s}`, expected `random`, `zeroes`, `text-like` or `semi-compressible`"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ValuePattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ValuePattern::Random => "random",
+            ValuePattern::Zeroes => "zeroes",
+            ValuePattern::TextLike => "text-like",
+            ValuePattern::SemiCompressible => "semi-compressible",
+        })
+    }
+}
+
+/// Shape of the values inserted by every phase.
+#[derive(Clone, Copy, Debug)]
+pub struct ValueGen {
+    pub pattern: ValuePattern,
+    /// Smallest uncompressed value size in bytes.
+    pub min_size: usize,
+    /// Largest uncompressed value size in bytes; sizes are drawn uniformly in between.
+    pub max_size: usize,
+    /// Codec applied to each value before insert.
+    pub codec: Codec,
+    /// Records replacing the pattern; the sizes are then the bounds of their encoding.
+    pub schema: Option<Schema>,
+}
+
+impl ValueGen {
+    pub fn fixed(pattern: ValuePattern, size: usize) -> Self {
+        Self {
+            pattern,
+            min_size: size,
+            max_size: size,
+            codec: Codec::None,
+            schema: None,
+        }
+    }
+
+    /// Generate `schema` records instead of the pattern.
+    pub fn with_schema(self, schema: Schema) -> Self {
+        Self {
+            min_size: schema.min_size(),
+            max_size: schema.max_size(),
+            schema: Some(schema),
+            ..self
+        }
+    }
+
+    /// Uncompressed value for `key`, from the schema if any.
+    fn raw(&self, key: u64) -> Vec<u8> {
+        match self.schema {
+            Some(schema) => schema.encode(key),
+            None => self.pattern.generate(self.size()),
+        }
+    }
+
+    /// Average uncompressed value size, used for size estimates.
+    pub fn mean_size(&self) -> usize {
+        (self.min_size + self.max_size) / 2
+    }
+
+    fn size(&self) -> usize {
+        if self.min_size == self.max_size {
+            self.min_size
+        } else {
+            rand::rng().random_range(self.min_size..=self.max_size)
+        }
+    }
+
+    /// Generate the value to insert under `key`, compressed with `codec`; only schema
+    /// records depend on the key.
+    pub fn generate(&self, key: u64) -> Vec<u8> {
+        self.codec.compress(self.raw(key))
+    }
+
+    /// Like [`ValueGen::generate`], but into `value`, reusing its allocation when values
+    /// are not compressed.
+    pub fn generate_into(&self, key: u64, value: &mut Vec<u8>) {
+        match (self.codec, self.schema) {
+            (Codec::None, Some(schema)) => schema.encode_into(key, value),
+            (Codec::None, None) => self.pattern.generate_into(value, self.size()),
+            (codec, _) => *value = codec.compress(self.raw(key)),
+        }
+    }
+}
+
+impl fmt::Display for ValueGen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(schema) = self.schema {
+            write!(f, "{}-{} B records {schema}", self.min_size, self.max_size)
+        } else if self.min_size == self.max_size {
+            write!(f, "{} B {}", self.min_size, self.pattern)
+        } else {
+            write!(f, "{}-{} B {}", self.min_size, self.max_size, self.pattern)
+        }
+    }
+}
+
+/// Values generated per message on the [`ValueStream`] channel, amortizing its overhead.
+const STREAM_CHUNK: usize = 256;
+/// Chunks buffered ahead of the consumer per generator thread.
+const STREAM_DEPTH: usize = 4;
+
+/// Values generated ahead of time on background threads for successive keys, so that the
+/// RNG work of the fill phase overlaps with its I/O instead of adding to it.
+///
+/// Generator threads stop shortly after the stream is dropped.
+pub struct ValueStream {
+    values: ValueGen,
+    /// One channel per generator thread, thread `t` of `n` generating chunks `t`, `t + n`,
+    /// and so on, so that chunks are received in key order.
+    rxs: Vec<Receiver<Vec<Vec<u8>>>>,
+    /// Chunks received so far.
+    received: usize,
+    chunk: std::vec::IntoIter<Vec<u8>>,
+    /// Key the next value of `chunk` was generated for.
+    next_key: u64,
+    /// Current value; generated in place when there are no generator threads.
+    value: Vec<u8>,
+}
+
+impl ValueStream {
+    /// Generate `values` for the keys from `start_key` on `threads` background threads, or
+    /// inline when `threads` is 0.
+    pub fn spawn(values: ValueGen, threads: usize, start_key: u64) -> Self {
+        let rxs = (0..threads)
+            .map(|thread| {
+                let (tx, rx): (SyncSender<Vec<Vec<u8>>>, _) = sync_channel(STREAM_DEPTH);
+                thread::Builder::new()
+                    .name("value-gen".to_owned())
+                    .spawn(move || {
+                        for chunk in (thread..).step_by(threads) {
+                            let first = start_key + (chunk * STREAM_CHUNK) as u64;
+                            let chunk = (first..first + STREAM_CHUNK as u64)
+                                .map(|key| values.generate(key))
+                                .collect();
+                            if tx.send(chunk).is_err() {
+                                break;
+                            }
+                        }
+                    })
+                    .expect("failed to spawn value generator thread");
+                rx
+            })
+            .collect();
+        Self {
+            values,
+            rxs,
+            received: 0,
+            chunk: Vec::new().into_iter(),
+            next_key: start_key,
+            value: Vec::with_capacity(values.max_size),
+        }
+    }
+
+    /// The value for `key`, waiting for the generators if they have fallen behind. Values
+    /// generated for keys skipped since the previous call are dropped. It is only valid
+    /// until the next call.
+    pub fn next_value(&mut self, key: u64) -> &[u8] {
+        while !self.rxs.is_empty() && self.next_key <= key {
+            if self.chunk.len() == 0 {
+                let rx = &self.rxs[self.received % self.rxs.len()];
+                let Ok(chunk) = rx.recv() else {
+                    break;
+                };
+                self.chunk = chunk.into_iter();
+                self.received += 1;
+            }
+            let value = self.chunk.next().expect("chunks are not empty");
+            self.next_key += 1;
+            if self.next_key > key {
+                self.value = value;
+                return &self.value;
+            }
+        }
+        self.values.generate_into(key, &mut self.value);
+        &self.value
+    }
+}
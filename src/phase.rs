@@ -0,0 +1,243 @@
+//! Names of the pipeline phases, for running a subset of them with `--phases`, and the
+//! [`DbPhase`] trait the phases run against each benchmark database implement.
+
+use crate::BenchDb;
+use crate::report::Section;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// One phase of a run, in pipeline order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    Fill,
+    WriteBench,
+    Batch,
+    DirtyPages,
+    ManyTables,
+    Alternate,
+    Barrier,
+    PinnedSnapshot,
+    Savepoints,
+    FreePages,
+    Queue,
+    Ttl,
+    SyncStrategies,
+    CommitBreakdown,
+    TornWrites,
+    BitRot,
+    ReadOnlyOpen,
+    MicroOps,
+    Iteration,
+    Pipeline,
+    Backup,
+    Migration,
+    KeyEncoding,
+    ShutdownOpen,
+    CheckpointThread,
+    ColdStart,
+    Mix,
+    Retry,
+    BulkDelete,
+    Engines,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 30] = [
+        Phase::Fill,
+        Phase::WriteBench,
+        Phase::Batch,
+        Phase::DirtyPages,
+        Phase::ManyTables,
+        Phase::Alternate,
+        Phase::Barrier,
+        Phase::PinnedSnapshot,
+        Phase::Savepoints,
+        Phase::FreePages,
+        Phase::Queue,
+        Phase::Ttl,
+        Phase::SyncStrategies,
+        Phase::CommitBreakdown,
+        Phase::TornWrites,
+        Phase::BitRot,
+        Phase::ReadOnlyOpen,
+        Phase::MicroOps,
+        Phase::Iteration,
+        Phase::Pipeline,
+        Phase::Backup,
+        Phase::Migration,
+        Phase::KeyEncoding,
+        Phase::ShutdownOpen,
+        Phase::CheckpointThread,
+        Phase::ColdStart,
+        Phase::Mix,
+        Phase::Retry,
+        Phase::BulkDelete,
+        Phase::Engines,
+    ];
+
+    /// Name used by `--phases`, the `phase` tracing spans and the metrics.
+    pub fn name(self) -> &'static str {
+        match self {
+            Phase::Fill => "fill",
+            Phase::WriteBench => "write-bench",
+            Phase::Batch => "batch",
+            Phase::DirtyPages => "dirty-pages",
+            Phase::ManyTables => "many-tables",
+            Phase::Alternate => "alternate",
+            Phase::Barrier => "barrier",
+            Phase::PinnedSnapshot => "pinned-snapshot",
+            Phase::Savepoints => "savepoints",
+            Phase::FreePages => "free-pages",
+            Phase::Queue => "queue",
+            Phase::Ttl => "ttl",
+            Phase::SyncStrategies => "sync-strategies",
+            Phase::CommitBreakdown => "commit-breakdown",
+            Phase::TornWrites => "torn-writes",
+            Phase::BitRot => "bit-rot",
+            Phase::ReadOnlyOpen => "read-only-open",
+            Phase::MicroOps => "micro-ops",
+            Phase::Iteration => "iteration",
+            Phase::Pipeline => "pipeline",
+            Phase::Backup => "backup",
+            Phase::Migration => "migration",
+            Phase::KeyEncoding => "key-encoding",
+            Phase::ShutdownOpen => "shutdown-open",
+            Phase::CheckpointThread => "checkpoint-thread",
+            Phase::ColdStart => "cold-start",
+            Phase::Mix => "mix",
+            Phase::Retry => "retry",
+            Phase::BulkDelete => "bulk-delete",
+            Phase::Engines => "engines",
+        }
+    }
+}
+
+impl FromStr for Phase {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Phase::ALL
+            .into_iter()
+            .find(|phase| phase.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = Phase::ALL.iter().map(|phase| phase.name()).collect();
+                format!("unknown phase `{s}`, expected one of {}", names.join(", "))
+            })
+    }
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Parse a comma-separated list of phase names.
+pub fn parse_list(value: &str) -> Result<Vec<Phase>, String> {
+    value.split(',').map(|name| name.trim().parse()).collect()
+}
+
+/// A phase run against each benchmark database in turn, reporting what it measured as
+/// sections.
+pub trait DbPhase {
+    fn phase(&self) -> Phase;
+
+    /// Run against `bench_db`, returning its sections.
+    fn run(&mut self, bench_db: &mut BenchDb) -> Result<Vec<Section>, redb::Error>;
+
+    /// Sections comparing the databases, once the phase has run against all of them.
+    fn finish(&mut self) -> Vec<Section> {
+        Vec::new()
+    }
+
+    /// Mean open after an unclean exit of the database with `quick_repair`, for the
+    /// `RESULT` lines, if the phase measured it.
+    fn repair_time(&self, quick_repair: bool) -> Option<Duration> {
+        let _ = quick_repair;
+        None
+    }
+}
+
+type RunFn<R> = Box<dyn FnMut(&mut BenchDb) -> Result<R, redb::Error>>;
+type SectionsFn<R> = Box<dyn Fn(&R, &str) -> Vec<Section>>;
+type CompareFn<R> = Box<dyn Fn(&[R]) -> Vec<Section>>;
+type RepairFn<R> = fn(&R) -> (bool, Duration);
+
+/// A [`DbPhase`] made of a function running it against one database and one turning that
+/// database's report, with its label, into sections.
+pub struct PerDb<R> {
+    phase: Phase,
+    run: RunFn<R>,
+    sections: SectionsFn<R>,
+    compare: Option<CompareFn<R>>,
+    repair: Option<RepairFn<R>>,
+    first_only: bool,
+    reports: Vec<R>,
+}
+
+impl<R> PerDb<R> {
+    pub fn new(
+        phase: Phase,
+        run: impl FnMut(&mut BenchDb) -> Result<R, redb::Error> + 'static,
+        sections: impl Fn(&R, &str) -> Vec<Section> + 'static,
+    ) -> Self {
+        Self {
+            phase,
+            run: Box::new(run),
+            sections: Box::new(sections),
+            compare: None,
+            repair: None,
+            first_only: false,
+            reports: Vec::new(),
+        }
+    }
+
+    /// Add sections comparing the reports of every database.
+    pub fn compare(mut self, compare: impl Fn(&[R]) -> Vec<Section> + 'static) -> Self {
+        self.compare = Some(Box::new(compare));
+        self
+    }
+
+    /// Report the repair time `repair` reads from each report, with its quick_repair setting.
+    pub fn repair(mut self, repair: RepairFn<R>) -> Self {
+        self.repair = Some(repair);
+        self
+    }
+
+    /// Run against the first database only, for phases covering both settings on their own.
+    pub fn first_only(mut self) -> Self {
+        self.first_only = true;
+        self
+    }
+}
+
+impl<R> DbPhase for PerDb<R> {
+    fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    fn run(&mut self, bench_db: &mut BenchDb) -> Result<Vec<Section>, redb::Error> {
+        if self.first_only && !self.reports.is_empty() {
+            return Ok(Vec::new());
+        }
+        let report = (self.run)(bench_db)?;
+        let sections = (self.sections)(&report, &bench_db.label());
+        self.reports.push(report);
+        Ok(sections)
+    }
+
+    fn finish(&mut self) -> Vec<Section> {
+        self.compare
+            .as_ref()
+            .map_or_else(Vec::new, |compare| compare(&self.reports))
+    }
+
+    fn repair_time(&self, quick_repair: bool) -> Option<Duration> {
+        let repair = self.repair?;
+        self.reports
+            .iter()
+            .map(repair)
+            .find_map(|(setting, time)| (setting == quick_repair).then_some(time))
+    }
+}
@@ -0,0 +1,349 @@
+//! End-to-end throughput of the pipeline we would deploy around redb's single writer:
+//! worker threads prepare complete batches, generating (and compressing) the values of their
+//! own slice of the keyspace, and hand them over a bounded channel to one committer thread,
+//! which only inserts and commits.
+//!
+//! The same batches are also prepared and committed inline by a single thread, as the
+//! baseline the pipeline has to beat.
+//!
+//! The pipelined run also tracks the queue in front of the committer: how many prepared
+//! batches wait for it over time and how long a batch takes from ready to committed, which
+//! is the application-side buffer a deployment needs to absorb the commit latency.
+
+use crate::clock::{self, Stamp};
+use crate::config::MIB;
+use crate::payload::ValueGen;
+use crate::report::Section;
+use crate::{BenchDb, BenchmarkStats, StatsAccumulator, hooks, record_op};
+use redb::{Database, Error, TableDefinition};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use tracing::{info, info_span};
+
+pub const PIPELINE_TABLE: TableDefinition<u64, &[u8]> = TableDefinition::new("benchmark_pipeline");
+/// Batches committed per run.
+pub const BATCHES: usize = 1000;
+/// Inserts per batch.
+pub const BATCH_ENTRIES: usize = 100;
+/// Default number of worker threads.
+pub const DEFAULT_WORKERS: usize = 4;
+/// Prepared batches the channel holds per worker before workers block.
+const QUEUED_PER_WORKER: usize = 2;
+/// Keys per worker: worker `w` writes keys from `w * KEYSPACE` up.
+const KEYSPACE: u64 = 1 << 40;
+/// Rows of the queue depth over time.
+const DEPTH_ROWS: usize = 20;
+
+type Batch = Vec<(u64, Vec<u8>)>;
+
+/// A batch on its way to the committer.
+struct Prepared {
+    batch: Batch,
+    ready: Stamp,
+}
+
+/// The queue in front of the committer during the pipelined run.
+pub struct QueueStats {
+    /// Batches the channel holds before workers block.
+    pub capacity: usize,
+    /// From a batch being prepared to its commit returning.
+    pub ready_to_commit: BenchmarkStats,
+    /// `(time since the run started, batches waiting)` at every commit, counting the one
+    /// about to be committed and those whose workers wait for room in the channel.
+    pub depth: Vec<(Duration, usize)>,
+}
+
+impl QueueStats {
+    pub fn max_depth(&self) -> usize {
+        self.depth
+            .iter()
+            .map(|&(_, depth)| depth)
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn mean_depth(&self) -> f64 {
+        let total: usize = self.depth.iter().map(|&(_, depth)| depth).sum();
+        total as f64 / self.depth.len().max(1) as f64
+    }
+}
+
+pub struct PipelineRun {
+    /// Preparing threads; 0 when the committer prepares its own batches.
+    pub workers: usize,
+    pub elapsed: Duration,
+    pub entries: u64,
+    pub bytes: u64,
+    /// Inserting and committing one batch.
+    pub commit: BenchmarkStats,
+    /// Time the committer waited for a prepared batch.
+    pub committer_idle: Duration,
+    /// Time the workers, summed, waited for room in the channel.
+    pub workers_blocked: Duration,
+}
+
+impl PipelineRun {
+    pub fn entries_per_second(&self) -> f64 {
+        self.entries as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+
+    pub fn mib_per_second(&self) -> f64 {
+        self.bytes as f64 / MIB as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+pub struct PipelineReport {
+    pub inline: PipelineRun,
+    pub pipelined: PipelineRun,
+    pub queue: QueueStats,
+}
+
+/// Prepare batch `index` of the `round`th run of `worker`, which owns keys from
+/// `worker * KEYSPACE` up.
+fn prepare(worker: usize, round: u64, index: u64, values: ValueGen) -> Batch {
+    let first = worker as u64 * KEYSPACE + (round * BATCHES as u64 + index) * BATCH_ENTRIES as u64;
+    (first..first + BATCH_ENTRIES as u64)
+        .map(|key| (key, values.generate(key)))
+        .collect()
+}
+
+/// Insert and commit `batch`, returning its latency and value bytes.
+fn commit(
+    db: &Database,
+    bench_db: &BenchDb,
+    op: usize,
+    batch: &Batch,
+) -> Result<(Duration, usize), Error> {
+    let start = clock::now();
+    let mut write_txn = hooks::begin_write(db)?;
+    write_txn.set_quick_repair(bench_db.quick_repair);
+    let mut bytes = 0;
+    {
+        let mut table = write_txn.open_table(PIPELINE_TABLE)?;
+        for (key, value) in batch {
+            table.insert(key, value.as_slice())?;
+            bytes += value.len();
+        }
+    }
+    write_txn.commit()?;
+    let duration = start.elapsed();
+    record_op(
+        "pipeline",
+        &bench_db.path,
+        bench_db.quick_repair,
+        op as u64,
+        batch.len(),
+        bytes,
+        duration,
+    );
+    Ok((duration, bytes))
+}
+
+fn run_inline(db: &Database, bench_db: &BenchDb, values: ValueGen) -> Result<PipelineRun, Error> {
+    let mut latencies = StatsAccumulator::new();
+    let mut bytes = 0;
+    let start = clock::now();
+    for i in 0..BATCHES {
+        let batch = prepare(0, 0, i as u64, values);
+        let (duration, batch_bytes) = commit(db, bench_db, i, &batch)?;
+        latencies.record(duration);
+        bytes += batch_bytes as u64;
+    }
+    Ok(PipelineRun {
+        workers: 0,
+        elapsed: start.elapsed(),
+        entries: (BATCHES * BATCH_ENTRIES) as u64,
+        bytes,
+        commit: latencies.stats(),
+        committer_idle: Duration::ZERO,
+        workers_blocked: Duration::ZERO,
+    })
+}
+
+fn run_pipelined(
+    db: &Database,
+    bench_db: &BenchDb,
+    workers: usize,
+    values: ValueGen,
+) -> Result<(PipelineRun, QueueStats), Error> {
+    let capacity = workers * QUEUED_PER_WORKER;
+    let (sender, receiver) = mpsc::sync_channel::<Prepared>(capacity);
+    let waiting = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|worker| {
+                let sender = sender.clone();
+                let waiting = &waiting;
+                scope.spawn(move || {
+                    let mut blocked = Duration::ZERO;
+                    // Worker `w` prepares every batch `i` with `i % workers == w`, in its
+                    // own keyspace and past the keys of the inline run.
+                    for index in (worker..BATCHES).step_by(workers) {
+                        let batch = prepare(worker, 1, (index / workers) as u64, values);
+                        let start = clock::now();
+                        waiting.fetch_add(1, Ordering::Relaxed);
+                        let prepared = Prepared {
+                            batch,
+                            ready: start,
+                        };
+                        if sender.send(prepared).is_err() {
+                            // The committer failed; its error is returned below.
+                            break;
+                        }
+                        blocked += start.elapsed();
+                    }
+                    blocked
+                })
+            })
+            .collect();
+        drop(sender);
+
+        let mut latencies = StatsAccumulator::new();
+        let mut bytes = 0;
+        let mut committer_idle = Duration::ZERO;
+        let mut ready_to_commit = StatsAccumulator::new();
+        let mut depth = Vec::with_capacity(BATCHES);
+        let start = clock::now();
+        let committed = (|| {
+            for i in 0..BATCHES {
+                let wait = clock::now();
+                let Ok(prepared) = receiver.recv() else { break };
+                committer_idle += wait.elapsed();
+                depth.push((start.elapsed(), waiting.fetch_sub(1, Ordering::Relaxed)));
+                let (duration, batch_bytes) = commit(db, bench_db, i, &prepared.batch)?;
+                ready_to_commit.record(prepared.ready.elapsed());
+                latencies.record(duration);
+                bytes += batch_bytes as u64;
+            }
+            Ok::<_, Error>(())
+        })();
+        let elapsed = start.elapsed();
+        drop(receiver);
+        let workers_blocked = handles
+            .into_iter()
+            .map(|handle| handle.join().expect("pipeline worker panicked"))
+            .sum();
+        committed?;
+
+        let run = PipelineRun {
+            workers,
+            elapsed,
+            entries: (latencies.count() * BATCH_ENTRIES) as u64,
+            bytes,
+            commit: latencies.stats(),
+            committer_idle,
+            workers_blocked,
+        };
+        let queue = QueueStats {
+            capacity,
+            ready_to_commit: ready_to_commit.stats(),
+            depth,
+        };
+        Ok((run, queue))
+    })
+}
+
+/// Commit [`BATCHES`] batches of [`BATCH_ENTRIES`] inserts into a pipeline table of
+/// `bench_db`, first prepared inline by the committer, then by `workers` threads feeding it.
+pub fn benchmark_pipeline(
+    bench_db: &BenchDb,
+    workers: usize,
+    values: ValueGen,
+) -> Result<PipelineReport, Error> {
+    let quick_repair = bench_db.quick_repair;
+    let _span = info_span!("pipeline", db_path = %bench_db.path, quick_repair).entered();
+    info!(
+        workers,
+        batches = BATCHES,
+        batch_entries = BATCH_ENTRIES,
+        "Benchmarking pipelined writers"
+    );
+
+    let db = bench_db.create(
+        Database::builder().set_cache_size(1024 * 1024 * 1024), // 1GB cache
+    )?;
+    let inline = run_inline(&db, bench_db, values)?;
+    let (pipelined, queue) = run_pipelined(&db, bench_db, workers.max(1), values)?;
+    info!(
+        inline = inline.entries_per_second(),
+        pipelined = pipelined.entries_per_second(),
+        max_queue_depth = queue.max_depth(),
+        "Entries per second"
+    );
+    Ok(PipelineReport {
+        inline,
+        pipelined,
+        queue,
+    })
+}
+
+impl PipelineReport {
+    pub fn sections(&self, label: &str) -> Vec<Section> {
+        let mut section = Section::new(format!("Pipelined Writers - {label}"))
+            .field("Batches", BATCHES)
+            .field("Inserts per batch", BATCH_ENTRIES)
+            .field(
+                "Pipeline speedup",
+                self.pipelined.entries_per_second()
+                    / self.inline.entries_per_second().max(f64::EPSILON),
+            )
+            .table([
+                "writers",
+                "elapsed",
+                "entries per second",
+                "MiB per second",
+                "mean commit",
+                "committer idle",
+                "writers blocked",
+            ]);
+        for run in [&self.inline, &self.pipelined] {
+            section = section.row(vec![
+                if run.workers == 0 {
+                    "inline".into()
+                } else {
+                    run.workers.into()
+                },
+                run.elapsed.into(),
+                run.entries_per_second().into(),
+                run.mib_per_second().into(),
+                run.commit.avg_write_time.into(),
+                run.committer_idle.into(),
+                run.workers_blocked.into(),
+            ]);
+        }
+        let section = section.note(
+            "An idle committer means the writers cannot keep up; blocked writers mean the commits cannot",
+        );
+        vec![section, self.queue_section(label)]
+    }
+
+    fn queue_section(&self, label: &str) -> Section {
+        let queue = &self.queue;
+        let batch_bytes = self.pipelined.bytes as f64 / self.pipelined.commit.count.max(1) as f64;
+        let mut section = Section::new(format!("Pipeline Queue - {label}"))
+            .field("Channel capacity (batches)", queue.capacity)
+            .field("Max queue depth (batches)", queue.max_depth())
+            .field("Mean queue depth (batches)", queue.mean_depth())
+            .field(
+                "Peak buffered (MiB)",
+                queue.max_depth() as f64 * batch_bytes / MIB as f64,
+            )
+            .field("Mean ready-to-commit", queue.ready_to_commit.avg_write_time)
+            .field("Max ready-to-commit", queue.ready_to_commit.max_write_time)
+            .table(["elapsed", "max depth", "mean depth"]);
+        let rows = queue.depth.len().div_ceil(DEPTH_ROWS).max(1);
+        for window in queue.depth.chunks(rows) {
+            let depths = window.iter().map(|&(_, depth)| depth);
+            section = section.row(vec![
+                window.last().map_or(Duration::ZERO, |&(at, _)| at).into(),
+                depths.clone().max().unwrap_or(0).into(),
+                (depths.sum::<usize>() as f64 / window.len() as f64).into(),
+            ]);
+        }
+        section.note(
+            "Depth counts batches prepared but not yet picked up, including those whose worker waits for room",
+        )
+    }
+}
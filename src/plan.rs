@@ -0,0 +1,218 @@
+//! `--dry-run`: a short calibration burst in the target directory, extrapolated to the
+//! configured run, so a misconfigured multi-hour run is caught before it starts.
+
+use crate::checkpoint::FillProgress;
+use crate::config::{Config, FillTarget, MIB};
+use crate::error::{BenchError, Context};
+use crate::report::Section;
+use crate::warmup::Warmup;
+use crate::{
+    BenchDb, StatsAccumulator, benchmark_writes, cleanup_db, clock, fill_database, hooks, platform,
+    preflight,
+};
+use redb::{Database, Error};
+use std::path::Path;
+use std::time::Duration;
+use tracing::{info, info_span};
+
+/// Value bytes inserted into each calibration database.
+const CALIBRATION_BYTES: u64 = 32 * MIB;
+/// File growth ahead of allocation assumed on top of the calibrated page overhead, as
+/// observed on full-size runs.
+const GROWTH_HEADROOM: f64 = 1.25;
+/// Timed single-write transactions per calibration database.
+const CALIBRATION_WRITES: usize = 50;
+
+/// Throughput measured by the calibration burst, per quick_repair setting (false, true).
+#[derive(Clone, Copy, Debug)]
+pub struct Calibration {
+    /// Value bytes inserted per second by the fill phase.
+    pub fill_rate: [f64; 2],
+    /// Mean latency of a single-write transaction.
+    pub write_latency: [Duration; 2],
+    /// Allocated page bytes per value byte inserted. The file size itself is not used: redb
+    /// grows the file in steps far ahead of allocation at calibration sizes.
+    pub file_overhead: f64,
+}
+
+/// Fill a small database per quick_repair setting in `dir` and time a few writes on it,
+/// removing the databases afterwards.
+pub fn calibrate(
+    dir: &Path,
+    config: &Config,
+    template: &BenchDb,
+) -> Result<Calibration, BenchError> {
+    let _span = info_span!("calibrate", dir = %dir.display()).entered();
+    let mut calibration_config = config.clone();
+    calibration_config.fill_target = FillTarget::Bytes(CALIBRATION_BYTES);
+
+    let mut fill_rate = [0.0; 2];
+    let mut write_latency = [Duration::ZERO; 2];
+    let mut file_overhead: f64 = 0.0;
+    for (i, quick_repair) in [false, true].into_iter().enumerate() {
+        let mut bench_db = BenchDb::new(
+            dir.join(format!("dry_run_quick_repair_{quick_repair}.redb"))
+                .display()
+                .to_string(),
+            quick_repair,
+        );
+        bench_db.backend = template.backend;
+        cleanup_db(&bench_db.path);
+
+        let result = (|| {
+            let start = clock::now();
+            bench_db.next_key = fill_database(
+                &bench_db,
+                &calibration_config,
+                FillProgress::default(),
+                |_| {},
+            )?;
+            fill_rate[i] = CALIBRATION_BYTES as f64 / start.elapsed().as_secs_f64();
+            let allocated = allocated_bytes(&bench_db).in_phase("calibration", &bench_db.path)?;
+            file_overhead = file_overhead.max(allocated as f64 / CALIBRATION_BYTES as f64);
+
+            let mut latencies = StatsAccumulator::new();
+            benchmark_writes(
+                &mut bench_db,
+                CALIBRATION_WRITES,
+                Warmup::default(),
+                config.values,
+                config.keys,
+                config.seed,
+                |sample, _| latencies.record(sample.latency),
+            )?;
+            if !latencies.is_empty() {
+                write_latency[i] = latencies.stats().avg_write_time;
+            }
+            Ok::<_, BenchError>(())
+        })();
+        cleanup_db(&bench_db.path);
+        result?;
+    }
+
+    info!(?fill_rate, ?write_latency, file_overhead, "Calibrated");
+    Ok(Calibration {
+        fill_rate,
+        write_latency,
+        file_overhead,
+    })
+}
+
+/// Bytes of the pages allocated in `bench_db`, read through an aborted write transaction.
+fn allocated_bytes(bench_db: &BenchDb) -> Result<u64, Error> {
+    let db = bench_db.create(&mut Database::builder())?;
+    let write_txn = hooks::begin_write(&db)?;
+    let stats = write_txn.stats()?;
+    write_txn.abort()?;
+    Ok(stats.allocated_pages() * stats.page_size() as u64)
+}
+
+impl Calibration {
+    /// Time to fill both databases to `config.fill_target`; a concurrent fill is assumed
+    /// to take as long as the slower database alone, as if the disk kept up with both.
+    pub fn fill_time(&self, config: &Config) -> Duration {
+        let bytes = match config.fill_target {
+            FillTarget::Bytes(bytes) => bytes,
+            FillTarget::Entries(entries) => entries * config.values.mean_size() as u64,
+        };
+        let fills = self
+            .fill_rate
+            .iter()
+            .map(|rate| Duration::from_secs_f64(bytes as f64 / rate.max(f64::EPSILON)));
+        if config.fill_concurrent {
+            fills.max().unwrap_or_default()
+        } else {
+            fills.sum()
+        }
+    }
+
+    /// Time for `commits` single-write transactions on each of the two databases.
+    pub fn commits_time(&self, commits: u64) -> Duration {
+        self.write_latency
+            .iter()
+            .map(|latency| latency.mul_f64(commits as f64))
+            .sum()
+    }
+
+    pub fn section(&self, title: impl Into<String>) -> Section {
+        Section::new(title)
+            .field(
+                "Fill rate, quick_repair(false) (MiB/s)",
+                self.fill_rate[0] / MIB as f64,
+            )
+            .field(
+                "Fill rate, quick_repair(true) (MiB/s)",
+                self.fill_rate[1] / MIB as f64,
+            )
+            .field("Write latency, quick_repair(false)", self.write_latency[0])
+            .field("Write latency, quick_repair(true)", self.write_latency[1])
+            .field("Allocated bytes per value byte", self.file_overhead)
+    }
+}
+
+/// One phase the run would execute, and its estimated duration when it can be extrapolated
+/// from the calibration.
+pub struct PlannedPhase {
+    pub name: String,
+    pub detail: String,
+    pub estimate: Option<Duration>,
+}
+
+pub struct Plan {
+    pub phases: Vec<PlannedPhase>,
+    /// Disk space the run needs in the directory, with the calibrated file overhead.
+    pub disk_usage: u64,
+    pub available: Option<u64>,
+}
+
+impl Plan {
+    pub fn new(
+        dir: &Path,
+        config: &Config,
+        calibration: &Calibration,
+        phases: Vec<PlannedPhase>,
+    ) -> Self {
+        Self {
+            phases,
+            disk_usage: preflight::estimated_disk_usage(
+                config,
+                calibration.file_overhead * GROWTH_HEADROOM,
+            ),
+            available: platform::available_space(dir).ok(),
+        }
+    }
+
+    pub fn section(&self, title: impl Into<String>) -> Section {
+        let estimated: Duration = self.phases.iter().filter_map(|p| p.estimate).sum();
+        let unestimated = self.phases.iter().filter(|p| p.estimate.is_none()).count();
+        let mut section = Section::new(title)
+            .field("Estimated time", estimated)
+            .field("Estimated disk usage (MiB)", self.disk_usage / MIB);
+        if let Some(available) = self.available {
+            section = section
+                .field("Available disk space (MiB)", available / MIB)
+                .field(
+                    "Fits",
+                    if available >= self.disk_usage {
+                        "yes"
+                    } else {
+                        "NO"
+                    },
+                );
+        }
+        section = section.table(["phase", "work", "estimated time"]);
+        for phase in &self.phases {
+            section = section.row(vec![
+                phase.name.as_str().into(),
+                phase.detail.as_str().into(),
+                phase.estimate.map_or_else(|| "-".into(), Into::into),
+            ]);
+        }
+        if unestimated > 0 {
+            section = section.note(format!(
+                "{unestimated} phase(s) not extrapolated from the calibration are excluded from the estimated time"
+            ));
+        }
+        section
+    }
+}
@@ -0,0 +1,367 @@
+//! The operating-system specific pieces behind one interface: evicting a file from the page
+//! cache, the process's disk I/O counters, the filesystem type and free space of a
+//! directory, the space a file occupies, whether the TSC is invariant, and crash injection by killing a forked writer.
+//!
+//! Linux has all of them. macOS evicts through `msync(MS_INVALIDATE)` on a mapping of the
+//! file, reads its counters from `proc_pid_rusage` and forks like Linux. Windows has the
+//! counters and free space but can neither evict a single file nor fork. Wherever a piece is
+//! missing, callers get `false`, `None` or an [`io::ErrorKind::Unsupported`] error and carry
+//! on without it.
+
+use std::io;
+use std::path::Path;
+
+/// Drop the cached pages of the file at `path`, once written back; `false` when the
+/// platform cannot evict a single file.
+#[cfg(target_os = "linux")]
+pub fn evict(path: &Path) -> io::Result<bool> {
+    use std::os::fd::AsRawFd;
+    let file = std::fs::File::open(path)?;
+    file.sync_all()?;
+    // SAFETY: the descriptor is valid for the duration of the call.
+    match unsafe { libc::posix_fadvise(file.as_raw_fd(), 0, 0, libc::POSIX_FADV_DONTNEED) } {
+        0 => Ok(true),
+        errno => Err(io::Error::from_raw_os_error(errno)),
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn evict(path: &Path) -> io::Result<bool> {
+    use std::os::fd::AsRawFd;
+    let file = std::fs::File::open(path)?;
+    file.sync_all()?;
+    let len = file.metadata()?.len() as usize;
+    if len == 0 {
+        return Ok(true);
+    }
+    // SAFETY: a read-only shared mapping of the whole open file, unmapped below.
+    let addr = unsafe {
+        libc::mmap(
+            std::ptr::null_mut(),
+            len,
+            libc::PROT_READ,
+            libc::MAP_SHARED,
+            file.as_raw_fd(),
+            0,
+        )
+    };
+    if addr == libc::MAP_FAILED {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: `addr` maps `len` bytes; invalidating drops the cached pages of the file.
+    let invalidated = unsafe { libc::msync(addr, len, libc::MS_INVALIDATE) };
+    let error = io::Error::last_os_error();
+    // SAFETY: the mapping made above, no longer used.
+    unsafe { libc::munmap(addr, len) };
+    if invalidated == 0 {
+        Ok(true)
+    } else {
+        Err(error)
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn evict(_path: &Path) -> io::Result<bool> {
+    Ok(false)
+}
+
+/// Bytes the process has read from and written to storage, as accounted by the OS.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IoCounters {
+    pub read_bytes: u64,
+    pub write_bytes: u64,
+}
+
+/// The process's I/O counters so far: `/proc/self/io` on Linux, or `None` when it cannot
+/// be read (some containers hide it).
+#[cfg(target_os = "linux")]
+pub fn io_counters() -> Option<IoCounters> {
+    let io = std::fs::read_to_string("/proc/self/io").ok()?;
+    let field = |name: &str| {
+        io.lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+            .and_then(|value| value.trim().parse().ok())
+    };
+    Some(IoCounters {
+        read_bytes: field("read_bytes")?,
+        write_bytes: field("write_bytes")?,
+    })
+}
+
+/// The process's disk I/O counters so far, from `proc_pid_rusage`.
+#[cfg(target_os = "macos")]
+pub fn io_counters() -> Option<IoCounters> {
+    // SAFETY: `rusage_info_v2` is plain data and the all-zero value is valid.
+    let mut info: libc::rusage_info_v2 = unsafe { std::mem::zeroed() };
+    // SAFETY: `info` is a valid out-buffer for the V2 flavor.
+    let rc = unsafe {
+        libc::proc_pid_rusage(
+            libc::getpid(),
+            libc::RUSAGE_INFO_V2,
+            (&mut info as *mut libc::rusage_info_v2).cast(),
+        )
+    };
+    (rc == 0).then_some(IoCounters {
+        read_bytes: info.ri_diskio_bytesread,
+        write_bytes: info.ri_diskio_byteswritten,
+    })
+}
+
+/// The process's I/O counters so far, from `GetProcessIoCounters`. Windows counts every
+/// read and write call, so cached I/O is included.
+#[cfg(windows)]
+pub fn io_counters() -> Option<IoCounters> {
+    use windows_sys::Win32::System::Threading::{
+        GetCurrentProcess, GetProcessIoCounters, IO_COUNTERS,
+    };
+    // SAFETY: `IO_COUNTERS` is plain data and the all-zero value is valid.
+    let mut counters: IO_COUNTERS = unsafe { std::mem::zeroed() };
+    // SAFETY: the pseudo-handle of the current process is always valid and `counters` is a
+    // valid out-pointer.
+    if unsafe { GetProcessIoCounters(GetCurrentProcess(), &mut counters) } == 0 {
+        return None;
+    }
+    Some(IoCounters {
+        read_bytes: counters.ReadTransferCount,
+        write_bytes: counters.WriteTransferCount,
+    })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn io_counters() -> Option<IoCounters> {
+    None
+}
+
+/// Free space available to unprivileged users on the filesystem containing `dir`.
+#[cfg(unix)]
+pub fn available_space(dir: &Path) -> io::Result<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = CString::new(dir.as_os_str().as_bytes())?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `path` is a valid NUL-terminated string and `stat` is a valid out-pointer.
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+/// `path` as a NUL-terminated wide string, with `.` for the empty path.
+#[cfg(windows)]
+fn wide(path: &Path) -> Vec<u16> {
+    use std::os::windows::ffi::OsStrExt;
+    let path = if path.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        path
+    };
+    path.as_os_str().encode_wide().chain([0]).collect()
+}
+
+#[cfg(windows)]
+pub fn available_space(dir: &Path) -> io::Result<u64> {
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+    let mut available = 0u64;
+    // SAFETY: `wide` is NUL-terminated and the out-pointers are valid or null.
+    let ok = unsafe {
+        GetDiskFreeSpaceExW(
+            wide(dir).as_ptr(),
+            &mut available,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    if ok == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(available)
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn available_space(_dir: &Path) -> io::Result<u64> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "free space query not supported on this platform",
+    ))
+}
+
+/// Disk space the file at `path` occupies, which is less than its length while a
+/// preallocated region is still sparse; elsewhere than on Unix, its length.
+#[cfg(unix)]
+pub fn allocated_size(path: &Path) -> io::Result<u64> {
+    use std::os::unix::fs::MetadataExt;
+    // `st_blocks` counts 512-byte units whatever the filesystem's block size.
+    Ok(std::fs::metadata(path)?.blocks() * 512)
+}
+
+#[cfg(not(unix))]
+pub fn allocated_size(path: &Path) -> io::Result<u64> {
+    Ok(std::fs::metadata(path)?.len())
+}
+
+/// Name of the filesystem type holding `dir`, for labelling results, or `None` when it is
+/// not one we recognize.
+#[cfg(target_os = "linux")]
+pub fn filesystem_type(dir: &Path) -> Option<&'static str> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = CString::new(dir.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `path` is a valid NUL-terminated string and `stat` is a valid out-pointer.
+    if unsafe { libc::statfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    // Magic numbers from linux/magic.h; ZFS's is defined by OpenZFS.
+    Some(match stat.f_type as u64 {
+        0xEF53 => "ext4",
+        0x5846_5342 => "xfs",
+        0x9123_683E => "btrfs",
+        0x2FC1_2FC1 => "zfs",
+        0xF2F5_2010 => "f2fs",
+        0x0102_1994 => "tmpfs",
+        0x794C_7630 => "overlayfs",
+        0x6969 => "nfs",
+        _ => return None,
+    })
+}
+
+#[cfg(target_os = "macos")]
+pub fn filesystem_type(dir: &Path) -> Option<&'static str> {
+    use std::ffi::{CStr, CString};
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = CString::new(dir.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `path` is a valid NUL-terminated string and `stat` is a valid out-pointer.
+    if unsafe { libc::statfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    // SAFETY: the kernel NUL-terminates the type name within the array.
+    let name = unsafe { CStr::from_ptr(stat.f_fstypename.as_ptr()) };
+    Some(match name.to_bytes() {
+        b"apfs" => "apfs",
+        b"hfs" => "hfs+",
+        b"zfs" => "zfs",
+        b"exfat" => "exfat",
+        b"msdos" => "fat",
+        b"nfs" => "nfs",
+        b"smbfs" => "smb",
+        _ => return None,
+    })
+}
+
+#[cfg(windows)]
+pub fn filesystem_type(dir: &Path) -> Option<&'static str> {
+    use windows_sys::Win32::Storage::FileSystem::{GetVolumeInformationW, GetVolumePathNameW};
+    let mut root = [0u16; 261];
+    let mut name = [0u16; 261];
+    // SAFETY: `wide` is NUL-terminated and the buffers are as long as passed.
+    let ok = unsafe {
+        GetVolumePathNameW(wide(dir).as_ptr(), root.as_mut_ptr(), root.len() as u32) != 0
+            && GetVolumeInformationW(
+                root.as_ptr(),
+                std::ptr::null_mut(),
+                0,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                name.as_mut_ptr(),
+                name.len() as u32,
+            ) != 0
+    };
+    if !ok {
+        return None;
+    }
+    let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+    Some(match String::from_utf16_lossy(&name[..len]).as_str() {
+        "NTFS" => "ntfs",
+        "ReFS" => "refs",
+        "exFAT" => "exfat",
+        "FAT32" => "fat",
+        _ => return None,
+    })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn filesystem_type(_dir: &Path) -> Option<&'static str> {
+    None
+}
+
+/// Whether the TSC ticks at a constant rate through frequency changes and sleep states. On
+/// Linux the kernel's view, which also excludes TSCs it found unreliable; elsewhere CPUID.
+#[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+pub fn invariant_tsc() -> bool {
+    std::fs::read_to_string("/proc/cpuinfo").is_ok_and(|cpuinfo| {
+        cpuinfo
+            .lines()
+            .find(|line| line.starts_with("flags"))
+            .is_some_and(|flags| {
+                let flags: Vec<_> = flags.split_whitespace().collect();
+                flags.contains(&"constant_tsc") && flags.contains(&"nonstop_tsc")
+            })
+    })
+}
+
+#[cfg(all(target_arch = "x86_64", not(target_os = "linux")))]
+pub fn invariant_tsc() -> bool {
+    use std::arch::x86_64::__cpuid;
+    // Leaf 0x8000_0007, if the CPU has it, flags the invariant TSC in EDX bit 8.
+    __cpuid(0x8000_0000).eax >= 0x8000_0007 && __cpuid(0x8000_0007).edx & (1 << 8) != 0
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+pub fn invariant_tsc() -> bool {
+    false
+}
+
+/// Run `work` in a forked child, which kills itself with `SIGKILL` once `work` returns
+/// `true`, leaving everything it opened exactly as a crashed process leaves it; wait for it.
+/// An error if the child exited any other way.
+///
+/// # Safety
+///
+/// No other thread may be running: the child would inherit the locks they hold.
+#[cfg(unix)]
+pub unsafe fn crash_after(work: impl FnOnce() -> bool) -> io::Result<()> {
+    // SAFETY: single-threaded, as the caller guarantees.
+    match unsafe { libc::fork() } {
+        -1 => Err(io::Error::last_os_error()),
+        0 => {
+            let code = if work() {
+                // SAFETY: kill the calling process; nothing runs after it.
+                unsafe { libc::kill(libc::getpid(), libc::SIGKILL) }
+            } else {
+                1
+            };
+            // SAFETY: leave without running destructors, like the crash it stands for.
+            unsafe { libc::_exit(code) }
+        }
+        pid => {
+            let mut status = 0;
+            // SAFETY: `pid` is our child and `status` outlives the call.
+            if unsafe { libc::waitpid(pid, &mut status, 0) } == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            if libc::WIFSIGNALED(status) && libc::WTERMSIG(status) == libc::SIGKILL {
+                Ok(())
+            } else {
+                Err(io::Error::other(format!(
+                    "the writer exited before crashing (status {status})"
+                )))
+            }
+        }
+    }
+}
+
+/// # Safety
+///
+/// None needed; crash injection is unsupported here.
+#[cfg(not(unix))]
+pub unsafe fn crash_after(_work: impl FnOnce() -> bool) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "crash injection forks a writer process, which needs a Unix platform",
+    ))
+}
@@ -0,0 +1,464 @@
+//! `plot` subcommand: charts rendered from result files, so comparing runs does not go
+//! through a spreadsheet.
+//!
+//! JSON reports (`--report json:PATH`) give a throughput bar per section with a writes per
+//! second field. JSON Lines streams (`--stream-jsonl`) give latency CDFs and latency over
+//! time, with one series per phase and quick_repair setting. Rendering requires the `plot`
+//! cargo feature; loading the files does not.
+
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Whether this binary was built with chart rendering.
+pub const AVAILABLE: bool = cfg!(feature = "plot");
+
+/// Phases left out of the latency charts unless listed: fill batches are not comparable
+/// to single transactions and would stretch the axes.
+pub const DEFAULT_EXCLUDED: &[&str] = &["fill"];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChartFormat {
+    #[default]
+    Svg,
+    Png,
+}
+
+impl FromStr for ChartFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "svg" => Ok(ChartFormat::Svg),
+            "png" => Ok(ChartFormat::Png),
+            _ => Err(format!(
+                "unknown chart format `{s}`, expected `svg` or `png`"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ChartFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ChartFormat::Svg => "svg",
+            ChartFormat::Png => "png",
+        })
+    }
+}
+
+/// One throughput bar: a report section with a writes per second field.
+pub struct Throughput {
+    pub label: String,
+    pub writes_per_second: f64,
+}
+
+/// Operations of one phase and configuration from a stream, in completion order.
+pub struct LatencySeries {
+    pub label: String,
+    /// `(seconds since the first operation, latency)`.
+    pub points: Vec<(f64, Duration)>,
+}
+
+#[derive(Default)]
+pub struct PlotData {
+    pub throughput: Vec<Throughput>,
+    pub latency: Vec<LatencySeries>,
+}
+
+impl PlotData {
+    /// Load reports and streams from `paths`, keeping the stream operations of `phases`, or of
+    /// every phase but [`DEFAULT_EXCLUDED`] if empty.
+    pub fn load(paths: &[PathBuf], phases: &[String]) -> io::Result<Self> {
+        let mut data = PlotData::default();
+        for path in paths {
+            let text = fs::read_to_string(path)?;
+            // Labels name the file only when several are compared.
+            let prefix = if paths.len() > 1 {
+                let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+                format!("{stem}: ")
+            } else {
+                String::new()
+            };
+            match serde_json::from_str::<Value>(&text) {
+                Ok(document) if document.get("sections").is_some() => {
+                    data.load_report(&document, &prefix)
+                }
+                _ => data.load_stream(&text, &prefix, phases, path)?,
+            }
+        }
+        if data.throughput.is_empty() && data.latency.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "no throughput or latency data found in the given files",
+            ));
+        }
+        Ok(data)
+    }
+
+    fn load_report(&mut self, document: &Value, prefix: &str) {
+        let sections = document["sections"].as_array().into_iter().flatten();
+        for section in sections {
+            if let Some(writes_per_second) = section["fields"]["writes_per_second"].as_f64() {
+                let title = section["title"].as_str().unwrap_or_default();
+                self.throughput.push(Throughput {
+                    label: format!("{prefix}{title}"),
+                    writes_per_second,
+                });
+            }
+        }
+    }
+
+    fn load_stream(
+        &mut self,
+        text: &str,
+        prefix: &str,
+        phases: &[String],
+        path: &Path,
+    ) -> io::Result<()> {
+        let mut series: BTreeMap<(String, bool), Vec<(u64, Duration)>> = BTreeMap::new();
+        for (i, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: Value = serde_json::from_str(line).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}:{}: {e}", path.display(), i + 1),
+                )
+            })?;
+            if record["type"] != "op" {
+                continue;
+            }
+            let phase = record["phase"].as_str().unwrap_or_default();
+            let included = if phases.is_empty() {
+                !DEFAULT_EXCLUDED.contains(&phase)
+            } else {
+                phases.iter().any(|p| p == phase)
+            };
+            let (Some(ts_ms), Some(latency_ns)) =
+                (record["ts_ms"].as_u64(), record["latency_ns"].as_u64())
+            else {
+                continue;
+            };
+            if included {
+                let quick_repair = record["quick_repair"].as_bool().unwrap_or_default();
+                series
+                    .entry((phase.to_owned(), quick_repair))
+                    .or_default()
+                    .push((ts_ms, Duration::from_nanos(latency_ns)));
+            }
+        }
+        for ((phase, quick_repair), ops) in series {
+            let first = ops.first().map_or(0, |&(ts_ms, _)| ts_ms);
+            self.latency.push(LatencySeries {
+                label: format!("{prefix}{phase} quick_repair({quick_repair})"),
+                points: ops
+                    .into_iter()
+                    .map(|(ts_ms, latency)| ((ts_ms - first) as f64 / 1000.0, latency))
+                    .collect(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Fonts tried, in order, when `--font` is not given.
+#[cfg(feature = "plot")]
+const FONT_CANDIDATES: &[&str] = &[
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/TTF/DejaVuSans.ttf",
+    "/usr/share/fonts/dejavu-sans-fonts/DejaVuSans.ttf",
+    "/usr/share/fonts/truetype/liberation/LiberationSans-Regular.ttf",
+    "/System/Library/Fonts/Supplemental/Arial.ttf",
+    "C:\\Windows\\Fonts\\arial.ttf",
+];
+
+/// Render the charts `data` has data for into `out_dir`, returning the files written.
+#[cfg(feature = "plot")]
+pub fn render(
+    data: &PlotData,
+    out_dir: &Path,
+    format: ChartFormat,
+    font: Option<&Path>,
+) -> io::Result<Vec<PathBuf>> {
+    charts::register_font(font)?;
+    fs::create_dir_all(out_dir)?;
+    let mut written = Vec::new();
+    for chart in charts::Chart::ALL {
+        if chart.has_data(data) {
+            let path = out_dir.join(format!("{}.{format}", chart.name()));
+            charts::render(chart, data, &path, format).map_err(io::Error::other)?;
+            tracing::info!(path = %path.display(), "Wrote chart");
+            written.push(path);
+        }
+    }
+    Ok(written)
+}
+
+#[cfg(not(feature = "plot"))]
+pub fn render(
+    _data: &PlotData,
+    _out_dir: &Path,
+    _format: ChartFormat,
+    _font: Option<&Path>,
+) -> io::Result<Vec<PathBuf>> {
+    Err(io::Error::other(
+        "the plot subcommand requires building with `--features plot`",
+    ))
+}
+
+#[cfg(feature = "plot")]
+mod charts {
+    use super::{FONT_CANDIDATES, PlotData};
+    use crate::plot::ChartFormat;
+    use plotters::coord::Shift;
+    use plotters::prelude::*;
+    use plotters::style::FontStyle;
+    use std::io;
+    use std::path::Path;
+    use std::time::Duration;
+
+    const SIZE: (u32, u32) = (1200, 700);
+    /// Points per series in the latency-over-time chart; operations are bucketed into this
+    /// many windows and each window plotted at its median.
+    const WINDOWS: usize = 200;
+
+    #[derive(Clone, Copy)]
+    pub enum Chart {
+        Throughput,
+        LatencyCdf,
+        LatencyOverTime,
+    }
+
+    impl Chart {
+        pub const ALL: [Chart; 3] = [Chart::Throughput, Chart::LatencyCdf, Chart::LatencyOverTime];
+
+        pub fn name(self) -> &'static str {
+            match self {
+                Chart::Throughput => "throughput",
+                Chart::LatencyCdf => "latency_cdf",
+                Chart::LatencyOverTime => "latency_over_time",
+            }
+        }
+
+        pub fn has_data(self, data: &PlotData) -> bool {
+            match self {
+                Chart::Throughput => !data.throughput.is_empty(),
+                Chart::LatencyCdf | Chart::LatencyOverTime => !data.latency.is_empty(),
+            }
+        }
+    }
+
+    /// Register the font used for all chart text; PNG rendering has no font of its own and
+    /// SVG layout needs the metrics.
+    pub fn register_font(font: Option<&Path>) -> io::Result<()> {
+        let path = match font {
+            Some(path) => path.to_owned(),
+            None => FONT_CANDIDATES
+                .iter()
+                .map(Path::new)
+                .find(|path| path.exists())
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::NotFound,
+                        "no system font found, pass one with --font",
+                    )
+                })?
+                .to_owned(),
+        };
+        let bytes: &'static [u8] = Vec::leak(std::fs::read(&path)?);
+        plotters::style::register_font("sans-serif", FontStyle::Normal, bytes).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} is not a usable TrueType font", path.display()),
+            )
+        })
+    }
+
+    pub fn render(
+        chart: Chart,
+        data: &PlotData,
+        path: &Path,
+        format: ChartFormat,
+    ) -> Result<(), String> {
+        match format {
+            ChartFormat::Svg => draw(chart, data, SVGBackend::new(path, SIZE).into_drawing_area()),
+            ChartFormat::Png => draw(
+                chart,
+                data,
+                BitMapBackend::new(path, SIZE).into_drawing_area(),
+            ),
+        }
+    }
+
+    fn draw<DB: DrawingBackend>(
+        chart: Chart,
+        data: &PlotData,
+        root: DrawingArea<DB, Shift>,
+    ) -> Result<(), String> {
+        root.fill(&WHITE).map_err(|e| e.to_string())?;
+        match chart {
+            Chart::Throughput => throughput(data, &root),
+            Chart::LatencyCdf => latency_cdf(data, &root),
+            Chart::LatencyOverTime => latency_over_time(data, &root),
+        }
+        .map_err(|e| e.to_string())?;
+        root.present().map_err(|e| e.to_string())
+    }
+
+    type DrawResult<DB> = Result<(), DrawingAreaErrorKind<<DB as DrawingBackend>::ErrorType>>;
+
+    fn micros(latency: Duration) -> f64 {
+        latency.as_secs_f64() * 1e6
+    }
+
+    /// Horizontal bars, so long section titles stay readable.
+    fn throughput<DB: DrawingBackend>(
+        data: &PlotData,
+        root: &DrawingArea<DB, Shift>,
+    ) -> DrawResult<DB> {
+        let bars = &data.throughput;
+        let max = bars.iter().map(|b| b.writes_per_second).fold(0.0, f64::max);
+        let mut chart = ChartBuilder::on(root)
+            .caption("Throughput", ("sans-serif", 28))
+            .margin(20)
+            .x_label_area_size(40)
+            .y_label_area_size(SIZE.0 / 3)
+            .build_cartesian_2d(0.0..max * 1.1, (0..bars.len()).into_segmented())?;
+        chart
+            .configure_mesh()
+            .disable_y_mesh()
+            .x_desc("writes per second")
+            .y_label_formatter(&|segment| match segment {
+                SegmentValue::CenterOf(i) => {
+                    bars.get(*i).map(|b| b.label.clone()).unwrap_or_default()
+                }
+                _ => String::new(),
+            })
+            .draw()?;
+        chart.draw_series(bars.iter().enumerate().map(|(i, bar)| {
+            let mut rect = Rectangle::new(
+                [
+                    (0.0, SegmentValue::Exact(i)),
+                    (bar.writes_per_second, SegmentValue::Exact(i + 1)),
+                ],
+                Palette99::pick(i).filled(),
+            );
+            rect.set_margin(4, 4, 0, 0);
+            rect
+        }))?;
+        Ok(())
+    }
+
+    /// Fraction of operations at or below each latency, on a log latency axis.
+    fn latency_cdf<DB: DrawingBackend>(
+        data: &PlotData,
+        root: &DrawingArea<DB, Shift>,
+    ) -> DrawResult<DB> {
+        let latencies = data
+            .latency
+            .iter()
+            .flat_map(|s| s.points.iter().map(|&(_, l)| micros(l)));
+        let (min, max) = latencies.fold((f64::MAX, 0.0_f64), |(min, max), l| {
+            (min.min(l), max.max(l))
+        });
+        let mut chart = ChartBuilder::on(root)
+            .caption("Latency CDF", ("sans-serif", 28))
+            .margin(20)
+            .x_label_area_size(40)
+            .y_label_area_size(60)
+            .build_cartesian_2d((min.max(0.1)..max.max(1.0)).log_scale(), 0.0..1.0)?;
+        chart
+            .configure_mesh()
+            .x_desc("latency (μs)")
+            .y_desc("fraction of operations")
+            .draw()?;
+        for (i, series) in data.latency.iter().enumerate() {
+            let mut sorted: Vec<f64> = series.points.iter().map(|&(_, l)| micros(l)).collect();
+            sorted.sort_by(f64::total_cmp);
+            let n = sorted.len() as f64;
+            let color = Palette99::pick(i);
+            chart
+                .draw_series(LineSeries::new(
+                    sorted
+                        .iter()
+                        .enumerate()
+                        .map(|(rank, &l)| (l.max(0.1), (rank + 1) as f64 / n)),
+                    color.stroke_width(2),
+                ))?
+                .label(&series.label)
+                .legend(move |(x, y)| {
+                    PathElement::new([(x, y), (x + 20, y)], color.stroke_width(2))
+                });
+        }
+        chart
+            .configure_series_labels()
+            .position(SeriesLabelPosition::LowerRight)
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()?;
+        Ok(())
+    }
+
+    /// Windowed median latency against time since each series' first operation.
+    fn latency_over_time<DB: DrawingBackend>(
+        data: &PlotData,
+        root: &DrawingArea<DB, Shift>,
+    ) -> DrawResult<DB> {
+        let windowed: Vec<Vec<(f64, f64)>> = data
+            .latency
+            .iter()
+            .map(|series| {
+                let size = series.points.len().div_ceil(WINDOWS).max(1);
+                series
+                    .points
+                    .chunks(size)
+                    .map(|window| {
+                        let mut latencies: Vec<f64> =
+                            window.iter().map(|&(_, l)| micros(l)).collect();
+                        latencies.sort_by(f64::total_cmp);
+                        let (t, _) = window[window.len() - 1];
+                        (t, latencies[latencies.len() / 2])
+                    })
+                    .collect()
+            })
+            .collect();
+        let points = windowed.iter().flatten();
+        let (t_max, l_max) = points.fold((0.0_f64, 0.0_f64), |(t_max, l_max), &(t, l)| {
+            (t_max.max(t), l_max.max(l))
+        });
+        let mut chart = ChartBuilder::on(root)
+            .caption("Latency over time (windowed median)", ("sans-serif", 28))
+            .margin(20)
+            .x_label_area_size(40)
+            .y_label_area_size(60)
+            .build_cartesian_2d(0.0..t_max.max(1e-3), 0.0..l_max * 1.1)?;
+        chart
+            .configure_mesh()
+            .x_desc("seconds since first operation")
+            .y_desc("latency (μs)")
+            .draw()?;
+        for (i, (series, points)) in data.latency.iter().zip(windowed).enumerate() {
+            let color = Palette99::pick(i);
+            chart
+                .draw_series(LineSeries::new(points, color.stroke_width(2)))?
+                .label(&series.label)
+                .legend(move |(x, y)| {
+                    PathElement::new([(x, y), (x + 20, y)], color.stroke_width(2))
+                });
+        }
+        chart
+            .configure_series_labels()
+            .position(SeriesLabelPosition::UpperRight)
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+//! Checks run before PHASE 1 so that a misconfigured run fails in seconds, not hours.
+
+use crate::config::{Config, FillTarget, MIB};
+use crate::platform::available_space;
+use std::path::Path;
+use tracing::{info, warn};
+
+/// Extra headroom on top of the raw estimate, for redb page overhead and metadata.
+const SLACK_FRACTION: f64 = 0.10;
+const SLACK_BYTES: u64 = 512 * MIB;
+
+/// Database file bytes per value byte assumed when nothing was measured: the preflight check
+/// sizes each file like the data it holds, leaving page overhead to the slack.
+pub const FILE_OVERHEAD: f64 = 1.0;
+
+/// Disk space needed for one run: two filled databases plus the benchmark writes, at
+/// `file_overhead` file bytes per value byte, with slack.
+pub fn estimated_disk_usage(config: &Config, file_overhead: f64) -> u64 {
+    let fill_bytes = match config.fill_target {
+        FillTarget::Bytes(bytes) => bytes,
+        FillTarget::Entries(entries) => entries * config.values.mean_size() as u64,
+    };
+    let bench_bytes = (config.benchmark_writes * config.values.mean_size()) as u64;
+    let raw = (2 * (fill_bytes + bench_bytes)) as f64 * file_overhead;
+    raw as u64 + (raw * SLACK_FRACTION) as u64 + SLACK_BYTES
+}
+
+/// Largest fill target in bytes whose [`estimated_disk_usage`] fits in `space`, or `None`
+/// when not even the benchmark writes would fit.
+pub fn fitting_fill_bytes(config: &Config, space: u64, file_overhead: f64) -> Option<u64> {
+    let raw = space.checked_sub(SLACK_BYTES)? as f64 / (1.0 + SLACK_FRACTION) / file_overhead;
+    let bench_bytes = (config.benchmark_writes * config.values.mean_size()) as f64;
+    let fill_bytes = raw / 2.0 - bench_bytes;
+    (fill_bytes >= 1.0).then_some(fill_bytes as u64)
+}
+
+/// Fail if the filesystem holding `dir` cannot fit the estimated disk usage of `config`.
+pub fn check_disk_space(dir: &Path, config: &Config) -> Result<(), String> {
+    let required = estimated_disk_usage(config, FILE_OVERHEAD);
+    let available = match available_space(dir) {
+        Ok(available) => available,
+        Err(e) => {
+            warn!(error = %e, "Could not determine free disk space, skipping preflight check");
+            return Ok(());
+        }
+    };
+
+    info!(
+        required_mib = required / MIB,
+        available_mib = available / MIB,
+        "Disk space preflight"
+    );
+
+    if available < required {
+        return Err(format!(
+            "not enough free disk space in {}: need ~{} MiB (2 × target + slack), have {} MiB",
+            dir.display(),
+            required / MIB,
+            available / MIB
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GIB;
+
+    fn config(fill_bytes: u64) -> Config {
+        Config {
+            fill_target: FillTarget::Bytes(fill_bytes),
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn disk_usage_is_twice_the_data_plus_slack() {
+        let config = config(10 * GIB);
+        let bench_bytes = (config.benchmark_writes * config.values.mean_size()) as u64;
+        let raw = 2 * (10 * GIB + bench_bytes);
+        assert_eq!(
+            estimated_disk_usage(&config, 1.0),
+            raw + raw / 10 + SLACK_BYTES
+        );
+        assert!(estimated_disk_usage(&config, 1.5) > estimated_disk_usage(&config, 1.0));
+    }
+
+    #[test]
+    fn disk_usage_sizes_entry_targets_by_the_mean_value() {
+        let mut by_entries = Config {
+            fill_target: FillTarget::Entries(1_000_000),
+            ..Config::default()
+        };
+        let bytes = 1_000_000 * by_entries.values.mean_size() as u64;
+        assert_eq!(
+            estimated_disk_usage(&by_entries, FILE_OVERHEAD),
+            estimated_disk_usage(&config(bytes), FILE_OVERHEAD)
+        );
+        by_entries.fill_target = FillTarget::Entries(0);
+        assert!(estimated_disk_usage(&by_entries, FILE_OVERHEAD) > SLACK_BYTES);
+    }
+
+    #[test]
+    fn fitting_fill_inverts_the_estimate() {
+        for overhead in [1.0, 1.3, 2.0] {
+            let target = 10 * GIB;
+            let space = estimated_disk_usage(&config(target), overhead);
+            let fitting = fitting_fill_bytes(&config(target), space, overhead).unwrap();
+            // Rounding may lose a few bytes, never add any.
+            assert!(
+                fitting <= target && target - fitting < 64,
+                "{fitting} for {target}"
+            );
+            assert!(estimated_disk_usage(&config(fitting), overhead) <= space);
+        }
+    }
+
+    #[test]
+    fn fitting_fill_shrinks_with_the_space_and_the_overhead() {
+        let config = config(GIB);
+        let at = |space, overhead| fitting_fill_bytes(&config, space, overhead).unwrap();
+        assert!(at(4 * GIB, 1.0) < at(8 * GIB, 1.0));
+        assert!(at(8 * GIB, 2.0) < at(8 * GIB, 1.0));
+    }
+
+    #[test]
+    fn nothing_fits_in_less_than_the_slack() {
+        let config = config(GIB);
+        assert_eq!(fitting_fill_bytes(&config, 0, 1.0), None);
+        assert_eq!(fitting_fill_bytes(&config, SLACK_BYTES, 1.0), None);
+        // Room for the slack but not for the benchmark writes.
+        assert_eq!(fitting_fill_bytes(&config, SLACK_BYTES + 1024, 1.0), None);
+    }
+}
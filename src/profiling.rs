@@ -0,0 +1,62 @@
+//! CPU sampling during benchmark phases, written out as one flamegraph per configuration.
+//!
+//! Requires the `profiling` cargo feature; without it [`Profiler::start`] returns an error.
+
+use std::io;
+use std::path::PathBuf;
+use tracing::info;
+
+/// Whether this binary was built with profiling support.
+pub const AVAILABLE: bool = cfg!(feature = "profiling");
+
+/// Sampling frequency in Hz.
+#[cfg(feature = "profiling")]
+const FREQUENCY: i32 = 999;
+
+pub struct Profiler {
+    #[cfg(feature = "profiling")]
+    guard: pprof::ProfilerGuard<'static>,
+    output: PathBuf,
+}
+
+impl Profiler {
+    /// Start sampling; the flamegraph is written to `flamegraph_<label>.svg` on [`finish`].
+    ///
+    /// [`finish`]: Profiler::finish
+    #[cfg(feature = "profiling")]
+    pub fn start(label: &str) -> io::Result<Self> {
+        let guard = pprof::ProfilerGuardBuilder::default()
+            .frequency(FREQUENCY)
+            .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+            .build()
+            .map_err(io::Error::other)?;
+
+        Ok(Self {
+            guard,
+            output: PathBuf::from(format!("flamegraph_{label}.svg")),
+        })
+    }
+
+    #[cfg(not(feature = "profiling"))]
+    pub fn start(_label: &str) -> io::Result<Self> {
+        Err(io::Error::other(
+            "--profile requires building with `--features profiling`",
+        ))
+    }
+
+    /// Stop sampling and write the flamegraph.
+    #[cfg(feature = "profiling")]
+    pub fn finish(self) -> io::Result<()> {
+        let report = self.guard.report().build().map_err(io::Error::other)?;
+        let file = std::fs::File::create(&self.output)?;
+        report.flamegraph(file).map_err(io::Error::other)?;
+        info!(path = %self.output.display(), "Wrote flamegraph");
+        Ok(())
+    }
+
+    #[cfg(not(feature = "profiling"))]
+    pub fn finish(self) -> io::Result<()> {
+        info!(path = %self.output.display(), "Profiling disabled, no flamegraph written");
+        Ok(())
+    }
+}
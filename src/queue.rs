@@ -0,0 +1,140 @@
+//! Durable work queue: appends at the tail and `pop_first` from the head, each in its own
+//! transaction, tracking whether the file settles at a steady size.
+
+use crate::config::MIB;
+use crate::payload::ValueGen;
+use crate::report::Section;
+use crate::{BenchDb, BenchmarkStats, StatsAccumulator, clock, get_file_size, hooks, record_op};
+use redb::{Database, Error, TableDefinition};
+use std::time::Duration;
+use tracing::{info, info_span};
+
+pub const QUEUE_TABLE: TableDefinition<u64, &[u8]> = TableDefinition::new("benchmark_queue");
+/// Entries enqueued before timing starts, so the head is never empty.
+pub const DEPTH: u64 = 1000;
+/// Number of file size samples taken over the run.
+const SIZE_SAMPLES: usize = 10;
+
+pub struct QueueReport {
+    pub push: BenchmarkStats,
+    pub pop: BenchmarkStats,
+    /// (completed push/pop pairs, file size in bytes)
+    pub file_sizes: Vec<(usize, u64)>,
+}
+
+/// Perform `num_ops` push/pop pairs against a queue table in `bench_db`, after enqueueing
+/// [`DEPTH`] untimed entries.
+pub fn benchmark_queue(
+    bench_db: &BenchDb,
+    num_ops: usize,
+    values: ValueGen,
+) -> Result<QueueReport, Error> {
+    let quick_repair = bench_db.quick_repair;
+    let _span = info_span!("queue", db_path = %bench_db.path, quick_repair).entered();
+    info!(num_ops, depth = DEPTH, "Benchmarking push/pop queue");
+
+    let db = bench_db.create(
+        Database::builder().set_cache_size(1024 * 1024 * 1024), // 1GB cache
+    )?;
+
+    let mut tail = 0u64;
+    let write_txn = hooks::begin_write(&db)?;
+    {
+        let mut queue = write_txn.open_table(QUEUE_TABLE)?;
+        for _ in 0..DEPTH {
+            queue.insert(tail, values.generate(tail).as_slice())?;
+            tail += 1;
+        }
+    }
+    write_txn.commit()?;
+
+    let mut push = StatsAccumulator::new();
+    let mut pop = StatsAccumulator::new();
+    let mut file_sizes = vec![(0, get_file_size(&bench_db.path).unwrap_or(0))];
+    let sample_every = num_ops.div_ceil(SIZE_SAMPLES).max(1);
+
+    for i in 0..num_ops {
+        let value = values.generate(tail);
+        let start = clock::now();
+        let mut write_txn = hooks::begin_write(&db)?;
+        write_txn.set_quick_repair(quick_repair);
+        {
+            let mut queue = write_txn.open_table(QUEUE_TABLE)?;
+            queue.insert(tail, value.as_slice())?;
+        }
+        write_txn.commit()?;
+        let duration = start.elapsed();
+        push.record(duration);
+        record_op(
+            "queue-push",
+            &bench_db.path,
+            quick_repair,
+            i as u64,
+            1,
+            value.len(),
+            duration,
+        );
+        tail += 1;
+
+        let start = clock::now();
+        let mut write_txn = hooks::begin_write(&db)?;
+        write_txn.set_quick_repair(quick_repair);
+        {
+            let mut queue = write_txn.open_table(QUEUE_TABLE)?;
+            if queue.pop_first()?.is_none() {
+                return Err(Error::Corrupted("queue unexpectedly empty".to_owned()));
+            }
+        }
+        write_txn.commit()?;
+        let duration = start.elapsed();
+        pop.record(duration);
+        record_op(
+            "queue-pop",
+            &bench_db.path,
+            quick_repair,
+            i as u64,
+            1,
+            0,
+            duration,
+        );
+
+        if (i + 1).is_multiple_of(sample_every) || i + 1 == num_ops {
+            file_sizes.push((i + 1, get_file_size(&bench_db.path).unwrap_or(0)));
+        }
+    }
+
+    Ok(QueueReport {
+        push: push.stats(),
+        pop: pop.stats(),
+        file_sizes,
+    })
+}
+
+impl QueueReport {
+    pub fn sections(&self, label: &str) -> Vec<Section> {
+        let pairs = self.push.count.min(self.pop.count);
+        let elapsed: Duration = self.push.total_duration + self.pop.total_duration;
+        let first = self.file_sizes.first().map_or(0, |&(_, size)| size);
+        let last = self.file_sizes.last().map_or(0, |&(_, size)| size);
+
+        let mut sizes = Section::new(format!("Queue - {label}"))
+            .field(
+                "Push/pop pairs per second",
+                pairs as f64 / elapsed.as_secs_f64(),
+            )
+            .field(
+                "File growth (MiB)",
+                (last as f64 - first as f64) / MIB as f64,
+            )
+            .table(["pairs", "file size (MiB)"]);
+        for &(pairs, size) in &self.file_sizes {
+            sizes = sizes.row(vec![pairs.into(), (size as f64 / MIB as f64).into()]);
+        }
+
+        vec![
+            self.push.section(format!("Queue Push - {label}")),
+            self.pop.section(format!("Queue Pop - {label}")),
+            sizes,
+        ]
+    }
+}
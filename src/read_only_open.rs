@@ -0,0 +1,129 @@
+//! Cost of opening a filled database read-only, as analytics jobs do, and of the first read
+//! after it, with the file evicted from the page cache (cold) and left cached (warm).
+//!
+//! redb 2 has no read-only mode, so there the databases are opened with `Database::open`
+//! and only read from.
+
+use crate::platform::evict;
+use crate::report::Section;
+use crate::{BenchDb, BenchmarkStats, StatsAccumulator, TABLE, clock, compat, record_op};
+use redb::{Builder, Error, ReadableTable};
+use std::path::Path;
+use std::time::Duration;
+use tracing::{info, info_span, warn};
+
+/// Timed opens per cache state.
+pub const ROUNDS: usize = 10;
+
+pub struct OpenStats {
+    pub open: BenchmarkStats,
+    /// First read transaction after the open, fetching the last key.
+    pub first_read: BenchmarkStats,
+}
+
+pub struct ReadOnlyOpenReport {
+    pub cold: OpenStats,
+    pub warm: OpenStats,
+    /// Whether the file could be evicted from the page cache before the cold opens.
+    pub evicted: bool,
+}
+
+/// Open and read the database once, returning the open and first read latencies.
+fn open_once(bench_db: &BenchDb) -> Result<(Duration, Duration), Error> {
+    let start = clock::now();
+    let db = compat::open_read_only(&Builder::new(), &bench_db.path)?;
+    let open = start.elapsed();
+
+    let start = clock::now();
+    let read_txn = compat::begin_read_only(&db)?;
+    let table = read_txn.open_table(TABLE)?;
+    table.last()?;
+    Ok((open, start.elapsed()))
+}
+
+fn open_rounds(bench_db: &BenchDb, cold: bool, evicted: &mut bool) -> Result<OpenStats, Error> {
+    let name = if cold { "open-cold" } else { "open-warm" };
+    let (mut open, mut first_read) = (StatsAccumulator::new(), StatsAccumulator::new());
+    for i in 0..ROUNDS {
+        if cold {
+            *evicted &= evict(Path::new(&bench_db.path))?;
+        }
+        let (open_time, read_time) = open_once(bench_db)?;
+        open.record(open_time);
+        first_read.record(read_time);
+        record_op(
+            name,
+            &bench_db.path,
+            bench_db.quick_repair,
+            i as u64,
+            0,
+            0,
+            open_time,
+        );
+    }
+    Ok(OpenStats {
+        open: open.stats(),
+        first_read: first_read.stats(),
+    })
+}
+
+/// Time [`ROUNDS`] read-only opens of `bench_db` with a cold and with a warm page cache.
+pub fn benchmark_read_only_open(bench_db: &BenchDb) -> Result<ReadOnlyOpenReport, Error> {
+    let quick_repair = bench_db.quick_repair;
+    let _span = info_span!("read_only_open", db_path = %bench_db.path, quick_repair).entered();
+    info!(
+        rounds = ROUNDS,
+        read_only = compat::READ_ONLY_OPEN,
+        "Benchmarking read-only open"
+    );
+
+    let mut evicted = true;
+    let cold = open_rounds(bench_db, true, &mut evicted)?;
+    if !evicted {
+        warn!("Could not evict the database from the page cache; cold opens ran warm");
+    }
+    // A first untimed open caches whatever the cold rounds left out.
+    open_once(bench_db)?;
+    let warm = open_rounds(bench_db, false, &mut evicted)?;
+
+    Ok(ReadOnlyOpenReport {
+        cold,
+        warm,
+        evicted,
+    })
+}
+
+impl ReadOnlyOpenReport {
+    pub fn section(&self, title: impl Into<String>) -> Section {
+        let mut section = Section::new(title)
+            .field("Opens per cache state", ROUNDS)
+            .field(
+                "Open mode",
+                if compat::READ_ONLY_OPEN {
+                    "read-only"
+                } else {
+                    "read-write (redb 2 has no read-only mode)"
+                },
+            )
+            .table([
+                "page cache",
+                "mean open",
+                "min open",
+                "max open",
+                "mean first read",
+            ]);
+        for (state, stats) in [("cold", &self.cold), ("warm", &self.warm)] {
+            section = section.row(vec![
+                state.into(),
+                stats.open.avg_write_time.into(),
+                stats.open.min_write_time.into(),
+                stats.open.max_write_time.into(),
+                stats.first_read.avg_write_time.into(),
+            ]);
+        }
+        if !self.evicted {
+            section = section.note("The file could not be evicted from the page cache");
+        }
+        section
+    }
+}
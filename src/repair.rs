@@ -0,0 +1,150 @@
+//! What the repair callback does while redb repairs a database after an unclean shutdown,
+//! selectable with `--repair-callback`, to see whether work done in the callback (such as
+//! reporting progress to a metrics pipeline) slows the repair down.
+
+use crate::slo::LatencyObjective;
+use redb::Builder;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::Duration;
+use tracing::info;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RepairCallback {
+    /// Return immediately.
+    Noop,
+    /// Log the progress through tracing.
+    #[default]
+    Log,
+    /// Print the progress to stdout.
+    Println,
+    /// Sleep this long on every call.
+    Sleep(Duration),
+}
+
+/// Invocations of an installed callback, shared with it.
+#[derive(Clone, Debug, Default)]
+pub struct RepairProbe(Arc<AtomicU64>);
+
+impl RepairProbe {
+    pub fn calls(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Whether redb ran a repair, which it reports through the callback.
+    pub fn repaired(&self) -> bool {
+        self.calls() > 0
+    }
+}
+
+impl RepairCallback {
+    /// Install the callback on `builder`, counting its invocations.
+    pub fn install(self, builder: &mut Builder) -> RepairProbe {
+        let probe = RepairProbe::default();
+        let calls = probe.0.clone();
+        builder.set_repair_callback(move |session| {
+            calls.fetch_add(1, Ordering::Relaxed);
+            match self {
+                RepairCallback::Noop => {}
+                RepairCallback::Log => {
+                    info!(progress = session.progress() * 100.0, "Repair progress");
+                }
+                RepairCallback::Println => {
+                    println!("Repair progress: {:.1}%", session.progress() * 100.0);
+                }
+                RepairCallback::Sleep(duration) => thread::sleep(duration),
+            }
+        });
+        probe
+    }
+}
+
+impl FromStr for RepairCallback {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "noop" => Ok(RepairCallback::Noop),
+            "log" => Ok(RepairCallback::Log),
+            "println" => Ok(RepairCallback::Println),
+            _ => match s.strip_prefix("sleep:") {
+                Some(duration) => duration
+                    .parse::<LatencyObjective>()
+                    .map(|sleep| RepairCallback::Sleep(sleep.0))
+                    .map_err(|_| format!("invalid sleep `{duration}`, expected e.g. `1ms`")),
+                None => Err(format!(
+                    "unknown repair callback `{s}`, expected `noop`, `log`, `println` or \
+                     `sleep:DURATION`"
+                )),
+            },
+        }
+    }
+}
+
+impl fmt::Display for RepairCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RepairCallback::Noop => f.write_str("noop"),
+            RepairCallback::Log => f.write_str("log"),
+            RepairCallback::Println => f.write_str("println"),
+            RepairCallback::Sleep(duration) => write!(f, "sleep:{duration:?}"),
+        }
+    }
+}
+
+/// Parse a comma-separated list of repair callbacks.
+pub fn parse_list(value: &str) -> Result<Vec<RepairCallback>, String> {
+    value.split(',').map(|name| name.trim().parse()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_callback() {
+        assert_eq!("noop".parse(), Ok(RepairCallback::Noop));
+        assert_eq!("log".parse(), Ok(RepairCallback::Log));
+        assert_eq!("println".parse(), Ok(RepairCallback::Println));
+        assert_eq!(
+            "sleep:1.5ms".parse(),
+            Ok(RepairCallback::Sleep(Duration::from_micros(1_500)))
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_callbacks_and_sleeps() {
+        assert!("sleep".parse::<RepairCallback>().is_err());
+        assert!("sleep:".parse::<RepairCallback>().is_err());
+        assert!("sleep:5".parse::<RepairCallback>().is_err());
+        assert!("print".parse::<RepairCallback>().is_err());
+    }
+
+    #[test]
+    fn callbacks_round_trip_through_display() {
+        for callback in [
+            RepairCallback::Noop,
+            RepairCallback::Log,
+            RepairCallback::Println,
+            RepairCallback::Sleep(Duration::from_millis(2)),
+        ] {
+            assert_eq!(callback.to_string().parse(), Ok(callback));
+        }
+    }
+
+    #[test]
+    fn parses_a_trimmed_list() {
+        assert_eq!(
+            parse_list("noop, sleep:1ms ,log"),
+            Ok(vec![
+                RepairCallback::Noop,
+                RepairCallback::Sleep(Duration::from_millis(1)),
+                RepairCallback::Log,
+            ])
+        );
+        assert!(parse_list("noop,,log").is_err());
+    }
+}
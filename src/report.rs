@@ -0,0 +1,727 @@
+//! End-of-run results as a format-independent [`Report`], rendered by a [`ReportSink`]
+//! per output format.
+//!
+//! Phases describe their results as [`Section`]s; the sinks never need to know which
+//! phase a section came from, so new phases and new formats can be added independently.
+
+use crate::console::{ColorChoice, Console, Verbosity};
+use crate::stream;
+use serde_json::json;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// A single reported quantity.
+#[derive(Clone, Debug)]
+pub enum Value {
+    Duration(Duration),
+    Float(f64),
+    Int(i64),
+    Text(String),
+}
+
+impl From<Duration> for Value {
+    fn from(value: Duration) -> Self {
+        Value::Duration(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Int(value)
+    }
+}
+
+impl From<u64> for Value {
+    fn from(value: u64) -> Self {
+        Value::Int(value as i64)
+    }
+}
+
+impl From<usize> for Value {
+    fn from(value: usize) -> Self {
+        Value::Int(value as i64)
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Text(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Text(value.to_owned())
+    }
+}
+
+impl Value {
+    /// Machine-readable form; durations are integer nanoseconds.
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            Value::Duration(d) => json!(d.as_nanos() as u64),
+            Value::Float(f) => json!(f),
+            Value::Int(i) => json!(i),
+            Value::Text(s) => json!(s),
+        }
+    }
+
+    /// Machine-readable form for CSV, matching [`Value::to_json`].
+    fn to_raw(&self) -> String {
+        match self {
+            Value::Duration(d) => d.as_nanos().to_string(),
+            Value::Float(f) => f.to_string(),
+            Value::Int(i) => i.to_string(),
+            Value::Text(s) => s.clone(),
+        }
+    }
+}
+
+/// Human-readable form used by the console, Markdown and HTML sinks.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Duration(d) => write!(f, "{d:?}"),
+            Value::Float(x) => write!(f, "{x:.2}"),
+            Value::Int(i) => write!(f, "{i}"),
+            Value::Text(s) => f.write_str(s),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Table {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+/// One titled block of results: labelled fields, an optional table and free-form notes.
+#[derive(Clone, Debug, Default)]
+pub struct Section {
+    pub title: String,
+    pub fields: Vec<(String, Value)>,
+    pub table: Option<Table>,
+    pub notes: Vec<String>,
+}
+
+impl Section {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn field(mut self, label: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.fields.push((label.into(), value.into()));
+        self
+    }
+
+    pub fn table<C: Into<String>>(mut self, columns: impl IntoIterator<Item = C>) -> Self {
+        self.table = Some(Table {
+            columns: columns.into_iter().map(Into::into).collect(),
+            rows: Vec::new(),
+        });
+        self
+    }
+
+    /// Append a row to the table started with [`Section::table`].
+    pub fn row(mut self, row: Vec<Value>) -> Self {
+        self.table
+            .as_mut()
+            .expect("Section::row called before Section::table")
+            .rows
+            .push(row);
+        self
+    }
+
+    pub fn note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Report {
+    pub title: String,
+    pub sections: Vec<Section>,
+    /// Run labels given with `--tag`, rendered by every sink.
+    pub tags: Vec<Tag>,
+}
+
+impl Report {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            sections: Vec::new(),
+            tags: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, section: Section) {
+        self.sections.push(section);
+    }
+}
+
+/// A free-form `key=value` label attached to a run with `--tag`, such as `disk=nvme0`, and
+/// carried by every exported result. Keys are letters, digits, `_`, `-` and `.`, so that
+/// they can name a CSV column or a `RESULT` key as they are; values are free-form.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tag {
+    pub key: String,
+    pub value: String,
+}
+
+impl Tag {
+    /// `key=value ...`, as shown under report titles.
+    pub fn line(tags: &[Tag]) -> String {
+        tags.iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// `,KEY...` for a CSV header and the matching `,VALUE...` for each of its rows.
+    pub fn csv_columns(tags: &[Tag]) -> (String, String) {
+        tags.iter()
+            .map(|tag| {
+                (
+                    format!(",{}", tag.key),
+                    format!(",{}", csv_escape(&tag.value)),
+                )
+            })
+            .unzip()
+    }
+
+    /// The tags as one JSON object.
+    pub fn to_json(tags: &[Tag]) -> serde_json::Value {
+        tags.iter()
+            .map(|tag| (tag.key.clone(), json!(tag.value)))
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+}
+
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.key, self.value)
+    }
+}
+
+impl FromStr for Tag {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (key, value) = s
+            .split_once('=')
+            .ok_or_else(|| format!("invalid tag `{s}`, expected KEY=VALUE"))?;
+        let valid = |c: char| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.');
+        if key.is_empty() || !key.chars().all(valid) {
+            return Err(format!(
+                "invalid tag key `{key}`: use letters, digits, `_`, `-` and `.`"
+            ));
+        }
+        Ok(Self {
+            key: key.to_owned(),
+            value: value.to_owned(),
+        })
+    }
+}
+
+/// Stable identifier for a label, used as the key in machine-readable formats:
+/// `"Average write time"` becomes `average_write_time`.
+pub fn slug(label: &str) -> String {
+    let mut slug = String::with_capacity(label.len());
+    for c in label.chars() {
+        match c {
+            'μ' => slug.push('u'),
+            c if c.is_ascii_alphanumeric() => slug.push(c.to_ascii_lowercase()),
+            _ if !slug.is_empty() && !slug.ends_with('_') => slug.push('_'),
+            _ => {}
+        }
+    }
+    while slug.ends_with('_') {
+        slug.pop();
+    }
+    slug
+}
+
+/// Renders a [`Report`] in one output format.
+pub trait ReportSink {
+    fn emit(&mut self, report: &Report) -> io::Result<()>;
+}
+
+/// The human-oriented summary printed at the end of every run.
+pub struct ConsoleSink<W> {
+    out: W,
+    console: Console,
+}
+
+impl<W: Write> ConsoleSink<W> {
+    pub fn new(out: W, console: Console) -> Self {
+        Self { out, console }
+    }
+}
+
+impl<W: Write> ReportSink for ConsoleSink<W> {
+    fn emit(&mut self, report: &Report) -> io::Result<()> {
+        let ConsoleSink { out, console } = self;
+        let tags = Tag::line(&report.tags);
+        let subtitles: &[&str] = if tags.is_empty() { &[] } else { &[&tags] };
+        console.banner(out, &report.title, subtitles)?;
+
+        for section in &report.sections {
+            console.heading(out, &section.title)?;
+            let fields: Vec<_> = section
+                .fields
+                .iter()
+                .map(|(label, value)| (label.clone(), value.to_string()))
+                .collect();
+            console.fields(out, &fields)?;
+
+            if let Some(table) = section.table.as_ref().filter(|_| !console.quiet()) {
+                let rows: Vec<Vec<String>> = table
+                    .rows
+                    .iter()
+                    .map(|row| row.iter().map(ToString::to_string).collect())
+                    .collect();
+                if !fields.is_empty() {
+                    writeln!(out)?;
+                }
+                console.table(out, &table.columns, &rows)?;
+            }
+
+            for note in &section.notes {
+                console.note(out, note)?;
+            }
+        }
+
+        if !console.quiet() {
+            console.banner(out, "BENCHMARK COMPLETE", &[])?;
+        }
+        out.flush()
+    }
+}
+
+/// One JSON document; each section carries the [`slug`] of its title as `key`.
+pub struct JsonSink<W> {
+    out: W,
+}
+
+impl<W: Write> JsonSink<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+}
+
+impl<W: Write> ReportSink for JsonSink<W> {
+    fn emit(&mut self, report: &Report) -> io::Result<()> {
+        let sections: Vec<_> = report
+            .sections
+            .iter()
+            .map(|section| {
+                let fields: serde_json::Map<_, _> = section
+                    .fields
+                    .iter()
+                    .map(|(label, value)| (slug(label), value.to_json()))
+                    .collect();
+                let mut object = json!({
+                    "key": slug(&section.title),
+                    "title": section.title,
+                    "fields": fields,
+                });
+                if let Some(table) = &section.table {
+                    let rows: Vec<serde_json::Map<_, _>> = table
+                        .rows
+                        .iter()
+                        .map(|row| {
+                            table
+                                .columns
+                                .iter()
+                                .zip(row)
+                                .map(|(column, value)| (slug(column), value.to_json()))
+                                .collect()
+                        })
+                        .collect();
+                    object["rows"] = json!(rows);
+                }
+                if !section.notes.is_empty() {
+                    object["notes"] = json!(section.notes);
+                }
+                object
+            })
+            .collect();
+
+        let document = json!({
+            "title": report.title,
+            "tags": Tag::to_json(&report.tags),
+            "sections": sections,
+        });
+        serde_json::to_writer_pretty(&mut self.out, &document)?;
+        writeln!(self.out)?;
+        self.out.flush()
+    }
+}
+
+/// Long-format CSV with one value per line: `section,row,field,value`, followed by one
+/// column per tag. `row` is empty for section fields and the zero-based row index for table
+/// cells.
+pub struct CsvSink<W> {
+    out: W,
+}
+
+impl<W: Write> CsvSink<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+}
+
+impl<W: Write> ReportSink for CsvSink<W> {
+    fn emit(&mut self, report: &Report) -> io::Result<()> {
+        let out = &mut self.out;
+        let (tag_keys, tag_values) = Tag::csv_columns(&report.tags);
+        writeln!(out, "section,row,field,value{tag_keys}")?;
+        for section in &report.sections {
+            let key = slug(&section.title);
+            for (label, value) in &section.fields {
+                writeln!(
+                    out,
+                    "{key},,{},{}{tag_values}",
+                    slug(label),
+                    csv_escape(&value.to_raw())
+                )?;
+            }
+            if let Some(table) = &section.table {
+                for (i, row) in table.rows.iter().enumerate() {
+                    for (column, value) in table.columns.iter().zip(row) {
+                        writeln!(
+                            out,
+                            "{key},{i},{},{}{tag_values}",
+                            slug(column),
+                            csv_escape(&value.to_raw())
+                        )?;
+                    }
+                }
+            }
+        }
+        out.flush()
+    }
+}
+
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// GitHub-flavoured Markdown, suitable for pasting into issues and PRs.
+pub struct MarkdownSink<W> {
+    out: W,
+}
+
+impl<W: Write> MarkdownSink<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+}
+
+impl<W: Write> ReportSink for MarkdownSink<W> {
+    fn emit(&mut self, report: &Report) -> io::Result<()> {
+        let out = &mut self.out;
+        writeln!(out, "# {}", report.title)?;
+        if !report.tags.is_empty() {
+            writeln!(out, "\n{}", md_escape(&Tag::line(&report.tags)))?;
+        }
+        for section in &report.sections {
+            writeln!(out, "\n## {}\n", section.title)?;
+            if !section.fields.is_empty() {
+                writeln!(out, "| Metric | Value |")?;
+                writeln!(out, "|---|---:|")?;
+                for (label, value) in &section.fields {
+                    writeln!(
+                        out,
+                        "| {} | {} |",
+                        md_escape(label),
+                        md_escape(&value.to_string())
+                    )?;
+                }
+            }
+            if let Some(table) = &section.table {
+                if !section.fields.is_empty() {
+                    writeln!(out)?;
+                }
+                let header: Vec<_> = table.columns.iter().map(|c| md_escape(c)).collect();
+                writeln!(out, "| {} |", header.join(" | "))?;
+                writeln!(out, "|{}", "---:|".repeat(table.columns.len()))?;
+                for row in &table.rows {
+                    let cells: Vec<_> = row.iter().map(|v| md_escape(&v.to_string())).collect();
+                    writeln!(out, "| {} |", cells.join(" | "))?;
+                }
+            }
+            for note in &section.notes {
+                writeln!(out, "\n{note}")?;
+            }
+        }
+        out.flush()
+    }
+}
+
+fn md_escape(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+/// A standalone HTML page with one table per section.
+pub struct HtmlSink<W> {
+    out: W,
+}
+
+impl<W: Write> HtmlSink<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+}
+
+impl<W: Write> ReportSink for HtmlSink<W> {
+    fn emit(&mut self, report: &Report) -> io::Result<()> {
+        let out = &mut self.out;
+        let title = html_escape(&report.title);
+        writeln!(out, "<!DOCTYPE html>")?;
+        writeln!(out, "<html>\n<head>\n<meta charset=\"utf-8\">")?;
+        writeln!(out, "<title>{title}</title>")?;
+        writeln!(
+            out,
+            "<style>body {{ font-family: sans-serif; }} table {{ border-collapse: collapse; }} \
+             td, th {{ border: 1px solid #ccc; padding: 2px 8px; }} td {{ text-align: right; }}</style>"
+        )?;
+        writeln!(out, "</head>\n<body>\n<h1>{title}</h1>")?;
+        if !report.tags.is_empty() {
+            writeln!(out, "<p>{}</p>", html_escape(&Tag::line(&report.tags)))?;
+        }
+
+        for section in &report.sections {
+            writeln!(out, "<h2>{}</h2>", html_escape(&section.title))?;
+            if !section.fields.is_empty() {
+                writeln!(out, "<table>")?;
+                for (label, value) in &section.fields {
+                    writeln!(
+                        out,
+                        "<tr><th>{}</th><td>{}</td></tr>",
+                        html_escape(label),
+                        html_escape(&value.to_string())
+                    )?;
+                }
+                writeln!(out, "</table>")?;
+            }
+            if let Some(table) = &section.table {
+                writeln!(out, "<table>\n<tr>")?;
+                for column in &table.columns {
+                    writeln!(out, "<th>{}</th>", html_escape(column))?;
+                }
+                writeln!(out, "</tr>")?;
+                for row in &table.rows {
+                    write!(out, "<tr>")?;
+                    for value in row {
+                        write!(out, "<td>{}</td>", html_escape(&value.to_string()))?;
+                    }
+                    writeln!(out, "</tr>")?;
+                }
+                writeln!(out, "</table>")?;
+            }
+            for note in &section.notes {
+                writeln!(out, "<p>{}</p>", html_escape(note))?;
+            }
+        }
+
+        writeln!(out, "</body>\n</html>")?;
+        out.flush()
+    }
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Output format selectable with `--report`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportFormat {
+    Console,
+    Json,
+    Csv,
+    Markdown,
+    Html,
+}
+
+impl ReportFormat {
+    /// `console` only affects [`ReportFormat::Console`].
+    pub fn sink<'a>(self, out: impl Write + 'a, console: Console) -> Box<dyn ReportSink + 'a> {
+        match self {
+            ReportFormat::Console => Box::new(ConsoleSink::new(out, console)),
+            ReportFormat::Json => Box::new(JsonSink::new(out)),
+            ReportFormat::Csv => Box::new(CsvSink::new(out)),
+            ReportFormat::Markdown => Box::new(MarkdownSink::new(out)),
+            ReportFormat::Html => Box::new(HtmlSink::new(out)),
+        }
+    }
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "console" => Ok(ReportFormat::Console),
+            "json" => Ok(ReportFormat::Json),
+            "csv" => Ok(ReportFormat::Csv),
+            "markdown" | "md" => Ok(ReportFormat::Markdown),
+            "html" => Ok(ReportFormat::Html),
+            _ => Err(format!(
+                "unknown report format `{s}`, expected `console`, `json`, `csv`, `markdown` or `html`"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ReportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ReportFormat::Console => "console",
+            ReportFormat::Json => "json",
+            ReportFormat::Csv => "csv",
+            ReportFormat::Markdown => "markdown",
+            ReportFormat::Html => "html",
+        })
+    }
+}
+
+/// A format and where to write it, parsed from `FORMAT` (stdout) or `FORMAT:PATH`.
+#[derive(Clone, Debug)]
+pub struct ReportTarget {
+    pub format: ReportFormat,
+    pub path: Option<PathBuf>,
+}
+
+impl ReportTarget {
+    pub fn emit(
+        &self,
+        report: &Report,
+        color: ColorChoice,
+        verbosity: Verbosity,
+    ) -> io::Result<()> {
+        match &self.path {
+            Some(path) => {
+                let console = Console::new(color, verbosity, false);
+                let out = BufWriter::new(File::create(path)?);
+                self.format.sink(out, console).emit(report)
+            }
+            None => {
+                let (out, is_terminal) = stream::human_output();
+                let console = Console::new(color, verbosity, is_terminal);
+                self.format.sink(out, console).emit(report)
+            }
+        }
+    }
+}
+
+impl Default for ReportTarget {
+    fn default() -> Self {
+        Self {
+            format: ReportFormat::Console,
+            path: None,
+        }
+    }
+}
+
+impl FromStr for ReportTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (format, path) = match s.split_once(':') {
+            Some((format, path)) => (format, Some(PathBuf::from(path))),
+            None => (s, None),
+        };
+        Ok(Self {
+            format: format.parse()?,
+            path,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_target_defaults_to_stdout() {
+        let target: ReportTarget = "csv".parse().unwrap();
+        assert_eq!(target.format, ReportFormat::Csv);
+        assert_eq!(target.path, None);
+    }
+
+    #[test]
+    fn report_target_splits_the_path_at_the_first_colon() {
+        let target: ReportTarget = "json:out/run.json".parse().unwrap();
+        assert_eq!(target.format, ReportFormat::Json);
+        assert_eq!(target.path, Some(PathBuf::from("out/run.json")));
+
+        let target: ReportTarget = "html:C:/reports/run.html".parse().unwrap();
+        assert_eq!(target.format, ReportFormat::Html);
+        assert_eq!(target.path, Some(PathBuf::from("C:/reports/run.html")));
+    }
+
+    #[test]
+    fn report_target_rejects_unknown_formats() {
+        assert!("yaml".parse::<ReportTarget>().is_err());
+        assert!("yaml:out.yaml".parse::<ReportTarget>().is_err());
+        assert!("".parse::<ReportTarget>().is_err());
+    }
+
+    #[test]
+    fn report_formats_round_trip_through_their_names() {
+        for format in [
+            ReportFormat::Console,
+            ReportFormat::Json,
+            ReportFormat::Csv,
+            ReportFormat::Markdown,
+            ReportFormat::Html,
+        ] {
+            assert_eq!(format.to_string().parse::<ReportFormat>(), Ok(format));
+        }
+    }
+
+    #[test]
+    fn tag_splits_at_the_first_equals_sign() {
+        let tag: Tag = "disk=nvme0".parse().unwrap();
+        assert_eq!((tag.key.as_str(), tag.value.as_str()), ("disk", "nvme0"));
+        let tag: Tag = "kernel.opts=a=b c".parse().unwrap();
+        assert_eq!(
+            (tag.key.as_str(), tag.value.as_str()),
+            ("kernel.opts", "a=b c")
+        );
+        let tag: Tag = "note=".parse().unwrap();
+        assert_eq!(tag.value, "");
+        assert_eq!(tag.to_string(), "note=");
+    }
+
+    #[test]
+    fn tag_rejects_invalid_keys() {
+        assert!("disk".parse::<Tag>().is_err());
+        assert!("=nvme0".parse::<Tag>().is_err());
+        assert!("disk name=nvme0".parse::<Tag>().is_err());
+        assert!("disk,name=nvme0".parse::<Tag>().is_err());
+    }
+}
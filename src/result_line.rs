@@ -0,0 +1,93 @@
+//! One `RESULT key=value ...` line per configuration at the end of a run, in a fixed format
+//! for log scrapers: the keys always appear in the same order, numbers use `.` as the
+//! decimal separator and no grouping, latencies are integer microseconds and sizes bytes.
+//! A value that was not measured in this run is `na`. Each `--tag KEY=VALUE` follows as
+//! `tag.KEY="VALUE"`, in the order given.
+
+use crate::report::Tag;
+use crate::stats::{StatsAccumulator, Volume};
+use std::fmt;
+use std::time::Duration;
+
+/// Bumped whenever a key is added, removed or changes meaning.
+pub const VERSION: u32 = 3;
+
+/// Write-benchmark figures of one configuration.
+pub struct WriteFigures {
+    pub writes: usize,
+    pub writes_per_second: f64,
+    pub avg: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+    /// What each write wrote, for the per-key and per-KiB latencies.
+    pub volume: Option<Volume>,
+}
+
+impl WriteFigures {
+    /// Figures of the `latencies` recorded, `None` without any; the p99 is read off their
+    /// histogram.
+    pub fn new(latencies: &StatsAccumulator) -> Option<Self> {
+        let p99 = latencies.percentile(99.0)?;
+        let stats = latencies.stats();
+        Some(Self {
+            writes: stats.count,
+            writes_per_second: stats.writes_per_second,
+            avg: stats.avg_write_time,
+            p99,
+            max: stats.max_write_time,
+            volume: None,
+        })
+    }
+}
+
+pub struct ResultLine {
+    pub dir: String,
+    pub quick_repair: bool,
+    pub writes: Option<WriteFigures>,
+    pub file_size: Option<u64>,
+    /// Mean open after an unclean shutdown, from the shutdown-open phase.
+    pub repair: Option<Duration>,
+    pub tags: Vec<Tag>,
+}
+
+/// `value`, or `na` when it was not measured.
+struct Field<T>(Option<T>);
+
+impl<T: fmt::Display> fmt::Display for Field<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            Some(value) => value.fmt(f),
+            None => f.write_str("na"),
+        }
+    }
+}
+
+impl fmt::Display for ResultLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let writes = self.writes.as_ref();
+        let us = |d: Duration| d.as_micros();
+        let per_unit = |unit: fn(&Volume, Duration) -> f64| {
+            writes.and_then(|w| Some(format!("{:.3}", unit(&w.volume?, w.avg))))
+        };
+        write!(
+            f,
+            "RESULT version={VERSION} dir={:?} quick_repair={} writes={} writes_per_second={} \
+             avg_us={} p99_us={} max_us={} us_per_key={} us_per_kib={} file_bytes={} repair_us={}",
+            self.dir,
+            self.quick_repair,
+            Field(writes.map(|w| w.writes)),
+            Field(writes.map(|w| format!("{:.2}", w.writes_per_second))),
+            Field(writes.map(|w| us(w.avg))),
+            Field(writes.map(|w| us(w.p99))),
+            Field(writes.map(|w| us(w.max))),
+            Field(per_unit(Volume::us_per_key)),
+            Field(per_unit(Volume::us_per_kib)),
+            Field(self.file_size),
+            Field(self.repair.map(us)),
+        )?;
+        for tag in &self.tags {
+            write!(f, " tag.{}={:?}", tag.key, tag.value)?;
+        }
+        Ok(())
+    }
+}
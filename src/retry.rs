@@ -0,0 +1,225 @@
+//! Transient storage errors and the application-side retry we would pair with quick_repair:
+//! each benchmark database is opened through a [`FaultBackend`] failing every write and
+//! sync with probability `--fault-rate`, and takes `--retry-writes` single-insert commits.
+//!
+//! A failed commit poisons redb's `Database`, every later transaction failing with
+//! `PreviousIo`, so a retry drops it, waits with exponential backoff, reopens it, which
+//! repairs the file as its quick_repair setting allows, and commits again; after
+//! `--retry-max` retries the write is abandoned. Faults are only injected into the
+//! commits, not into the reopens, and the database is opened through the benchmark's
+//! [`crate::backend::SyncBackend`] whatever `--backend` says.
+
+use crate::backend::{FaultBackend, FaultHandle};
+use crate::payload::ValueGen;
+use crate::repair::RepairCallback;
+use crate::report::Section;
+use crate::{BenchDb, BenchmarkStats, StatsAccumulator, clock, record_op, write_single};
+use redb::{Database, Error};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+use tracing::{debug, info, info_span, warn};
+
+/// Probability of a transient error per write and sync when the phase is listed in
+/// `--phases` without `--fault-rate`.
+pub const DEFAULT_FAULT_RATE: f64 = 0.01;
+/// Default writes per database.
+pub const DEFAULT_WRITES: usize = 1000;
+/// Default retries of a write before it is abandoned.
+pub const DEFAULT_MAX_RETRIES: usize = 5;
+/// Default wait before the first retry of a write, in milliseconds.
+pub const DEFAULT_BACKOFF_MS: u64 = 10;
+
+/// How failed writes are retried.
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    pub max_retries: usize,
+    /// Wait before the first retry; it doubles with every further retry of the same write.
+    pub backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Wait before retry `retry` (1-based) of a write.
+    pub fn delay(&self, retry: usize) -> Duration {
+        self.backoff
+            .saturating_mul(1 << retry.saturating_sub(1).min(16) as u32)
+    }
+}
+
+pub struct RetryReport {
+    pub fault_rate: f64,
+    pub policy: RetryPolicy,
+    pub writes: usize,
+    /// Writes committed, possibly after retries.
+    pub committed: usize,
+    /// Writes given up on after [`RetryPolicy::max_retries`] retries.
+    pub abandoned: usize,
+    /// Transient errors the backend returned.
+    pub injected: usize,
+    pub retries: usize,
+    /// Committed writes that needed at least one retry.
+    pub retried_writes: usize,
+    /// Reopens after a failed commit, `None` when no commit failed.
+    pub reopens: Option<BenchmarkStats>,
+    /// Reopens on which redb ran the repair callback; quick repairs do not.
+    pub repairs: usize,
+    /// Time from the first attempt of each committed write to its commit, backoff and
+    /// reopens included; `None` when none committed.
+    pub latency: Option<BenchmarkStats>,
+    /// Time for all the writes, abandoned ones included.
+    pub elapsed: Duration,
+}
+
+/// Open `path` through a fresh [`FaultBackend`] with `callback` installed, returning
+/// whether redb repaired it.
+fn open(path: &str, callback: RepairCallback) -> Result<(Database, FaultHandle, bool), Error> {
+    let (backend, handle) = FaultBackend::open(Path::new(path))?;
+    let mut builder = Database::builder();
+    builder.set_cache_size(1024 * 1024 * 1024); // 1GB cache
+    let probe = callback.install(&mut builder);
+    let db = builder.create_with_backend(backend)?;
+    Ok((db, handle, probe.repaired()))
+}
+
+/// Make `writes` single-insert commits on `bench_db` with transient errors injected at
+/// `fault_rate`, retrying failed ones with `policy`.
+pub fn benchmark_retry(
+    bench_db: &mut BenchDb,
+    fault_rate: f64,
+    policy: RetryPolicy,
+    writes: usize,
+    values: ValueGen,
+    callback: RepairCallback,
+) -> Result<RetryReport, Error> {
+    let quick_repair = bench_db.quick_repair;
+    let _span = info_span!("retry", db_path = %bench_db.path, quick_repair).entered();
+    info!(
+        fault_rate,
+        max_retries = policy.max_retries,
+        backoff = ?policy.backoff,
+        writes,
+        "Writing with transient errors"
+    );
+
+    let (mut db, mut handle, _) = open(&bench_db.path, callback)?;
+    handle.set_error_rate(fault_rate);
+    let mut injected = 0;
+    let mut latencies = StatsAccumulator::new();
+    let mut reopens = StatsAccumulator::new();
+    let (mut committed, mut abandoned, mut retries, mut retried_writes, mut repairs) =
+        (0, 0, 0, 0, 0);
+
+    let start = clock::now();
+    for i in 0..writes {
+        let value = values.generate(bench_db.next_key);
+        let write_start = clock::now();
+        let mut retry = 0;
+        loop {
+            let before = handle.injected();
+            let result = write_single(&db, bench_db.next_key, &value, quick_repair);
+            let failed = handle.injected() > before;
+            match result {
+                Ok(latency) => {
+                    record_op(
+                        "retry",
+                        &bench_db.path,
+                        quick_repair,
+                        i as u64,
+                        1,
+                        value.len(),
+                        latency,
+                    );
+                    latencies.record(write_start.elapsed());
+                    committed += 1;
+                    retried_writes += usize::from(retry > 0);
+                    bench_db.next_key += 1;
+                    break;
+                }
+                Err(e) if failed => {
+                    debug!(error = %e, retry, "Commit failed with a transient error");
+                    // The database is poisoned; only a reopen clears it.
+                    injected += handle.injected();
+                    drop(db);
+                    let give_up = retry == policy.max_retries;
+                    if !give_up {
+                        retry += 1;
+                        retries += 1;
+                        thread::sleep(policy.delay(retry));
+                    }
+                    let reopen_start = clock::now();
+                    let repaired;
+                    (db, handle, repaired) = open(&bench_db.path, callback)?;
+                    reopens.record(reopen_start.elapsed());
+                    repairs += usize::from(repaired);
+                    handle.set_error_rate(fault_rate);
+                    if give_up {
+                        warn!(key = bench_db.next_key, retries = retry, "Abandoned write");
+                        abandoned += 1;
+                        break;
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+    let elapsed = start.elapsed();
+    injected += handle.injected();
+    // Let the drop shut the database down cleanly for the later phases.
+    handle.set_error_rate(0.0);
+    info!(
+        committed,
+        abandoned,
+        injected,
+        retries,
+        ?elapsed,
+        "Writes with transient errors done"
+    );
+
+    Ok(RetryReport {
+        fault_rate,
+        policy,
+        writes,
+        committed,
+        abandoned,
+        injected,
+        retries,
+        retried_writes,
+        reopens: (!reopens.is_empty()).then(|| reopens.stats()),
+        repairs,
+        latency: (!latencies.is_empty()).then(|| latencies.stats()),
+        elapsed,
+    })
+}
+
+impl RetryReport {
+    pub fn section(&self, title: impl Into<String>) -> Section {
+        let mut section = Section::new(title)
+            .field("Fault rate per write and sync", self.fault_rate)
+            .field("Max retries", self.policy.max_retries)
+            .field("Initial backoff", self.policy.backoff)
+            .field("Writes", self.writes)
+            .field("Committed", self.committed)
+            .field("Abandoned", self.abandoned)
+            .field("Transient errors", self.injected)
+            .field("Retries", self.retries)
+            .field("Writes retried", self.retried_writes)
+            .field("Elapsed", self.elapsed)
+            .field(
+                "Effective writes per second",
+                self.committed as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON),
+            );
+        if let Some(latency) = &self.latency {
+            section = section
+                .field("Mean write time, retries included", latency.avg_write_time)
+                .field("Max write time, retries included", latency.max_write_time);
+        }
+        match &self.reopens {
+            Some(reopens) => section
+                .field("Reopens", reopens.count)
+                .field("Reopens running the repair callback", self.repairs)
+                .field("Mean reopen", reopens.avg_write_time)
+                .field("Max reopen", reopens.max_write_time),
+            None => section.note("No commit failed; nothing was retried"),
+        }
+    }
+}
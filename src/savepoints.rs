@@ -0,0 +1,130 @@
+//! Commits that each create a persistent savepoint which is kept, measuring how commit
+//! latency and file size grow with the number of live savepoints.
+
+use crate::config::MIB;
+use crate::payload::ValueGen;
+use crate::report::Section;
+use crate::stats::LinearFit;
+use crate::{BenchDb, TABLE, clock, get_file_size, hooks, record_op};
+use redb::{Database, Error};
+use std::time::Duration;
+use tracing::{info, info_span};
+
+/// Number of windows the run is divided into for the report table.
+const WINDOWS: usize = 10;
+
+pub struct SavepointSample {
+    /// Live persistent savepoints after this commit.
+    pub savepoints: usize,
+    pub latency: Duration,
+    pub file_size: u64,
+}
+
+pub struct SavepointReport {
+    pub samples: Vec<SavepointSample>,
+}
+
+/// Perform `count` single-insert transactions on `bench_db`, each creating a persistent
+/// savepoint. The savepoints are deleted again, untimed, before returning so later phases
+/// run against an unpinned database.
+pub fn benchmark_savepoints(
+    bench_db: &mut BenchDb,
+    count: usize,
+    values: ValueGen,
+) -> Result<SavepointReport, Error> {
+    let quick_repair = bench_db.quick_repair;
+    let _span = info_span!("savepoints", db_path = %bench_db.path, quick_repair).entered();
+    info!(count, "Accumulating persistent savepoints");
+
+    let db = bench_db.create(
+        Database::builder().set_cache_size(1024 * 1024 * 1024), // 1GB cache
+    )?;
+
+    let mut ids = Vec::with_capacity(count);
+    let mut samples = Vec::with_capacity(count);
+    for (i, key) in (bench_db.next_key..).take(count).enumerate() {
+        let value = values.generate(key);
+
+        let start = clock::now();
+        let mut write_txn = hooks::begin_write(&db)?;
+        write_txn.set_quick_repair(quick_repair);
+        // Savepoints can only be taken before the transaction modifies anything.
+        ids.push(write_txn.persistent_savepoint()?);
+        {
+            let mut table = write_txn.open_table(TABLE)?;
+            table.insert(key, value.as_slice())?;
+        }
+        write_txn.commit()?;
+        let latency = start.elapsed();
+        record_op(
+            "savepoints",
+            &bench_db.path,
+            quick_repair,
+            i as u64,
+            1,
+            value.len(),
+            latency,
+        );
+
+        samples.push(SavepointSample {
+            savepoints: i + 1,
+            latency,
+            file_size: get_file_size(&bench_db.path).unwrap_or(0),
+        });
+    }
+    bench_db.next_key += count as u64;
+
+    let write_txn = hooks::begin_write(&db)?;
+    for id in ids {
+        write_txn.delete_persistent_savepoint(id)?;
+    }
+    write_txn.commit()?;
+
+    Ok(SavepointReport { samples })
+}
+
+impl SavepointReport {
+    /// Fit of commit latency (μs) against the number of live savepoints.
+    pub fn fit(&self) -> Option<LinearFit> {
+        let points: Vec<(f64, f64)> = self
+            .samples
+            .iter()
+            .map(|s| (s.savepoints as f64, s.latency.as_secs_f64() * 1e6))
+            .collect();
+        LinearFit::new(&points)
+    }
+
+    pub fn section(&self, title: impl Into<String>) -> Section {
+        let first = self.samples.first().map_or(0, |s| s.file_size);
+        let last = self.samples.last().map_or(0, |s| s.file_size);
+        let mut section = Section::new(title)
+            .field(
+                "File growth (MiB)",
+                (last as f64 - first as f64) / MIB as f64,
+            )
+            .table(["savepoints", "avg latency", "file size (MiB)"]);
+
+        let window = self.samples.len().div_ceil(WINDOWS).max(1);
+        for chunk in self.samples.chunks(window) {
+            let latency = chunk.iter().map(|s| s.latency).sum::<Duration>() / chunk.len() as u32;
+            let last = &chunk[chunk.len() - 1];
+            section = section.row(vec![
+                last.savepoints.into(),
+                latency.into(),
+                (last.file_size as f64 / MIB as f64).into(),
+            ]);
+        }
+
+        if let Some(fit) = self.fit() {
+            section = section
+                .field("Fit intercept (μs)", fit.intercept)
+                .field("Fit slope (μs per savepoint)", fit.slope)
+                .field("Fit correlation (r)", fit.r)
+                .note(format!(
+                    "Commit latency ≈ {:.0} μs + {:.2} μs × savepoints (r = {:.3})",
+                    fit.intercept, fit.slope, fit.r
+                ));
+        }
+        section
+    }
+}
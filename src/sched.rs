@@ -0,0 +1,239 @@
+//! `--pin-cpus`, `--nice` and `--ionice`: CPU affinity and scheduling priority of the
+//! benchmark, to cut run-to-run variance from other tenants on shared machines.
+//!
+//! Linux keeps affinity, nice value and I/O priority per thread, and a new thread inherits
+//! them from the one creating it. They are therefore applied on the main thread before any
+//! other is spawned, and then cover the generator, metrics and watchdog threads too.
+
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+use tracing::info;
+
+/// CPUs to run on, parsed from a list of indices and ranges such as `0-3,6`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CpuSet(pub Vec<usize>);
+
+impl FromStr for CpuSet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid CPU list `{s}`, expected e.g. `0-3,6`");
+        let mut cpus = Vec::new();
+        for part in s.split(',') {
+            let (first, last) = match part.split_once('-') {
+                Some((first, last)) => (first, last),
+                None => (part, part),
+            };
+            let first: usize = first.trim().parse().map_err(|_| invalid())?;
+            let last: usize = last.trim().parse().map_err(|_| invalid())?;
+            if first > last {
+                return Err(invalid());
+            }
+            cpus.extend(first..=last);
+        }
+        cpus.sort_unstable();
+        cpus.dedup();
+        Ok(Self(cpus))
+    }
+}
+
+impl fmt::Display for CpuSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Collapse back into ranges.
+        let mut first = true;
+        let mut i = 0;
+        while i < self.0.len() {
+            let start = self.0[i];
+            while i + 1 < self.0.len() && self.0[i + 1] == self.0[i] + 1 {
+                i += 1;
+            }
+            if !first {
+                f.write_str(",")?;
+            }
+            first = false;
+            match self.0[i] {
+                end if end == start => write!(f, "{start}")?,
+                end => write!(f, "{start}-{end}")?,
+            }
+            i += 1;
+        }
+        Ok(())
+    }
+}
+
+/// I/O scheduling class, as for ionice(1).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IoClass {
+    Realtime,
+    BestEffort,
+    Idle,
+}
+
+/// I/O priority parsed from `realtime:N`, `best-effort:N` (N from 0, highest, to 7) or
+/// `idle`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IoPriority {
+    pub class: IoClass,
+    pub level: u8,
+}
+
+impl FromStr for IoPriority {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (class, level) = match s.split_once(':') {
+            Some((class, level)) => (class, Some(level)),
+            None => (s, None),
+        };
+        let class = match class {
+            "realtime" | "rt" => IoClass::Realtime,
+            "best-effort" | "be" => IoClass::BestEffort,
+            "idle" => IoClass::Idle,
+            _ => {
+                return Err(format!(
+                    "unknown I/O class `{class}`, expected `realtime`, `best-effort` or `idle`"
+                ));
+            }
+        };
+        let level = match (class, level) {
+            (IoClass::Idle, None) => 0,
+            (IoClass::Idle, Some(_)) => return Err("the idle I/O class takes no level".into()),
+            (_, None) => 4,
+            (_, Some(level)) => match level.parse() {
+                Ok(level @ 0..=7) => level,
+                _ => {
+                    return Err(format!(
+                        "invalid I/O priority level `{level}`, expected 0-7"
+                    ));
+                }
+            },
+        };
+        Ok(Self { class, level })
+    }
+}
+
+impl fmt::Display for IoPriority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.class {
+            IoClass::Realtime => write!(f, "realtime:{}", self.level),
+            IoClass::BestEffort => write!(f, "best-effort:{}", self.level),
+            IoClass::Idle => f.write_str("idle"),
+        }
+    }
+}
+
+/// Restrict the calling thread, and the threads it spawns from now on, to `cpus`.
+#[cfg(target_os = "linux")]
+pub fn pin_cpus(cpus: &CpuSet) -> io::Result<()> {
+    // SAFETY: `cpu_set_t` is plain data and the all-zero value is the empty set.
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    for &cpu in &cpus.0 {
+        if cpu >= libc::CPU_SETSIZE as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("CPU {cpu} is out of range"),
+            ));
+        }
+        // SAFETY: `cpu` was checked to be below CPU_SETSIZE.
+        unsafe { libc::CPU_SET(cpu, &mut set) };
+    }
+    // SAFETY: `set` is a valid cpu_set_t of the size passed.
+    if unsafe { libc::sched_setaffinity(0, size_of::<libc::cpu_set_t>(), &set) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    info!(%cpus, "Pinned to CPUs");
+    Ok(())
+}
+
+/// Set the nice value of the calling thread, inherited by the threads it spawns (on macOS,
+/// of the whole process). Values below 0 need `CAP_SYS_NICE`, or root on macOS.
+#[cfg(unix)]
+pub fn set_nice(nice: i32) -> io::Result<()> {
+    // SAFETY: plain syscall on the calling thread.
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    info!(nice, "Set scheduling priority");
+    Ok(())
+}
+
+/// Set the I/O priority of the calling thread, inherited by the threads it spawns. The
+/// realtime class needs `CAP_SYS_ADMIN`.
+#[cfg(target_os = "linux")]
+pub fn set_io_priority(priority: IoPriority) -> io::Result<()> {
+    // From linux/ioprio.h; libc does not wrap ioprio_set.
+    const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+    const IOPRIO_CLASS_SHIFT: u32 = 13;
+    let class = match priority.class {
+        IoClass::Realtime => 1,
+        IoClass::BestEffort => 2,
+        IoClass::Idle => 3,
+    };
+    let ioprio = (class << IOPRIO_CLASS_SHIFT) | libc::c_int::from(priority.level);
+    // SAFETY: plain syscall on the calling thread.
+    if unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, ioprio) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    info!(%priority, "Set I/O priority");
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn unsupported(option: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("{option} is only supported on Linux"),
+    )
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn pin_cpus(_cpus: &CpuSet) -> io::Result<()> {
+    Err(unsupported("--pin-cpus"))
+}
+
+#[cfg(not(unix))]
+pub fn set_nice(_nice: i32) -> io::Result<()> {
+    Err(unsupported("--nice"))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn set_io_priority(_priority: IoPriority) -> io::Result<()> {
+    Err(unsupported("--ionice"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cpus(s: &str) -> Vec<usize> {
+        s.parse::<CpuSet>().unwrap().0
+    }
+
+    #[test]
+    fn cpu_set_expands_ranges() {
+        assert_eq!(cpus("0-3,6"), [0, 1, 2, 3, 6]);
+        assert_eq!(cpus("5"), [5]);
+        assert_eq!(cpus(" 1 - 2 , 4 "), [1, 2, 4]);
+    }
+
+    #[test]
+    fn cpu_set_sorts_and_dedups() {
+        assert_eq!(cpus("6,2-3,3,0"), [0, 2, 3, 6]);
+        assert_eq!(cpus("1-4,2-5"), [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn cpu_set_rejects_malformed_lists() {
+        for list in ["", "a", "1,", "-1", "3-1", "1-2-3", "0x1"] {
+            assert!(list.parse::<CpuSet>().is_err(), "`{list}` parsed");
+        }
+    }
+
+    #[test]
+    fn cpu_set_displays_as_ranges() {
+        assert_eq!("6,0-3,2".parse::<CpuSet>().unwrap().to_string(), "0-3,6");
+        assert_eq!("1,3,5".parse::<CpuSet>().unwrap().to_string(), "1,3,5");
+        assert_eq!("7".parse::<CpuSet>().unwrap().to_string(), "7");
+    }
+}
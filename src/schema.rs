@@ -0,0 +1,338 @@
+//! Structured synthetic records for `--schema`, e.g. `fields=8,strings=4:64,blobs=2:512`:
+//! instead of raw bytes, every value is a record of fixed integer fields, variable-length
+//! strings and nested blobs, encoded with bincode, so that the stored values have the shape
+//! and the encoding overhead of a real application's.
+//!
+//! The record inserted under a key is a pure function of the schema, `--seed` and the key,
+//! whichever database, phase or generator thread produces it: runs with the same seed insert
+//! the same records under the same keys. Encoding happens when a value is generated, outside
+//! the timed commits; the "Synthetic Records" section reports what it costs per record.
+
+use crate::clock;
+use crate::payload::WORDS;
+use crate::report::Section;
+use rand::rngs::StdRng;
+use rand::seq::IndexedRandom;
+use rand::{Rng, SeedableRng};
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Records encoded to time the encoding for the report.
+const ENCODE_SAMPLES: u64 = 1000;
+/// Bytes bincode adds to each blob level: the tag, the data length and the child's
+/// presence.
+const BLOB_OVERHEAD: usize = 4 + 8 + 1;
+
+#[derive(Serialize)]
+struct Record {
+    id: u64,
+    timestamp_ms: u64,
+    fields: Vec<u64>,
+    strings: Vec<String>,
+    blobs: Vec<Blob>,
+}
+
+#[derive(Serialize)]
+struct Blob {
+    tag: u32,
+    data: Vec<u8>,
+    child: Option<Box<Blob>>,
+}
+
+/// Shape of the synthetic records; keys omitted from `--schema` take the defaults of
+/// [`Schema::default`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Schema {
+    /// Fixed 64-bit integer fields per record, besides the id and the timestamp.
+    pub fields: usize,
+    /// Strings per record, of words from a small vocabulary.
+    pub strings: usize,
+    /// Longest string in bytes; lengths are drawn uniformly from 0 up to it.
+    pub string_len: usize,
+    /// Blobs per record.
+    pub blobs: usize,
+    /// Random data bytes per blob, split across its levels.
+    pub blob_size: usize,
+    /// Levels of each blob: every level but the innermost nests the next.
+    pub depth: usize,
+    /// Seed the records derive from; set from `--seed`.
+    pub seed: u64,
+}
+
+impl Default for Schema {
+    fn default() -> Self {
+        Self {
+            fields: 8,
+            strings: 4,
+            string_len: 64,
+            blobs: 2,
+            blob_size: 512,
+            depth: 2,
+            seed: 0,
+        }
+    }
+}
+
+impl Schema {
+    /// Encoded size of a record whose strings are all empty.
+    pub fn min_size(&self) -> usize {
+        // Id and timestamp, then one length prefix per field, string and blob vector.
+        16 + 8
+            + 8 * self.fields
+            + 8
+            + 8 * self.strings
+            + 8
+            + self.blobs * (BLOB_OVERHEAD * self.depth + self.blob_size)
+    }
+
+    /// Encoded size of a record whose strings are all [`Schema::string_len`] long.
+    pub fn max_size(&self) -> usize {
+        self.min_size() + self.strings * self.string_len
+    }
+
+    fn blob(&self, rng: &mut StdRng, level: usize) -> Blob {
+        let share = self.blob_size / self.depth;
+        let len = if level + 1 == self.depth {
+            self.blob_size - share * level
+        } else {
+            share
+        };
+        let mut data = vec![0; len];
+        rng.fill(data.as_mut_slice());
+        Blob {
+            tag: rng.random(),
+            data,
+            child: (level + 1 < self.depth).then(|| Box::new(self.blob(rng, level + 1))),
+        }
+    }
+
+    fn record(&self, key: u64) -> Record {
+        let mut rng = StdRng::seed_from_u64(self.seed.wrapping_add(key));
+        Record {
+            id: key,
+            timestamp_ms: rng.random(),
+            fields: (0..self.fields).map(|_| rng.random()).collect(),
+            strings: (0..self.strings)
+                .map(|_| {
+                    let len = rng.random_range(0..=self.string_len);
+                    let mut string = String::with_capacity(len + 16);
+                    while string.len() < len {
+                        string.push_str(WORDS.choose(&mut rng).unwrap());
+                        string.push(' ');
+                    }
+                    string.truncate(len);
+                    string
+                })
+                .collect(),
+            blobs: (0..self.blobs).map(|_| self.blob(&mut rng, 0)).collect(),
+        }
+    }
+
+    /// The record for `key`, encoded.
+    pub fn encode(&self, key: u64) -> Vec<u8> {
+        bincode::serialize(&self.record(key)).expect("records encode")
+    }
+
+    /// Like [`Schema::encode`], but into `value`, reusing its allocation.
+    pub fn encode_into(&self, key: u64, value: &mut Vec<u8>) {
+        value.clear();
+        bincode::serialize_into(&mut *value, &self.record(key)).expect("records encode");
+    }
+
+    /// Time encoding [`ENCODE_SAMPLES`] records, at keys the run does not reach, for the
+    /// report.
+    pub fn section(&self, title: impl Into<String>) -> Section {
+        let mut bytes = 0;
+        let start = clock::now();
+        for index in 0..ENCODE_SAMPLES {
+            bytes += self.encode(u64::MAX - index).len();
+        }
+        let elapsed = start.elapsed();
+        Section::new(title)
+            .field("Schema", self.to_string())
+            .field("Seed", self.seed.to_string())
+            .field(
+                "Encoded size (min-max)",
+                format!("{}-{} B", self.min_size(), self.max_size()),
+            )
+            .field(
+                "Mean encoded size (B)",
+                bytes as f64 / ENCODE_SAMPLES as f64,
+            )
+            .field(
+                "Mean build and encode time",
+                Duration::from_secs_f64(elapsed.as_secs_f64() / ENCODE_SAMPLES as f64),
+            )
+            .note(
+                "Values are encoded when generated, outside the timed commits; add the encode \
+                 time to a commit latency for the application's end-to-end cost",
+            )
+    }
+}
+
+impl fmt::Display for Schema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "fields={},strings={}:{},blobs={}:{},depth={}",
+            self.fields, self.strings, self.string_len, self.blobs, self.blob_size, self.depth
+        )
+    }
+}
+
+impl FromStr for Schema {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let number = |key: &str, value: &str| {
+            value
+                .trim()
+                .parse::<usize>()
+                .map_err(|_| format!("invalid number `{value}` for `{key}` in schema `{s}`"))
+        };
+        let pair = |key: &str, value: &str| {
+            let (count, size) = value
+                .split_once(':')
+                .ok_or_else(|| format!("invalid `{key}={value}`, expected e.g. `{key}=4:64`"))?;
+            Ok::<_, String>((number(key, count)?, number(key, size)?))
+        };
+
+        let mut schema = Schema::default();
+        let mut seen: Vec<&str> = Vec::new();
+        for part in s.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+            let (key, value) = part.split_once('=').ok_or_else(|| {
+                format!("invalid schema entry `{part}`, expected e.g. `fields=8`")
+            })?;
+            let key = key.trim();
+            if seen.contains(&key) {
+                return Err(format!("`{key}` appears twice in the schema"));
+            }
+            seen.push(key);
+            match key {
+                "fields" => schema.fields = number(key, value)?,
+                "strings" => (schema.strings, schema.string_len) = pair(key, value)?,
+                "blobs" => (schema.blobs, schema.blob_size) = pair(key, value)?,
+                "depth" => schema.depth = number(key, value)?,
+                _ => {
+                    return Err(format!(
+                        "unknown schema key `{key}`, expected fields, strings, blobs or depth"
+                    ));
+                }
+            }
+        }
+        if schema.depth == 0 {
+            return Err("schema depth must be at least 1".to_owned());
+        }
+        Ok(schema)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema(s: &str) -> Schema {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn schema_parses_every_key() {
+        assert_eq!(
+            schema("fields=3,strings=2:16,blobs=1:100,depth=3"),
+            Schema {
+                fields: 3,
+                strings: 2,
+                string_len: 16,
+                blobs: 1,
+                blob_size: 100,
+                depth: 3,
+                seed: 0,
+            }
+        );
+        assert_eq!(
+            schema(" fields = 3 , ,depth=1"),
+            Schema {
+                fields: 3,
+                depth: 1,
+                ..Schema::default()
+            }
+        );
+    }
+
+    #[test]
+    fn schema_defaults_omitted_keys() {
+        assert_eq!(schema(""), Schema::default());
+        assert_eq!(
+            schema("fields=8,strings=4:64,blobs=2:512"),
+            Schema::default()
+        );
+    }
+
+    #[test]
+    fn schema_rejects_malformed_entries() {
+        for s in [
+            "fields",
+            "fields=x",
+            "fields=-1",
+            "strings=4",
+            "blobs=2:big",
+            "depth=0",
+            "rows=5",
+            "fields=1,fields=2",
+        ] {
+            assert!(s.parse::<Schema>().is_err(), "`{s}` parsed");
+        }
+    }
+
+    #[test]
+    fn schema_round_trips_through_display() {
+        let parsed = schema("fields=1,strings=0:0,blobs=3:7,depth=4");
+        assert_eq!(schema(&parsed.to_string()), parsed);
+        assert_eq!(schema(&Schema::default().to_string()), Schema::default());
+    }
+
+    #[test]
+    fn min_size_is_the_encoding_without_strings() {
+        for s in [
+            "fields=8,strings=4:0,blobs=2:512,depth=2",
+            "fields=0,strings=0:0,blobs=0:0,depth=1",
+            "fields=1,strings=3:0,blobs=3:100,depth=3",
+            "fields=2,strings=1:0,blobs=1:5,depth=1",
+        ] {
+            let schema = schema(s);
+            assert_eq!(schema.min_size(), schema.max_size());
+            for key in 0..5 {
+                assert_eq!(schema.encode(key).len(), schema.min_size(), "{s}");
+            }
+        }
+    }
+
+    #[test]
+    fn encoded_records_stay_within_the_size_bounds() {
+        let schema = Schema::default();
+        for key in 0..200 {
+            let len = schema.encode(key).len();
+            assert!(
+                (schema.min_size()..=schema.max_size()).contains(&len),
+                "{len}"
+            );
+        }
+    }
+
+    #[test]
+    fn records_derive_from_the_seed_and_the_key() {
+        let schema = Schema {
+            seed: 42,
+            ..Schema::default()
+        };
+        assert_eq!(schema.encode(7), schema.encode(7));
+        assert_ne!(schema.encode(7), schema.encode(8));
+        assert_ne!(schema.encode(7), Schema::default().encode(7));
+
+        let mut value = vec![0xff; 3];
+        schema.encode_into(7, &mut value);
+        assert_eq!(value, schema.encode(7));
+    }
+}
@@ -0,0 +1,169 @@
+//! Open latency after a clean shutdown against after an unclean exit, the four-way
+//! comparison behind choosing a quick_repair setting for production.
+//!
+//! Each trial commits one write and, with the database still open, copies its file, which
+//! leaves the copy as an exit without closing would: marked as needing repair, with the last
+//! commit durable. The database is then dropped cleanly and both files are reopened with the
+//! repair callback installed.
+
+use crate::config::MIB;
+use crate::payload::ValueGen;
+use crate::repair::RepairCallback;
+use crate::report::Section;
+use crate::{BenchDb, BenchmarkStats, StatsAccumulator, clock, get_file_size, hooks, record_op};
+use redb::{Builder, Database, Error, TableDefinition};
+use std::fs;
+use std::time::Duration;
+use tracing::{info, info_span};
+
+/// Table the write before each shutdown goes to.
+pub const SHUTDOWN_TABLE: TableDefinition<u64, &[u8]> = TableDefinition::new("benchmark_shutdown");
+/// Opens timed per shutdown kind.
+pub const TRIALS: usize = 3;
+
+/// Opens after one kind of shutdown.
+pub struct Opens {
+    pub latency: BenchmarkStats,
+    /// Opens that ran a repair.
+    pub repaired: usize,
+    pub callback_calls: u64,
+}
+
+pub struct ShutdownOpenReport {
+    pub quick_repair: bool,
+    pub file_size: u64,
+    pub clean: Opens,
+    pub unclean: Opens,
+}
+
+/// Time opening a database with `callback` installed through `open`.
+fn timed_open(
+    callback: RepairCallback,
+    open: impl FnOnce(&mut Builder) -> Result<Database, redb::DatabaseError>,
+) -> Result<(Duration, bool, u64), Error> {
+    let mut builder = Database::builder();
+    builder.set_cache_size(1024 * 1024 * 1024); // 1GB cache
+    let probe = callback.install(&mut builder);
+    let start = clock::now();
+    let db = open(&mut builder)?;
+    let elapsed = start.elapsed();
+    drop(db);
+    Ok((elapsed, probe.repaired(), probe.calls()))
+}
+
+/// Opens folded in as they are timed.
+#[derive(Default)]
+struct OpensAccumulator {
+    latencies: StatsAccumulator,
+    repaired: usize,
+    callback_calls: u64,
+}
+
+impl OpensAccumulator {
+    fn record(&mut self, (open, repaired, calls): (Duration, bool, u64)) {
+        self.latencies.record(open);
+        self.repaired += usize::from(repaired);
+        self.callback_calls += calls;
+    }
+
+    fn opens(&self) -> Opens {
+        Opens {
+            latency: self.latencies.stats(),
+            repaired: self.repaired,
+            callback_calls: self.callback_calls,
+        }
+    }
+}
+
+/// Reopen `bench_db` [`TRIALS`] times after a clean shutdown and a copy of it after as many
+/// unclean exits, with `callback` installed.
+pub fn benchmark_shutdown_open(
+    bench_db: &BenchDb,
+    values: ValueGen,
+    callback: RepairCallback,
+) -> Result<ShutdownOpenReport, Error> {
+    let quick_repair = bench_db.quick_repair;
+    let _span = info_span!("shutdown_open", db_path = %bench_db.path, quick_repair).entered();
+    info!(
+        trials = TRIALS,
+        "Benchmarking open after clean and unclean shutdowns"
+    );
+
+    let copy = format!("{}.unclean", bench_db.path);
+    let mut clean = OpensAccumulator::default();
+    let mut unclean = OpensAccumulator::default();
+    for trial in 0..TRIALS {
+        let db = bench_db.create(
+            Database::builder().set_cache_size(1024 * 1024 * 1024), // 1GB cache
+        )?;
+        let value = values.generate(trial as u64);
+        let start = clock::now();
+        let mut write_txn = hooks::begin_write(&db)?;
+        write_txn.set_quick_repair(quick_repair);
+        {
+            let mut table = write_txn.open_table(SHUTDOWN_TABLE)?;
+            table.insert(trial as u64, value.as_slice())?;
+        }
+        write_txn.commit()?;
+        record_op(
+            "shutdown-open",
+            &bench_db.path,
+            quick_repair,
+            trial as u64,
+            1,
+            value.len(),
+            start.elapsed(),
+        );
+        fs::copy(&bench_db.path, &copy)?;
+        drop(db);
+
+        clean.record(timed_open(callback, |builder| bench_db.create(builder))?);
+        let outcome = timed_open(callback, |builder| builder.create(&copy));
+        let _ = fs::remove_file(&copy);
+        unclean.record(outcome?);
+    }
+
+    let report = ShutdownOpenReport {
+        quick_repair,
+        file_size: get_file_size(&bench_db.path)?,
+        clean: clean.opens(),
+        unclean: unclean.opens(),
+    };
+    info!(
+        clean = ?report.clean.latency.avg_write_time,
+        unclean = ?report.unclean.latency.avg_write_time,
+        "Mean open latency"
+    );
+    Ok(report)
+}
+
+/// One row per quick_repair setting and shutdown kind.
+pub fn comparison_section(title: impl Into<String>, reports: &[ShutdownOpenReport]) -> Section {
+    let mut section = Section::new(title).field("Opens per cell", TRIALS).table([
+        "quick_repair",
+        "shutdown",
+        "file size (MiB)",
+        "mean open",
+        "min open",
+        "max open",
+        "repaired",
+        "callback calls",
+    ]);
+    for report in reports {
+        for (shutdown, opens) in [("clean", &report.clean), ("unclean", &report.unclean)] {
+            section = section.row(vec![
+                report.quick_repair.to_string().into(),
+                shutdown.into(),
+                (report.file_size as f64 / MIB as f64).into(),
+                opens.latency.avg_write_time.into(),
+                opens.latency.min_write_time.into(),
+                opens.latency.max_write_time.into(),
+                opens.repaired.into(),
+                opens.callback_calls.into(),
+            ]);
+        }
+    }
+    section.note(
+        "An unclean exit leaves the file marked for repair; quick_repair decides how long it takes",
+    )
+}
@@ -0,0 +1,166 @@
+//! Commit latency as the database grows: `--size-probes N` pauses the fill N times, evenly
+//! spaced through its target with the last at the end, for a burst of [`PROBE_WRITES`]
+//! single-insert commits with the database's quick_repair setting, and records the space
+//! the file occupies (not its length, which grows in preallocated steps) with the burst's
+//! median and p99. A least-squares fit per setting, and one of the
+//! quick_repair(true) overhead, shows whether the overhead stays constant or grows with
+//! the file.
+//!
+//! Probe keys are fresh and stay in the database, counting towards the fill target.
+
+use crate::config::{GIB, MIB};
+use crate::payload::ValueGen;
+use crate::report::Section;
+use crate::stats::LinearFit;
+use crate::{platform, record_op, write_single};
+use redb::{Database, Error};
+use std::path::Path;
+use std::time::Duration;
+use tracing::debug;
+
+/// Commits timed per probe.
+pub const PROBE_WRITES: usize = 100;
+/// Overhead change across the probed sizes, relative to its mean, below which it is
+/// reported as constant.
+const CONSTANT_WITHIN: f64 = 0.10;
+
+#[derive(Clone, Copy, Debug)]
+pub struct SizeProbe {
+    /// Bytes the file occupied when the probe started.
+    pub file_size: u64,
+    pub p50: Duration,
+    pub p99: Duration,
+}
+
+/// Time [`PROBE_WRITES`] commits of fresh keys from `*next_key` on `db`, advancing it, and
+/// return the value bytes written with the probe.
+pub fn probe(
+    db: &Database,
+    db_path: &str,
+    quick_repair: bool,
+    next_key: &mut u64,
+    values: ValueGen,
+) -> Result<(SizeProbe, u64), Error> {
+    let file_size = platform::allocated_size(Path::new(db_path)).unwrap_or(0);
+    let mut latencies = Vec::with_capacity(PROBE_WRITES);
+    let mut bytes = 0;
+    for _ in 0..PROBE_WRITES {
+        let value = values.generate(*next_key);
+        let latency = write_single(db, *next_key, &value, quick_repair)?;
+        record_op(
+            "size-probe",
+            db_path,
+            quick_repair,
+            *next_key,
+            1,
+            value.len(),
+            latency,
+        );
+        latencies.push(latency);
+        bytes += value.len() as u64;
+        *next_key += 1;
+    }
+    latencies.sort_unstable();
+    // Nearest rank.
+    let rank = |percent: usize| latencies[(PROBE_WRITES * percent).div_ceil(100) - 1];
+    let probe = SizeProbe {
+        file_size,
+        p50: rank(50),
+        p99: rank(99),
+    };
+    debug!(file_size, p50 = ?probe.p50, p99 = ?probe.p99, "Size probe");
+    Ok((probe, bytes))
+}
+
+/// Probes of quick_repair(false) and quick_repair(true), in fill order.
+pub struct SizeScalingReport {
+    pub probes: [Vec<SizeProbe>; 2],
+}
+
+/// Fit of a latency in μs against the file size in GiB.
+fn fit(points: impl Iterator<Item = (u64, Duration)>) -> Option<LinearFit> {
+    let points: Vec<_> = points
+        .map(|(size, latency)| (size as f64 / GIB as f64, latency.as_secs_f64() * 1e6))
+        .collect();
+    LinearFit::new(&points)
+}
+
+impl SizeScalingReport {
+    /// The quick_repair(true) median minus the quick_repair(false) one at each probe, against
+    /// the mean file size of the two.
+    fn overhead(&self) -> Vec<(f64, f64)> {
+        let [off, on] = &self.probes;
+        off.iter()
+            .zip(on)
+            .map(|(off, on)| {
+                (
+                    (off.file_size + on.file_size) as f64 / 2.0 / GIB as f64,
+                    (on.p50.as_secs_f64() - off.p50.as_secs_f64()) * 1e6,
+                )
+            })
+            .collect()
+    }
+
+    pub fn section(&self, title: impl Into<String>) -> Section {
+        let mut section = Section::new(title).field("Writes per probe", PROBE_WRITES);
+        for (quick_repair, probes) in [false, true].into_iter().zip(&self.probes) {
+            let p50 = fit(probes.iter().map(|p| (p.file_size, p.p50)));
+            let p99 = fit(probes.iter().map(|p| (p.file_size, p.p99)));
+            if let (Some(p50), Some(p99)) = (p50, p99) {
+                section = section
+                    .field(
+                        format!("p50 slope, quick_repair({quick_repair}) (μs per GiB)"),
+                        p50.slope,
+                    )
+                    .field(format!("p50 fit r, quick_repair({quick_repair})"), p50.r)
+                    .field(
+                        format!("p99 slope, quick_repair({quick_repair}) (μs per GiB)"),
+                        p99.slope,
+                    )
+                    .field(format!("p99 fit r, quick_repair({quick_repair})"), p99.r);
+            }
+        }
+
+        let overhead = self.overhead();
+        let overhead_fit = LinearFit::new(&overhead);
+        if let Some(fit) = overhead_fit {
+            section = section
+                .field("Overhead intercept (μs)", fit.intercept)
+                .field("Overhead slope (μs per GiB)", fit.slope)
+                .field("Overhead fit r", fit.r);
+        }
+
+        section = section.table(["quick_repair", "on disk (MiB)", "p50", "p99"]);
+        for (quick_repair, probes) in [false, true].into_iter().zip(&self.probes) {
+            for probe in probes {
+                section = section.row(vec![
+                    quick_repair.to_string().into(),
+                    (probe.file_size as f64 / MIB as f64).into(),
+                    probe.p50.into(),
+                    probe.p99.into(),
+                ]);
+            }
+        }
+
+        let (Some(fit), Some(&(smallest, _)), Some(&(largest, _))) =
+            (overhead_fit, overhead.first(), overhead.last())
+        else {
+            return section.note("Fewer than two probes per setting: nothing to fit");
+        };
+        let mean = overhead.iter().map(|&(_, y)| y).sum::<f64>() / overhead.len() as f64;
+        let change = fit.slope * (largest - smallest);
+        let trend = if change.abs() <= CONSTANT_WITHIN * mean.abs() {
+            "stays roughly constant"
+        } else if change > 0.0 {
+            "grows with the file"
+        } else {
+            "shrinks as the file grows"
+        };
+        section.note(format!(
+            "The quick_repair(true) median overhead {trend}: intercept {:.0} μs, slope {:.2} μs \
+             per GiB, changing by {change:.0} μs over the probed sizes against a mean of \
+             {mean:.0} μs",
+            fit.intercept, fit.slope
+        ))
+    }
+}
@@ -0,0 +1,181 @@
+//! Accounting of operations exceeding a latency objective, with the worst offenders and
+//! when they happened so they can be lined up with system logs.
+
+use crate::report::Section;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Number of slowest operations listed per configuration.
+pub const WORST: usize = 5;
+
+/// Milliseconds since the Unix epoch.
+pub fn unix_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// `unix_ms` as `YYYY-MM-DD HH:MM:SS.mmm` UTC.
+pub fn format_utc(unix_ms: u64) -> String {
+    let secs = unix_ms / 1000;
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+
+    // Civil-from-days (H. Hinnant), valid for any date after 1970.
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}.{:03}",
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60,
+        unix_ms % 1000
+    )
+}
+
+/// A latency objective parsed from `5ms`, `250us`, `1.5s` and the like.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LatencyObjective(pub Duration);
+
+impl FromStr for LatencyObjective {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let split = s
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+        let number: f64 = number
+            .parse()
+            .map_err(|_| format!("invalid latency objective `{s}`, expected e.g. `5ms`"))?;
+        let scale = match unit {
+            "ns" => 1e-9,
+            "us" | "µs" | "μs" => 1e-6,
+            "ms" => 1e-3,
+            "s" => 1.0,
+            _ => {
+                return Err(format!(
+                    "unknown unit in latency objective `{s}`, expected `ns`, `us`, `ms` or `s`"
+                ));
+            }
+        };
+        Ok(Self(Duration::from_secs_f64(number * scale)))
+    }
+}
+
+impl fmt::Display for LatencyObjective {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
+/// One timed operation: when it completed and how long it took.
+#[derive(Clone, Copy, Debug)]
+pub struct OpSample {
+    /// Position of the operation within its phase.
+    pub index: usize,
+    pub unix_ms: u64,
+    pub latency: Duration,
+}
+
+pub struct SloReport {
+    pub objective: LatencyObjective,
+    pub total: usize,
+    pub violations: usize,
+    /// Up to [`WORST`] slowest violating operations, slowest first.
+    pub worst: Vec<OpSample>,
+}
+
+impl SloReport {
+    /// No operation recorded yet; operations are counted with [`SloReport::record`] as they
+    /// complete, keeping only the [`WORST`] offenders.
+    pub fn new(objective: LatencyObjective) -> Self {
+        Self {
+            objective,
+            total: 0,
+            violations: 0,
+            worst: Vec::with_capacity(WORST + 1),
+        }
+    }
+
+    pub fn record(&mut self, sample: OpSample) {
+        self.total += 1;
+        if sample.latency <= self.objective.0 {
+            return;
+        }
+        self.violations += 1;
+        let at = self
+            .worst
+            .partition_point(|worst| worst.latency >= sample.latency);
+        if at < WORST {
+            self.worst.insert(at, sample);
+            self.worst.truncate(WORST);
+        }
+    }
+
+    pub fn fraction(&self) -> f64 {
+        self.violations as f64 / self.total.max(1) as f64
+    }
+
+    pub fn section(&self, title: impl Into<String>) -> Section {
+        let mut section = Section::new(title)
+            .field("Objective", self.objective.0)
+            .field("Operations", self.total)
+            .field("Exceeding objective", self.violations)
+            .field("Fraction exceeding (%)", self.fraction() * 100.0);
+        if !self.worst.is_empty() {
+            section = section.table(["op", "latency", "completed at (UTC)"]);
+            for sample in &self.worst {
+                section = section.row(vec![
+                    sample.index.into(),
+                    sample.latency.into(),
+                    format_utc(sample.unix_ms).into(),
+                ]);
+            }
+        }
+        section
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn objective(s: &str) -> Result<Duration, String> {
+        s.parse::<LatencyObjective>().map(|objective| objective.0)
+    }
+
+    #[test]
+    fn parses_latency_objectives() {
+        assert_eq!(objective("5ms"), Ok(Duration::from_millis(5)));
+        assert_eq!(objective("1.5s"), Ok(Duration::from_millis(1_500)));
+        assert_eq!(objective("250us"), Ok(Duration::from_micros(250)));
+        assert_eq!(objective("250µs"), Ok(Duration::from_micros(250)));
+        assert_eq!(objective("100ns"), Ok(Duration::from_nanos(100)));
+    }
+
+    #[test]
+    fn rejects_missing_numbers_and_units() {
+        assert!(objective("5").is_err());
+        assert!(objective("µs").is_err());
+        assert!(objective("ms5").is_err());
+        assert!(objective("5min").is_err());
+        assert!(objective("").is_err());
+    }
+
+    #[test]
+    fn formats_utc_timestamps() {
+        assert_eq!(format_utc(0), "1970-01-01 00:00:00.000");
+        assert_eq!(format_utc(951_782_400_000), "2000-02-29 00:00:00.000");
+        assert_eq!(format_utc(1_735_689_599_999), "2024-12-31 23:59:59.999");
+    }
+}
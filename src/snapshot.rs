@@ -0,0 +1,184 @@
+//! A long-lived read transaction pinned across a burst of overwrites: checks that the
+//! snapshot keeps seeing the old values and measures the file growth caused by the pages
+//! it keeps from being reused.
+
+use crate::alternate::checksum;
+use crate::config::MIB;
+use crate::payload::ValueGen;
+use crate::report::Section;
+use crate::{
+    BenchDb, BenchmarkStats, StatsAccumulator, TABLE, clock, compat, get_file_size, hooks,
+    record_op,
+};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use redb::{Database, Error};
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use tracing::{info, info_span};
+
+pub struct SnapshotReport {
+    /// Overwrites performed while the snapshot was pinned.
+    pub pinned: BenchmarkStats,
+    /// File growth over the same number of overwrites without a reader.
+    pub baseline_growth: u64,
+    pub pinned_growth: u64,
+    /// Overwritten keys whose old value was still visible through the snapshot.
+    pub verified: usize,
+}
+
+/// Overwrite `num_writes` keys drawn from `rng`, calling `before_write` with each key and
+/// the value about to be written to it.
+fn overwrite_random(
+    db: &Database,
+    bench_db: &BenchDb,
+    phase: &str,
+    num_writes: usize,
+    values: ValueGen,
+    rng: &mut impl Rng,
+    mut before_write: impl FnMut(u64, &[u8]) -> Result<(), Error>,
+) -> Result<StatsAccumulator, Error> {
+    let mut latencies = StatsAccumulator::new();
+    for i in 0..num_writes {
+        let key = rng.random_range(0..bench_db.next_key.max(1));
+        let value = values.generate(key);
+        before_write(key, &value)?;
+
+        let start = clock::now();
+        let mut write_txn = hooks::begin_write(db)?;
+        write_txn.set_quick_repair(bench_db.quick_repair);
+        {
+            let mut table = write_txn.open_table(TABLE)?;
+            table.insert(key, value.as_slice())?;
+        }
+        write_txn.commit()?;
+        let duration = start.elapsed();
+        latencies.record(duration);
+        record_op(
+            phase,
+            &bench_db.path,
+            bench_db.quick_repair,
+            i as u64,
+            1,
+            value.len(),
+            duration,
+        );
+    }
+    Ok(latencies)
+}
+
+/// Overwrite `num_writes` random keys of `bench_db` without any reader, then again while a
+/// read transaction opened beforehand stays alive, and verify what that snapshot sees. Keys
+/// are drawn from an RNG seeded with `seed`, so both databases overwrite the same ones.
+pub fn benchmark_pinned_snapshot(
+    bench_db: &BenchDb,
+    num_writes: usize,
+    values: ValueGen,
+    seed: u64,
+) -> Result<SnapshotReport, Error> {
+    let _span =
+        info_span!("snapshot", db_path = %bench_db.path, quick_repair = bench_db.quick_repair)
+            .entered();
+    info!(num_writes, "Overwriting keys under a pinned read snapshot");
+
+    let db = bench_db.create(
+        Database::builder().set_cache_size(1024 * 1024 * 1024), // 1GB cache
+    )?;
+    let file_size = || get_file_size(&bench_db.path).unwrap_or(0);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let before = file_size();
+    overwrite_random(
+        &db,
+        bench_db,
+        "snapshot-baseline",
+        num_writes,
+        values,
+        &mut rng,
+        |_, _| Ok(()),
+    )?;
+    let baseline_growth = file_size().saturating_sub(before);
+
+    let read_txn = compat::begin_read(&db)?;
+    let snapshot = read_txn.open_table(TABLE)?;
+    let before = file_size();
+    // Checksums of each overwritten key as the snapshot saw it before the first overwrite,
+    // and of the last value written to it. Both can be equal, e.g. with `zeroes` values or
+    // a schema, whose records only depend on the key.
+    let mut written: HashMap<u64, (u64, u64)> = HashMap::new();
+    let latencies = overwrite_random(
+        &db,
+        bench_db,
+        "snapshot-pinned",
+        num_writes,
+        values,
+        &mut rng,
+        |key, value| {
+            let last = checksum(value);
+            match written.entry(key) {
+                Entry::Occupied(mut entry) => entry.get_mut().1 = last,
+                Entry::Vacant(entry) => {
+                    let old = snapshot.get(key)?.ok_or_else(|| {
+                        Error::Corrupted(format!("key {key} missing from snapshot"))
+                    })?;
+                    entry.insert((checksum(old.value()), last));
+                }
+            }
+            Ok(())
+        },
+    )?;
+    let pinned_growth = file_size().saturating_sub(before);
+
+    // Every overwritten key must still read back its pre-snapshot value through the pinned
+    // reader and its last written value through a fresh one.
+    let current_txn = compat::begin_read(&db)?;
+    let current = current_txn.open_table(TABLE)?;
+    let mut verified = 0;
+    for (&key, &(pre_snapshot, last)) in &written {
+        let old = snapshot
+            .get(key)?
+            .ok_or_else(|| Error::Corrupted(format!("key {key} missing from snapshot")))?;
+        let new = current
+            .get(key)?
+            .ok_or_else(|| Error::Corrupted(format!("key {key} missing after overwrite")))?;
+        if checksum(new.value()) != last {
+            return Err(Error::Corrupted(format!(
+                "key {key} does not hold its last written value"
+            )));
+        }
+        if checksum(old.value()) != pre_snapshot {
+            return Err(Error::Corrupted(format!(
+                "key {key} changed under the pinned snapshot"
+            )));
+        }
+        verified += 1;
+    }
+    info!(
+        verified,
+        baseline_growth_mib = baseline_growth / MIB,
+        pinned_growth_mib = pinned_growth / MIB,
+        "Verified snapshot isolation"
+    );
+
+    Ok(SnapshotReport {
+        pinned: latencies.stats(),
+        baseline_growth,
+        pinned_growth,
+        verified,
+    })
+}
+
+impl SnapshotReport {
+    pub fn section(&self, title: impl Into<String>) -> Section {
+        let mib = |bytes: u64| bytes as f64 / MIB as f64;
+        self.pinned
+            .section(title)
+            .field("Growth without reader (MiB)", mib(self.baseline_growth))
+            .field("Growth with pinned reader (MiB)", mib(self.pinned_growth))
+            .field(
+                "Extra growth (MiB)",
+                mib(self.pinned_growth) - mib(self.baseline_growth),
+            )
+            .field("Keys verified through snapshot", self.verified)
+    }
+}
@@ -0,0 +1,728 @@
+//! Summary statistics over per-operation latencies.
+//!
+//! Latencies are folded into a [`StatsAccumulator`] as they are measured, which keeps the
+//! count, mean and M2 (Welford's online variance), the extremes, a log-linear histogram and
+//! the slowest operations in memory that does not grow with the number of operations. The
+//! order statistics (trimmed and winsorized means, quartiles, percentiles) are read off the
+//! histogram, whose buckets are below 1/128 of their value wide. Tests needing the raw
+//! latencies, like [`MannWhitney`], run on a bounded [`Reservoir`] sample of them.
+
+use crate::backend::IoTimes;
+use crate::report::Section;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Fraction of operations dropped (trimmed) or clamped (winsorized) at each end.
+pub const TRIM: f64 = 0.05;
+/// Number of slowest operations kept for reporting.
+pub const TOP_K: usize = 5;
+/// Latencies kept by a [`Reservoir`].
+pub const RESERVOIR_SIZE: usize = 10_000;
+
+/// Histogram sub-buckets per power of two, as a shift; values below twice that are exact.
+const SUB_BUCKET_BITS: u32 = 7;
+const SUB_BUCKETS: u64 = 1 << SUB_BUCKET_BITS;
+
+/// Histogram bucket of a latency of `ns` nanoseconds.
+fn bucket(ns: u64) -> u64 {
+    if ns < 2 * SUB_BUCKETS {
+        return ns;
+    }
+    let shift = 63 - ns.leading_zeros() - SUB_BUCKET_BITS;
+    u64::from(shift) * SUB_BUCKETS + (ns >> shift)
+}
+
+/// Midpoint in nanoseconds of the values falling in `bucket`.
+fn bucket_value(bucket: u64) -> u64 {
+    if bucket < 2 * SUB_BUCKETS {
+        return bucket;
+    }
+    let shift = bucket / SUB_BUCKETS - 1;
+    let low = (bucket - shift * SUB_BUCKETS) << shift;
+    low + (1 << shift) / 2
+}
+
+/// Online accumulator of per-operation latencies, updated with [`StatsAccumulator::record`]
+/// as each operation completes.
+#[derive(Clone, Debug, Default)]
+pub struct StatsAccumulator {
+    count: u64,
+    total: Duration,
+    /// Running mean and sum of squared deviations, in nanoseconds.
+    mean: f64,
+    m2: f64,
+    min: Duration,
+    max: Duration,
+    /// Operations per [`bucket`].
+    histogram: BTreeMap<u64, u64>,
+    /// Up to [`TOP_K`] slowest operations as `(op index, latency)`, in no particular order.
+    slowest: Vec<(usize, Duration)>,
+}
+
+impl StatsAccumulator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fold in the latency of the next operation, numbering operations in recording order.
+    pub fn record(&mut self, latency: Duration) {
+        self.record_at(self.count as usize, latency);
+    }
+
+    /// Fold in the latency of operation `index` of the phase, for when not every operation
+    /// is recorded, as under `--sample-every`.
+    pub fn record_at(&mut self, index: usize, latency: Duration) {
+        let first = self.count == 0;
+        self.count += 1;
+        self.total += latency;
+        let ns = latency.as_nanos() as f64;
+        let delta = ns - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (ns - self.mean);
+        if first {
+            (self.min, self.max) = (latency, latency);
+        } else {
+            self.min = self.min.min(latency);
+            self.max = self.max.max(latency);
+        }
+        *self
+            .histogram
+            .entry(bucket(latency.as_nanos() as u64))
+            .or_default() += 1;
+
+        if self.slowest.len() < TOP_K {
+            self.slowest.push((index, latency));
+        } else if let Some(fastest) = self.slowest.iter_mut().min_by_key(|(_, d)| *d)
+            && latency > fastest.1
+        {
+            *fastest = (index, latency);
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.count as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// `(latency, operations)` per non-empty histogram bucket, fastest first, with the
+    /// latency clamped to the extremes seen.
+    fn buckets(&self) -> impl Iterator<Item = (Duration, u64)> + '_ {
+        self.histogram.iter().map(|(&bucket, &n)| {
+            let value = Duration::from_nanos(bucket_value(bucket));
+            (value.clamp(self.min, self.max), n)
+        })
+    }
+
+    /// Latency of the operation of `rank` (0-based) in ascending order.
+    fn at_rank(&self, rank: u64) -> Duration {
+        let mut seen = 0;
+        for (value, n) in self.buckets() {
+            seen += n;
+            if rank < seen {
+                return value;
+            }
+        }
+        self.max
+    }
+
+    /// Nearest-rank `p`th percentile of the latencies recorded so far, or `None` if there
+    /// are none.
+    pub fn percentile(&self, p: f64) -> Option<Duration> {
+        if self.is_empty() {
+            return None;
+        }
+        let rank = ((self.count as f64 * p / 100.0).ceil() as u64).clamp(1, self.count);
+        Some(self.at_rank(rank - 1))
+    }
+
+    /// Sum of the latencies of the operations ranked `low..high` in ascending order.
+    fn sum_ranks(&self, low: u64, high: u64) -> Duration {
+        let (mut seen, mut sum) = (0, Duration::ZERO);
+        for (value, n) in self.buckets() {
+            let overlap = (seen + n).min(high).saturating_sub(seen.max(low));
+            sum += value * overlap as u32;
+            seen += n;
+        }
+        sum
+    }
+
+    /// Summary of the operations recorded so far.
+    ///
+    /// # Panics
+    ///
+    /// If no operation was recorded.
+    pub fn stats(&self) -> BenchmarkStats {
+        assert!(!self.is_empty(), "no operations recorded");
+        let count = self.count;
+        let avg_write_time = self.total / count as u32;
+        let writes_per_second = count as f64 / self.total.as_secs_f64();
+        let std_dev = if count > 1 {
+            Duration::from_nanos((self.m2 / (count - 1) as f64).sqrt() as u64)
+        } else {
+            Duration::ZERO
+        };
+
+        let cut = (count as f64 * TRIM) as u64;
+        let kept = self.sum_ranks(cut, count - cut);
+        let trimmed_mean = kept / (count - 2 * cut) as u32;
+        let winsorized_mean =
+            (kept + self.at_rank(cut) * cut as u32 + self.at_rank(count - cut - 1) * cut as u32)
+                / count as u32;
+
+        let quartile = |q: f64| self.at_rank(((count - 1) as f64 * q).round() as u64);
+        let (q1, q3) = (quartile(0.25), quartile(0.75));
+        let fence = q3 + (q3 - q1) * 3;
+        let outliers = self
+            .buckets()
+            .filter(|&(value, _)| value > fence)
+            .map(|(_, n)| n as usize)
+            .sum();
+
+        let mut slowest = self.slowest.clone();
+        slowest.sort_by_key(|&(index, d)| (Reverse(d), index));
+
+        BenchmarkStats {
+            count: count as usize,
+            total_duration: self.total,
+            avg_write_time,
+            min_write_time: self.min,
+            max_write_time: self.max,
+            std_dev,
+            writes_per_second,
+            trimmed_mean,
+            winsorized_mean,
+            outliers,
+            slowest,
+            syncs_per_commit: None,
+            sync_time_per_commit: None,
+            volume: None,
+        }
+    }
+}
+
+impl Extend<Duration> for StatsAccumulator {
+    fn extend<I: IntoIterator<Item = Duration>>(&mut self, latencies: I) {
+        for latency in latencies {
+            self.record(latency);
+        }
+    }
+}
+
+impl FromIterator<Duration> for StatsAccumulator {
+    fn from_iter<I: IntoIterator<Item = Duration>>(latencies: I) -> Self {
+        let mut accumulator = Self::new();
+        accumulator.extend(latencies);
+        accumulator
+    }
+}
+
+/// Uniform random sample of up to [`RESERVOIR_SIZE`] of the latencies recorded (Vitter's
+/// algorithm R), every latency being equally likely to be kept however many there are.
+#[derive(Clone, Debug)]
+pub struct Reservoir {
+    seen: u64,
+    samples: Vec<Duration>,
+    rng: StdRng,
+}
+
+impl Reservoir {
+    /// An empty reservoir, replacing samples as drawn from `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seen: 0,
+            samples: Vec::new(),
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn record(&mut self, latency: Duration) {
+        self.seen += 1;
+        if self.samples.len() < RESERVOIR_SIZE {
+            self.samples.push(latency);
+        } else {
+            let slot = self.rng.random_range(0..self.seen);
+            if let Some(sample) = self.samples.get_mut(slot as usize) {
+                *sample = latency;
+            }
+        }
+    }
+
+    /// The latencies kept, in no particular order.
+    pub fn samples(&self) -> &[Duration] {
+        &self.samples
+    }
+}
+
+/// Mean keys and value bytes written per operation, to normalize latencies across value
+/// and batch sizes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Volume {
+    pub keys: f64,
+    pub bytes: f64,
+}
+
+impl Volume {
+    /// `keys` and `bytes` written over `ops` operations, or `None` without operations.
+    pub fn new(keys: u64, bytes: u64, ops: usize) -> Option<Self> {
+        (ops > 0).then(|| Self {
+            keys: keys as f64 / ops as f64,
+            bytes: bytes as f64 / ops as f64,
+        })
+    }
+
+    /// Microseconds per key written, for an operation taking `latency`.
+    pub fn us_per_key(&self, latency: Duration) -> f64 {
+        latency.as_secs_f64() * 1e6 / self.keys.max(f64::EPSILON)
+    }
+
+    /// Microseconds per KiB of values written, for an operation taking `latency`.
+    pub fn us_per_kib(&self, latency: Duration) -> f64 {
+        latency.as_secs_f64() * 1e6 / (self.bytes / 1024.0).max(f64::EPSILON)
+    }
+}
+
+pub struct BenchmarkStats {
+    pub count: usize,
+    pub total_duration: Duration,
+    pub avg_write_time: Duration,
+    pub min_write_time: Duration,
+    pub max_write_time: Duration,
+    /// Sample standard deviation of the latencies.
+    pub std_dev: Duration,
+    pub writes_per_second: f64,
+    /// Mean of the operations left after dropping [`TRIM`] of them at each end.
+    pub trimmed_mean: Duration,
+    /// Mean after clamping the [`TRIM`] fastest and slowest operations to the nearest kept value.
+    pub winsorized_mean: Duration,
+    /// Operations beyond Tukey's far-out fence, `Q3 + 3 × IQR`.
+    pub outliers: usize,
+    /// Up to [`TOP_K`] slowest operations as `(op index, latency)`, slowest first.
+    pub slowest: Vec<(usize, Duration)>,
+    /// Mean storage backend sync calls per commit, when the phase counted them.
+    pub syncs_per_commit: Option<f64>,
+    /// Mean time spent in those sync calls per commit.
+    pub sync_time_per_commit: Option<Duration>,
+    /// What each operation wrote, when the phase counted it.
+    pub volume: Option<Volume>,
+}
+
+impl BenchmarkStats {
+    /// Summary of latencies already collected, through a [`StatsAccumulator`].
+    pub fn new(durations: &[Duration]) -> Self {
+        durations
+            .iter()
+            .copied()
+            .collect::<StatsAccumulator>()
+            .stats()
+    }
+
+    /// Attach the sync calls counted over `commits` of the operations.
+    pub fn with_syncs(mut self, io: &IoTimes, commits: usize) -> Self {
+        if commits > 0 {
+            self.syncs_per_commit = Some(io.syncs as f64 / commits as f64);
+            self.sync_time_per_commit = Some(io.sync / commits as u32);
+        }
+        self
+    }
+
+    /// Attach the keys and value bytes written over `ops` of the operations.
+    pub fn with_volume(mut self, keys: u64, bytes: u64, ops: usize) -> Self {
+        self.volume = Volume::new(keys, bytes, ops).or(self.volume);
+        self
+    }
+
+    pub fn section(&self, title: impl Into<String>) -> Section {
+        let mut section = Section::new(title)
+            .field("Total duration", self.total_duration)
+            .field("Average write time", self.avg_write_time)
+            .field("Min write time", self.min_write_time)
+            .field("Max write time", self.max_write_time)
+            .field("Std deviation", self.std_dev)
+            .field("Writes per second", self.writes_per_second)
+            .field(
+                format!("Trimmed mean ({:.0}%)", TRIM * 100.0),
+                self.trimmed_mean,
+            )
+            .field(
+                format!("Winsorized mean ({:.0}%)", TRIM * 100.0),
+                self.winsorized_mean,
+            )
+            .field("Outliers (> Q3 + 3×IQR)", self.outliers);
+        if let (Some(syncs), Some(time)) = (self.syncs_per_commit, self.sync_time_per_commit) {
+            section = section
+                .field("Syncs per commit", syncs)
+                .field("Sync time per commit", time);
+        }
+        if let Some(volume) = self.volume {
+            section = section
+                .field("Keys per op", volume.keys)
+                .field("Value bytes per op", volume.bytes)
+                .field(
+                    "Latency per key (μs)",
+                    volume.us_per_key(self.avg_write_time),
+                )
+                .field(
+                    "Latency per KiB (μs)",
+                    volume.us_per_kib(self.avg_write_time),
+                );
+        }
+        section
+    }
+
+    /// [`BenchmarkStats::section`] with a table of the slowest operations, so a single stall
+    /// can be told apart from a general slowdown.
+    pub fn detailed_section(&self, title: impl Into<String>) -> Section {
+        let mut section = self
+            .section(title)
+            .table(["op", "latency", "× trimmed mean"]);
+        for &(index, latency) in &self.slowest {
+            section = section.row(vec![
+                index.into(),
+                latency.into(),
+                (latency.as_secs_f64() / self.trimmed_mean.as_secs_f64().max(f64::EPSILON)).into(),
+            ]);
+        }
+        section
+    }
+}
+
+/// Least-squares fit `y = slope * x + intercept`, with Pearson's correlation coefficient.
+#[derive(Clone, Copy, Debug)]
+pub struct LinearFit {
+    pub slope: f64,
+    pub intercept: f64,
+    pub r: f64,
+}
+
+impl LinearFit {
+    /// Returns `None` with fewer than two points or when `x` is constant.
+    pub fn new(points: &[(f64, f64)]) -> Option<Self> {
+        let n = points.len() as f64;
+        if points.len() < 2 {
+            return None;
+        }
+
+        let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+        let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+        let (mut sxx, mut syy, mut sxy) = (0.0, 0.0, 0.0);
+        for (x, y) in points {
+            sxx += (x - mean_x) * (x - mean_x);
+            syy += (y - mean_y) * (y - mean_y);
+            sxy += (x - mean_x) * (y - mean_y);
+        }
+        if sxx == 0.0 {
+            return None;
+        }
+
+        let slope = sxy / sxx;
+        let r = if syy == 0.0 {
+            0.0
+        } else {
+            sxy / (sxx * syy).sqrt()
+        };
+        Some(Self {
+            slope,
+            intercept: mean_y - slope * mean_x,
+            r,
+        })
+    }
+}
+
+/// Two-sided Mann-Whitney U test of whether one latency sample tends to be larger than the
+/// other, using the tie-corrected normal approximation (fine for the sample sizes used here).
+#[derive(Clone, Copy, Debug)]
+pub struct MannWhitney {
+    /// U statistic of the first sample.
+    pub u: f64,
+    pub p_value: f64,
+    /// Rank-biserial correlation in [-1, 1]: the probability that a value from the second
+    /// sample exceeds one from the first, minus the reverse. 0 means no difference.
+    pub effect_size: f64,
+}
+
+impl MannWhitney {
+    /// Returns `None` if either sample is empty.
+    pub fn new(a: &[Duration], b: &[Duration]) -> Option<Self> {
+        if a.is_empty() || b.is_empty() {
+            return None;
+        }
+        let (n1, n2) = (a.len() as f64, b.len() as f64);
+        let n = n1 + n2;
+
+        let mut all: Vec<(Duration, bool)> = a
+            .iter()
+            .map(|&d| (d, true))
+            .chain(b.iter().map(|&d| (d, false)))
+            .collect();
+        all.sort_unstable_by_key(|&(d, _)| d);
+
+        // Average ranks over ties, accumulating the tie correction as we go.
+        let (mut rank_sum_a, mut ties) = (0.0, 0.0);
+        let mut i = 0;
+        while i < all.len() {
+            let j = i + all[i..].iter().take_while(|(d, _)| *d == all[i].0).count();
+            let rank = (i + j + 1) as f64 / 2.0;
+            rank_sum_a += rank * all[i..j].iter().filter(|(_, in_a)| *in_a).count() as f64;
+            let t = (j - i) as f64;
+            ties += t * t * t - t;
+            i = j;
+        }
+
+        let u = rank_sum_a - n1 * (n1 + 1.0) / 2.0;
+        let mean = n1 * n2 / 2.0;
+        let variance = n1 * n2 / 12.0 * ((n + 1.0) - ties / (n * (n - 1.0)).max(1.0));
+        let p_value = if variance > 0.0 {
+            // Continuity-corrected z; p = 2 * P(Z > |z|) = erfc(|z| / sqrt 2).
+            let z = ((u - mean).abs() - 0.5).max(0.0) / variance.sqrt();
+            erfc(z / std::f64::consts::SQRT_2).min(1.0)
+        } else {
+            1.0
+        };
+
+        Some(Self {
+            u,
+            p_value,
+            effect_size: 1.0 - 2.0 * u / (n1 * n2),
+        })
+    }
+}
+
+/// Complementary error function (Numerical Recipes `erfcc`, relative error below 1.2e-7).
+fn erfc(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.5 * x.abs());
+    let poly = -x * x - 1.265_512_23
+        + t * (1.000_023_68
+            + t * (0.374_091_96
+                + t * (0.096_784_18
+                    + t * (-0.186_288_06
+                        + t * (0.278_868_07
+                            + t * (-1.135_203_98
+                                + t * (1.488_515_87 + t * (-0.822_152_23 + t * 0.170_872_77))))))));
+    let r = t * poly.exp();
+    if x >= 0.0 { r } else { 2.0 - r }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{actual} is not {expected}"
+        );
+    }
+
+    #[test]
+    fn linear_fit_recovers_an_exact_line() {
+        let fit = LinearFit::new(&[(0.0, 1.0), (1.0, 3.0), (2.0, 5.0), (4.0, 9.0)]).unwrap();
+        assert_close(fit.slope, 2.0);
+        assert_close(fit.intercept, 1.0);
+        assert_close(fit.r, 1.0);
+
+        let falling = LinearFit::new(&[(1.0, 4.0), (2.0, 2.0), (3.0, 0.0)]).unwrap();
+        assert_close(falling.slope, -2.0);
+        assert_close(falling.r, -1.0);
+    }
+
+    #[test]
+    fn linear_fit_of_a_flat_line_has_no_correlation() {
+        let fit = LinearFit::new(&[(1.0, 7.0), (2.0, 7.0), (3.0, 7.0)]).unwrap();
+        assert_close(fit.slope, 0.0);
+        assert_close(fit.intercept, 7.0);
+        assert_close(fit.r, 0.0);
+    }
+
+    #[test]
+    fn linear_fit_needs_two_distinct_x() {
+        assert!(LinearFit::new(&[]).is_none());
+        assert!(LinearFit::new(&[(1.0, 2.0)]).is_none());
+        assert!(LinearFit::new(&[(3.0, 1.0), (3.0, 5.0)]).is_none());
+    }
+
+    fn micros(values: impl IntoIterator<Item = u64>) -> StatsAccumulator {
+        values.into_iter().map(Duration::from_micros).collect()
+    }
+
+    /// Whether `actual` is within the histogram's resolution of `expected`.
+    fn assert_near(actual: Duration, expected: Duration) {
+        let error = actual.abs_diff(expected).as_nanos() as f64;
+        assert!(
+            error <= expected.as_nanos() as f64 / SUB_BUCKETS as f64,
+            "{actual:?} is not {expected:?}"
+        );
+    }
+
+    #[test]
+    fn histogram_buckets_are_exact_then_relative() {
+        for ns in 0..2 * SUB_BUCKETS {
+            assert_eq!(bucket_value(bucket(ns)), ns);
+        }
+        for ns in [300, 1_000, 65_537, 1_234_567, 987_654_321, u64::MAX / 4] {
+            let value = bucket_value(bucket(ns));
+            assert!(ns.abs_diff(value) <= ns / SUB_BUCKETS, "{ns} -> {value}");
+        }
+    }
+
+    #[test]
+    fn empty_accumulator_has_no_stats() {
+        let accumulator = StatsAccumulator::new();
+        assert!(accumulator.is_empty());
+        assert_eq!(accumulator.count(), 0);
+        assert_eq!(accumulator.percentile(50.0), None);
+        assert_eq!(accumulator.percentile(99.0), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "no operations recorded")]
+    fn empty_accumulator_stats_panic() {
+        StatsAccumulator::new().stats();
+    }
+
+    #[test]
+    fn single_latency_is_every_statistic() {
+        let stats = micros([250]).stats();
+        let latency = Duration::from_micros(250);
+        assert_eq!(stats.count, 1);
+        assert_eq!(
+            (
+                stats.min_write_time,
+                stats.avg_write_time,
+                stats.max_write_time
+            ),
+            (latency, latency, latency)
+        );
+        assert_eq!(stats.std_dev, Duration::ZERO);
+        assert_eq!(stats.trimmed_mean, latency);
+        assert_eq!(stats.winsorized_mean, latency);
+        assert_eq!(stats.outliers, 0);
+        assert_eq!(stats.slowest, [(0, latency)]);
+    }
+
+    #[test]
+    fn accumulator_summarizes_a_uniform_run() {
+        let accumulator = micros(1..=100);
+        let stats = accumulator.stats();
+        assert_eq!(stats.count, 100);
+        assert_eq!(stats.total_duration, Duration::from_micros(5050));
+        assert_eq!(stats.avg_write_time, Duration::from_nanos(50_500));
+        assert_eq!(stats.min_write_time, Duration::from_micros(1));
+        assert_eq!(stats.max_write_time, Duration::from_micros(100));
+        // Sample variance of 1..=100 is 841.67.
+        assert_near(stats.std_dev, Duration::from_nanos(29_011));
+        assert_close(stats.writes_per_second, 100.0 / 0.00505);
+        // The 5 fastest and slowest are dropped, or clamped to 6 and 95, around a mean of 50.5.
+        assert_near(stats.trimmed_mean, Duration::from_nanos(50_500));
+        assert_near(stats.winsorized_mean, Duration::from_nanos(50_500));
+        assert_eq!(stats.outliers, 0);
+
+        assert_near(
+            accumulator.percentile(50.0).unwrap(),
+            Duration::from_micros(50),
+        );
+        assert_near(
+            accumulator.percentile(99.0).unwrap(),
+            Duration::from_micros(99),
+        );
+        assert_near(
+            accumulator.percentile(100.0).unwrap(),
+            Duration::from_micros(100),
+        );
+        assert_near(
+            accumulator.percentile(0.0).unwrap(),
+            Duration::from_micros(1),
+        );
+    }
+
+    #[test]
+    fn accumulator_counts_far_outliers() {
+        let mut accumulator: StatsAccumulator =
+            [Duration::from_millis(1); 99].into_iter().collect();
+        accumulator.record(Duration::from_secs(1));
+        let stats = accumulator.stats();
+        assert_eq!(stats.outliers, 1);
+        assert_near(stats.trimmed_mean, Duration::from_millis(1));
+        assert_eq!(stats.max_write_time, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn accumulator_keeps_the_slowest_ops_with_their_index() {
+        let mut accumulator = StatsAccumulator::new();
+        for (i, us) in [5, 90, 3, 70, 80, 1, 60, 100, 2].into_iter().enumerate() {
+            accumulator.record_at(i * 10, Duration::from_micros(us));
+        }
+        let slowest: Vec<_> = accumulator
+            .stats()
+            .slowest
+            .into_iter()
+            .map(|(index, latency)| (index, latency.as_micros()))
+            .collect();
+        assert_eq!(slowest, [(70, 100), (10, 90), (40, 80), (30, 70), (60, 60)]);
+    }
+
+    #[test]
+    fn reservoir_keeps_a_bounded_uniform_sample() {
+        let mut small = Reservoir::new(1);
+        for us in 0..100 {
+            small.record(Duration::from_micros(us));
+        }
+        assert_eq!(small.samples().len(), 100);
+
+        let (mut first, mut second) = (Reservoir::new(7), Reservoir::new(7));
+        for ns in 0..10 * RESERVOIR_SIZE as u64 {
+            first.record(Duration::from_nanos(ns));
+            second.record(Duration::from_nanos(ns));
+        }
+        assert_eq!(first.samples().len(), RESERVOIR_SIZE);
+        assert_eq!(first.samples(), second.samples());
+        // Uniform over 0..100000 ns: the sample mean is 50000 with a standard error of 289.
+        let mean = first.samples().iter().sum::<Duration>() / RESERVOIR_SIZE as u32;
+        assert!(mean.abs_diff(Duration::from_nanos(50_000)) < Duration::from_nanos(1_500));
+    }
+
+    fn millis(values: impl IntoIterator<Item = u64>) -> Vec<Duration> {
+        values.into_iter().map(Duration::from_millis).collect()
+    }
+
+    #[test]
+    fn mann_whitney_detects_separated_samples() {
+        let (fast, slow) = (millis(1..=10), millis(11..=20));
+        let test = MannWhitney::new(&fast, &slow).unwrap();
+        assert_close(test.u, 0.0);
+        assert_close(test.effect_size, 1.0);
+        // z = (50 - 0.5) / sqrt(100 * 21 / 12) = 3.742, two-sided p = 1.8e-4.
+        assert!((test.p_value - 1.82e-4).abs() < 1e-5, "{}", test.p_value);
+
+        let reversed = MannWhitney::new(&slow, &fast).unwrap();
+        assert_close(reversed.u, 100.0);
+        assert_close(reversed.effect_size, -1.0);
+        assert_close(reversed.p_value, test.p_value);
+    }
+
+    #[test]
+    fn mann_whitney_finds_no_difference_between_equal_samples() {
+        let sample = millis([3, 1, 4, 1, 5, 9, 2, 6]);
+        let test = MannWhitney::new(&sample, &sample).unwrap();
+        assert_close(test.u, 32.0);
+        assert_close(test.effect_size, 0.0);
+        assert_close(test.p_value, 1.0);
+
+        let constant = millis([7; 5]);
+        let test = MannWhitney::new(&constant, &constant).unwrap();
+        assert_close(test.effect_size, 0.0);
+        assert_close(test.p_value, 1.0);
+    }
+
+    #[test]
+    fn mann_whitney_needs_both_samples() {
+        let sample = millis([1, 2, 3]);
+        assert!(MannWhitney::new(&[], &sample).is_none());
+        assert!(MannWhitney::new(&sample, &[]).is_none());
+    }
+}
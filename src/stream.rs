@@ -0,0 +1,83 @@
+//! Optional JSON Lines stream with one object per completed operation (or fill batch),
+//! flushed as it is written so nothing is lost if the process dies mid-run.
+//!
+//! Like [`crate::metrics`], recording is a no-op until [`open`] has been called.
+
+use crate::slo::unix_ms;
+use serde_json::json;
+use std::fs::File;
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+static STREAM: OnceLock<Mutex<Box<dyn Write + Send>>> = OnceLock::new();
+static ON_STDOUT: AtomicBool = AtomicBool::new(false);
+
+/// Start streaming to `path`, or to stdout if `path` is `-`.
+pub fn open(path: &str) -> io::Result<()> {
+    let on_stdout = path == "-";
+    let writer: Box<dyn Write + Send> = if on_stdout {
+        Box::new(io::stdout())
+    } else {
+        Box::new(File::create(path)?)
+    };
+    STREAM
+        .set(Mutex::new(writer))
+        .map_err(|_| io::Error::other("JSON Lines stream already open"))?;
+    ON_STDOUT.store(on_stdout, Ordering::Relaxed);
+    Ok(())
+}
+
+pub fn is_open() -> bool {
+    STREAM.get().is_some()
+}
+
+/// Where human-oriented output (the banner, the console report) goes, with whether it is a
+/// terminal: stdout, or stderr while the stream is written to stdout so that the stream
+/// stays parseable.
+pub fn human_output() -> (Box<dyn Write>, bool) {
+    if ON_STDOUT.load(Ordering::Relaxed) {
+        let stderr = io::stderr();
+        let is_terminal = stderr.is_terminal();
+        (Box::new(stderr), is_terminal)
+    } else {
+        let stdout = io::stdout();
+        let is_terminal = stdout.is_terminal();
+        (Box::new(stdout), is_terminal)
+    }
+}
+
+/// Write one record; errors are ignored so that a full pipe never aborts a run.
+pub fn emit(record: serde_json::Value) {
+    if let Some(stream) = STREAM.get() {
+        let mut writer = stream.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = writeln!(writer, "{record}").and_then(|()| writer.flush());
+    }
+}
+
+/// Record one committed transaction of `entries` inserts or deletes.
+pub fn record_op(
+    phase: &str,
+    db_path: &str,
+    quick_repair: bool,
+    op: u64,
+    entries: usize,
+    bytes: usize,
+    latency: Duration,
+) {
+    if !is_open() {
+        return;
+    }
+    emit(json!({
+        "type": "op",
+        "ts_ms": unix_ms(),
+        "phase": phase,
+        "db_path": db_path,
+        "quick_repair": quick_repair,
+        "op": op,
+        "entries": entries,
+        "bytes": bytes,
+        "latency_ns": latency.as_nanos() as u64,
+    }));
+}
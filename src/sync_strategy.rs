@@ -0,0 +1,106 @@
+//! The timed single writes repeated through [`SyncBackend`] with each [`SyncStrategy`],
+//! next to redb's own file backend, to see which OS-level sync mechanism suits each
+//! quick_repair setting best.
+
+use crate::backend::{SyncBackend, SyncStrategy};
+use crate::payload::ValueGen;
+use crate::report::Section;
+use crate::{BenchDb, BenchmarkStats, StatsAccumulator, record_op, write_single};
+use redb::{Database, Error};
+use std::path::Path;
+use tracing::{info, info_span};
+
+pub struct SyncStrategyReport {
+    /// redb's own file backend, as the reference.
+    pub default: BenchmarkStats,
+    pub strategies: Vec<(SyncStrategy, BenchmarkStats)>,
+}
+
+fn time_writes(
+    db: &Database,
+    bench_db: &mut BenchDb,
+    phase: &str,
+    num_writes: usize,
+    values: ValueGen,
+) -> Result<BenchmarkStats, Error> {
+    let mut stats = StatsAccumulator::new();
+    for i in 0..num_writes {
+        let value = values.generate(bench_db.next_key);
+        let duration = write_single(db, bench_db.next_key, &value, bench_db.quick_repair)?;
+        bench_db.next_key += 1;
+        stats.record(duration);
+        record_op(
+            phase,
+            &bench_db.path,
+            bench_db.quick_repair,
+            i as u64,
+            1,
+            value.len(),
+            duration,
+        );
+    }
+    Ok(stats.stats())
+}
+
+/// Time `num_writes` single-insert transactions on `bench_db` with redb's file backend and
+/// then with a [`SyncBackend`] per strategy.
+pub fn benchmark_sync_strategies(
+    bench_db: &mut BenchDb,
+    num_writes: usize,
+    values: ValueGen,
+) -> Result<SyncStrategyReport, Error> {
+    let _span =
+        info_span!("sync_strategy", db_path = %bench_db.path, quick_repair = bench_db.quick_repair)
+            .entered();
+    info!(num_writes, "Comparing sync strategies");
+
+    let db = Database::builder()
+        .set_cache_size(1024 * 1024 * 1024) // 1GB cache
+        .create(&bench_db.path)?;
+    let default = time_writes(&db, bench_db, "sync-default", num_writes, values)?;
+    drop(db);
+
+    let mut strategies = Vec::with_capacity(SyncStrategy::ALL.len());
+    for strategy in SyncStrategy::ALL {
+        let backend = SyncBackend::open(Path::new(&bench_db.path), strategy)?;
+        let db = Database::builder()
+            .set_cache_size(1024 * 1024 * 1024) // 1GB cache
+            .create_with_backend(backend)?;
+        let phase = format!("sync-{strategy}");
+        let stats = time_writes(&db, bench_db, &phase, num_writes, values)?;
+        info!(%strategy, avg = ?stats.avg_write_time, "Sync strategy done");
+        strategies.push((strategy, stats));
+    }
+
+    Ok(SyncStrategyReport {
+        default,
+        strategies,
+    })
+}
+
+impl SyncStrategyReport {
+    pub fn section(&self, title: impl Into<String>) -> Section {
+        let row = |name: String, stats: &BenchmarkStats| {
+            vec![
+                name.into(),
+                stats.avg_write_time.into(),
+                stats.trimmed_mean.into(),
+                stats.max_write_time.into(),
+                stats.writes_per_second.into(),
+            ]
+        };
+        let mut section = Section::new(title)
+            .table([
+                "backend",
+                "avg write",
+                "trimmed mean",
+                "max write",
+                "writes/s",
+            ])
+            .row(row("redb file backend".to_owned(), &self.default));
+        for (strategy, stats) in &self.strategies {
+            section = section.row(row(strategy.to_string(), stats));
+        }
+        section
+    }
+}
@@ -0,0 +1,215 @@
+//! Simulated crashes mid-commit with a torn last write, checking that reopening with the
+//! repair callback recovers exactly the last committed state, and how long repair takes.
+//!
+//! Each trial runs on a copy of the benchmark database, since a crash may leave the file
+//! unopenable and later phases still need it.
+
+use crate::alternate::checksum;
+use crate::backend::{FaultBackend, TearMode};
+use crate::payload::ValueGen;
+use crate::repair::RepairCallback;
+use crate::report::Section;
+use crate::{BenchDb, TABLE, clock, compat, hooks};
+use rand::Rng;
+use redb::{Database, Error};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use tracing::{info, info_span, warn};
+
+/// Commits made, and recorded as the expected state, before arming the crash.
+const COMMITTED: u64 = 10;
+/// Attempts to reach the armed write before giving up on a trial.
+const MAX_ATTEMPTS: usize = 5;
+
+pub struct TornWriteOutcome {
+    pub mode: TearMode,
+    /// Write of the crashing commit that was torn (1-based).
+    pub torn_write: usize,
+    /// Whether redb reported a repair on reopen.
+    pub repaired: bool,
+    /// Invocations of the repair callback.
+    pub callback_calls: u64,
+    /// Time to reopen, including any repair.
+    pub reopen: Duration,
+    /// Committed keys missing or holding the wrong value after reopening.
+    pub lost: usize,
+    /// Keys of the crashed commit visible after reopening.
+    pub phantom: usize,
+    /// Set when the database could not be reopened at all.
+    pub open_error: Option<String>,
+}
+
+pub struct TornWriteReport {
+    pub outcomes: Vec<TornWriteOutcome>,
+}
+
+fn insert(db: &Database, key: u64, value: &[u8], quick_repair: bool) -> Result<(), Error> {
+    let mut write_txn = hooks::begin_write(db)?;
+    write_txn.set_quick_repair(quick_repair);
+    {
+        let mut table = write_txn.open_table(TABLE)?;
+        table.insert(key, value)?;
+    }
+    write_txn.commit()?;
+    Ok(())
+}
+
+/// On a copy of `bench_db` per [`TearMode`], commit a few values, crash on a random write
+/// of the next commit, then reopen with `callback` installed and verify.
+pub fn benchmark_torn_writes(
+    bench_db: &BenchDb,
+    values: ValueGen,
+    callback: RepairCallback,
+) -> Result<TornWriteReport, Error> {
+    let quick_repair = bench_db.quick_repair;
+    let _span = info_span!("torn_write", db_path = %bench_db.path, quick_repair).entered();
+
+    let copy = format!("{}.torn", bench_db.path);
+    let mut outcomes = Vec::new();
+    for mode in [TearMode::Truncate, TearMode::Corrupt] {
+        fs::copy(&bench_db.path, &copy)?;
+        let outcome = trial(&copy, bench_db, mode, values, callback);
+        let _ = fs::remove_file(&copy);
+        outcomes.push(outcome?);
+    }
+    Ok(TornWriteReport { outcomes })
+}
+
+fn trial(
+    path: &str,
+    bench_db: &BenchDb,
+    mode: TearMode,
+    values: ValueGen,
+    callback: RepairCallback,
+) -> Result<TornWriteOutcome, Error> {
+    let quick_repair = bench_db.quick_repair;
+    let mut next_key = bench_db.next_key;
+    let mut expected = HashMap::new();
+    let mut crashed_keys = Vec::new();
+    let torn_write;
+    {
+        let (backend, handle) = FaultBackend::open(Path::new(path))?;
+        let db = Database::builder()
+            .set_cache_size(1024 * 1024 * 1024) // 1GB cache
+            .create_with_backend(backend)?;
+
+        let mut writes_per_commit = 1;
+        for _ in 0..COMMITTED {
+            let value = values.generate(next_key);
+            let before = handle.writes();
+            insert(&db, next_key, &value, quick_repair)?;
+            writes_per_commit = handle.writes() - before;
+            expected.insert(next_key, checksum(&value));
+            next_key += 1;
+        }
+
+        torn_write = rand::rng().random_range(1..=writes_per_commit.max(1));
+        handle.arm(torn_write, mode);
+        info!(%mode, torn_write, writes_per_commit, "Armed simulated crash");
+        for _ in 0..MAX_ATTEMPTS {
+            let value = values.generate(next_key);
+            crashed_keys.push(next_key);
+            let result = insert(&db, next_key, &value, quick_repair);
+            next_key += 1;
+            match result {
+                Err(_) if handle.crashed() => break,
+                Err(e) => return Err(e),
+                Ok(()) => {
+                    // The commit needed fewer writes than armed for; it is durable.
+                    expected.insert(crashed_keys.pop().unwrap(), checksum(&value));
+                }
+            }
+        }
+        if !handle.crashed() {
+            warn!("Armed write was never reached, trial is inconclusive");
+        }
+        // Dropping the database cannot shut it down cleanly: every write now fails.
+    }
+
+    let mut builder = Database::builder();
+    builder.set_cache_size(1024 * 1024 * 1024); // 1GB cache
+    let probe = callback.install(&mut builder);
+    let start = clock::now();
+    let opened = builder.create(path);
+    let reopen = start.elapsed();
+    let db = match opened {
+        Ok(db) => db,
+        Err(e) => {
+            warn!(error = %e, "Could not reopen after simulated crash");
+            return Ok(TornWriteOutcome {
+                mode,
+                torn_write,
+                repaired: probe.repaired(),
+                callback_calls: probe.calls(),
+                reopen,
+                lost: expected.len(),
+                phantom: 0,
+                open_error: Some(e.to_string()),
+            });
+        }
+    };
+
+    let read_txn = compat::begin_read(&db)?;
+    let table = read_txn.open_table(TABLE)?;
+    let mut lost = 0;
+    for (&key, &sum) in &expected {
+        match table.get(key)? {
+            Some(value) if checksum(value.value()) == sum => {}
+            _ => lost += 1,
+        }
+    }
+    let mut phantom = 0;
+    for &key in &crashed_keys {
+        if table.get(key)?.is_some() {
+            phantom += 1;
+        }
+    }
+    let repaired = probe.repaired();
+    info!(%mode, repaired, ?reopen, lost, phantom, "Reopened after simulated crash");
+
+    Ok(TornWriteOutcome {
+        mode,
+        torn_write,
+        repaired,
+        callback_calls: probe.calls(),
+        reopen,
+        lost,
+        phantom,
+        open_error: None,
+    })
+}
+
+impl TornWriteReport {
+    pub fn section(&self, title: impl Into<String>) -> Section {
+        let mut section = Section::new(title).table([
+            "torn write",
+            "write #",
+            "repaired",
+            "callback calls",
+            "reopen time",
+            "lost keys",
+            "phantom keys",
+            "result",
+        ]);
+        for outcome in &self.outcomes {
+            let result = match &outcome.open_error {
+                Some(e) => format!("open failed: {e}"),
+                None if outcome.lost == 0 && outcome.phantom == 0 => "ok".to_owned(),
+                None => "STATE MISMATCH".to_owned(),
+            };
+            section = section.row(vec![
+                outcome.mode.to_string().into(),
+                outcome.torn_write.into(),
+                outcome.repaired.to_string().into(),
+                outcome.callback_calls.into(),
+                outcome.reopen.into(),
+                outcome.lost.into(),
+                outcome.phantom.into(),
+                result.into(),
+            ]);
+        }
+        section
+    }
+}
@@ -0,0 +1,198 @@
+//! Time-series retention: appends keyed by monotonic timestamps, each in its own
+//! transaction, with everything older than a retention window deleted every
+//! [`PRUNE_EVERY`] appends, tracking the throughput and file size the run settles at.
+
+use crate::config::MIB;
+use crate::payload::ValueGen;
+use crate::report::Section;
+use crate::{BenchDb, BenchmarkStats, StatsAccumulator, clock, get_file_size, hooks, record_op};
+use redb::{Database, Error, ReadableTableMetadata, TableDefinition};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, info, info_span};
+
+pub const TTL_TABLE: TableDefinition<u64, &[u8]> = TableDefinition::new("benchmark_ttl");
+/// Appends between two pruning transactions.
+pub const PRUNE_EVERY: usize = 100;
+/// Number of samples taken over the run.
+const SAMPLES: usize = 10;
+
+/// State of the table after `appends` appends.
+pub struct TtlSample {
+    pub appends: usize,
+    /// Wall time since the first append, including pruning.
+    pub elapsed: Duration,
+    pub live_entries: u64,
+    pub file_size: u64,
+}
+
+pub struct TtlReport {
+    pub window: Duration,
+    pub append: BenchmarkStats,
+    /// `None` if the run was too short to prune.
+    pub prune: Option<BenchmarkStats>,
+    pub pruned_entries: u64,
+    pub samples: Vec<TtlSample>,
+}
+
+/// Nanoseconds since the Unix epoch, strictly after `last`.
+fn timestamp_after(last: u64) -> u64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64;
+    now.max(last + 1)
+}
+
+/// Perform `num_appends` timestamp-keyed appends against a TTL table in `bench_db`, deleting
+/// the entries older than `window` every [`PRUNE_EVERY`] appends.
+pub fn benchmark_ttl(
+    bench_db: &BenchDb,
+    num_appends: usize,
+    window: Duration,
+    values: ValueGen,
+) -> Result<TtlReport, Error> {
+    let quick_repair = bench_db.quick_repair;
+    let _span = info_span!("ttl", db_path = %bench_db.path, quick_repair).entered();
+    info!(num_appends, ?window, "Benchmarking TTL retention");
+
+    let db = bench_db.create(
+        Database::builder().set_cache_size(1024 * 1024 * 1024), // 1GB cache
+    )?;
+
+    let mut append = StatsAccumulator::new();
+    let mut prune = StatsAccumulator::new();
+    let mut pruned_entries = 0;
+    let mut live_entries = 0;
+    let mut samples = vec![TtlSample {
+        appends: 0,
+        elapsed: Duration::ZERO,
+        live_entries,
+        file_size: get_file_size(&bench_db.path).unwrap_or(0),
+    }];
+    let sample_every = num_appends.div_ceil(SAMPLES).max(1);
+    let mut last_key = 0;
+    let run_start = clock::now();
+
+    for i in 0..num_appends {
+        last_key = timestamp_after(last_key);
+        let value = values.generate(last_key);
+        let start = clock::now();
+        let mut write_txn = hooks::begin_write(&db)?;
+        write_txn.set_quick_repair(quick_repair);
+        {
+            let mut table = write_txn.open_table(TTL_TABLE)?;
+            table.insert(last_key, value.as_slice())?;
+        }
+        write_txn.commit()?;
+        let duration = start.elapsed();
+        append.record(duration);
+        live_entries += 1;
+        record_op(
+            "ttl-append",
+            &bench_db.path,
+            quick_repair,
+            i as u64,
+            1,
+            value.len(),
+            duration,
+        );
+
+        if (i + 1).is_multiple_of(PRUNE_EVERY) {
+            let cutoff = timestamp_after(0).saturating_sub(window.as_nanos() as u64);
+            let start = clock::now();
+            let mut write_txn = hooks::begin_write(&db)?;
+            write_txn.set_quick_repair(quick_repair);
+            // Measured inside the transaction rather than tracked by hand: a reused
+            // database still holds the previous run's entries.
+            let (before, remaining) = {
+                let mut table = write_txn.open_table(TTL_TABLE)?;
+                let before = table.len()?;
+                table.retain_in(..cutoff, |_, _| false)?;
+                (before, table.len()?)
+            };
+            write_txn.commit()?;
+            let duration = start.elapsed();
+            let removed = before.saturating_sub(remaining);
+            debug!(removed, remaining, "Pruned expired entries");
+            record_op(
+                "ttl-prune",
+                &bench_db.path,
+                quick_repair,
+                prune.count() as u64,
+                removed as usize,
+                0,
+                duration,
+            );
+            prune.record(duration);
+            pruned_entries += removed;
+            live_entries = remaining;
+        }
+
+        if (i + 1).is_multiple_of(sample_every) || i + 1 == num_appends {
+            samples.push(TtlSample {
+                appends: i + 1,
+                elapsed: run_start.elapsed(),
+                live_entries,
+                file_size: get_file_size(&bench_db.path).unwrap_or(0),
+            });
+        }
+    }
+
+    Ok(TtlReport {
+        window,
+        append: append.stats(),
+        prune: (!prune.is_empty()).then(|| prune.stats()),
+        pruned_entries,
+        samples,
+    })
+}
+
+impl TtlReport {
+    /// Appends per second and file growth over the second half of the run, after the
+    /// table has filled up to the window.
+    fn steady_state(&self) -> (f64, i64) {
+        let (first, last) = match (
+            self.samples.get(self.samples.len() / 2),
+            self.samples.last(),
+        ) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return (0.0, 0),
+        };
+        let elapsed = (last.elapsed - first.elapsed).as_secs_f64();
+        let rate = (last.appends - first.appends) as f64 / elapsed.max(f64::EPSILON);
+        (rate, last.file_size as i64 - first.file_size as i64)
+    }
+
+    pub fn sections(&self, label: &str) -> Vec<Section> {
+        let (rate, growth) = self.steady_state();
+        let last = self.samples.last();
+
+        let mut retention = Section::new(format!("TTL Retention - {label}"))
+            .field("Retention window", self.window)
+            .field("Steady-state appends per second", rate)
+            .field("Steady-state file growth (MiB)", growth as f64 / MIB as f64)
+            .field(
+                "Final file size (MiB)",
+                last.map_or(0, |s| s.file_size) as f64 / MIB as f64,
+            )
+            .field("Live entries", last.map_or(0, |s| s.live_entries))
+            .field("Pruned entries", self.pruned_entries)
+            .table(["appends", "elapsed", "live entries", "file size (MiB)"]);
+        for sample in &self.samples {
+            retention = retention.row(vec![
+                sample.appends.into(),
+                sample.elapsed.into(),
+                sample.live_entries.into(),
+                (sample.file_size as f64 / MIB as f64).into(),
+            ]);
+        }
+        retention = retention.note("Steady state is measured over the second half of the appends");
+
+        let mut sections = vec![self.append.section(format!("TTL Append - {label}"))];
+        if let Some(prune) = &self.prune {
+            sections.push(prune.section(format!("TTL Prune - {label}")));
+        }
+        sections.push(retention);
+        sections
+    }
+}
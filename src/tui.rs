@@ -0,0 +1,410 @@
+//! Live terminal dashboard for attended runs (`--tui`): throughput, a sparkline of commit
+//! latency, the database file size and phase progress, redrawn four times a second. While it
+//! is drawn, log lines go to a pane of the dashboard instead of stderr.
+//!
+//! Requires the `tui` cargo feature; without it [`start`] returns an error. Like
+//! [`crate::metrics`], recording is a no-op until [`start`] has been called. The terminal is
+//! in raw mode while the dashboard runs, so Ctrl-C is read as a key: it (or `q`) aborts the
+//! run with [`EXIT_CODE`], as the signal would.
+
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Whether this binary was built with dashboard support.
+pub const AVAILABLE: bool = cfg!(feature = "tui");
+
+/// Process exit status after aborting from the dashboard, the one of a run ended by SIGINT.
+pub const EXIT_CODE: i32 = 130;
+
+/// Interval between redraws.
+#[cfg(feature = "tui")]
+const TICK: Duration = Duration::from_millis(250);
+/// Redraws kept in the sparklines: one minute.
+#[cfg(feature = "tui")]
+const HISTORY: usize = 240;
+/// Log lines kept for the log pane.
+const LOG_LINES: usize = 200;
+
+#[derive(Default)]
+struct Live {
+    phase: String,
+    /// Index of `phase` among the planned phases.
+    phase_index: Option<usize>,
+    phase_started: Option<Instant>,
+    /// Committed transactions when `phase` started.
+    phase_ops: u64,
+    /// Share of `phase` done, for the phases that report it.
+    progress: Option<f64>,
+    db_path: String,
+    quick_repair: bool,
+    /// Totals over the run, so that the renderer can take differences between redraws.
+    ops: u64,
+    bytes: u64,
+    latency: Duration,
+    /// Slowest commit of `phase`.
+    max_latency: Duration,
+    logs: VecDeque<String>,
+}
+
+struct State {
+    /// Names of the phases of the run, in order.
+    phases: Vec<String>,
+    #[cfg(feature = "tui")]
+    started: Instant,
+    live: Mutex<Live>,
+    /// Whether the dashboard owns the terminal.
+    running: AtomicBool,
+    renderer: Mutex<Option<JoinHandle<()>>>,
+}
+
+static STATE: OnceLock<State> = OnceLock::new();
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn live() -> Option<MutexGuard<'static, Live>> {
+    STATE.get().map(|state| lock(&state.live))
+}
+
+/// Take over the terminal and draw the dashboard from a background thread until [`stop`],
+/// showing the progress through `phases`.
+#[cfg(feature = "tui")]
+pub fn start(phases: &[&str]) -> io::Result<()> {
+    let state = State {
+        phases: phases.iter().map(|&name| name.to_owned()).collect(),
+        started: Instant::now(),
+        live: Mutex::default(),
+        running: AtomicBool::new(false),
+        renderer: Mutex::new(None),
+    };
+    if STATE.set(state).is_err() {
+        return Err(io::Error::other("dashboard already started"));
+    }
+    let state = STATE.get().expect("dashboard state was just set");
+    let terminal = ratatui::try_init()?;
+    state.running.store(true, Ordering::Relaxed);
+    let renderer = std::thread::Builder::new()
+        .name("tui".to_owned())
+        .spawn(move || {
+            let result = render::run(state, terminal);
+            state.running.store(false, Ordering::Relaxed);
+            ratatui::restore();
+            if let Err(e) = result {
+                tracing::warn!(error = %e, "Dashboard failed, logging to stderr");
+            }
+        })?;
+    *lock(&state.renderer) = Some(renderer);
+    Ok(())
+}
+
+#[cfg(not(feature = "tui"))]
+pub fn start(_phases: &[&str]) -> io::Result<()> {
+    Err(io::Error::other(
+        "--tui requires building with `--features tui`",
+    ))
+}
+
+/// Stop drawing and give the terminal back, so that the report can be printed.
+pub fn stop() {
+    if let Some(state) = STATE.get() {
+        state.running.store(false, Ordering::Relaxed);
+        if let Some(renderer) = lock(&state.renderer).take() {
+            let _ = renderer.join();
+        }
+    }
+}
+
+/// Record one committed transaction carrying `bytes` of values.
+pub fn record_op(phase: &str, db_path: &str, quick_repair: bool, bytes: usize, latency: Duration) {
+    if let Some(mut live) = live() {
+        if live.phase != phase {
+            set_live_phase(&mut live, phase);
+        }
+        if live.db_path != db_path {
+            db_path.clone_into(&mut live.db_path);
+        }
+        live.quick_repair = quick_repair;
+        live.ops += 1;
+        live.bytes += bytes as u64;
+        live.latency += latency;
+        live.max_latency = live.max_latency.max(latency);
+    }
+}
+
+/// Mark `phase` as the currently running phase.
+pub fn set_phase(phase: &str) {
+    if let Some(mut live) = live() {
+        set_live_phase(&mut live, phase);
+    }
+}
+
+fn set_live_phase(live: &mut Live, phase: &str) {
+    phase.clone_into(&mut live.phase);
+    live.phase_index = STATE
+        .get()
+        .and_then(|state| state.phases.iter().position(|name| name == phase));
+    live.phase_started = Some(Instant::now());
+    live.phase_ops = live.ops;
+    live.progress = None;
+    live.max_latency = Duration::ZERO;
+}
+
+/// Report that `fraction` (0 to 1) of the current phase is done.
+pub fn set_progress(fraction: f64) {
+    if let Some(mut live) = live() {
+        live.progress = Some(fraction.clamp(0.0, 1.0));
+    }
+}
+
+/// Log output shown in the dashboard while it runs, and written to stderr otherwise; pass
+/// [`log_writer`] to the `tracing` subscriber.
+pub struct LogWriter;
+
+pub fn log_writer() -> LogWriter {
+    LogWriter
+}
+
+impl Write for LogWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match STATE.get() {
+            Some(state) if state.running.load(Ordering::Relaxed) => {
+                let mut live = lock(&state.live);
+                for line in String::from_utf8_lossy(buf).lines() {
+                    if live.logs.len() == LOG_LINES {
+                        live.logs.pop_front();
+                    }
+                    live.logs.push_back(line.to_owned());
+                }
+                Ok(buf.len())
+            }
+            _ => io::stderr().write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stderr().flush()
+    }
+}
+
+#[cfg(feature = "tui")]
+mod render {
+    use super::{HISTORY, Live, State, TICK, lock};
+    use crate::config::MIB;
+    use crate::event_log;
+    use ratatui::DefaultTerminal;
+    use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+    use ratatui::layout::{Constraint, Layout};
+    use ratatui::style::{Color, Style};
+    use ratatui::widgets::{Block, Gauge, Paragraph, Sparkline};
+    use ratatui::{Frame, text::Line};
+    use std::collections::VecDeque;
+    use std::io;
+    use std::sync::atomic::Ordering;
+    use std::time::{Duration, Instant};
+
+    /// What each redraw shows, taken from [`Live`] under its lock.
+    struct Snapshot {
+        phase: String,
+        phase_index: Option<usize>,
+        phase_elapsed: Duration,
+        phase_ops: u64,
+        progress: Option<f64>,
+        db_path: String,
+        quick_repair: bool,
+        max_latency: Duration,
+        logs: Vec<String>,
+    }
+
+    /// Rates between consecutive redraws.
+    #[derive(Default)]
+    struct History {
+        last: Option<(Instant, u64, u64, Duration)>,
+        ops_per_second: VecDeque<u64>,
+        /// Mean commit latency between redraws, in microseconds.
+        latency_us: VecDeque<u64>,
+        mib_per_second: f64,
+    }
+
+    impl History {
+        fn update(&mut self, now: Instant, live: &Live) {
+            if let Some((then, ops, bytes, latency)) = self.last {
+                let seconds = now.duration_since(then).as_secs_f64().max(f64::EPSILON);
+                let committed = live.ops - ops;
+                push(
+                    &mut self.ops_per_second,
+                    (committed as f64 / seconds) as u64,
+                );
+                let mean = (live.latency - latency)
+                    .checked_div(committed as u32)
+                    .unwrap_or_default();
+                push(&mut self.latency_us, mean.as_micros() as u64);
+                self.mib_per_second = (live.bytes - bytes) as f64 / MIB as f64 / seconds;
+            }
+            self.last = Some((now, live.ops, live.bytes, live.latency));
+        }
+    }
+
+    fn push(history: &mut VecDeque<u64>, value: u64) {
+        if history.len() == HISTORY {
+            history.pop_front();
+        }
+        history.push_back(value);
+    }
+
+    pub(super) fn run(state: &'static State, mut terminal: DefaultTerminal) -> io::Result<()> {
+        let mut history = History::default();
+        while state.running.load(Ordering::Relaxed) {
+            let now = Instant::now();
+            let snapshot = {
+                let live = lock(&state.live);
+                history.update(now, &live);
+                Snapshot {
+                    phase: live.phase.clone(),
+                    phase_index: live.phase_index,
+                    phase_elapsed: live
+                        .phase_started
+                        .map_or(Duration::ZERO, |started| now - started),
+                    phase_ops: live.ops - live.phase_ops,
+                    progress: live.progress,
+                    db_path: live.db_path.clone(),
+                    quick_repair: live.quick_repair,
+                    max_latency: live.max_latency,
+                    logs: live.logs.iter().cloned().collect(),
+                }
+            };
+            terminal.draw(|frame| draw(frame, state, &snapshot, &history))?;
+
+            if event::poll(TICK)?
+                && let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+                && (key.code == KeyCode::Char('q')
+                    || (key.code == KeyCode::Char('c')
+                        && key.modifiers.contains(KeyModifiers::CONTROL)))
+            {
+                abort();
+            }
+        }
+        Ok(())
+    }
+
+    /// End the run from the dashboard, as SIGINT would, keeping what the recorders hold.
+    fn abort() -> ! {
+        ratatui::restore();
+        let _ = event_log::flush();
+        eprintln!("Run aborted from the dashboard");
+        std::process::exit(super::EXIT_CODE)
+    }
+
+    fn draw(frame: &mut Frame, state: &State, snapshot: &Snapshot, history: &History) {
+        let [header, gauge, stats, throughput, latency, logs] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(3),
+            Constraint::Length(4),
+            Constraint::Length(6),
+            Constraint::Length(6),
+            Constraint::Min(3),
+        ])
+        .areas(frame.area());
+
+        let elapsed = state.started.elapsed().as_secs();
+        frame.render_widget(
+            Line::from(format!(
+                " redb {} quick_repair benchmark · {:02}:{:02}:{:02} elapsed · q to abort",
+                crate::REDB_VERSION,
+                elapsed / 3600,
+                elapsed / 60 % 60,
+                elapsed % 60
+            )),
+            header,
+        );
+
+        let phases = state.phases.len().max(1);
+        let index = snapshot.phase_index.unwrap_or(0);
+        let done = (index as f64 + snapshot.progress.unwrap_or(0.0)) / phases as f64;
+        let label = match snapshot.progress {
+            Some(fraction) => format!(
+                "{} {:.0}% · phase {} of {phases}",
+                snapshot.phase,
+                fraction * 100.0,
+                index + 1
+            ),
+            None => format!("{} · phase {} of {phases}", snapshot.phase, index + 1),
+        };
+        frame.render_widget(
+            Gauge::default()
+                .block(Block::bordered().title(" Progress "))
+                .gauge_style(Style::new().fg(Color::Green))
+                .ratio(done.clamp(0.0, 1.0))
+                .label(label),
+            gauge,
+        );
+
+        let file_size = std::fs::metadata(&snapshot.db_path).map_or(0, |m| m.len());
+        frame.render_widget(
+            Paragraph::new(vec![
+                Line::from(format!(
+                    "{} (quick_repair({})) · {} MiB",
+                    snapshot.db_path,
+                    snapshot.quick_repair,
+                    file_size / MIB
+                )),
+                Line::from(format!(
+                    "{} commits in {:.1?} · {} commits/s · {:.1} MiB/s · slowest {:.2?}",
+                    snapshot.phase_ops,
+                    snapshot.phase_elapsed,
+                    history.ops_per_second.back().unwrap_or(&0),
+                    history.mib_per_second,
+                    snapshot.max_latency
+                )),
+            ])
+            .block(Block::bordered().title(" Database ")),
+            stats,
+        );
+
+        frame.render_widget(
+            Sparkline::default()
+                .block(Block::bordered().title(" Commits per second "))
+                .style(Style::new().fg(Color::Cyan))
+                .data(tail(&history.ops_per_second, throughput.width)),
+            throughput,
+        );
+        frame.render_widget(
+            Sparkline::default()
+                .block(Block::bordered().title(format!(
+                    " Mean commit latency (μs), last {} ",
+                    history.latency_us.back().unwrap_or(&0)
+                )))
+                .style(Style::new().fg(Color::Yellow))
+                .data(tail(&history.latency_us, latency.width)),
+            latency,
+        );
+
+        let lines = logs.height.saturating_sub(2) as usize;
+        let shown = &snapshot.logs[snapshot.logs.len().saturating_sub(lines)..];
+        frame.render_widget(
+            Paragraph::new(
+                shown
+                    .iter()
+                    .map(|line| Line::from(line.as_str()))
+                    .collect::<Vec<_>>(),
+            )
+            .block(Block::bordered().title(" Log ")),
+            logs,
+        );
+    }
+
+    /// The newest values of `history` that fit in a bordered widget `width` columns wide.
+    fn tail(history: &VecDeque<u64>, width: u16) -> Vec<u64> {
+        let fit = width.saturating_sub(2) as usize;
+        history
+            .iter()
+            .skip(history.len().saturating_sub(fit))
+            .copied()
+            .collect()
+    }
+}
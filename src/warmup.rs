@@ -0,0 +1,74 @@
+//! Unmeasured writes issued before timing begins, so cold-cache and first-allocation
+//! effects don't pollute the comparison.
+
+use crate::payload::ValueGen;
+use crate::write_single;
+use redb::{Database, Error};
+use std::time::Duration;
+use tracing::info;
+
+/// Writes per throughput window when waiting for steady state.
+const WINDOW: u64 = 100;
+/// Consecutive windows whose throughput differs by less than this are considered stable.
+const STABLE_TOLERANCE: f64 = 0.05;
+/// Give up waiting for steady state after this many windows.
+const MAX_WINDOWS: u64 = 100;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Warmup {
+    /// Fixed number of unmeasured writes.
+    pub writes: u64,
+    /// After the fixed writes, keep warming up until throughput stabilizes.
+    pub until_stable: bool,
+}
+
+/// Perform the warmup writes starting at `start_key`, returning the number of keys used.
+pub fn warm_up(
+    db: &Database,
+    start_key: u64,
+    warmup: Warmup,
+    values: ValueGen,
+    quick_repair: bool,
+) -> Result<u64, Error> {
+    let mut key = start_key;
+
+    for _ in 0..warmup.writes {
+        write_single(db, key, &values.generate(key), quick_repair)?;
+        key += 1;
+    }
+
+    if warmup.until_stable {
+        let mut previous: Option<f64> = None;
+        let mut stable = false;
+
+        for _ in 0..MAX_WINDOWS {
+            let mut elapsed = Duration::ZERO;
+            for _ in 0..WINDOW {
+                elapsed += write_single(db, key, &values.generate(key), quick_repair)?;
+                key += 1;
+            }
+            let throughput = WINDOW as f64 / elapsed.as_secs_f64();
+
+            if let Some(previous) = previous
+                && ((throughput - previous) / previous).abs() < STABLE_TOLERANCE
+            {
+                stable = true;
+                break;
+            }
+            previous = Some(throughput);
+        }
+
+        if !stable {
+            info!(
+                max_windows = MAX_WINDOWS,
+                "Throughput did not stabilize during warmup"
+            );
+        }
+    }
+
+    let writes = key - start_key;
+    if writes > 0 {
+        info!(writes, "Warmup complete");
+    }
+    Ok(writes)
+}
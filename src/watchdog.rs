@@ -0,0 +1,161 @@
+//! Watchdog aborting the run when no operation completes for `--stall-timeout`, so a hung
+//! fsync or a deadlock fails an unattended run instead of stalling it forever.
+//!
+//! A hung system call cannot be interrupted from another thread, so the watchdog ends the
+//! process: it logs the stalled phase, appends a `stall` record to the JSON Lines stream,
+//! flushes the event log and exits with [`EXIT_CODE`]. Partial results are whatever those
+//! recorders and the last `--checkpoint` hold; the latter can be resumed.
+//!
+//! Like [`crate::metrics`], heartbeats are a no-op until [`start`] has been called.
+
+use crate::{event_log, slo, stream, tui};
+use serde_json::json;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::error;
+
+/// Process exit status after a stall.
+pub const EXIT_CODE: i32 = 3;
+
+struct State {
+    epoch: Instant,
+    /// Milliseconds since `epoch` of the last completed operation or phase start.
+    last_beat_ms: AtomicU64,
+    phase: Mutex<String>,
+}
+
+static STATE: OnceLock<State> = OnceLock::new();
+
+/// A stall timeout parsed from `90s`, `10m`, `1h` and the like.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StallTimeout(pub Duration);
+
+impl FromStr for StallTimeout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let split = s
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+        let number: f64 = number
+            .parse()
+            .map_err(|_| format!("invalid stall timeout `{s}`, expected e.g. `10m`"))?;
+        let scale = match unit {
+            "ms" => 1e-3,
+            "s" => 1.0,
+            "m" => 60.0,
+            "h" => 3600.0,
+            _ => {
+                return Err(format!(
+                    "unknown unit in stall timeout `{s}`, expected `ms`, `s`, `m` or `h`"
+                ));
+            }
+        };
+        Ok(Self(Duration::from_secs_f64(number * scale)))
+    }
+}
+
+impl fmt::Display for StallTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
+/// Start watching for stalls longer than `timeout`.
+pub fn start(timeout: StallTimeout) {
+    let state = State {
+        epoch: Instant::now(),
+        last_beat_ms: AtomicU64::new(0),
+        phase: Mutex::new(String::new()),
+    };
+    if STATE.set(state).is_err() {
+        return;
+    }
+    thread::Builder::new()
+        .name("watchdog".to_owned())
+        .spawn(move || watch(timeout.0))
+        .expect("failed to spawn watchdog thread");
+}
+
+fn watch(timeout: Duration) {
+    let state = STATE
+        .get()
+        .expect("watchdog state is set before the thread starts");
+    let poll = (timeout / 4).clamp(Duration::from_millis(10), Duration::from_secs(1));
+    loop {
+        thread::sleep(poll);
+        let now_ms = state.epoch.elapsed().as_millis() as u64;
+        let idle = Duration::from_millis(now_ms - state.last_beat_ms.load(Ordering::Relaxed));
+        if idle < timeout {
+            continue;
+        }
+
+        let phase = state
+            .phase
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        // Give the terminal back first, so that the error is not lost in the dashboard.
+        tui::stop();
+        error!(
+            phase,
+            ?idle,
+            ?timeout,
+            "No operation completed within the stall timeout, aborting the run"
+        );
+        stream::emit(json!({
+            "type": "stall",
+            "ts_ms": slo::unix_ms(),
+            "phase": phase,
+            "idle_ms": idle.as_millis() as u64,
+        }));
+        let _ = event_log::flush();
+        std::process::exit(EXIT_CODE);
+    }
+}
+
+/// Note that an operation completed.
+pub fn heartbeat() {
+    if let Some(state) = STATE.get() {
+        let now_ms = state.epoch.elapsed().as_millis() as u64;
+        state.last_beat_ms.store(now_ms, Ordering::Relaxed);
+    }
+}
+
+/// Note that `phase` started, which counts as a heartbeat.
+pub fn set_phase(phase: &str) {
+    if let Some(state) = STATE.get() {
+        *state.phase.lock().unwrap_or_else(|e| e.into_inner()) = phase.to_owned();
+        heartbeat();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timeout(s: &str) -> Result<Duration, String> {
+        s.parse::<StallTimeout>().map(|timeout| timeout.0)
+    }
+
+    #[test]
+    fn parses_stall_timeouts() {
+        assert_eq!(timeout("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(timeout("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(timeout("1.5m"), Ok(Duration::from_secs(90)));
+        assert_eq!(timeout("2h"), Ok(Duration::from_secs(7_200)));
+    }
+
+    #[test]
+    fn rejects_missing_numbers_and_units() {
+        assert!(timeout("10").is_err());
+        assert!(timeout("m").is_err());
+        assert!(timeout("10us").is_err());
+        assert!(timeout("s10").is_err());
+    }
+}